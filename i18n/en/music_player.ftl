@@ -10,3 +10,383 @@ tooltip-play-button = Play
 tooltip-pause-button = Pause
 tooltip-prev-button = Previous
 tooltip-next-button = Next
+nav-diagnostics-label = Codecs & Diagnostics
+library-unsupported-codec = No installed GStreamer plugin can decode this file
+diagnostics-inspecting = Inspecting GStreamer plugins…
+diagnostics-gstreamer-version = GStreamer {$version}
+diagnostics-formats = Library formats
+diagnostics-format-supported = Supported
+diagnostics-format-missing = Missing decoder for {$caps}
+diagnostics-unsupported-files = Unplayable library files ({$count})
+diagnostics-decoders = Audio decoders ({$count})
+diagnostics-audio-sinks = Audio sinks ({$count})
+file = File
+open-playlist = Open playlist…
+save-queue = Save queue as playlist…
+playlist-files = Playlists
+nav-playlists-label = Playlists
+playlist-new = New playlist
+playlist-default-name = Playlist {$number}
+playlist-unresolved = { $count ->
+    [one] 1 entry of the playlist wasn't found:
+   *[other] {$count} entries of the playlist weren't found:
+}
+playlist-unresolved-more = …and {$count} more
+playlist-unresolved-dismiss = Dismiss
+playlist-copy-name = {$name} (copy)
+playlist-name = Playlist name
+playlist-empty-selection = Create a playlist or select one to view its tracks
+playlist-play = Play
+playlist-append = Add to queue
+playlist-rename = Rename
+playlist-duplicate = Duplicate
+playlist-delete = Delete
+add-to-playlist = Add to “{$name}”
+add-to-new-playlist = Add to new playlist
+cancel = Cancel
+save = Save
+smart-playlists = Smart playlists
+smart-playlist-new = New smart playlist
+smart-playlist-default-name = Smart playlist {$number}
+smart-edit-rules = Edit rules
+smart-reshuffle = Shuffle again
+smart-match = Match
+smart-match-suffix = of the following rules
+smart-match-all = all
+smart-match-any = any
+smart-add-rule = Add rule
+smart-remove-rule = Remove rule
+smart-limit = Limit to
+smart-no-limit = No limit
+smart-limit-tracks = tracks
+smart-limit-minutes = minutes
+smart-limit-hours = hours
+smart-sort = Order by
+smart-field-title = Title
+smart-field-artist = Artist
+smart-field-album = Album
+smart-field-genre = Genre
+smart-field-year = Year
+smart-field-duration = Length
+smart-field-play-count = Play count
+smart-field-skip-count = Skip count
+smart-field-rating = Rating
+smart-field-favorite = Favorite
+smart-field-date-added = Date added
+smart-field-first-played = First played
+smart-field-last-played = Last played
+smart-op-is = is
+smart-op-is-not = is not
+smart-op-contains = contains
+smart-op-does-not-contain = does not contain
+smart-op-less-than = is less than
+smart-op-at-most = is at most
+smart-op-greater-than = is greater than
+smart-op-at-least = is at least
+smart-op-in-last = in the last (days)
+smart-op-not-in-last = not in the last (days)
+smart-op-is-empty = is not set
+smart-op-is-set = is marked
+smart-op-is-not-set = is not marked
+smart-sort-random = Random
+smart-sort-most-played = Most played
+smart-sort-least-played = Least played
+smart-sort-recently-added = Recently added
+smart-sort-recently-played = Recently played
+smart-sort-highest-rated = Highest rated
+smart-sort-artist = Artist
+smart-sort-title = Title
+smart-sort-year = Year
+library-title = Title
+library-plays = Plays
+library-skips = Skips
+library-last-played = Last played
+library-listened = Listened
+library-rating = Rating
+library-never = Never
+library-today = Today
+library-days-ago = { $days ->
+    [one] Yesterday
+   *[other] {$days} days ago
+}
+nav-history-label = Recently played
+history-unavailable = The listening history could not be loaded
+history-empty = Tracks you listen to will show up here
+history-yesterday = Yesterday
+history-replay = Play again
+history-enqueue = Add to queue
+history-skipped = Skipped
+history-stopped = Stopped before the end
+history-source-library = Library
+history-source-playlist = Playlist
+history-source-radio = Radio
+history-export = Export…
+history-prune = Remove listens older than
+history-prune-button = Remove
+history-prune-age = { $days ->
+    [365] a year
+   *[other] {$days} days
+}
+nav-statistics-label = Statistics
+stats-loading = Computing statistics…
+stats-range-week = Last 7 days
+stats-range-month = Last 30 days
+stats-range-year = Last year
+stats-range-all = All time
+stats-hours-listened = {$hours} hours listened
+stats-listens = { $listens ->
+    [one] 1 listen
+   *[other] {$listens} listens
+}
+stats-plays = { $plays ->
+    [one] 1 play
+   *[other] {$plays} plays
+}
+stats-none = Nothing yet
+stats-unknown = Unknown
+stats-top-artists = Top artists
+stats-top-albums = Top albums
+stats-top-tracks = Top tracks
+stats-by-hour = By hour of day
+stats-by-weekday = By day of week
+stats-monday = Mon
+stats-tuesday = Tue
+stats-wednesday = Wed
+stats-thursday = Thu
+stats-friday = Fri
+stats-saturday = Sat
+stats-sunday = Sun
+stats-genres = Genres
+stats-library = Library
+stats-tracks = { $tracks ->
+    [one] 1 track
+   *[other] {$tracks} tracks
+}
+stats-library-duration = {$hours} hours of music
+stats-library-size = {$size} on disk
+rating-favorite = Add to favorites
+rating-unfavorite = Remove from favorites
+edit-tags = Edit tags…
+library-edit-tags = { $count ->
+    [0] Edit tags
+    [one] Edit tags of 1 track
+   *[other] Edit tags of {$count} tracks
+}
+library-select-none = Clear selection
+library-look-up-album = Look up album
+tag-editor-title = { $count ->
+    [one] Edit tags
+   *[other] Edit tags of {$count} tracks
+}
+tag-editor-mixed = Mixed values
+tag-editor-revert = Keep the current values
+tag-editor-save = Save
+tag-editor-cancel = Cancel
+tag-editor-failed = { $count ->
+    [one] The tags of 1 file could not be written
+   *[other] The tags of {$count} files could not be written
+}
+tag-editor-identify = Identify
+tag-editor-identifying = Identifying…
+tag-editor-no-match = AcoustID doesn't know this recording
+tag-title = Title
+tag-artist = Artist
+tag-album = Album
+tag-album-artist = Album artist
+tag-track = Track
+tag-disc = Disc
+tag-year = Year
+tag-genre = Genre
+tag-comment = Comment
+tag-musicbrainz-recording = MusicBrainz recording
+tag-musicbrainz-release = MusicBrainz release
+tag-musicbrainz-release-group = MusicBrainz release group
+tag-musicbrainz-artist = MusicBrainz artist
+album-lookup-title = Look up album
+album-lookup-body = { $count ->
+    [one] Tags MusicBrainz has for the selected track.
+   *[other] Tags MusicBrainz has for the {$count} selected tracks.
+}
+album-lookup-searching = Looking for releases…
+album-lookup-no-album = The selected tracks have no album to look up
+album-lookup-no-match = MusicBrainz has no release like this album
+album-lookup-candidate = {$title} by {$artist} ({$details}) · {$score}% match
+album-lookup-tracks = { $count ->
+    [one] 1 track
+   *[other] {$count} tracks
+}
+album-lookup-current = Current
+album-lookup-proposed = Proposed
+album-lookup-unmatched = Not on this release; left unchanged
+album-lookup-unchanged = Already tagged like this release
+album-lookup-apply = Apply
+album-lookup-cancel = Cancel
+album-lookup-failed = { $count ->
+    [one] The tags of 1 file could not be written
+   *[other] The tags of {$count} files could not be written
+}
+artwork-choose = Choose a cover image
+artwork-images = Images
+artwork-set = Set cover…
+artwork-use-folder = Use folder cover
+artwork-extract = Save cover…
+artwork-remove = Remove artwork
+organize-library = Organize library…
+organize-scope-library = Moves and renames every library track after its tags.
+organize-scope-selection = { $count ->
+    [one] Moves and renames the selected track after its tags.
+   *[other] Moves and renames the {$count} selected tracks after their tags.
+}
+organize-template = Path template
+organize-placeholders = Placeholders: {"{"}albumartist{"}"} {"{"}artist{"}"} {"{"}album{"}"} {"{"}title{"}"} {"{"}year{"}"} {"{"}track:02{"}"} {"{"}disc{"}"} {"{"}genre{"}"} {"{"}ext{"}"}
+organize-summary = {$tracks} tracks to move, {$companions} accompanying files, {$unchanged} already in place
+organize-preview = Preview
+organize-run = Organize
+organize-cancel = Cancel
+organize-failed = { $count ->
+    [one] 1 file could not be moved
+   *[other] {$count} files could not be moved
+}
+nav-duplicates-label = Duplicates
+duplicates-find = Find duplicates
+duplicates-hint = Look for tracks that are in the library more than once, as identical files or as the same song in another format.
+duplicates-searching = Looking for duplicates…
+duplicates-none = No duplicates found
+duplicates-by-hash = Identical files
+duplicates-by-tags = Same artist and title
+duplicates-by-fingerprint = Sounds the same (slow the first time)
+duplicates-tolerance = Lengths may differ by
+duplicates-tolerance-seconds = { $seconds ->
+    [one] 1 second
+   *[other] {$seconds} seconds
+}
+duplicates-summary = { $groups ->
+    [one] 1 track with {$extra} extra copies
+   *[other] {$groups} tracks with {$extra} extra copies
+}
+duplicates-matched-hash = Identical files
+duplicates-matched-tags = Same tags
+duplicates-matched-fingerprint = Sounds the same
+duplicates-trash = Move others to trash
+duplicates-trash-all = Move all others to trash
+duplicates-failed = { $count ->
+    [one] 1 file could not be moved to the trash
+   *[other] {$count} files could not be moved to the trash
+}
+duplicates-unreadable = { $count ->
+    [one] 1 file could not be read and was not compared by content
+   *[other] {$count} files could not be read and were not compared by content
+}
+lyrics-edit = Edit lyrics
+lyrics-editor-placeholder = Paste the lyrics here
+lyrics-editor-text-hint = One line of lyrics per line. Lines already timed in LRC format keep their times.
+lyrics-editor-start-timing = Time lines
+lyrics-editor-timing-hint = Play the track and press Space or Enter as each line begins. Backspace takes the last stamp back; click a line to stamp it next.
+lyrics-editor-progress = {$timed} of {$total} lines timed
+lyrics-editor-untimed = Every line needs a time before the lyrics can be saved
+lyrics-editor-save-lrc = Save as .lrc
+lyrics-editor-embed = Save in file
+lyrics-editor-edit-text = Edit text
+lyrics-editor-cancel = Cancel
+nav-equalizer-label = Equalizer
+equalizer-enabled = Enabled
+equalizer-unavailable = The equalizer needs the GStreamer Good plugins, which are not installed.
+equalizer-preset = Preset
+equalizer-preset-name = Preset name
+equalizer-save-preset = Save preset
+equalizer-delete-preset = Delete preset
+equalizer-bound-hint = Picked for the playing track's genre or the output device. Changing the settings overrides it until the next track.
+equalizer-preamp = Preamp
+equalizer-hz = {$value} Hz
+equalizer-khz = {$value} kHz
+equalizer-genres = Presets for genres
+equalizer-genre = Genre
+equalizer-bind-genre = Use preset for genre
+equalizer-unbind = Stop using preset
+equalizer-output-device = Preset for the output device
+equalizer-device = {$device}
+equalizer-device-bound = {$device}: {$preset}
+equalizer-device-unknown = The output device is not known
+equalizer-bind-device = Use preset on this device
+equalizer-preset-flat = Flat
+equalizer-preset-rock = Rock
+equalizer-preset-pop = Pop
+equalizer-preset-jazz = Jazz
+equalizer-preset-classical = Classical
+equalizer-preset-electronic = Electronic
+equalizer-preset-bass-boost = Bass Boost
+equalizer-preset-treble-boost = Treble Boost
+equalizer-preset-vocal = Vocal
+parametric-title = Parametric equalizer
+parametric-import = Import AutoEQ or Equalizer APO file
+parametric-files = Equalizer settings
+parametric-skipped = { $count ->
+    [one] 1 line of the file was not understood and was skipped
+   *[other] {$count} lines of the file were not understood and were skipped
+}
+parametric-new = New profile
+parametric-edit = Edit
+parametric-delete = Delete profile
+parametric-summary = { $filters ->
+    [one] 1 filter
+   *[other] {$filters} filters
+}, preamp {$preamp} dB
+parametric-device = {$device} uses the profile picked above
+parametric-name = Profile name
+parametric-preamp = Preamp
+parametric-add-filter = Add filter
+parametric-remove-filter = Remove filter
+parametric-save = Save
+parametric-cancel = Cancel
+parametric-hz = Hz
+parametric-db = dB
+parametric-q = Q
+parametric-peak = Peak
+parametric-low-shelf = Low shelf
+parametric-high-shelf = High shelf
+parametric-low-pass = Low pass
+parametric-high-pass = High pass
+speed = Speed
+speed-value = {$speed}×
+speed-normal = Reset
+speed-remember-track = Keep speed for this track
+speed-remember-album = Keep speed for this album
+speed-remembered = Kept at {$speed}×
+speed-forget = Forget
+pitch = Pitch
+pitch-value = { $semitones ->
+    [0] No shift
+    [one] {$shift} semitone
+   *[other] {$shift} semitones
+}
+pitch-unavailable = Shifting the pitch needs the SoundTouch plugin from GStreamer's Bad plugins.
+practice-title = A–B loop
+practice-set-a = Set A
+practice-set-b = Set B
+practice-clear = Clear
+practice-hint = Set A and B where the section starts and ends to loop it. The [ and ] keys set them too, and \ clears them.
+practice-looping = { $count ->
+    [0] Looping {$start} – {$end}
+    [one] Looping {$start} – {$end}, played 1 time
+   *[other] Looping {$start} – {$end}, played {$count} times
+}
+practice-count-in = Again in {$seconds}…
+practice-off = Off
+practice-gap = Pause before repeating
+practice-seconds = { $seconds ->
+    [one] 1 second
+   *[other] {$seconds} seconds
+}
+practice-step = Speed up by
+practice-step-value = {$step}%
+practice-step-every = Speed up every
+practice-repetitions = { $count ->
+    [one] repetition
+   *[other] {$count} repetitions
+}
+practice-start-rate = Starting speed
+practice-target-rate = Up to
+practice-bookmark-name = Loop name
+practice-bookmark-save = Save loop
+practice-bookmark-loop = Loop this section
+practice-bookmark-delete = Delete loop
@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Identifying recordings by their Chromaprint fingerprint with the AcoustID
+//! web service, and the compressed fingerprint format it and the library use.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::Value;
+
+/// Chromaprint's default algorithm, the one AcoustID indexes.
+pub const ALGORITHM: u8 = 1;
+
+/// Largest gap between set bits stored in three bits; larger ones continue in
+/// the five-bit section.
+const MAX_NORMAL: u8 = 7;
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Compress a raw fingerprint the way Chromaprint does and encode it as
+/// URL-safe base64, as AcoustID takes it.
+pub fn encode_fingerprint(fingerprint: &[u32]) -> String {
+    let mut gaps = Vec::new();
+    let mut previous = 0;
+    for &item in fingerprint {
+        // Consecutive items mostly differ in a few bits
+        let mut changed = item ^ previous;
+        previous = item;
+        let (mut bit, mut last_bit) = (1u8, 0u8);
+        while changed != 0 {
+            if changed & 1 != 0 {
+                gaps.push(bit - last_bit);
+                last_bit = bit;
+            }
+            changed >>= 1;
+            bit += 1;
+        }
+        gaps.push(0);
+    }
+
+    let len = fingerprint.len() as u32;
+    let mut data = vec![ALGORITHM, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+    let normal: Vec<u8> = gaps.iter().map(|g| (*g).min(MAX_NORMAL)).collect();
+    let exceptional: Vec<u8> = gaps
+        .iter()
+        .filter(|g| **g >= MAX_NORMAL)
+        .map(|g| g - MAX_NORMAL)
+        .collect();
+    pack(&normal, 3, &mut data);
+    pack(&exceptional, 5, &mut data);
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+/// The raw fingerprint in an encoded one.
+pub fn decode_fingerprint(encoded: &str) -> Result<Vec<u32>> {
+    let data = URL_SAFE_NO_PAD
+        .decode(encoded.trim())
+        .map_err(|e| anyhow!("invalid fingerprint: {e}"))?;
+    let Some((header, body)) = data.split_first_chunk::<4>() else {
+        bail!("fingerprint is too short");
+    };
+    let len = usize::from(header[1]) << 16 | usize::from(header[2]) << 8 | usize::from(header[3]);
+
+    // Three-bit gaps up to the end of the last item, then the larger ones
+    let mut normal = Vec::new();
+    let mut reader = BitReader::new(body);
+    let mut items = 0;
+    while items < len {
+        let gap = reader
+            .read(3)
+            .ok_or_else(|| anyhow!("fingerprint is truncated"))?;
+        if gap == 0 {
+            items += 1;
+        }
+        normal.push(gap);
+    }
+    let mut reader = BitReader::new(&body[(normal.len() * 3).div_ceil(8)..]);
+
+    let mut fingerprint = Vec::with_capacity(len);
+    let (mut item, mut last_bit, mut previous) = (0u32, 0u32, 0u32);
+    for gap in normal {
+        if gap == 0 {
+            previous ^= item;
+            fingerprint.push(previous);
+            (item, last_bit) = (0, 0);
+            continue;
+        }
+        let mut gap = u32::from(gap);
+        if gap == u32::from(MAX_NORMAL) {
+            gap += u32::from(
+                reader
+                    .read(5)
+                    .ok_or_else(|| anyhow!("fingerprint is truncated"))?,
+            );
+        }
+        last_bit += gap;
+        if last_bit > 32 {
+            bail!("invalid fingerprint");
+        }
+        item |= 1 << (last_bit - 1);
+    }
+    Ok(fingerprint)
+}
+
+/// Append `values` of `bits` bits each to `data`, lowest bits first.
+fn pack(values: &[u8], bits: u32, data: &mut Vec<u8>) {
+    let (mut buffer, mut buffered) = (0u32, 0u32);
+    for value in values {
+        buffer |= (u32::from(*value) & ((1 << bits) - 1)) << buffered;
+        buffered += bits;
+        while buffered >= 8 {
+            data.push(buffer as u8);
+            buffer >>= 8;
+            buffered -= 8;
+        }
+    }
+    if buffered > 0 {
+        data.push(buffer as u8);
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn read(&mut self, bits: usize) -> Option<u8> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self.data.get(self.position / 8)?;
+            value |= ((byte >> (self.position % 8)) & 1) << i;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+/// A recording AcoustID found for a fingerprint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    /// How well the fingerprint matched, from 0 to 1
+    pub score: f64,
+    /// MusicBrainz recording ID
+    pub recording_id: String,
+    pub title: Option<String>,
+    pub artists: Vec<String>,
+    /// Title of an album the recording is on
+    pub album: Option<String>,
+}
+
+impl Match {
+    /// The artists as credited together.
+    pub fn artist(&self) -> Option<String> {
+        (!self.artists.is_empty()).then(|| self.artists.join(", "))
+    }
+}
+
+/// An AcoustID server and the application key to look fingerprints up with.
+#[derive(Debug, Clone)]
+pub struct AcoustId {
+    api_root: String,
+    key: String,
+    agent: ureq::Agent,
+}
+
+impl AcoustId {
+    pub const DEFAULT_API_ROOT: &'static str = "https://api.acoustid.org/v2";
+
+    /// A client for the server at `api_root`, such as `DEFAULT_API_ROOT` or a
+    /// local mirror.
+    pub fn new(api_root: &str, key: &str) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("music-player/", env!("CARGO_PKG_VERSION")))
+            .build();
+        Self {
+            api_root: api_root.trim_end_matches('/').to_string(),
+            key: key.trim().to_string(),
+            agent,
+        }
+    }
+
+    /// Recordings matching an encoded fingerprint of a track `duration` long,
+    /// best match first.
+    pub fn lookup(&self, fingerprint: &str, duration: Duration) -> Result<Vec<Match>> {
+        let duration = duration.as_secs().to_string();
+        let response = self
+            .agent
+            .post(&format!("{}/lookup", self.api_root))
+            .send_form(&[
+                ("client", self.key.as_str()),
+                ("meta", "recordings releasegroups compress"),
+                ("duration", &duration),
+                ("fingerprint", fingerprint),
+            ]);
+        let body: Value = match response {
+            Ok(response) => response.into_json()?,
+            Err(ureq::Error::Status(_, response)) => response.into_json()?,
+            Err(err) => return Err(anyhow!("AcoustID is unreachable: {err}")),
+        };
+        if body["status"] != "ok" {
+            let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+            bail!("AcoustID lookup failed: {message}");
+        }
+
+        let mut matches = Vec::new();
+        for result in body["results"].as_array().into_iter().flatten() {
+            let score = result["score"].as_f64().unwrap_or(0.0);
+            for recording in result["recordings"].as_array().into_iter().flatten() {
+                let Some(id) = recording["id"].as_str() else {
+                    continue;
+                };
+                let names = |key: &str| -> Vec<String> {
+                    recording[key]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|v| v["name"].as_str().map(str::to_string))
+                        .collect()
+                };
+                // Prefer an album over singles and compilations
+                let groups = recording["releasegroups"].as_array();
+                let album = groups
+                    .into_iter()
+                    .flatten()
+                    .find(|g| g["type"] == "Album")
+                    .or_else(|| groups.and_then(|g| g.first()))
+                    .and_then(|g| g["title"].as_str().map(str::to_string));
+                matches.push(Match {
+                    score,
+                    recording_id: id.to_string(),
+                    title: recording["title"].as_str().map(str::to_string),
+                    artists: names("artists"),
+                    album,
+                });
+            }
+        }
+        // Recordings without a title were only ever submitted by ID
+        matches.retain(|m| m.title.is_some());
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobble::test_server;
+
+    #[test]
+    fn round_trips_fingerprints() {
+        let fingerprint: Vec<u32> = (0..300u32)
+            .map(|i| i.wrapping_mul(2_654_435_761) ^ (i << 28))
+            .collect();
+        let encoded = encode_fingerprint(&fingerprint);
+        assert!(!encoded.contains(['+', '/', '=']));
+        assert_eq!(decode_fingerprint(&encoded).unwrap(), fingerprint);
+
+        assert!(decode_fingerprint(&encode_fingerprint(&[])).unwrap().is_empty());
+        assert!(decode_fingerprint("AQAAAw").is_err());
+    }
+
+    #[test]
+    fn encodes_like_chromaprint() {
+        // Items 1, 1, 3 change bits 1, none and 2: gaps 1, 0 | 0 | 2, 0,
+        // packed three bits each from the lowest bit up
+        let encoded = encode_fingerprint(&[1, 1, 3]);
+        let data = URL_SAFE_NO_PAD.decode(encoded).unwrap();
+        assert_eq!(data, [ALGORITHM, 0, 0, 3, 0b0000_0001, 0b0000_0100]);
+    }
+
+    #[test]
+    fn looks_up_recordings() {
+        let (root, requests) = test_server::serve(vec![(
+            "200 OK",
+            r#"{"status":"ok","results":[
+                {"id":"a","score":0.6,"recordings":[{"id":"r2","title":"Other",
+                    "artists":[{"name":"B"}]}]},
+                {"id":"b","score":0.95,"recordings":[{"id":"r1","title":"Song",
+                    "artists":[{"name":"A"},{"name":"C"}],
+                    "releasegroups":[{"title":"Single","type":"Single"},
+                                     {"title":"Record","type":"Album"}]},
+                    {"id":"r3"}]}]}"#,
+        )]);
+        let matches = AcoustId::new(&root, "key")
+            .lookup("AQAA", Duration::from_secs(215))
+            .unwrap();
+
+        let request = requests.recv().unwrap();
+        assert!(request.head.starts_with("POST /lookup "));
+        assert!(request.body.contains("client=key"));
+        assert!(request.body.contains("duration=215"));
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].recording_id, "r1");
+        assert_eq!(matches[0].artist().as_deref(), Some("A, C"));
+        assert_eq!(matches[0].album.as_deref(), Some("Record"));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let (root, _requests) = test_server::serve(vec![(
+            "400 Bad Request",
+            r#"{"status":"error","error":{"code":4,"message":"invalid API key"}}"#,
+        )]);
+        let err = AcoustId::new(&root, "wrong")
+            .lookup("AQAA", Duration::from_secs(1))
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid API key"));
+
+        let result = AcoustId::new(&test_server::unreachable(), "key")
+            .lookup("AQAA", Duration::from_secs(1));
+        assert!(result.is_err());
+    }
+}
@@ -3,28 +3,63 @@
 use crate::config::Config;
 use crate::fl;
 use cosmic::app::context_drawer;
+use cosmic::dialog::file_chooser::{self, FileFilter};
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::alignment::Vertical;
+use cosmic::iced::keyboard::{self, key::Named, Key};
 use cosmic::iced::{Alignment, Length, Subscription};
 use cosmic::prelude::*;
 use cosmic::widget::{self, icon, menu, nav_bar};
 use cosmic::{cosmic_theme, theme};
 use futures_util::SinkExt;
-use music_player::audio::backend::{MediaPlayer, TrackMetadata};
+use music_player::acoustid::{self, encode_fingerprint};
+use music_player::audio::artwork::{self, Thumbnail};
+use music_player::audio::backend::{self, MediaPlayer, TrackMetadata, MAX_RATE, MIN_RATE};
+use music_player::audio::cue::{expand_cue_sheets, CueTrack};
+use music_player::audio::diagnostics::Diagnostics;
+use music_player::audio::fingerprint::{self, FINGERPRINT_LENGTH};
+use music_player::audio::listen::{ListenEnd, ListenSession};
+use music_player::audio::lyrics;
+use music_player::duplicates::{self, DuplicateGroup, DuplicateOptions};
+use music_player::equalizer::{self, EqualizerPreset, BANDS};
+use music_player::history::{History, HistoryEntry, PlaySource};
+use music_player::lrc::Lyrics;
+use music_player::musicbrainz::{self, Candidate};
+use music_player::organize::{self, Move, Plan, Template};
+use music_player::parametric::{self, Filter, FilterKind, ParametricProfile};
+use music_player::practice::{self, LoopBookmark, PracticeSettings};
+use music_player::stats::{self, Statistics, StatsRange};
 use music_player::audio::mpris::{self, MprisCommand, MprisEvent};
+use music_player::audio::rating::{self, MAX_STARS};
+use music_player::audio::tags::{self, FieldValue, TagChanges, TagField};
 use music_player::audio::queue::{scan_music_dir, Queue};
-use music_player::audio::metadata::parse_files_metadata;
+use music_player::audio::metadata::{file_size, modified_time, parse_files_metadata, unix_time};
+use music_player::playlist::smart::{Field, Limit, Rule, SmartPlaylist, SortOrder};
+use music_player::playlist::store::{PlaylistStore, TrackRef};
+use music_player::playlist::{self, PlaylistEntry};
+use music_player::scrobble::{self, Scrobble, ScrobbleCommand};
 use directories::ProjectDirs;
 use serde_json;
 use std::fs;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc;
 
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 const APP_ICON: &[u8] = include_bytes!("../resources/icons/hicolor/scalable/apps/icon.svg");
 
+/// Largest width or height of cover thumbnails, in pixels.
+const ARTWORK_SIZE: u32 = 512;
+
+/// How many tracks' cover art is kept in memory. A thumbnail takes up to a
+/// megabyte, so only the last few tracks played keep theirs.
+const ARTWORK_CACHE_SIZE: usize = 16;
+
+/// How long the library metadata is left unchanged before it is saved, so a
+/// run of plays and edits is written once.
+const LIBRARY_META_SAVE_DELAY: Duration = Duration::from_secs(2);
+
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
 pub struct AppModel {
@@ -42,10 +77,84 @@ pub struct AppModel {
     audio: Option<MediaPlayer>,
     /// Playback queue
     queue: Queue,
+    /// Entries of the last loaded playlist file that no track was found for
+    unresolved_entries: Vec<String>,
     /// Library tracks scanned from user's Music directory
     library_tracks: Vec<PathBuf>,
     /// Parsed metadata cache for library items
     library_meta: HashMap<PathBuf, TrackMetadata>,
+    /// Metadata of tracks the last scan didn't find, kept with their play
+    /// statistics in case they come back, e.g. once a drive is mounted again
+    missing_meta: HashMap<PathBuf, TrackMetadata>,
+    /// When the library metadata was first changed since it was last saved
+    library_meta_changed_at: Option<Instant>,
+    /// Whether the library metadata is being written out
+    library_meta_saving: bool,
+    /// Whether library tracks are being fingerprinted in the background
+    fingerprinting: bool,
+    /// CUE sheet tracks carved out of single-file rips, keyed by library path
+    virtual_tracks: HashMap<PathBuf, CueTrack>,
+    /// GStreamer codec availability, inspected at startup
+    diagnostics: Option<Diagnostics>,
+    /// Library files the installed plugins can't decode, as of the last scan
+    /// or inspection
+    unsupported_tracks: Vec<PathBuf>,
+    /// User-created named playlists
+    playlists: PlaylistStore,
+    /// Playlist shown on the playlists page
+    selected_playlist: Option<u32>,
+    /// Playlist whose name is being edited, with the pending name
+    renaming_playlist: Option<(u32, String)>,
+    /// Smart playlist tracks as of the last evaluation, keyed by playlist id
+    smart_results: HashMap<u32, Vec<PathBuf>>,
+    /// Smart playlist whose rules are being edited
+    smart_editor: Option<SmartEditor>,
+    /// Dropdown labels for the smart playlist editor
+    smart_labels: pages::SmartLabels,
+    /// Library tracks selected for editing together
+    library_selection: HashSet<PathBuf>,
+    /// Tracks whose tags are being edited
+    tag_editor: Option<TagEditor>,
+    /// Cover art of the last tracks that were playing, loaded on demand
+    artwork: ArtworkCache,
+    /// Lyrics of tracks that were playing, loaded on demand
+    lyrics: HashMap<PathBuf, Option<Lyrics>>,
+    /// The lyrics being written or timed for the playing track
+    lyrics_editor: Option<LyricsEditor>,
+    /// The library organizer, while it is open
+    organizer: Option<Organizer>,
+    /// The selected tracks being looked up on MusicBrainz as an album
+    album_lookup: Option<AlbumLookup>,
+    /// Checks and results of the duplicates finder
+    duplicates: DuplicateFinder,
+    /// Dropdown labels for `pages::DUPLICATE_TOLERANCES`
+    duplicate_tolerance_labels: Vec<String>,
+    /// The equalizer page's state and the preset bound to what is playing
+    equalizer: EqualizerPanel,
+    /// Pitch shift in semitones, kept across tracks
+    pitch_shift: i32,
+    /// The A–B loop set on the playing track
+    practice: PracticeLoop,
+    /// The track currently being listened to, for play statistics
+    listening: Option<ListenSession>,
+    /// Log of past listens, if the data directory is available
+    history: Option<History>,
+    /// Where the tracks in the queue were started from
+    play_source: PlaySource,
+    /// Dropdown labels for the history pruning ages
+    history_prune_labels: Vec<String>,
+    /// Selected index into `pages::PRUNE_AGES`
+    history_prune_choice: usize,
+    /// Period covered by the statistics page
+    stats_range: StatsRange,
+    /// Statistics as last computed, if any
+    statistics: Option<Statistics>,
+    /// Dropdown labels for `StatsRange::ALL`
+    stats_range_labels: Vec<String>,
+    /// Column the library is sorted by
+    library_sort: LibrarySort,
+    /// Whether the library is sorted in descending order
+    library_sort_descending: bool,
     /// Cached label for the footer's now playing text
     now_playing_label: String,
     /// Current playback position in milliseconds
@@ -60,6 +169,14 @@ pub struct AppModel {
     mpris_tx: Option<mpsc::Sender<MprisCommand>>,
     /// MPRIS event channel (from MPRIS task)
     mpris_rx: Option<mpsc::Receiver<MprisEvent>>,
+    /// The default output device each time it changes, for presets bound to it
+    output_device_rx: Option<crossbeam_channel::Receiver<Option<String>>>,
+    /// Command channel to the scrobbling thread
+    scrobble_tx: Option<crossbeam_channel::Sender<ScrobbleCommand>>,
+    /// Track last announced to the scrobbler as playing now
+    scrobble_announced: Option<PathBuf>,
+    /// Listen of the announced track, until it has been heard long enough to submit
+    scrobble_pending: Option<Scrobble>,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -75,18 +192,278 @@ pub enum Message {
     Pause,
     Stop,
     LoadPath(String),
-    /// Library scan completed
-    LibraryScanned(Vec<PathBuf>),
+    /// Library scan completed, with CUE sheet tracks keyed by their library path
+    LibraryScanned(Vec<PathBuf>, HashMap<PathBuf, CueTrack>),
     /// Library metadata parsed for a batch of files
     LibraryMetadataParsed(Vec<(PathBuf, TrackMetadata)>),
+    /// GStreamer registry inspection completed
+    DiagnosticsReady(Option<Diagnostics>),
     /// Add a path to the playback queue without starting playback
     Enqueue(String),
     Next,
     Prev,
+    /// A track's cover thumbnail and folder cover were looked up
+    ArtworkLoaded(PathBuf, Option<Thumbnail>, Option<PathBuf>),
+    /// Ask the user for an image to use as the playing album's cover
+    SetCoverFromFile,
+    /// Embed an image in every track of the playing album
+    SetCover(PathBuf),
+    /// Ask the user where to save the playing track's embedded cover
+    ExtractCover,
+    ExtractCoverSelected(PathBuf),
+    /// Strip all pictures from the tracks of the playing album
+    RemoveArtwork,
+    /// Artwork was changed in these files, with the errors of those that failed
+    ArtworkWritten(Vec<PathBuf>, Vec<String>),
+    /// Open the library organizer
+    OrganizeOpen,
+    OrganizeTemplate(String),
+    /// Work out the moves for the template without making them
+    OrganizePreview,
+    OrganizePlanned(Plan),
+    /// Make the previewed moves
+    OrganizeRun,
+    /// Files were moved, with the errors of those that couldn't be
+    OrganizeDone(Vec<Move>, Vec<String>),
+    OrganizeCancel,
+    /// Turn finding identical files on or off
+    DuplicatesByHash(bool),
+    /// Turn finding tracks with the same artist and title on or off
+    DuplicatesByTags(bool),
+    /// Turn finding tracks that sound the same on or off
+    DuplicatesByFingerprint(bool),
+    /// Select how much lengths may differ, by index into `pages::DUPLICATE_TOLERANCES`
+    DuplicatesTolerance(usize),
+    /// Look for duplicates in the library with the selected checks
+    DuplicatesFind,
+    /// Duplicates were found, with the fingerprints computed to compare tracks
+    /// and the errors of files that couldn't be read
+    DuplicatesFound(Vec<DuplicateGroup>, Vec<(PathBuf, String)>, Vec<String>),
+    /// Choose which track of a group to keep
+    DuplicatesKeep(usize, usize),
+    /// Move every track of a group but the one kept to the trash
+    DuplicatesTrashGroup(usize),
+    /// Move every track but the one kept in each group to the trash
+    DuplicatesTrashAll,
+    /// Files were moved to the trash, each with the track kept in its place,
+    /// and the errors of those that couldn't be
+    DuplicatesTrashed(Vec<(PathBuf, PathBuf)>, Vec<String>),
+    /// Tracks gone from the library were found again by how they sound, as new
+    /// paths of the old ones. Carries what the library knew about the old
+    /// tracks, and the fingerprints computed for new ones.
+    ReencodesFound(HashMap<PathBuf, PathBuf>, Vec<(PathBuf, TrackMetadata)>, Vec<(PathBuf, String)>),
+    /// The library metadata was written out, or failed to be
+    LibraryMetaSaved,
+    /// Fingerprints of library tracks computed in the background after a scan
+    FingerprintsComputed(Vec<(PathBuf, String)>),
+    /// Logging in to Last.fm gave a session key to save in place of the password
+    LastfmSession(Result<String, String>),
+    /// Ask the user for a playlist file to load into the queue
+    OpenPlaylist,
+    /// Replace the queue with the tracks of the selected playlist file
+    PlaylistSelected(PathBuf),
+    /// Ask the user where to save the current queue as a playlist
+    SaveQueue,
+    /// Write the current queue to the selected playlist file
+    SaveQueueSelected(PathBuf),
+    /// Hide the list of playlist entries that weren't found
+    DismissUnresolved,
+    /// A file dialog was dismissed or failed
+    DialogClosed,
+    /// Create an empty named playlist
+    PlaylistCreate,
+    /// Show a playlist on the playlists page
+    PlaylistSelect(u32),
+    /// Start editing a playlist's name
+    PlaylistRename(u32),
+    /// The pending playlist name was edited
+    PlaylistNameInput(String),
+    /// Apply the pending playlist name
+    PlaylistRenameSubmit,
+    PlaylistDuplicate(u32),
+    PlaylistDelete(u32),
+    /// Move a playlist entry from one index to another
+    PlaylistMoveTrack(u32, usize, usize),
+    PlaylistRemoveTrack(u32, usize),
+    /// Replace the queue with a playlist and start playing it
+    PlaylistPlay(u32),
+    /// Append a playlist to the queue
+    PlaylistAppend(u32),
+    /// Add a library or queue track to an existing playlist
+    AddToPlaylist(TrackSource, u32),
+    /// Add a library or queue track to a newly created playlist
+    AddToNewPlaylist(TrackSource),
+    /// Create a smart playlist and open its rule editor
+    SmartPlaylistCreate,
+    /// Open the rule editor for a smart playlist
+    SmartPlaylistEdit(u32),
+    /// The smart playlist being edited was changed
+    SmartPlaylistEdited(SmartEdit),
+    /// Apply the edited rules and re-evaluate the playlist
+    SmartPlaylistSave,
+    SmartPlaylistCancel,
+    /// Pick a new random order for a shuffled smart playlist
+    SmartPlaylistReshuffle(u32),
+    /// Sort the library by a column, toggling the direction if it already is
+    LibrarySort(LibrarySort),
+    /// Rate a library track with 1 to 5 stars; 0 removes the rating
+    RateTrack(PathBuf, u8),
+    /// Mark or unmark a library track as a favorite
+    ToggleFavorite(PathBuf),
+    /// Tags were written to a file, or failed to be
+    TagsWritten(PathBuf, Result<(), String>),
+    /// Select or deselect a library track for editing together
+    LibrarySelect(PathBuf, bool),
+    LibrarySelectNone,
+    /// Edit the tags of a library or queue track, or of the selection it belongs to
+    EditTags(TrackSource),
+    /// Edit the tags of the selected library tracks
+    EditSelectedTags,
+    /// A field was changed in the tag editor
+    TagEditorInput(TagField, String),
+    /// Drop the change to a field, keeping what the tracks hold
+    TagEditorRevert(TagField),
+    /// Look the track being edited up on AcoustID by its fingerprint
+    TagEditorIdentify,
+    /// The best AcoustID match for the track being edited, with its
+    /// fingerprint if it had to be computed
+    TagEditorIdentified(Result<Option<acoustid::Match>, String>, Option<(PathBuf, String)>),
+    /// Write the changed fields to every track being edited
+    TagEditorSave,
+    TagEditorCancel,
+    /// Tags were written, with the files' metadata read back and the files
+    /// that failed, with why
+    TagEditorSaved(Vec<(PathBuf, TrackMetadata)>, Vec<(PathBuf, String)>),
+    /// Look the selected library tracks up on MusicBrainz as one album
+    LookUpAlbum,
+    /// Releases that could be the album, best fit first
+    AlbumCandidates(Result<Vec<Candidate>, String>),
+    /// Show the changes another release makes, by index into the candidates
+    AlbumCandidateSelect(usize),
+    /// Write the selected release's tags and IDs to the tracks
+    AlbumLookupApply,
+    /// Tags were written, with the files' metadata read back and the files
+    /// that failed, with why
+    AlbumTagsWritten(Vec<(PathBuf, TrackMetadata)>, Vec<(PathBuf, String)>),
+    AlbumLookupCancel,
+    /// Ask the user where to export the listening history
+    HistoryExport,
+    /// Write the history as CSV or JSON, depending on the file's extension
+    HistoryExportSelected(PathBuf),
+    /// Select how old history entries have to be to get pruned
+    HistoryPruneChoice(usize),
+    /// Remove history entries older than the selected age
+    HistoryPrune,
+    /// Show statistics for another period, by index into `StatsRange::ALL`
+    StatsRange(usize),
+    /// Statistics were computed in the background
+    StatsReady(Box<Statistics>),
     /// Periodic UI tick to update position/duration
     Tick,
     /// Seek to a fraction of the current duration (0.0 - 1.0)
     SeekTo(f32),
+    /// Seek to a position in the current track
+    SeekToTime(Duration),
+    /// A track's lyrics were looked up
+    LyricsLoaded(PathBuf, Option<Lyrics>),
+    /// Write or time the lyrics of the playing track
+    LyricsEdit,
+    /// The lyrics text was edited
+    LyricsEditorText(widget::text_editor::Action),
+    /// Time the lines of the text as the track plays
+    LyricsEditorStartTiming,
+    /// Go back to editing the lyrics as text
+    LyricsEditorEditText,
+    /// Stamp the selected line with the playback position and select the next
+    LyricsEditorStamp,
+    /// Select the line before the selected one and clear its time
+    LyricsEditorUndo,
+    /// Select a line for the next stamp
+    LyricsEditorSelect(usize),
+    /// Move a line's time by some milliseconds
+    LyricsEditorNudge(usize, i64),
+    /// Save the timed lyrics next to the track, or else in its tags
+    LyricsEditorSave(LyricsTarget),
+    LyricsEditorCancel,
+    /// Lyrics were saved for a track, or failed to be
+    LyricsSaved(PathBuf, Result<(), String>),
+    /// Turn the equalizer on or off
+    EqualizerEnabled(bool),
+    /// Use a preset, by index into the equalizer's presets
+    EqualizerPreset(usize),
+    /// Set a band's gain, in tenths of a decibel
+    EqualizerBand(usize, i32),
+    /// Set the preamp, in tenths of a decibel
+    EqualizerPreamp(i32),
+    /// A slider was let go; save the settings it made
+    EqualizerSliderReleased,
+    /// The name to save the settings as a preset under was edited
+    EqualizerPresetName(String),
+    /// Save the settings as a preset, replacing the one of the same name
+    EqualizerSavePreset,
+    /// Delete the user preset in use
+    EqualizerDeletePreset,
+    /// The genre to bind the preset in use to was edited
+    EqualizerGenre(String),
+    /// Use the preset in use for tracks of the entered genre
+    EqualizerBindGenre,
+    /// Stop using a preset for tracks of a genre, by genre key
+    EqualizerUnbindGenre(String),
+    /// Use the preset in use on the output device
+    EqualizerBindDevice,
+    EqualizerUnbindDevice,
+    /// The default audio output device was looked up
+    /// Turn the parametric equalizer on or off
+    ParametricEnabled(bool),
+    /// Use a profile on every device without one of its own, by index into
+    /// the profiles
+    ParametricProfile(usize),
+    /// Ask the user for an AutoEQ or Equalizer APO file to import
+    ParametricImport,
+    ParametricImportSelected(PathBuf),
+    /// A profile was read from a file, or failed to be
+    ParametricImported(Result<parametric::Import, String>),
+    /// Make a new profile
+    ParametricNew,
+    /// Change the profile in use
+    ParametricEdit,
+    /// Delete the profile in use
+    ParametricDelete,
+    ParametricEditorName(String),
+    ParametricEditorPreamp(String),
+    ParametricFilterAdd,
+    ParametricFilterRemove(usize),
+    ParametricFilterEnabled(usize, bool),
+    /// Change a filter's kind, by index into `FilterKind::ALL`
+    ParametricFilterKind(usize, usize),
+    /// A filter's frequency, gain or Q was edited
+    ParametricFilterInput(usize, FilterInput, String),
+    /// Save the profile being edited, replacing the one of the same name
+    ParametricEditorSave,
+    ParametricEditorCancel,
+    /// Use the profile in use on the output device
+    ParametricBindDevice,
+    ParametricUnbindDevice,
+    /// Play at a speed, in percent
+    PlaybackRate(u32),
+    /// Shift the pitch by semitones
+    PitchShift(i32),
+    /// Play the current track, or its whole album, at the speed set from now on
+    RememberRate(RateScope),
+    /// Play the current track and its album at normal speed again
+    ForgetRate,
+    /// Set the start or the end of the loop to where playback is
+    LoopSetA,
+    LoopSetB,
+    LoopClear,
+    LoopName(String),
+    /// Save the loop as a bookmark of the track, replacing one of the same name
+    LoopSave,
+    /// Loop a bookmark of the playing track, by index
+    LoopLoad(usize),
+    LoopDelete(usize),
+    Practice(PracticeSettings),
+    PracticeSave,
 }
 
 /// Create a COSMIC application from the app model
@@ -130,6 +507,36 @@ impl cosmic::Application for AppModel {
             .data::<Page>(Page::Page2)
             .icon(icon::from_name("folder-music-symbolic"));
 
+        nav.insert()
+            .text(fl!("nav-playlists-label"))
+            .data::<Page>(Page::Page4)
+            .icon(icon::from_name("view-list-symbolic"));
+
+        nav.insert()
+            .text(fl!("nav-history-label"))
+            .data::<Page>(Page::Page5)
+            .icon(icon::from_name("document-open-recent-symbolic"));
+
+        nav.insert()
+            .text(fl!("nav-statistics-label"))
+            .data::<Page>(Page::Page6)
+            .icon(icon::from_name("utilities-system-monitor-symbolic"));
+
+        nav.insert()
+            .text(fl!("nav-duplicates-label"))
+            .data::<Page>(Page::Page7)
+            .icon(icon::from_name("edit-copy-symbolic"));
+
+        nav.insert()
+            .text(fl!("nav-equalizer-label"))
+            .data::<Page>(Page::Page8)
+            .icon(icon::from_name("multimedia-equalizer-symbolic"));
+
+        nav.insert()
+            .text(fl!("nav-diagnostics-label"))
+            .data::<Page>(Page::Page3)
+            .icon(icon::from_name("dialog-information-symbolic"));
+
         // Construct the app model with the runtime's core.
         let mut app = AppModel {
             core,
@@ -162,9 +569,45 @@ impl cosmic::Application for AppModel {
             },
             // Start with an empty queue
             queue: Queue::new(),
+            unresolved_entries: Vec::new(),
             // Library will be populated asynchronously
             library_tracks: Vec::new(),
             library_meta: HashMap::new(),
+            missing_meta: HashMap::new(),
+            library_meta_changed_at: None,
+            library_meta_saving: false,
+            fingerprinting: false,
+            virtual_tracks: HashMap::new(),
+            diagnostics: None,
+            unsupported_tracks: Vec::new(),
+            playlists: PlaylistStore::default(),
+            selected_playlist: None,
+            renaming_playlist: None,
+            smart_results: HashMap::new(),
+            smart_editor: None,
+            smart_labels: pages::SmartLabels::default(),
+            library_selection: HashSet::new(),
+            tag_editor: None,
+            artwork: ArtworkCache::default(),
+            lyrics: HashMap::new(),
+            lyrics_editor: None,
+            organizer: None,
+            album_lookup: None,
+            duplicates: DuplicateFinder::default(),
+            duplicate_tolerance_labels: pages::tolerance_labels(),
+            equalizer: EqualizerPanel::default(),
+            pitch_shift: 0,
+            practice: PracticeLoop::default(),
+            listening: None,
+            history: None,
+            play_source: PlaySource::Library,
+            history_prune_labels: pages::prune_labels(),
+            history_prune_choice: 0,
+            stats_range: StatsRange::default(),
+            statistics: None,
+            stats_range_labels: pages::stats_range_labels(),
+            library_sort: LibrarySort::Title,
+            library_sort_descending: false,
             now_playing_label: String::from("No track"),
             position_ms: 0,
             duration_ms: 0,
@@ -172,17 +615,26 @@ impl cosmic::Application for AppModel {
             mpris_needs_metadata_flush: false,
             mpris_tx: None,
             mpris_rx: None,
+            output_device_rx: None,
+            scrobble_tx: None,
+            scrobble_announced: None,
+            scrobble_pending: None,
         };
 
         // Initialize MPRIS manager
         let mpris = mpris::start(Self::APP_ID);
         app.mpris_tx = Some(mpris.cmd_tx);
         app.mpris_rx = Some(mpris.evt_rx);
+        app.output_device_rx = Some(backend::watch_output_device());
 
-        // Load cached library metadata if available
+        // Load library metadata if available. It used to be kept in the cache
+        // directory, before it held play statistics.
         if let Some(pd) = ProjectDirs::from("io.github", "bloomdevelop", "music-player") {
-            let cache_file = pd.cache_dir().join("library_meta.json");
-            if let Ok(bytes) = fs::read(&cache_file) {
+            let files = [
+                pd.data_dir().join("library_meta.json"),
+                pd.cache_dir().join("library_meta.json"),
+            ];
+            if let Some(bytes) = files.iter().find_map(|f| fs::read(f).ok()) {
                 if let Ok(map) = serde_json::from_slice::<HashMap<PathBuf, TrackMetadata>>(&bytes)
                 {
                     app.library_meta = map;
@@ -190,19 +642,79 @@ impl cosmic::Application for AppModel {
             }
         }
 
+        // Load named playlists
+        if let Some(file) = playlists_file() {
+            match PlaylistStore::load(&file) {
+                Ok(store) => app.playlists = store,
+                Err(err) => eprintln!("failed to load playlists: {err}"),
+            }
+        }
+        app.selected_playlist = app.first_playlist();
+
+        // Load the listening history
+        if let Some(file) = history_file() {
+            match History::load(file) {
+                Ok(history) => app.history = Some(history),
+                Err(err) => eprintln!("failed to load listening history: {err}"),
+            }
+        }
+        app.refresh_smart_playlists();
+
+        // Apply the saved equalizer settings
+        app.refresh_equalizer_presets();
+        app.apply_equalizer();
+
+        // Start the scrobbler, picking up listens that couldn't be submitted last time
+        if let Some(dir) = scrobble_queue_dir() {
+            app.scrobble_tx = Some(scrobble::start(dir, app.config.scrobblers()));
+        }
+
         // Create a startup command that sets the window title.
         let command = app.update_title();
 
         // Start scanning the user's Music directory in the background and
         // send a LibraryScanned message when complete.
-        let home = std::env::var("HOME").unwrap_or_else(|_| String::from("."));
-        let music_dir = std::path::PathBuf::from(format!("{}/Music", home));
+        let music_dir = music_dir();
+        let scan_options = app.config.scan_options();
         let scan_task = cosmic::task::future(async move {
-            let tracks = scan_music_dir(music_dir);
-            Message::LibraryScanned(tracks)
+            let tracks = scan_music_dir(music_dir, &scan_options);
+            let (tracks, virtual_tracks) = expand_cue_sheets(tracks);
+            Message::LibraryScanned(tracks, virtual_tracks)
         });
 
-        (app, Task::batch(vec![command, scan_task]))
+        // Inspect the GStreamer registry for available decoders and sinks.
+        let extensions = app.config.library_extensions.clone();
+        let diagnostics_task = cosmic::task::future(async move {
+            let diagnostics = match Diagnostics::inspect(&extensions) {
+                Ok(d) => Some(d),
+                Err(err) => {
+                    eprintln!("failed to inspect GStreamer registry: {err}");
+                    None
+                }
+            };
+            Message::DiagnosticsReady(diagnostics)
+        });
+
+        let login_task = app.lastfm_login();
+
+        (
+            app,
+            Task::batch(vec![command, scan_task, diagnostics_task, login_task]),
+        )
+    }
+
+    /// Display the tag editor, album lookup or library organizer over the
+    /// application while open.
+    fn dialog(&self) -> Option<Element<'_, Self::Message>> {
+        if let Some(editor) = &self.tag_editor {
+            return Some(pages::tag_editor_dialog(self, editor));
+        }
+        if let Some(lookup) = &self.album_lookup {
+            return Some(pages::album_lookup_dialog(self, lookup));
+        }
+        self.organizer
+            .as_ref()
+            .map(|organizer| pages::organizer_dialog(self, organizer))
     }
 
     /// Display a context drawer if the context page is requested.
@@ -227,13 +739,31 @@ impl cosmic::Application for AppModel {
 
     /// Elements to pack at the start of the header bar.
     fn header_start(&'_ self) -> Vec<Element<'_, Self::Message>> {
-        let menu_bar = menu::bar(vec![menu::Tree::with_children(
-            menu::root(fl!("view")).apply(Element::from),
-            menu::items(
-                &self.key_binds,
-                vec![menu::Item::Button(fl!("about"), None, MenuAction::About)],
+        let menu_bar = menu::bar(vec![
+            menu::Tree::with_children(
+                menu::root(fl!("file")).apply(Element::from),
+                menu::items(
+                    &self.key_binds,
+                    vec![
+                        menu::Item::Button(fl!("open-playlist"), None, MenuAction::OpenPlaylist),
+                        menu::Item::Button(fl!("save-queue"), None, MenuAction::SaveQueue),
+                        menu::Item::Divider,
+                        menu::Item::Button(
+                            fl!("organize-library"),
+                            None,
+                            MenuAction::OrganizeLibrary,
+                        ),
+                    ],
+                ),
+            ),
+            menu::Tree::with_children(
+                menu::root(fl!("view")).apply(Element::from),
+                menu::items(
+                    &self.key_binds,
+                    vec![menu::Item::Button(fl!("about"), None, MenuAction::About)],
+                ),
             ),
-        )]);
+        ]);
 
         vec![menu_bar.into()]
     }
@@ -256,7 +786,13 @@ impl cosmic::Application for AppModel {
         // Activate the page in the model.
         self.nav.activate(id);
 
-        self.update_title()
+        // Statistics are computed when the page is opened, so they are current
+        let refresh = match self.nav.data::<Page>(id) {
+            Some(Page::Page6) => self.compute_statistics(),
+            _ => Task::none(),
+        };
+
+        Task::batch(vec![self.update_title(), refresh])
     }
 
     /// Register subscriptions for this application.
@@ -267,7 +803,7 @@ impl cosmic::Application for AppModel {
     fn subscription(&self) -> Subscription<Self::Message> {
         struct MySubscription;
 
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             // Create a subscription which emits updates through a channel.
             Subscription::run_with_id(
                 std::any::TypeId::of::<MySubscription>(),
@@ -289,7 +825,24 @@ impl cosmic::Application for AppModel {
                 }),
             // Periodic tick to update seek bar (every 200ms)
             cosmic::iced::time::every(Duration::from_millis(200)).map(|_| Message::Tick),
-        ])
+        ];
+        // Space or Enter stamps the selected line while lyrics are being timed,
+        // Backspace takes the last stamp back
+        if self.lyrics_editor.as_ref().is_some_and(|e| e.timing) {
+            subscriptions.push(keyboard::on_key_press(|key, _modifiers| match key {
+                Key::Named(Named::Space | Named::Enter) => Some(Message::LyricsEditorStamp),
+                Key::Named(Named::Backspace) => Some(Message::LyricsEditorUndo),
+                _ => None,
+            }));
+        }
+        // [ and ] set the loop's start and end, \\ clears it
+        subscriptions.push(keyboard::on_key_press(|key, _modifiers| match key.as_ref() {
+            Key::Character("[") => Some(Message::LoopSetA),
+            Key::Character("]") => Some(Message::LoopSetB),
+            Key::Character("\\") => Some(Message::LoopClear),
+            _ => None,
+        }));
+        Subscription::batch(subscriptions)
     }
 
     /// Handles messages emitted by the application and its widgets.
@@ -318,7 +871,36 @@ impl cosmic::Application for AppModel {
             }
 
             Message::UpdateConfig(config) => {
+                let scrobblers_changed = config.scrobblers_differ(&self.config);
+                let bindings_changed = config.equalizer_bindings_differ(&self.config);
                 self.config = config;
+                if scrobblers_changed {
+                    if let Some(tx) = &self.scrobble_tx {
+                        let _ = tx.send(ScrobbleCommand::SetScrobblers(self.config.scrobblers()));
+                    }
+                }
+                if bindings_changed {
+                    self.refresh_equalizer_presets();
+                    self.rebind_equalizer();
+                } else {
+                    self.apply_equalizer();
+                }
+                if scrobblers_changed {
+                    return self.lastfm_login();
+                }
+            }
+
+            Message::LastfmSession(Ok(key)) => {
+                // Saving the key sets up scrobbling with it; the password
+                // isn't needed any more
+                self.save_config("the Last.fm session key", |config, context| {
+                    config.set_lastfm_session_key(context, key)?;
+                    config.set_lastfm_password(context, String::new())
+                });
+            }
+
+            Message::LastfmSession(Err(err)) => {
+                eprintln!("failed to log in to Last.fm: {err}");
             }
 
             Message::LaunchUrl(url) => match open::that_detached(&url) {
@@ -335,8 +917,8 @@ impl cosmic::Application for AppModel {
 
                 if let Some(player) = &self.audio {
                     // If there's a current queue track and nothing loaded, load it.
-                    if let Some(track) = self.queue.current() {
-                        if let Err(err) = player.load_path(track) {
+                    if self.queue.current().is_some() {
+                        if let Err(err) = self.load_current_track(player) {
                             eprintln!("failed to load track from queue: {err}");
                         }
                     }
@@ -358,7 +940,7 @@ impl cosmic::Application for AppModel {
                         if let Some(tx) = &self.mpris_tx {
                             // Read current metadata and duration
                             if let Some(ap) = &self.audio {
-                                let md = ap.metadata();
+                                let md = self.current_metadata(ap);
                                 println!("mpris: metadata: {md:?}");
                                 let have_any =
                                     md.title.is_some() || md.artist.is_some() || md.album.is_some();
@@ -369,6 +951,7 @@ impl cosmic::Application for AppModel {
                                         artist: md.artist,
                                         album: md.album,
                                         length: len,
+                                        rating: md.rating,
                                     });
                                     self.mpris_needs_metadata_flush = false;
                                 }
@@ -395,6 +978,7 @@ impl cosmic::Application for AppModel {
             }
 
             Message::Stop => {
+                self.finish_listen(ListenEnd::Stopped);
                 if let Some(player) = &self.audio {
                     if let Err(err) = player.stop() {
                         eprintln!("failed to stop: {err}");
@@ -411,13 +995,14 @@ impl cosmic::Application for AppModel {
             }
 
             Message::LoadPath(path) => {
+                self.play_source = PlaySource::Library;
                 if let Some(player) = &self.audio {
                     let p = Path::new(&path);
                     // Ensure queue knows about this selection so Next/Prev operate
                     self.queue.select_or_push(PathBuf::from(p));
                     // Stop current playback to ensure a clean transition
                     let _ = player.stop();
-                    if let Err(err) = player.load_path(p) {
+                    if let Err(err) = self.load_current_track(player) {
                         eprintln!("failed to load path {path}: {err}");
                     } else if let Err(err) = player.play() {
                         eprintln!("failed to start playback: {err}");
@@ -443,7 +1028,7 @@ impl cosmic::Application for AppModel {
                             if let Some(tx) = &self.mpris_tx {
                                 // Read current metadata and duration
                                 if let Some(ap) = &self.audio {
-                                    let md = ap.metadata();
+                                    let md = self.current_metadata(ap);
                                     println!("mpris: metadata: {md:?}");
                                     let have_any =
                                         md.title.is_some() || md.artist.is_some() || md.album.is_some();
@@ -454,6 +1039,7 @@ impl cosmic::Application for AppModel {
                                             artist: md.artist,
                                             album: md.album,
                                             length: len,
+                                            rating: md.rating,
                                         });
                                         self.mpris_needs_metadata_flush = false;
                                     }
@@ -464,29 +1050,128 @@ impl cosmic::Application for AppModel {
                 }
             }
 
-            Message::LibraryScanned(paths) => {
-                // Store paths
+            Message::LibraryScanned(paths, virtual_tracks) => {
+                // Store paths, setting aside the metadata of files that are gone
+                // rather than dropping it: they may only be out of reach for now
                 self.library_tracks = paths.clone();
+                self.refresh_unsupported_tracks();
+                let present: std::collections::HashSet<&PathBuf> = paths.iter().collect();
+                for path in &paths {
+                    if let Some(md) = self.missing_meta.remove(path) {
+                        self.library_meta.insert(path.clone(), md);
+                    }
+                }
+                let missing: Vec<PathBuf> = self
+                    .library_meta
+                    .keys()
+                    .filter(|p| !present.contains(p))
+                    .cloned()
+                    .collect();
+                for path in missing {
+                    if let Some(md) = self.library_meta.remove(&path) {
+                        self.missing_meta.insert(path, md);
+                    }
+                }
+                // Tracks that are gone but were listened to, rated or
+                // favorited, in case they come back re-encoded
+                let gone: Vec<(PathBuf, TrackMetadata)> = self
+                    .missing_meta
+                    .iter()
+                    .filter(|(_, md)| md.fingerprint.is_some())
+                    .filter(|(_, md)| {
+                        md.play_count > 0 || md.skip_count > 0 || md.rating.is_some() || md.favorite
+                    })
+                    .map(|(p, md)| (p.clone(), md.clone()))
+                    .collect();
+                let added: Vec<PathBuf> = paths
+                    .iter()
+                    .filter(|p| !virtual_tracks.contains_key(*p))
+                    .filter(|p| !self.library_meta.contains_key(*p))
+                    .cloned()
+                    .collect();
+                self.library_selection.retain(|p| present.contains(p));
+                // CUE tracks take their metadata from the sheet; parse only real files
+                for (path, track) in &virtual_tracks {
+                    let mut md = track.metadata();
+                    match self.library_meta.get(path) {
+                        Some(previous) => md.keep_library_fields(previous),
+                        None => md.date_added = modified_time(&track.file),
+                    }
+                    self.library_meta.insert(path.clone(), md);
+                }
+                let paths: Vec<PathBuf> = paths
+                    .into_iter()
+                    .filter(|p| !virtual_tracks.contains_key(p))
+                    .collect();
+                self.virtual_tracks = virtual_tracks;
+                self.refresh_smart_playlists();
                 // Spawn background metadata parsing
                 let parse_task = cosmic::task::future(async move {
                     let results = parse_files_metadata(&paths);
                     Message::LibraryMetadataParsed(results)
                 });
-                return Task::batch(vec![parse_task]);
+                if gone.is_empty() || added.is_empty() {
+                    return Task::batch(vec![parse_task]);
+                }
+                let relink_task = cosmic::task::future(async move {
+                    let mut added: Vec<(PathBuf, TrackMetadata)> = added
+                        .into_iter()
+                        .map(|p| (p, TrackMetadata::default()))
+                        .collect();
+                    let (added, fingerprints) = tokio::task::spawn_blocking(move || {
+                        let fingerprints = fingerprint_tracks(&mut added);
+                        (added, fingerprints)
+                    })
+                    .await
+                    .unwrap_or_default();
+                    let old: Vec<(PathBuf, &TrackMetadata)> =
+                        gone.iter().map(|(p, md)| (p.clone(), md)).collect();
+                    let new: Vec<(PathBuf, &TrackMetadata)> =
+                        added.iter().map(|(p, md)| (p.clone(), md)).collect();
+                    let moved = duplicates::find_reencodes(&old, &new);
+                    Message::ReencodesFound(moved, gone, fingerprints)
+                });
+                return Task::batch(vec![parse_task, relink_task]);
+            }
+
+            Message::LibraryMetaSaved => {
+                self.library_meta_saving = false;
             }
 
             Message::LibraryMetadataParsed(pairs) => {
-                for (p, md) in pairs {
+                for (p, mut md) in pairs {
+                    if let Some(previous) = self.library_meta.get(&p) {
+                        md.keep_library_fields(previous);
+                    }
                     self.library_meta.insert(p, md);
                 }
-                // Save cache to disk
-                if let Some(pd) = ProjectDirs::from("io.github", "bloomdevelop", "music-player") {
-                    let _ = fs::create_dir_all(pd.cache_dir());
-                    let cache_file = pd.cache_dir().join("library_meta.json");
-                    if let Ok(bytes) = serde_json::to_vec(&self.library_meta) {
-                        let _ = fs::write(cache_file, bytes);
+                self.save_library_meta();
+                // Follow playlist entries whose files were moved within the library
+                if self.playlists.relink(&self.library_meta) {
+                    self.save_playlists();
+                }
+                self.refresh_smart_playlists();
+                return self.fingerprint_library();
+            }
+
+            Message::FingerprintsComputed(fingerprints) => {
+                self.fingerprinting = false;
+                self.store_fingerprints(fingerprints);
+            }
+
+            Message::DiagnosticsReady(diagnostics) => {
+                if let Some(d) = &diagnostics {
+                    for format in d.formats.iter().filter(|f| !f.is_supported()) {
+                        eprintln!(
+                            "no GStreamer decoder for .{} ({}): missing {}",
+                            format.extension,
+                            format.description,
+                            format.missing.join(", ")
+                        );
                     }
                 }
+                self.diagnostics = diagnostics;
+                self.refresh_unsupported_tracks();
             }
 
             Message::Enqueue(path) => {
@@ -494,11 +1179,14 @@ impl cosmic::Application for AppModel {
             }
 
             Message::Next => {
+                if self.queue.peek_next().is_some() {
+                    self.finish_listen(ListenEnd::Skipped);
+                }
                 if let Some(next) = self.queue.next().cloned() {
                     if let Some(player) = &self.audio {
                         // Stop current playback before loading the next track
                         let _ = player.stop();
-                        if let Err(err) = player.load_path(&next) {
+                        if let Err(err) = self.load_current_track(player) {
                             eprintln!("failed to load next track: {err}");
                         } else if let Err(err) = player.play() {
                             eprintln!("failed to play next track: {err}");
@@ -527,7 +1215,7 @@ impl cosmic::Application for AppModel {
                     if let Some(player) = &self.audio {
                         // Stop current playback before loading the previous track
                         let _ = player.stop();
-                        if let Err(err) = player.load_path(&prev) {
+                        if let Err(err) = self.load_current_track(player) {
                             eprintln!("failed to load prev track: {err}");
                         } else if let Err(err) = player.play() {
                             eprintln!("failed to play prev track: {err}");
@@ -551,240 +1239,2953 @@ impl cosmic::Application for AppModel {
                 }
             }
 
-            Message::Tick => {
-                if let Some(player) = &self.audio {
-                    if let Some(dur) = player.duration() {
-                        self.duration_ms = dur.as_millis() as u64;
-                    }
-                    if let Some(pos) = player.position() {
-                        self.position_ms = pos.as_millis() as u64;
-                    }
-
-                    // Auto-advance on end-of-stream
-                    if player.take_eos() {
-                        if let Some(next) = self.queue.next().cloned() {
-                            if let Err(err) = player.load_path(&next) {
-                                eprintln!("failed to load next track at EOS: {err}");
-                            } else if let Err(err) = player.play() {
-                                eprintln!("failed to play next track at EOS: {err}");
-                            } else {
-                                self.is_playing = true;
-                                // Defer metadata send until tags parsed by GStreamer bus
-                                self.mpris_needs_metadata_flush = true;
-                                if let Some(tx) = &self.mpris_tx {
-                                    let _ = tx.try_send(MprisCommand::SetPlayback {
-                                        playing: true,
-                                        position: player.position(),
-                                    });
-                                }
-                            }
+            Message::OpenPlaylist => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new()
+                        .title(fl!("open-playlist"))
+                        .filter(playlist_filter());
+                    match dialog.open_file().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::PlaylistSelected(path),
+                            Err(()) => Message::DialogClosed,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::DialogClosed,
+                        Err(err) => {
+                            eprintln!("failed to open playlist dialog: {err}");
+                            Message::DialogClosed
                         }
                     }
+                });
+            }
 
-                    // Periodic MPRIS position update
-                    if let Some(tx) = &self.mpris_tx {
-                        let _ = tx.try_send(MprisCommand::SetPlayback {
-                            playing: self.is_playing,
-                            position: player.position(),
-                        });
-                    }
-
-                    // If a new track was loaded and we were waiting for tags, push metadata now
-                    if self.mpris_needs_metadata_flush {
-                        if let Some(tx) = &self.mpris_tx {
-                            // Read current metadata and duration
-                            let md = player.metadata();
-                            let have_any =
-                                md.title.is_some() || md.artist.is_some() || md.album.is_some();
-                            let len = player.duration();
-                            if have_any || len.is_some() {
-                                let _ = tx.try_send(MprisCommand::SetMetadata {
-                                    title: md.title.clone(),
-                                    artist: md.artist.clone(),
-                                    album: md.album.clone(),
-                                    length: len,
-                                });
-                                self.mpris_needs_metadata_flush = false;
-                            }
-                        }
+            Message::PlaylistSelected(path) => match playlist::load(&path, &self.library_meta) {
+                Ok(resolved) => {
+                    if let Some(player) = &self.audio {
+                        let _ = player.stop();
                     }
-
-                    // Update the footer label from current metadata (or filename fallback)
-                    let md = player.metadata();
-                    let new_label = match (md.title, md.artist) {
-                        (Some(title), Some(artist)) => format!("{title} — {artist}"),
-                        (Some(title), None) => title,
-                        (None, Some(artist)) => artist,
-                        (None, None) => self
-                            .queue
-                            .current()
-                            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
-                            .unwrap_or_else(|| String::from("No track")),
-                    };
-                    if new_label != self.now_playing_label {
-                        self.now_playing_label = new_label;
+                    self.is_playing = false;
+                    self.queue.replace(resolved.tracks);
+                    self.play_source = PlaySource::Playlist;
+                    // Show the queue, with what couldn't be found above it
+                    self.unresolved_entries =
+                        resolved.unresolved.into_iter().map(|e| e.location).collect();
+                    if !self.unresolved_entries.is_empty() {
+                        self.context_page = ContextPage::Queue;
+                        self.core.window.show_context = true;
                     }
                 }
-            }
+                Err(err) => {
+                    eprintln!("failed to load playlist {path:?}: {err}");
+                }
+            },
 
-            Message::SeekTo(frac) => {
-                if let Some(player) = &self.audio {
-                    if self.duration_ms > 0 {
-                        let frac = frac.clamp(0.0, 1.0);
-                        let target_ms = (self.duration_ms as f32 * frac) as u64;
-                        let _ = player.seek(Duration::from_millis(target_ms));
-                        // Reflect immediately in UI
-                        self.position_ms = target_ms;
-                        if let Some(tx) = &self.mpris_tx {
-                            let _ = tx.try_send(MprisCommand::SetPlayback {
-                                playing: self.is_playing,
-                                position: Some(Duration::from_millis(target_ms)),
-                            });
+            Message::SaveQueue => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::save::Dialog::new()
+                        .title(fl!("save-queue"))
+                        .file_name("queue.m3u8")
+                        .filter(playlist_filter());
+                    match dialog.save_file().await {
+                        Ok(response) => match response.url().map(|u| u.to_file_path()) {
+                            Some(Ok(path)) => Message::SaveQueueSelected(path),
+                            _ => Message::DialogClosed,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::DialogClosed,
+                        Err(err) => {
+                            eprintln!("failed to open save dialog: {err}");
+                            Message::DialogClosed
                         }
                     }
+                });
+            }
+
+            Message::SaveQueueSelected(path) => {
+                let entries = self.queue_playlist_entries();
+                if let Err(err) = playlist::save(&path, &entries) {
+                    eprintln!("failed to save queue to {path:?}: {err}");
                 }
             }
-        }
-        Task::none()
-    }
 
-    /// Describes the interface based on the current state of the application model.
-    ///
-    /// Application events will be processed through the view. Any messages emitted by
-    /// events received by widgets will be passed to the update method.
-    fn view(&self) -> Element<'_, Self::Message> {
-        // Main content
-        let content = pages::page_view(self);
+            Message::DismissUnresolved => {
+                self.unresolved_entries.clear();
+            }
 
-        // Footer with seek bar
-        let pos_ms = self.position_ms;
-        let dur_ms = self.duration_ms;
-        let (elapsed_str, total_str, frac) = if dur_ms > 0 {
-            (
-                format_time(pos_ms),
-                format_time(dur_ms),
-                (pos_ms as f32) / (dur_ms as f32),
-            )
-        } else {
-            (String::from("0:00"), String::from("0:00"), 0.0)
-        };
+            Message::DialogClosed => {}
 
-        let slider = widget::slider(0.0..=1.0, frac, Message::SeekTo)
-            .step(0.001)
-            .width(Length::Fill);
+            Message::PlaylistCreate => {
+                let number = self.playlists.playlists().len() + 1;
+                let name = fl!("playlist-default-name", number = number);
+                let id = self.playlists.create(name.clone());
+                self.selected_playlist = Some(id);
+                self.renaming_playlist = Some((id, name));
+                self.save_playlists();
+            }
 
-        // Play/Pause icon button per libcosmic
-        let play_pause_btn = if self.is_playing {
-            widget::button::icon(icon::from_name("media-playback-pause-symbolic"))
-                .tooltip(fl!("tooltip-pause-button"))
-                .on_press(Message::Pause)
-        } else {
-            widget::button::icon(icon::from_name("media-playback-start-symbolic"))
-                .tooltip(fl!("tooltip-play-button"))
-                .on_press(Message::Play)
-        };
+            Message::PlaylistSelect(id) => {
+                self.selected_playlist = Some(id);
+                self.renaming_playlist = None;
+                self.smart_editor = None;
+            }
 
-        let prev_btn = widget::button::icon(icon::from_name("media-skip-backward-symbolic"))
-            .tooltip(fl!("tooltip-prev-button"))
-            .on_press(Message::Prev);
+            Message::PlaylistRename(id) => {
+                if let Some(name) = self.playlists.name(id) {
+                    self.renaming_playlist = Some((id, name.to_string()));
+                }
+            }
 
-        let next_btn = widget::button::icon(icon::from_name("media-skip-forward-symbolic"))
-            .tooltip(fl!("tooltip-next-button"))
-            .on_press(Message::Next);
+            Message::PlaylistNameInput(name) => {
+                if let Some((_, pending)) = &mut self.renaming_playlist {
+                    *pending = name;
+                }
+            }
 
-        // Use cached now playing label (updated on Tick when tags arrive)
-        let song_label = self.now_playing_label.clone();
+            Message::PlaylistRenameSubmit => {
+                if let Some((id, name)) = self.renaming_playlist.take() {
+                    let name = name.trim();
+                    if !name.is_empty() && self.playlists.rename(id, name) {
+                        self.save_playlists();
+                    }
+                }
+            }
 
-        let footer_controls = widget::row()
-            .align_y(Vertical::Center)
-            .spacing(8)
-            .push(prev_btn)
-            .push(play_pause_btn)
-            .push(next_btn)
-            .push(widget::text(elapsed_str))
-            .push(slider)
-            .push(widget::text(total_str))
-            .width(Length::Fill);
+            Message::PlaylistDuplicate(id) => {
+                if let Some(name) = self.playlists.name(id) {
+                    let name = fl!("playlist-copy-name", name = name);
+                    self.selected_playlist = self.playlists.duplicate(id, name);
+                    self.save_playlists();
+                    self.refresh_smart_playlists();
+                }
+            }
 
-        let footer = widget::container(
-            widget::container(
-                widget::column()
-                    .spacing(6)
-                    .push(widget::text(song_label).width(Length::Fill))
-                    .push(footer_controls)
-                    .width(Length::Fill),
-            )
-            .padding([8, 12])
-            .class(cosmic::theme::Container::Card),
-        )
-        .padding([7, 2])
-        .width(Length::Fill);
+            Message::PlaylistDelete(id) => {
+                if self.playlists.delete(id) {
+                    if self.selected_playlist == Some(id) {
+                        self.selected_playlist = self.first_playlist();
+                    }
+                    self.smart_results.remove(&id);
+                    self.save_playlists();
+                }
+            }
 
-        widget::column()
-            .push(content)
-            .push(footer)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
-    }
-}
+            Message::PlaylistMoveTrack(id, from, to) => {
+                if self.playlists.move_track(id, from, to) {
+                    self.save_playlists();
+                }
+            }
 
-mod pages;
+            Message::PlaylistRemoveTrack(id, index) => {
+                if self.playlists.remove_track(id, index) {
+                    self.save_playlists();
+                }
+            }
 
-impl AppModel {
-    /// The about page for this app.
-    pub fn about(&self) -> Element<'static, Message> {
-        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+            Message::PlaylistPlay(id) => {
+                if let Some(tracks) = self.playlist_tracks(id) {
+                    self.play_source = PlaySource::Playlist;
+                    if let Some(player) = &self.audio {
+                        let _ = player.stop();
+                    }
+                    self.queue.replace(tracks);
+                    return self.update(Message::Play);
+                }
+            }
 
-        let icon = widget::svg(widget::svg::Handle::from_memory(APP_ICON));
+            Message::PlaylistAppend(id) => {
+                if let Some(tracks) = self.playlist_tracks(id) {
+                    self.queue.append(tracks);
+                }
+            }
 
-        let title = widget::text::title3(fl!("app-title"));
+            Message::AddToPlaylist(source, id) => {
+                if let Some(track) = self.track_ref(source) {
+                    if self.playlists.add_tracks(id, [track]) {
+                        self.save_playlists();
+                    }
+                }
+            }
+
+            Message::AddToNewPlaylist(source) => {
+                if let Some(track) = self.track_ref(source) {
+                    let number = self.playlists.playlists().len() + 1;
+                    let name = fl!("playlist-default-name", number = number);
+                    let id = self.playlists.create(name);
+                    self.playlists.add_tracks(id, [track]);
+                    self.selected_playlist = Some(id);
+                    self.save_playlists();
+                }
+            }
+
+            Message::SmartPlaylistCreate => {
+                let number = self.playlists.smart_playlists().len() + 1;
+                let name = fl!("smart-playlist-default-name", number = number);
+                let id = self.playlists.create_smart(name);
+                self.save_playlists();
+                self.selected_playlist = Some(id);
+                self.renaming_playlist = None;
+                return self.update(Message::SmartPlaylistEdit(id));
+            }
+
+            Message::SmartPlaylistEdit(id) => {
+                if let Some(playlist) = self.playlists.smart(id) {
+                    let mut draft = playlist.clone();
+                    if draft.rules.is_empty() {
+                        let field = Field::Genre;
+                        draft.rules.push(Rule::new(field, field.operators()[0], ""));
+                    }
+                    let limit_value = draft.limit.map(|l| l.value.to_string()).unwrap_or_default();
+                    self.smart_editor = Some(SmartEditor { draft, limit_value });
+                }
+            }
+
+            Message::SmartPlaylistEdited(edit) => {
+                if let Some(editor) = &mut self.smart_editor {
+                    editor.apply(edit);
+                }
+            }
+
+            Message::SmartPlaylistSave => {
+                if let Some(editor) = self.smart_editor.take() {
+                    if self.playlists.update_smart(editor.draft) {
+                        self.save_playlists();
+                        self.refresh_smart_playlists();
+                    }
+                }
+            }
+
+            Message::SmartPlaylistCancel => {
+                self.smart_editor = None;
+            }
+
+            Message::SmartPlaylistReshuffle(id) => {
+                if let Some(playlist) = self.playlists.smart_mut(id) {
+                    playlist.reshuffle();
+                    self.save_playlists();
+                    self.refresh_smart_playlists();
+                }
+            }
+
+            Message::HistoryExport => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::save::Dialog::new()
+                        .title(fl!("history-export"))
+                        .file_name("listening-history.csv")
+                        .filter(FileFilter::new("CSV").glob("*.csv"))
+                        .filter(FileFilter::new("JSON").glob("*.json"));
+                    match dialog.save_file().await {
+                        Ok(response) => match response.url().map(|u| u.to_file_path()) {
+                            Some(Ok(path)) => Message::HistoryExportSelected(path),
+                            _ => Message::DialogClosed,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::DialogClosed,
+                        Err(err) => {
+                            eprintln!("failed to open save dialog: {err}");
+                            Message::DialogClosed
+                        }
+                    }
+                });
+            }
+
+            Message::HistoryExportSelected(path) => {
+                if let Some(history) = &self.history {
+                    let is_json = path
+                        .extension()
+                        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+                    let result = if is_json {
+                        history.export_json(&path)
+                    } else {
+                        history.export_csv(&path)
+                    };
+                    if let Err(err) = result {
+                        eprintln!("failed to export history to {path:?}: {err}");
+                    }
+                }
+            }
+
+            Message::HistoryPruneChoice(choice) => {
+                self.history_prune_choice = choice;
+            }
+
+            Message::HistoryPrune => {
+                if let (Some(history), Some(days)) = (
+                    &mut self.history,
+                    pages::PRUNE_AGES.get(self.history_prune_choice),
+                ) {
+                    let cutoff = unix_time(SystemTime::now()).saturating_sub(days * 24 * 60 * 60);
+                    if let Err(err) = history.prune_before(cutoff) {
+                        eprintln!("failed to prune listening history: {err}");
+                    }
+                }
+            }
+
+            Message::StatsRange(index) => {
+                if let Some(range) = StatsRange::ALL.get(index) {
+                    self.stats_range = *range;
+                    return self.compute_statistics();
+                }
+            }
+
+            Message::StatsReady(statistics) => {
+                self.statistics = Some(*statistics);
+            }
+
+            Message::RateTrack(path, stars) => {
+                let rating = (stars > 0).then_some(stars.min(MAX_STARS));
+                let Some(md) = self.library_meta.get_mut(&path) else {
+                    return Task::none();
+                };
+                md.rating = rating;
+                self.library_meta_changed(&path);
+
+                // CUE tracks share their file, and the sheet holds their tags
+                if self.config.write_ratings_to_files && !self.virtual_tracks.contains_key(&path) {
+                    return cosmic::task::future(async move {
+                        let result = rating::write_rating(&path, rating).map_err(|e| e.to_string());
+                        Message::TagsWritten(path, result)
+                    });
+                }
+            }
+
+            Message::ToggleFavorite(path) => {
+                if let Some(md) = self.library_meta.get_mut(&path) {
+                    md.favorite = !md.favorite;
+                    self.library_meta_changed(&path);
+                }
+            }
+
+            Message::TagsWritten(path, result) => {
+                match result {
+                    Ok(()) => self.refresh_file_stats([&path]),
+                    Err(err) => eprintln!("failed to write tags to {:?}: {err}", path),
+                }
+            }
+
+            Message::LibrarySelect(path, selected) => {
+                if selected {
+                    self.library_selection.insert(path);
+                } else {
+                    self.library_selection.remove(&path);
+                }
+            }
+
+            Message::LibrarySelectNone => {
+                self.library_selection.clear();
+            }
+
+            Message::EditTags(source) => {
+                let path = match source {
+                    TrackSource::Library(index) => self.library_tracks.get(index),
+                    TrackSource::Queue(index) => self.queue.tracks().get(index),
+                };
+                if let Some(path) = path {
+                    let paths = if self.library_selection.contains(path) {
+                        self.library_selection.iter().cloned().collect()
+                    } else {
+                        vec![path.clone()]
+                    };
+                    self.open_tag_editor(paths);
+                }
+            }
+
+            Message::EditSelectedTags => {
+                let paths = self.library_selection.iter().cloned().collect();
+                self.open_tag_editor(paths);
+            }
+
+            Message::TagEditorInput(field, text) => {
+                if let Some(editor) = &mut self.tag_editor {
+                    editor.changes.insert(field, Some(text));
+                }
+            }
+
+            Message::TagEditorRevert(field) => {
+                if let Some(editor) = &mut self.tag_editor {
+                    editor.changes.remove(&field);
+                }
+            }
+
+            Message::TagEditorSave => {
+                let Some(editor) = &mut self.tag_editor else {
+                    return Task::none();
+                };
+                let changes = match tags::normalize(&editor.changes) {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        editor.error = Some(err.to_string());
+                        return Task::none();
+                    }
+                };
+                if changes.is_empty() {
+                    self.tag_editor = None;
+                    return Task::none();
+                }
+                editor.saving = true;
+                editor.error = None;
+                let paths = editor.paths.clone();
+                return cosmic::task::future(async move {
+                    // Each file is copied, rewritten and read back
+                    let editing = paths.clone();
+                    let saved = tokio::task::spawn_blocking(move || {
+                        let mut written = Vec::new();
+                        let mut failed = Vec::new();
+                        for path in editing {
+                            match tags::write_tags(&path, &changes) {
+                                Ok(()) => written.push(path),
+                                Err(err) => failed.push((path, err.to_string())),
+                            }
+                        }
+                        // Read the files back so the library shows what was stored
+                        (parse_files_metadata(&written), failed)
+                    })
+                    .await;
+                    match saved {
+                        Ok((parsed, failed)) => Message::TagEditorSaved(parsed, failed),
+                        Err(err) => {
+                            let err = format!("failed to save tags: {err}");
+                            let failed = paths.into_iter().map(|p| (p, err.clone())).collect();
+                            Message::TagEditorSaved(Vec::new(), failed)
+                        }
+                    }
+                });
+            }
+
+            Message::TagEditorIdentify => {
+                let (Some(client), Some(editor)) = (self.config.acoustid(), &mut self.tag_editor)
+                else {
+                    return Task::none();
+                };
+                let [path] = editor.paths.as_slice() else {
+                    return Task::none();
+                };
+                let path = path.clone();
+                let md = self.library_meta.get(&path).cloned().unwrap_or_default();
+                editor.identifying = true;
+                editor.error = None;
+                return cosmic::task::future(async move {
+                    // Decoding the audio and asking AcoustID both take a while
+                    let identified = tokio::task::spawn_blocking(move || {
+                        let mut computed = None;
+                        let fingerprint = match md.fingerprint {
+                            Some(fingerprint) => fingerprint,
+                            None => match fingerprint::fingerprint_file(&path) {
+                                Ok(raw) => {
+                                    let fingerprint = encode_fingerprint(&raw);
+                                    computed = Some((path, fingerprint.clone()));
+                                    fingerprint
+                                }
+                                Err(err) => return (Err(err.to_string()), None),
+                            },
+                        };
+                        let duration = md.duration.unwrap_or(FINGERPRINT_LENGTH);
+                        let result = client
+                            .lookup(&fingerprint, duration)
+                            .map(|matches| matches.into_iter().next())
+                            .map_err(|e| e.to_string());
+                        (result, computed)
+                    })
+                    .await;
+                    let (result, computed) =
+                        identified.unwrap_or_else(|err| (Err(err.to_string()), None));
+                    Message::TagEditorIdentified(result, computed)
+                });
+            }
+
+            Message::TagEditorIdentified(result, fingerprint) => {
+                self.store_fingerprints(fingerprint.into_iter().collect());
+                let Some(editor) = &mut self.tag_editor else {
+                    return Task::none();
+                };
+                editor.identifying = false;
+                match result {
+                    Ok(Some(found)) => {
+                        let proposed = [
+                            (TagField::Title, found.title.clone()),
+                            (TagField::Artist, found.artist()),
+                            (TagField::Album, found.album.clone()),
+                        ];
+                        for (field, value) in proposed {
+                            let Some(value) = value else { continue };
+                            // Only fields that would change show up as changes
+                            let unchanged = editor.values.iter().any(|(f, v)| {
+                                *f == field && *v == FieldValue::Same(Some(value.clone()))
+                            });
+                            if !unchanged {
+                                editor.changes.insert(field, Some(value));
+                            }
+                        }
+                    }
+                    Ok(None) => editor.error = Some(fl!("tag-editor-no-match")),
+                    Err(err) => {
+                        eprintln!("failed to identify track: {err}");
+                        editor.error = Some(err);
+                    }
+                }
+            }
+
+            Message::TagEditorCancel => {
+                self.tag_editor = None;
+            }
+
+            Message::TagEditorSaved(parsed, failed) => {
+                let playing = self
+                    .queue
+                    .current()
+                    .is_some_and(|current| parsed.iter().any(|(p, _)| p == current));
+                self.refresh_file_stats(parsed.iter().map(|(p, _)| p));
+                let task = self.update(Message::LibraryMetadataParsed(parsed));
+                if playing {
+                    self.mpris_needs_metadata_flush = true;
+                }
+
+                if failed.is_empty() {
+                    self.tag_editor = None;
+                } else if let Some(editor) = &mut self.tag_editor {
+                    // Leave the files that failed in the editor to retry
+                    for (_, err) in &failed {
+                        eprintln!("{err}");
+                    }
+                    editor.error = Some(fl!("tag-editor-failed", count = failed.len()));
+                    editor.paths = failed.into_iter().map(|(path, _)| path).collect();
+                    editor.saving = false;
+                }
+                return task;
+            }
+
+            Message::LookUpAlbum => {
+                let mut paths: Vec<PathBuf> = self
+                    .library_selection
+                    .iter()
+                    .filter(|p| !self.virtual_tracks.contains_key(*p))
+                    .cloned()
+                    .collect();
+                if paths.is_empty() {
+                    return Task::none();
+                }
+                // In album order, so the changes read like the track list
+                let default = TrackMetadata::default();
+                paths.sort_by_cached_key(|p| {
+                    let md = self.library_meta.get(p).unwrap_or(&default);
+                    (md.disc.unwrap_or(1), md.track.unwrap_or(u32::MAX), p.clone())
+                });
+                let tracks: Vec<TrackMetadata> = paths
+                    .iter()
+                    .map(|p| self.library_meta.get(p).cloned().unwrap_or_default())
+                    .collect();
+                let album = most_common(tracks.iter().filter_map(|md| md.album.clone()));
+                let artist = most_common(
+                    tracks
+                        .iter()
+                        .filter_map(|md| md.album_artist.clone().or_else(|| md.artist.clone())),
+                );
+                self.album_lookup = Some(AlbumLookup {
+                    paths,
+                    candidates: Vec::new(),
+                    labels: Vec::new(),
+                    selected: 0,
+                    error: album.is_none().then(|| fl!("album-lookup-no-album")),
+                    searching: album.is_some(),
+                    applying: false,
+                });
+                let Some(album) = album else {
+                    return Task::none();
+                };
+                let client = self.config.musicbrainz();
+                return cosmic::task::future(async move {
+                    // The client waits between requests to keep to the rate limit
+                    let result = tokio::task::spawn_blocking(move || {
+                        let tracks: Vec<&TrackMetadata> = tracks.iter().collect();
+                        client.find_releases(artist.as_deref(), &album, &tracks)
+                    })
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|result| result.map_err(|e| e.to_string()));
+                    Message::AlbumCandidates(result)
+                });
+            }
+
+            Message::AlbumCandidates(result) => {
+                let Some(lookup) = &mut self.album_lookup else {
+                    return Task::none();
+                };
+                lookup.searching = false;
+                match result {
+                    Ok(candidates) if candidates.is_empty() => {
+                        lookup.error = Some(fl!("album-lookup-no-match"));
+                    }
+                    Ok(candidates) => {
+                        lookup.labels = candidates.iter().map(pages::candidate_label).collect();
+                        lookup.candidates = candidates;
+                        lookup.selected = 0;
+                    }
+                    Err(err) => {
+                        eprintln!("failed to look up album: {err}");
+                        lookup.error = Some(err);
+                    }
+                }
+            }
+
+            Message::AlbumCandidateSelect(index) => {
+                if let Some(lookup) = &mut self.album_lookup {
+                    if index < lookup.candidates.len() {
+                        lookup.selected = index;
+                    }
+                }
+            }
+
+            Message::AlbumLookupApply => {
+                let Some(lookup) = &mut self.album_lookup else {
+                    return Task::none();
+                };
+                let changes: Vec<(PathBuf, TagChanges)> = lookup
+                    .changes(&self.library_meta)
+                    .into_iter()
+                    .filter_map(|(path, changes)| Some((path, changes?)))
+                    .filter(|(_, changes)| !changes.is_empty())
+                    .collect();
+                if changes.is_empty() {
+                    self.album_lookup = None;
+                    return Task::none();
+                }
+                lookup.applying = true;
+                lookup.error = None;
+                let paths: Vec<PathBuf> = changes.iter().map(|(p, _)| p.clone()).collect();
+                return cosmic::task::future(async move {
+                    let applied = tokio::task::spawn_blocking(move || {
+                        let mut written = Vec::new();
+                        let mut failed = Vec::new();
+                        for (path, changes) in changes {
+                            match tags::write_tags(&path, &changes) {
+                                Ok(()) => written.push(path),
+                                Err(err) => failed.push((path, err.to_string())),
+                            }
+                        }
+                        (parse_files_metadata(&written), failed)
+                    })
+                    .await;
+                    match applied {
+                        Ok((parsed, failed)) => Message::AlbumTagsWritten(parsed, failed),
+                        Err(err) => {
+                            let err = format!("failed to apply the album: {err}");
+                            let failed = paths.into_iter().map(|p| (p, err.clone())).collect();
+                            Message::AlbumTagsWritten(Vec::new(), failed)
+                        }
+                    }
+                });
+            }
+
+            Message::AlbumTagsWritten(parsed, failed) => {
+                let playing = self
+                    .queue
+                    .current()
+                    .is_some_and(|current| parsed.iter().any(|(p, _)| p == current));
+                self.refresh_file_stats(parsed.iter().map(|(p, _)| p));
+                let task = self.update(Message::LibraryMetadataParsed(parsed));
+                if playing {
+                    self.mpris_needs_metadata_flush = true;
+                }
+
+                if failed.is_empty() {
+                    self.album_lookup = None;
+                } else if let Some(lookup) = &mut self.album_lookup {
+                    // Leave the files that failed in the lookup to retry
+                    for (_, err) in &failed {
+                        eprintln!("{err}");
+                    }
+                    lookup.error = Some(fl!("album-lookup-failed", count = failed.len()));
+                    lookup.paths.retain(|p| failed.iter().any(|(f, _)| f == p));
+                    lookup.applying = false;
+                }
+                return task;
+            }
+
+            Message::AlbumLookupCancel => {
+                self.album_lookup = None;
+            }
+
+            Message::ArtworkLoaded(path, thumbnail, folder_cover) => {
+                // Dropped from the cache while loading; it is loaded again when shown
+                if !self.artwork.contains_key(&path) {
+                    return Task::none();
+                }
+                let thumbnail = thumbnail.map(|t| {
+                    widget::image::Handle::from_rgba(t.width, t.height, t.rgba)
+                });
+                self.artwork.insert(
+                    path,
+                    Artwork {
+                        loaded: true,
+                        thumbnail,
+                        folder_cover,
+                    },
+                );
+            }
+
+            Message::SetCoverFromFile => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new()
+                        .title(fl!("artwork-choose"))
+                        .filter(image_filter());
+                    match dialog.open_file().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::SetCover(path),
+                            Err(()) => Message::DialogClosed,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::DialogClosed,
+                        Err(err) => {
+                            eprintln!("failed to open image dialog: {err}");
+                            Message::DialogClosed
+                        }
+                    }
+                });
+            }
+
+            Message::SetCover(image) => {
+                let files = self.current_album_files();
+                let max_size = Some(self.config.artwork_max_size).filter(|s| *s > 0);
+                return cosmic::task::future(async move {
+                    // Decoding and recompressing the image, then rewriting every file
+                    let changed = tokio::task::spawn_blocking(move || {
+                        let cover = match artwork::load_image(&image, max_size) {
+                            Ok(cover) => cover,
+                            Err(err) => return (Vec::new(), vec![err.to_string()]),
+                        };
+                        change_files(files, |file| artwork::embed_cover(file, &cover))
+                    })
+                    .await;
+                    let (written, failed) =
+                        changed.unwrap_or_else(|err| (Vec::new(), vec![err.to_string()]));
+                    Message::ArtworkWritten(written, failed)
+                });
+            }
+
+            Message::ExtractCover => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::save::Dialog::new()
+                        .title(fl!("artwork-extract"))
+                        .file_name("cover");
+                    match dialog.save_file().await {
+                        Ok(response) => match response.url().map(|u| u.to_file_path()) {
+                            Some(Ok(path)) => Message::ExtractCoverSelected(path),
+                            _ => Message::DialogClosed,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::DialogClosed,
+                        Err(err) => {
+                            eprintln!("failed to open save dialog: {err}");
+                            Message::DialogClosed
+                        }
+                    }
+                });
+            }
+
+            Message::ExtractCoverSelected(dest) => {
+                let Some(file) = self.queue.current().map(|p| self.audio_file(p)) else {
+                    return Task::none();
+                };
+                return cosmic::task::future(async move {
+                    let extracted =
+                        tokio::task::spawn_blocking(move || artwork::extract_cover(&file, &dest))
+                            .await;
+                    match extracted {
+                        Ok(Err(err)) => eprintln!("{err}"),
+                        Err(err) => eprintln!("failed to extract cover: {err}"),
+                        Ok(Ok(_)) => {}
+                    }
+                    Message::DialogClosed
+                });
+            }
+
+            Message::RemoveArtwork => {
+                let files = self.current_album_files();
+                return cosmic::task::future(async move {
+                    let changed = tokio::task::spawn_blocking(move || {
+                        change_files(files, artwork::remove_artwork)
+                    })
+                    .await;
+                    let (written, failed) =
+                        changed.unwrap_or_else(|err| (Vec::new(), vec![err.to_string()]));
+                    Message::ArtworkWritten(written, failed)
+                });
+            }
+
+            Message::ArtworkWritten(written, failed) => {
+                for err in &failed {
+                    eprintln!("{err}");
+                }
+                self.refresh_file_stats(&written);
+                // Thumbnails of the changed files are loaded again when shown
+                let written: HashSet<PathBuf> = written.into_iter().collect();
+                let stale: Vec<PathBuf> = self
+                    .artwork
+                    .keys()
+                    .filter(|p| written.contains(&self.audio_file(p)))
+                    .cloned()
+                    .collect();
+                for path in stale {
+                    self.artwork.remove(&path);
+                }
+                return self.load_current_artwork();
+            }
+
+            Message::OrganizeOpen => {
+                self.organizer = Some(Organizer {
+                    template: self.config.organize_template.clone(),
+                    preview: None,
+                    error: None,
+                    running: false,
+                });
+                return self.update(Message::OrganizePreview);
+            }
+
+            Message::OrganizeTemplate(template) => {
+                if let Some(organizer) = &mut self.organizer {
+                    organizer.template = template;
+                    organizer.preview = None;
+                    organizer.error = None;
+                }
+            }
+
+            Message::OrganizePreview => {
+                let Some(organizer) = &mut self.organizer else {
+                    return Task::none();
+                };
+                let template = match Template::parse(&organizer.template) {
+                    Ok(template) => template,
+                    Err(err) => {
+                        organizer.error = Some(err.to_string());
+                        return Task::none();
+                    }
+                };
+                organizer.error = None;
+                let tracks = self.organize_tracks();
+                let library = self.library_tracks.clone();
+                return cosmic::task::future(async move {
+                    // Planning looks at every target and companion on disk
+                    let plan = tokio::task::spawn_blocking(move || {
+                        let tracks: Vec<(PathBuf, &TrackMetadata)> =
+                            tracks.iter().map(|(path, md)| (path.clone(), md)).collect();
+                        organize::plan(&tracks, &library, &music_dir(), &template)
+                    })
+                    .await;
+                    match plan {
+                        Ok(plan) => Message::OrganizePlanned(plan),
+                        Err(err) => {
+                            eprintln!("failed to plan organizing the library: {err}");
+                            Message::OrganizePlanned(Plan::default())
+                        }
+                    }
+                });
+            }
+
+            Message::OrganizePlanned(plan) => {
+                if let Some(organizer) = &mut self.organizer {
+                    organizer.preview = Some(plan);
+                }
+            }
+
+            Message::OrganizeRun => {
+                let Some(organizer) = &mut self.organizer else {
+                    return Task::none();
+                };
+                let Some(plan) = organizer.preview.clone() else {
+                    return Task::none();
+                };
+                organizer.running = true;
+                let template = organizer.template.clone();
+                if let Ok(context) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    if let Err(err) = self.config.set_organize_template(&context, template) {
+                        eprintln!("failed to save the organizer template: {err}");
+                    }
+                }
+                return cosmic::task::future(async move {
+                    let moved =
+                        tokio::task::spawn_blocking(move || organize::execute(&plan, &music_dir()))
+                            .await;
+                    let (done, failed) = moved.unwrap_or_else(|err| {
+                        (Vec::new(), vec![format!("failed to organize the library: {err}")])
+                    });
+                    Message::OrganizeDone(done, failed)
+                });
+            }
+
+            Message::OrganizeDone(done, failed) => {
+                for err in &failed {
+                    eprintln!("{err}");
+                }
+                self.follow_moved_files(&organize::moved_paths(&done));
+                match &mut self.organizer {
+                    Some(organizer) if !failed.is_empty() => {
+                        organizer.running = false;
+                        organizer.preview = None;
+                        organizer.error = Some(fl!("organize-failed", count = failed.len()));
+                    }
+                    _ => self.organizer = None,
+                }
+            }
+
+            Message::OrganizeCancel => {
+                self.organizer = None;
+            }
+
+            Message::DuplicatesByHash(on) => {
+                self.duplicates.options.by_hash = on;
+            }
+
+            Message::DuplicatesByTags(on) => {
+                self.duplicates.options.by_tags = on;
+            }
+
+            Message::DuplicatesByFingerprint(on) => {
+                self.duplicates.options.by_fingerprint = on;
+            }
+
+            Message::DuplicatesTolerance(index) => {
+                if let Some(secs) = pages::DUPLICATE_TOLERANCES.get(index) {
+                    self.duplicates.options.tolerance = Duration::from_secs(*secs);
+                }
+            }
+
+            Message::DuplicatesFind => {
+                self.duplicates.busy = true;
+                self.duplicates.error = None;
+                let options = self.duplicates.options;
+                // CUE tracks share their file, so they'd all match by hash
+                let mut tracks: Vec<(PathBuf, TrackMetadata)> = self
+                    .library_tracks
+                    .iter()
+                    .filter(|p| !self.virtual_tracks.contains_key(*p))
+                    .filter_map(|p| Some((p.clone(), self.library_meta.get(p)?.clone())))
+                    .collect();
+                return cosmic::task::future(async move {
+                    // Hashing and fingerprinting read every file
+                    let found = tokio::task::spawn_blocking(move || {
+                        let fingerprints = if options.by_fingerprint {
+                            fingerprint_tracks(&mut tracks)
+                        } else {
+                            Vec::new()
+                        };
+                        let tracks: Vec<(PathBuf, &TrackMetadata)> =
+                            tracks.iter().map(|(path, md)| (path.clone(), md)).collect();
+                        let (groups, failed) = duplicates::find_duplicates(&tracks, &options);
+                        (groups, fingerprints, failed)
+                    })
+                    .await;
+                    match found {
+                        Ok((groups, fingerprints, failed)) => {
+                            Message::DuplicatesFound(groups, fingerprints, failed)
+                        }
+                        Err(err) => Message::DuplicatesFound(
+                            Vec::new(),
+                            Vec::new(),
+                            vec![format!("failed to find duplicates: {err}")],
+                        ),
+                    }
+                });
+            }
+
+            Message::DuplicatesFound(groups, fingerprints, failed) => {
+                for err in &failed {
+                    eprintln!("{err}");
+                }
+                self.duplicates.error = (!failed.is_empty())
+                    .then(|| fl!("duplicates-unreadable", count = failed.len()));
+                self.store_fingerprints(fingerprints);
+                // Groups list the best copy first
+                self.duplicates.keep = vec![0; groups.len()];
+                self.duplicates.groups = Some(groups);
+                self.duplicates.busy = false;
+            }
+
+            Message::DuplicatesKeep(group, track) => {
+                // Groups may have changed since the choice was offered
+                let size = self.duplicates.groups.as_ref().and_then(|g| g.get(group));
+                if size.is_some_and(|found| track < found.tracks.len()) {
+                    if let Some(keep) = self.duplicates.keep.get_mut(group) {
+                        *keep = track;
+                    }
+                }
+            }
+
+            Message::DuplicatesTrashGroup(group) => {
+                return self.trash_duplicates(Some(group));
+            }
+
+            Message::DuplicatesTrashAll => {
+                return self.trash_duplicates(None);
+            }
+
+            Message::DuplicatesTrashed(trashed, failed) => {
+                for err in &failed {
+                    eprintln!("{err}");
+                }
+                self.duplicates.busy = false;
+                self.duplicates.error =
+                    (!failed.is_empty()).then(|| fl!("duplicates-failed", count = failed.len()));
+                self.remove_duplicates(&trashed);
+            }
+
+            Message::ReencodesFound(moved, gone, fingerprints) => {
+                let gone: HashMap<PathBuf, TrackMetadata> = gone.into_iter().collect();
+                for (from, to) in &moved {
+                    if let Some(old) = gone.get(from) {
+                        let md = self.library_meta.entry(to.clone()).or_default();
+                        duplicates::merge_plays(md, old);
+                        self.missing_meta.remove(from);
+                    }
+                }
+                self.store_fingerprints(fingerprints);
+                self.follow_moved_files(&moved);
+            }
+
+            Message::LibrarySort(sort) => {
+                if self.library_sort == sort {
+                    self.library_sort_descending = !self.library_sort_descending;
+                } else {
+                    self.library_sort = sort;
+                    // Statistics read best with the highest values first
+                    self.library_sort_descending = sort != LibrarySort::Title;
+                }
+            }
+
+            Message::Tick => {
+                // Requests from MPRIS clients
+                let mut mpris_tasks = Vec::new();
+                while let Some(event) = self.mpris_rx.as_mut().and_then(|rx| rx.try_recv().ok()) {
+                    let message = match event {
+                        MprisEvent::Play => Message::Play,
+                        MprisEvent::Pause => Message::Pause,
+                        MprisEvent::Next => Message::Next,
+                        MprisEvent::Previous => Message::Prev,
+                        MprisEvent::SeekTo(position) => Message::SeekToTime(position),
+                        MprisEvent::SeekBy(offset) => {
+                            let position = self.position_ms.saturating_add_signed(offset);
+                            Message::SeekToTime(Duration::from_millis(position))
+                        }
+                        MprisEvent::SetRate(rate) => {
+                            Message::PlaybackRate((rate * 100.0).round() as u32)
+                        }
+                    };
+                    mpris_tasks.push(self.update(message));
+                }
+
+                // A preset may be bound to the device that is now the default
+                let devices = self.output_device_rx.as_ref().map(|rx| rx.try_iter().last());
+                if let Some(Some(device)) = devices {
+                    if device != self.equalizer.device {
+                        self.equalizer.device = device;
+                        self.rebind_equalizer();
+                    }
+                }
+
+                self.track_listening();
+                let mut track_ended = false;
+                if let Some(player) = &self.audio {
+                    if let Some(dur) = player.duration() {
+                        self.duration_ms = dur.as_millis() as u64;
+                    }
+                    if let Some(pos) = player.position() {
+                        self.position_ms = pos.as_millis() as u64;
+                    }
+
+                    // Auto-advance on end-of-stream, or past the end of a CUE track
+                    // that plays straight into the next one
+                    let eos = player.take_eos();
+                    let segment_ended = !eos && player.segment_ended();
+                    if eos || segment_ended {
+                        track_ended = true;
+                        let current = self.queue.current().cloned();
+                        if let Some(next) = self.queue.next().cloned() {
+                            let contiguous = segment_ended
+                                && current
+                                    .and_then(|c| self.virtual_tracks.get(&c))
+                                    .zip(self.virtual_tracks.get(&next))
+                                    .is_some_and(|(c, n)| c.is_followed_by(n));
+                            if contiguous {
+                                // Gapless: the pipeline is already playing the next track
+                                let track = &self.virtual_tracks[&next];
+                                let play_through = self.plays_through();
+                                player.continue_segment(track.start, track.end, play_through);
+                                self.mpris_needs_metadata_flush = true;
+                            } else if let Err(err) = player
+                                .stop()
+                                .and_then(|()| self.load_current_track(player))
+                            {
+                                eprintln!("failed to load next track at EOS: {err}");
+                            } else if let Err(err) = player.play() {
+                                eprintln!("failed to play next track at EOS: {err}");
+                            } else {
+                                self.is_playing = true;
+                                // Defer metadata send until tags parsed by GStreamer bus
+                                self.mpris_needs_metadata_flush = true;
+                                if let Some(tx) = &self.mpris_tx {
+                                    let _ = tx.try_send(MprisCommand::SetPlayback {
+                                        playing: true,
+                                        position: player.position(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+
+                    // Periodic MPRIS position update
+                    if let Some(tx) = &self.mpris_tx {
+                        let _ = tx.try_send(MprisCommand::SetPlayback {
+                            playing: self.is_playing,
+                            position: player.position(),
+                        });
+                    }
+
+                    // If a new track was loaded and we were waiting for tags, push metadata now
+                    if self.mpris_needs_metadata_flush {
+                        if let Some(tx) = &self.mpris_tx {
+                            // Read current metadata and duration
+                            let md = self.current_metadata(player);
+                            let have_any =
+                                md.title.is_some() || md.artist.is_some() || md.album.is_some();
+                            let len = player.duration();
+                            if have_any || len.is_some() {
+                                let _ = tx.try_send(MprisCommand::SetMetadata {
+                                    title: md.title.clone(),
+                                    artist: md.artist.clone(),
+                                    album: md.album.clone(),
+                                    length: len,
+                                    rating: md.rating,
+                                });
+                                self.mpris_needs_metadata_flush = false;
+                            }
+                        }
+                    }
+
+                    // Update the footer label from current metadata (or filename fallback)
+                    let md = self.current_metadata(player);
+                    let new_label = match (md.title, md.artist) {
+                        (Some(title), Some(artist)) => format!("{title} — {artist}"),
+                        (Some(title), None) => title,
+                        (None, Some(artist)) => artist,
+                        (None, None) => self
+                            .queue
+                            .current()
+                            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                            .unwrap_or_else(|| String::from("No track")),
+                    };
+                    if new_label != self.now_playing_label {
+                        self.now_playing_label = new_label;
+                    }
+                }
+                if track_ended {
+                    self.finish_listen(ListenEnd::Completed);
+                }
+                self.advance_practice();
+                // Another track may call for another preset
+                let mut tasks = mpris_tasks;
+                tasks.extend([
+                    self.load_current_artwork(),
+                    self.load_current_lyrics(),
+                    self.flush_library_meta(),
+                ]);
+                if self.queue.current() != self.equalizer.track.as_ref() {
+                    self.equalizer.track = self.queue.current().cloned();
+                    self.rebind_equalizer();
+                }
+                return Task::batch(tasks);
+            }
+
+            Message::SeekTo(frac) => {
+                if self.duration_ms > 0 {
+                    let frac = frac.clamp(0.0, 1.0);
+                    let target_ms = (self.duration_ms as f32 * frac) as u64;
+                    self.seek_to(Duration::from_millis(target_ms));
+                }
+            }
+
+            Message::SeekToTime(position) => {
+                self.seek_to(position);
+            }
+
+            Message::LyricsLoaded(path, lyrics) => {
+                self.lyrics.insert(path, lyrics);
+            }
+
+            Message::LyricsEdit => {
+                let Some(path) = self.queue.current().cloned() else {
+                    return Task::none();
+                };
+                if self.virtual_tracks.contains_key(&path) {
+                    return Task::none();
+                }
+                let text = self
+                    .current_lyrics()
+                    .map(Lyrics::to_lrc)
+                    .unwrap_or_default();
+                self.lyrics_editor = Some(LyricsEditor {
+                    path,
+                    text: widget::text_editor::Content::with_text(&text),
+                    draft: Lyrics::default(),
+                    timing: false,
+                    selected: 0,
+                    saving: false,
+                    error: None,
+                });
+            }
+
+            Message::LyricsEditorText(action) => {
+                if let Some(editor) = &mut self.lyrics_editor {
+                    editor.text.perform(action);
+                }
+            }
+
+            Message::LyricsEditorStartTiming => {
+                if let Some(editor) = &mut self.lyrics_editor {
+                    let mut draft = Lyrics::parse_draft(&editor.text.text());
+                    // Blank lines between verses aren't timed unless they already are
+                    draft
+                        .lines
+                        .retain(|line| line.time.is_some() || !line.text.is_empty());
+                    // Carry on from the first line that has no time yet
+                    editor.selected = draft
+                        .lines
+                        .iter()
+                        .position(|line| line.time.is_none())
+                        .unwrap_or(0);
+                    editor.draft = draft;
+                    editor.timing = true;
+                    editor.error = None;
+                }
+            }
+
+            Message::LyricsEditorEditText => {
+                if let Some(editor) = &mut self.lyrics_editor {
+                    editor.text = widget::text_editor::Content::with_text(&editor.draft.to_lrc());
+                    editor.timing = false;
+                }
+            }
+
+            Message::LyricsEditorStamp => {
+                let position = self.playback_position();
+                let current = self.queue.current();
+                // Only the track being timed gives the lines their times
+                let editor = self
+                    .lyrics_editor
+                    .as_mut()
+                    .filter(|e| e.timing && current == Some(&e.path));
+                if let Some(editor) = editor {
+                    if let Some(line) = editor.draft.lines.get_mut(editor.selected) {
+                        line.set_time(position);
+                        editor.selected += 1;
+                    }
+                }
+            }
+
+            Message::LyricsEditorUndo => {
+                if let Some(editor) = self.lyrics_editor.as_mut().filter(|e| e.timing) {
+                    editor.selected = editor.selected.saturating_sub(1);
+                    if let Some(line) = editor.draft.lines.get_mut(editor.selected) {
+                        line.time = None;
+                    }
+                }
+            }
+
+            Message::LyricsEditorSelect(index) => {
+                if let Some(editor) = &mut self.lyrics_editor {
+                    editor.selected = index.min(editor.draft.lines.len());
+                }
+            }
+
+            Message::LyricsEditorNudge(index, delta) => {
+                if let Some(editor) = &mut self.lyrics_editor {
+                    if let Some(line) = editor.draft.lines.get_mut(index) {
+                        if let Some(time) = line.time {
+                            let ms = (time.as_millis() as i64 + delta).max(0) as u64;
+                            line.set_time(Duration::from_millis(ms));
+                        }
+                    }
+                }
+            }
+
+            Message::LyricsEditorSave(target) => {
+                let Some(editor) = &mut self.lyrics_editor else {
+                    return Task::none();
+                };
+                if editor.draft.lines.iter().any(|line| line.time.is_none()) {
+                    editor.error = Some(fl!("lyrics-editor-untimed"));
+                    return Task::none();
+                }
+                let mut timed = editor.draft.clone();
+                timed.lines.sort_by_key(|line| line.time);
+                editor.saving = true;
+                editor.error = None;
+                let path = editor.path.clone();
+                return cosmic::task::future(async move {
+                    let result = match target {
+                        LyricsTarget::Sidecar => lyrics::save_sidecar(&path, &timed),
+                        LyricsTarget::Embedded => lyrics::embed_lyrics(&path, &timed),
+                    };
+                    Message::LyricsSaved(path, result.map_err(|e| e.to_string()))
+                });
+            }
+
+            Message::LyricsEditorCancel => {
+                self.lyrics_editor = None;
+            }
+
+            Message::LyricsSaved(path, result) => {
+                match result {
+                    Ok(()) => {
+                        self.lyrics_editor = None;
+                        self.refresh_file_stats([&path]);
+                        // Looked up again on the next tick, from where they were saved
+                        self.lyrics.remove(&path);
+                    }
+                    Err(err) => {
+                        eprintln!("{err}");
+                        if let Some(editor) = &mut self.lyrics_editor {
+                            editor.saving = false;
+                            editor.error = Some(err);
+                        }
+                    }
+                }
+            }
+
+            Message::EqualizerEnabled(enabled) => {
+                self.save_config("the equalizer setting", |config, context| {
+                    config.set_equalizer_enabled(context, enabled)
+                });
+                self.apply_equalizer();
+            }
+
+            Message::EqualizerPreset(index) => {
+                let Some(preset) = self.equalizer.presets.get(index).cloned() else {
+                    return Task::none();
+                };
+                self.use_equalizer(preset);
+                self.save_equalizer();
+            }
+
+            Message::EqualizerBand(band, gain) => {
+                if band < BANDS {
+                    let mut settings = self.current_equalizer().clone();
+                    settings.gains[band] = gain;
+                    settings.name.clear();
+                    self.use_equalizer(settings.clamped());
+                }
+            }
+
+            Message::EqualizerPreamp(preamp) => {
+                let mut settings = self.current_equalizer().clone();
+                settings.preamp = preamp;
+                settings.name.clear();
+                self.use_equalizer(settings.clamped());
+            }
+
+            Message::EqualizerSliderReleased => {
+                self.save_equalizer();
+            }
+
+            Message::EqualizerPresetName(name) => {
+                self.equalizer.preset_name = name;
+            }
+
+            Message::EqualizerSavePreset => {
+                let name = self.equalizer.preset_name.trim().to_string();
+                if name.is_empty() {
+                    return Task::none();
+                }
+                let preset = EqualizerPreset {
+                    name,
+                    ..self.current_equalizer().clone()
+                };
+                let mut presets = self.config.equalizer_presets.clone();
+                match presets.iter_mut().find(|p| p.name == preset.name) {
+                    Some(existing) => *existing = preset.clone(),
+                    None => presets.push(preset.clone()),
+                }
+                self.save_config("equalizer presets", |config, context| {
+                    config.set_equalizer_presets(context, presets)
+                });
+                self.equalizer.preset_name.clear();
+                self.refresh_equalizer_presets();
+                self.use_equalizer(preset);
+                self.save_equalizer();
+            }
+
+            Message::EqualizerDeletePreset => {
+                let name = self.current_equalizer().name.clone();
+                let mut presets = self.config.equalizer_presets.clone();
+                presets.retain(|p| p.name != name);
+                let mut by_genre = self.config.equalizer_genre_presets.clone();
+                by_genre.retain(|_, preset| *preset != name);
+                let mut by_device = self.config.equalizer_device_presets.clone();
+                by_device.retain(|_, preset| *preset != name);
+                self.save_config("equalizer presets", |config, context| {
+                    config.set_equalizer_presets(context, presets)?;
+                    config.set_equalizer_genre_presets(context, by_genre)?;
+                    config.set_equalizer_device_presets(context, by_device)
+                });
+                // The settings stay as they are, as settings made by hand
+                let mut settings = self.current_equalizer().clone();
+                settings.name.clear();
+                self.config.equalizer = settings;
+                self.save_equalizer();
+                self.refresh_equalizer_presets();
+                self.rebind_equalizer();
+            }
+
+            Message::EqualizerGenre(genre) => {
+                self.equalizer.genre = genre;
+            }
+
+            Message::EqualizerBindGenre => {
+                let genre = equalizer::genre_key(&self.equalizer.genre);
+                let name = self.current_equalizer().name.clone();
+                if genre.is_empty() || name.is_empty() {
+                    return Task::none();
+                }
+                let mut by_genre = self.config.equalizer_genre_presets.clone();
+                by_genre.insert(genre, name);
+                self.save_config("equalizer bindings", |config, context| {
+                    config.set_equalizer_genre_presets(context, by_genre)
+                });
+                self.equalizer.genre.clear();
+                self.rebind_equalizer();
+            }
+
+            Message::EqualizerUnbindGenre(genre) => {
+                let mut by_genre = self.config.equalizer_genre_presets.clone();
+                by_genre.remove(&genre);
+                self.save_config("equalizer bindings", |config, context| {
+                    config.set_equalizer_genre_presets(context, by_genre)
+                });
+                self.rebind_equalizer();
+            }
+
+            Message::EqualizerBindDevice => {
+                let name = self.current_equalizer().name.clone();
+                let Some(device) = self.equalizer.device.clone() else {
+                    return Task::none();
+                };
+                if name.is_empty() {
+                    return Task::none();
+                }
+                let mut by_device = self.config.equalizer_device_presets.clone();
+                by_device.insert(device, name);
+                self.save_config("equalizer bindings", |config, context| {
+                    config.set_equalizer_device_presets(context, by_device)
+                });
+                self.rebind_equalizer();
+            }
+
+            Message::EqualizerUnbindDevice => {
+                let Some(device) = &self.equalizer.device else {
+                    return Task::none();
+                };
+                let mut by_device = self.config.equalizer_device_presets.clone();
+                by_device.remove(device);
+                self.save_config("equalizer bindings", |config, context| {
+                    config.set_equalizer_device_presets(context, by_device)
+                });
+                self.rebind_equalizer();
+            }
+
+            Message::ParametricEnabled(enabled) => {
+                self.save_config("the parametric equalizer setting", |config, context| {
+                    config.set_parametric_enabled(context, enabled)
+                });
+                self.apply_equalizer();
+            }
+
+            Message::ParametricProfile(index) => {
+                let Some(profile) = self.config.parametric_profiles.get(index) else {
+                    return Task::none();
+                };
+                let name = profile.name.clone();
+                self.save_config("the parametric profile", |config, context| {
+                    config.set_parametric_profile(context, name)
+                });
+                self.apply_equalizer();
+            }
+
+            Message::ParametricImport => {
+                return cosmic::task::future(async move {
+                    let dialog = file_chooser::open::Dialog::new()
+                        .title(fl!("parametric-import"))
+                        .filter(equalizer_filter());
+                    match dialog.open_file().await {
+                        Ok(response) => match response.url().to_file_path() {
+                            Ok(path) => Message::ParametricImportSelected(path),
+                            Err(()) => Message::DialogClosed,
+                        },
+                        Err(file_chooser::Error::Cancelled) => Message::DialogClosed,
+                        Err(err) => {
+                            eprintln!("failed to open equalizer dialog: {err}");
+                            Message::DialogClosed
+                        }
+                    }
+                });
+            }
+
+            Message::ParametricImportSelected(path) => {
+                return cosmic::task::future(async move {
+                    let result = fs::read(&path)
+                        .map_err(|e| format!("failed to read {:?}: {e}", path))
+                        .and_then(|bytes| {
+                            let text = String::from_utf8_lossy(&bytes);
+                            parametric::parse_apo(&parametric::profile_name(&path), &text)
+                                .map_err(|e| format!("{:?}: {e}", path))
+                        });
+                    Message::ParametricImported(result)
+                });
+            }
+
+            Message::ParametricImported(result) => match result {
+                Ok(import) => {
+                    for line in &import.skipped {
+                        eprintln!("equalizer import: skipped {line:?}");
+                    }
+                    self.equalizer.parametric_notice = (!import.skipped.is_empty())
+                        .then(|| fl!("parametric-skipped", count = import.skipped.len()));
+                    self.save_parametric_profile(import.profile, None);
+                }
+                Err(err) => {
+                    eprintln!("{err}");
+                    self.equalizer.parametric_notice = Some(err);
+                }
+            },
+
+            Message::ParametricNew => {
+                self.equalizer.parametric_notice = None;
+                self.equalizer.profile_editor =
+                    Some(ProfileEditor::new(None, ParametricProfile::default()));
+                self.apply_equalizer();
+            }
+
+            Message::ParametricEdit => {
+                let Some(profile) = self.current_parametric().cloned() else {
+                    return Task::none();
+                };
+                self.equalizer.parametric_notice = None;
+                self.equalizer.profile_editor =
+                    Some(ProfileEditor::new(Some(profile.name.clone()), profile));
+            }
+
+            Message::ParametricDelete => {
+                let Some(name) = self.current_parametric().map(|p| p.name.clone()) else {
+                    return Task::none();
+                };
+                let mut profiles = self.config.parametric_profiles.clone();
+                profiles.retain(|p| p.name != name);
+                let mut by_device = self.config.parametric_device_profiles.clone();
+                by_device.retain(|_, profile| *profile != name);
+                self.save_config("parametric profiles", |config, context| {
+                    config.set_parametric_profiles(context, profiles)?;
+                    config.set_parametric_device_profiles(context, by_device)
+                });
+                self.refresh_equalizer_presets();
+                self.apply_equalizer();
+            }
+
+            Message::ParametricEditorName(name) => {
+                if let Some(editor) = &mut self.equalizer.profile_editor {
+                    editor.profile.name = name;
+                }
+            }
+
+            Message::ParametricEditorPreamp(text) => {
+                if let Some(editor) = &mut self.equalizer.profile_editor {
+                    if let Ok(db) = text.trim().parse() {
+                        editor.profile.set_preamp(db);
+                    }
+                    editor.preamp = text;
+                }
+                self.apply_equalizer();
+            }
+
+            Message::ParametricFilterAdd => {
+                if let Some(editor) = &mut self.equalizer.profile_editor {
+                    let filter = Filter::default();
+                    editor.inputs.push(FilterInput::texts(&filter));
+                    editor.profile.filters.push(filter);
+                }
+                self.apply_equalizer();
+            }
+
+            Message::ParametricFilterRemove(index) => {
+                if let Some(editor) = &mut self.equalizer.profile_editor {
+                    if index < editor.profile.filters.len() {
+                        editor.profile.filters.remove(index);
+                        editor.inputs.remove(index);
+                    }
+                }
+                self.apply_equalizer();
+            }
+
+            Message::ParametricFilterEnabled(index, enabled) => {
+                if let Some(filter) = self.edited_filter(index) {
+                    filter.enabled = enabled;
+                }
+                self.apply_equalizer();
+            }
+
+            Message::ParametricFilterKind(index, kind) => {
+                let kind = FilterKind::ALL.get(kind).copied();
+                if let (Some(filter), Some(kind)) = (self.edited_filter(index), kind) {
+                    filter.kind = kind;
+                }
+                self.apply_equalizer();
+            }
+
+            Message::ParametricFilterInput(index, input, text) => {
+                let Some(editor) = &mut self.equalizer.profile_editor else {
+                    return Task::none();
+                };
+                let (Some(filter), Some(texts)) =
+                    (editor.profile.filters.get_mut(index), editor.inputs.get_mut(index))
+                else {
+                    return Task::none();
+                };
+                // Values apply as soon as they read as numbers
+                if let Ok(value) = text.trim().parse() {
+                    match input {
+                        FilterInput::Frequency => filter.set_frequency(value),
+                        FilterInput::Gain => filter.set_gain(value),
+                        FilterInput::Q => filter.set_q(value),
+                    }
+                }
+                texts[input as usize] = text;
+                self.apply_equalizer();
+            }
+
+            Message::ParametricEditorSave => {
+                let Some(mut editor) = self.equalizer.profile_editor.take() else {
+                    return Task::none();
+                };
+                editor.profile.name = editor.profile.name.trim().to_string();
+                if editor.profile.name.is_empty() {
+                    self.equalizer.profile_editor = Some(editor);
+                    return Task::none();
+                }
+                self.save_parametric_profile(editor.profile, editor.original);
+            }
+
+            Message::ParametricEditorCancel => {
+                self.equalizer.profile_editor = None;
+                self.apply_equalizer();
+            }
+
+            Message::ParametricBindDevice => {
+                let (Some(device), Some(profile)) =
+                    (self.equalizer.device.clone(), self.current_parametric())
+                else {
+                    return Task::none();
+                };
+                let mut by_device = self.config.parametric_device_profiles.clone();
+                by_device.insert(device, profile.name.clone());
+                self.save_config("parametric bindings", |config, context| {
+                    config.set_parametric_device_profiles(context, by_device)
+                });
+                self.apply_equalizer();
+            }
+
+            Message::PlaybackRate(percent) => {
+                if let Some(player) = &self.audio {
+                    self.set_rate(player, percent);
+                }
+            }
+
+            Message::PitchShift(semitones) => {
+                self.pitch_shift = semitones;
+                if let Some(player) = &self.audio {
+                    player.set_pitch(semitones);
+                }
+            }
+
+            Message::RememberRate(scope) => {
+                let (Some(path), Some(player)) = (self.queue.current().cloned(), &self.audio) else {
+                    return Task::none();
+                };
+                let percent = rate_percent(player.rate());
+                match scope {
+                    RateScope::Track => {
+                        if let Some(md) = self.library_meta.get_mut(&path) {
+                            md.playback_rate = Some(percent);
+                            self.library_meta_changed(&path);
+                        }
+                    }
+                    RateScope::Album => {
+                        let Some(key) = self.album_key(&path) else {
+                            return Task::none();
+                        };
+                        let mut rates = self.config.album_rates.clone();
+                        rates.insert(key, percent);
+                        self.save_config("album speeds", |config, context| {
+                            config.set_album_rates(context, rates)
+                        });
+                    }
+                }
+            }
+
+            Message::ForgetRate => {
+                let Some(path) = self.queue.current().cloned() else {
+                    return Task::none();
+                };
+                if let Some(md) = self.library_meta.get_mut(&path) {
+                    if md.playback_rate.take().is_some() {
+                        self.library_meta_changed(&path);
+                    }
+                }
+                if let Some(key) = self.album_key(&path) {
+                    let mut rates = self.config.album_rates.clone();
+                    if rates.remove(&key).is_some() {
+                        self.save_config("album speeds", |config, context| {
+                            config.set_album_rates(context, rates)
+                        });
+                    }
+                }
+                if let Some(player) = &self.audio {
+                    self.set_rate(player, 100);
+                }
+            }
+
+            Message::LoopSetA => {
+                self.set_loop_point(false);
+            }
+
+            Message::LoopSetB => {
+                self.set_loop_point(true);
+            }
+
+            Message::LoopClear => {
+                self.practice.a = None;
+                self.practice.b = None;
+                self.apply_loop();
+            }
+
+            Message::LoopName(name) => {
+                self.practice.name = name;
+            }
+
+            Message::LoopSave => {
+                let name = self.practice.name.trim().to_string();
+                let (Some(range), Some(path)) = (self.practice.range(), self.queue.current().cloned())
+                else {
+                    return Task::none();
+                };
+                if name.is_empty() {
+                    return Task::none();
+                }
+                if let Some(md) = self.library_meta.get_mut(&path) {
+                    practice::save_bookmark(&mut md.loops, LoopBookmark::new(&name, range));
+                    self.library_meta_changed(&path);
+                }
+                self.practice.name.clear();
+            }
+
+            Message::LoopLoad(index) => {
+                let Some(bookmark) = self.current_loops().get(index).cloned() else {
+                    return Task::none();
+                };
+                let (start, end) = bookmark.range();
+                self.practice.track = self.queue.current().cloned();
+                self.practice.a = Some(start);
+                self.practice.b = Some(end);
+                self.practice.name = bookmark.name;
+                self.apply_loop();
+            }
+
+            Message::LoopDelete(index) => {
+                let Some(path) = self.queue.current().cloned() else {
+                    return Task::none();
+                };
+                if let Some(md) = self.library_meta.get_mut(&path) {
+                    if index < md.loops.len() {
+                        md.loops.remove(index);
+                        self.library_meta_changed(&path);
+                    }
+                }
+            }
+
+            Message::Practice(settings) => {
+                let restart = settings.speeds_up() != self.config.practice.speeds_up()
+                    || settings.start_rate != self.config.practice.start_rate
+                    || (settings.gap > 0) != (self.config.practice.gap > 0);
+                self.config.practice = settings;
+                if restart {
+                    self.apply_loop();
+                }
+            }
+
+            Message::PracticeSave => {
+                let settings = self.config.practice;
+                self.save_config("the practice settings", |config, context| {
+                    config.set_practice(context, settings)
+                });
+            }
+
+            Message::ParametricUnbindDevice => {
+                let Some(device) = &self.equalizer.device else {
+                    return Task::none();
+                };
+                let mut by_device = self.config.parametric_device_profiles.clone();
+                by_device.remove(device);
+                self.save_config("parametric bindings", |config, context| {
+                    config.set_parametric_device_profiles(context, by_device)
+                });
+                self.apply_equalizer();
+            }
+        }
+        Task::none()
+    }
+
+    /// Describes the interface based on the current state of the application model.
+    ///
+    /// Application events will be processed through the view. Any messages emitted by
+    /// events received by widgets will be passed to the update method.
+    fn view(&self) -> Element<'_, Self::Message> {
+        // Main content
+        let content = pages::page_view(self);
+
+        // Footer with seek bar
+        let pos_ms = self.position_ms;
+        let dur_ms = self.duration_ms;
+        let (elapsed_str, total_str, frac) = if dur_ms > 0 {
+            (
+                format_time(pos_ms),
+                format_time(dur_ms),
+                (pos_ms as f32) / (dur_ms as f32),
+            )
+        } else {
+            (String::from("0:00"), String::from("0:00"), 0.0)
+        };
+
+        let slider = widget::slider(0.0..=1.0, frac, Message::SeekTo)
+            .step(0.001)
+            .width(Length::Fill);
+        // Loops are marked under the seek bar
+        let seek_bar: Element<'_, Message> = match pages::loop_markers(self) {
+            Some(markers) => widget::column()
+                .spacing(2)
+                .push(slider)
+                .push(markers)
+                .width(Length::Fill)
+                .into(),
+            None => slider.into(),
+        };
+
+        // Play/Pause icon button per libcosmic
+        let play_pause_btn = if self.is_playing {
+            widget::button::icon(icon::from_name("media-playback-pause-symbolic"))
+                .tooltip(fl!("tooltip-pause-button"))
+                .on_press(Message::Pause)
+        } else {
+            widget::button::icon(icon::from_name("media-playback-start-symbolic"))
+                .tooltip(fl!("tooltip-play-button"))
+                .on_press(Message::Play)
+        };
+
+        let prev_btn = widget::button::icon(icon::from_name("media-skip-backward-symbolic"))
+            .tooltip(fl!("tooltip-prev-button"))
+            .on_press(Message::Prev);
+
+        let next_btn = widget::button::icon(icon::from_name("media-skip-forward-symbolic"))
+            .tooltip(fl!("tooltip-next-button"))
+            .on_press(Message::Next);
+
+        // Use cached now playing label (updated on Tick when tags arrive)
+        let song_label = self.now_playing_label.clone();
+
+        let footer_controls = widget::row()
+            .align_y(Vertical::Center)
+            .spacing(8)
+            .push(prev_btn)
+            .push(play_pause_btn)
+            .push(next_btn)
+            .push(widget::text(elapsed_str))
+            .push(seek_bar)
+            .push(widget::text(total_str))
+            .width(Length::Fill);
+
+        let footer = widget::container(
+            widget::container(
+                widget::column()
+                    .spacing(6)
+                    .push(widget::text(song_label).width(Length::Fill))
+                    .push(footer_controls)
+                    .width(Length::Fill),
+            )
+            .padding([8, 12])
+            .class(cosmic::theme::Container::Card),
+        )
+        .padding([7, 2])
+        .width(Length::Fill);
+
+        widget::column()
+            .push(content)
+            .push(footer)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+mod pages;
+
+impl AppModel {
+    /// The about page for this app.
+    pub fn about(&self) -> Element<'static, Message> {
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let icon = widget::svg(widget::svg::Handle::from_memory(APP_ICON));
+
+        let title = widget::text::title3(fl!("app-title"));
 
         let hash = env!("VERGEN_GIT_SHA");
         let short_hash: String = hash.chars().take(7).collect();
         let date = env!("VERGEN_GIT_COMMIT_DATE");
 
-        let link = widget::button::link(REPOSITORY)
-            .on_press(Message::OpenRepositoryUrl)
-            .padding(0);
+        let link = widget::button::link(REPOSITORY)
+            .on_press(Message::OpenRepositoryUrl)
+            .padding(0);
+
+        widget::column()
+            .push(icon)
+            .push(title)
+            .push(link)
+            .push(
+                widget::button::link(fl!(
+                    "git-description",
+                    hash = short_hash.as_str(),
+                    date = date
+                ))
+                .on_press(Message::LaunchUrl(format!("{REPOSITORY}/commits/{hash}")))
+                .padding(0),
+            )
+            .align_x(Alignment::Center)
+            .spacing(space_xxs)
+            .into()
+    }
+
+    /// Updates the header and window titles.
+    pub fn update_title(&mut self) -> Task<cosmic::Action<Message>> {
+        let mut window_title = fl!("app-title");
+
+        if let Some(page) = self.nav.text(self.nav.active()) {
+            window_title.push_str(" — ");
+            window_title.push_str(page);
+        }
+
+        if let Some(id) = self.core.main_window_id() {
+            self.set_window_title(window_title, id)
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Read-only access to scanned library tracks.
+    pub fn library_tracks(&self) -> &[PathBuf] {
+        &self.library_tracks
+    }
+
+    /// Load the queue's current entry, cueing up CUE sheet tracks within their
+    /// file, to play at the speed remembered for it.
+    fn load_current_track(&self, player: &MediaPlayer) -> anyhow::Result<()> {
+        let Some(path) = self.queue.current() else {
+            return Ok(());
+        };
+        self.set_rate(player, self.remembered_rate(path).unwrap_or(100));
+        match self.virtual_tracks.get(path) {
+            Some(track) => {
+                player.load_segment(&track.file, track.start, track.end, self.plays_through())
+            }
+            None => player.load_path(path),
+        }
+    }
+
+    /// Play at `percent` of normal speed, and tell MPRIS clients.
+    fn set_rate(&self, player: &MediaPlayer, percent: u32) {
+        if let Err(err) = player.set_rate(f64::from(percent) / 100.0) {
+            eprintln!("failed to change the playback speed: {err}");
+        }
+        if let Some(tx) = &self.mpris_tx {
+            let _ = tx.try_send(MprisCommand::SetRate(player.rate()));
+        }
+    }
+
+    /// Key of the album a track is on in `Config::album_rates`.
+    pub fn album_key(&self, path: &Path) -> Option<String> {
+        let md = match self.virtual_tracks.get(path) {
+            Some(track) => track.metadata(),
+            None => self.library_meta.get(path)?.clone(),
+        };
+        let album = md.album.filter(|a| !a.trim().is_empty())?;
+        let artist = md.album_artist.or(md.artist).unwrap_or_default();
+        Some(format!("{}\u{1f}{}", artist.trim(), album.trim()).to_lowercase())
+    }
+
+    /// Loops saved with the playing track.
+    pub fn current_loops(&self) -> &[LoopBookmark] {
+        self.queue
+            .current()
+            .and_then(|p| self.library_meta.get(p))
+            .map(|md| md.loops.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Where playback of the current track is now. The position kept for
+    /// display is only updated on ticks, so it can be a tick behind.
+    fn playback_position(&self) -> Duration {
+        self.audio
+            .as_ref()
+            .and_then(|player| player.position())
+            .unwrap_or(Duration::from_millis(self.position_ms))
+    }
+
+    /// Set the loop's start, or its end, to where playback is, and loop once
+    /// both are set.
+    fn set_loop_point(&mut self, end: bool) {
+        if self.audio.is_none() || self.queue.current().is_none() {
+            return;
+        }
+        if self.practice.track.as_ref() != self.queue.current() {
+            self.practice = PracticeLoop {
+                track: self.queue.current().cloned(),
+                ..Default::default()
+            };
+        }
+        let position = Some(self.playback_position());
+        if end {
+            self.practice.b = position;
+        } else {
+            self.practice.a = position;
+        }
+        self.apply_loop();
+    }
+
+    /// Loop what is set on the playing track from its first time through, or
+    /// stop looping. Speeding up starts over from the practice settings.
+    fn apply_loop(&mut self) {
+        let Some(player) = &self.audio else {
+            return;
+        };
+        let settings = self.config.practice;
+        let range = self.practice.range();
+        self.practice.repetition = 0;
+        // Clearing the loop while waiting to go round again plays on
+        if self.practice.resume_at.take().is_some() && self.is_playing {
+            if let Err(err) = player.play() {
+                eprintln!("failed to play: {err}");
+            }
+        }
+        if let Err(err) = player.set_loop(range, settings.gap > 0) {
+            eprintln!("failed to loop: {err}");
+        }
+        if range.is_some() && settings.speeds_up() {
+            self.set_rate(player, settings.rate(0));
+            player.set_loop_rate(f64::from(settings.rate(1)) / 100.0);
+        }
+    }
+
+    /// Keep the loop going: speed it up as it repeats, pause before each time
+    /// through, and loop the track again after it was reloaded.
+    fn advance_practice(&mut self) {
+        if self.practice.track.as_ref() != self.queue.current() {
+            if self.practice.track.is_some() {
+                self.practice = PracticeLoop::default();
+            }
+            return;
+        }
+        if self.practice.range().is_none() {
+            return;
+        }
+        if self.audio.as_ref().is_some_and(|player| !player.is_looping()) {
+            self.apply_loop();
+        }
+        let Some(player) = &self.audio else {
+            return;
+        };
+        let settings = self.config.practice;
+        let repeats = player.take_loop_repeats();
+        if repeats > 0 {
+            self.practice.repetition += repeats;
+            if settings.speeds_up() {
+                let next = settings.rate(self.practice.repetition + 1);
+                player.set_loop_rate(f64::from(next) / 100.0);
+                if let Some(tx) = &self.mpris_tx {
+                    let _ = tx.try_send(MprisCommand::SetRate(player.rate()));
+                }
+            }
+            if settings.gap > 0 {
+                let gap = Duration::from_secs(u64::from(settings.gap));
+                self.practice.resume_at = Some(Instant::now() + gap);
+            }
+        }
+
+        if self.practice.resume_at.is_some_and(|at| at <= Instant::now()) {
+            self.practice.resume_at = None;
+            if self.is_playing {
+                if let Err(err) = player.play() {
+                    eprintln!("failed to play: {err}");
+                }
+            }
+        }
+    }
+
+    /// Speed remembered for a track, or else for its album, in percent.
+    pub fn remembered_rate(&self, path: &Path) -> Option<u32> {
+        self.library_meta
+            .get(path)
+            .and_then(|md| md.playback_rate)
+            .or_else(|| {
+                let key = self.album_key(path)?;
+                self.config.album_rates.get(&key).copied()
+            })
+    }
+
+    /// Whether the current CUE track runs straight into the next queue entry, so the
+    /// player should keep going past its end instead of stopping there.
+    fn plays_through(&self) -> bool {
+        let current = self.queue.current().and_then(|p| self.virtual_tracks.get(p));
+        let next = self.queue.peek_next().and_then(|p| self.virtual_tracks.get(p));
+        current.zip(next).is_some_and(|(c, n)| c.is_followed_by(n))
+    }
+
+    /// Metadata for the current track: sheet data for CUE tracks, stream tags otherwise.
+    fn current_metadata(&self, player: &MediaPlayer) -> TrackMetadata {
+        let current = self.queue.current();
+        current
+            .and_then(|p| self.virtual_tracks.get(p))
+            .map(CueTrack::metadata)
+            // The library is refreshed when tags are edited; the stream's tags aren't
+            .or_else(|| {
+                current
+                    .and_then(|p| self.library_meta.get(p))
+                    .filter(|md| md.title.is_some() || md.artist.is_some())
+                    .cloned()
+            })
+            .unwrap_or_else(|| player.metadata())
+    }
+
+    /// The file holding a track's audio, which for CUE tracks is shared with
+    /// the other tracks of the sheet.
+    fn audio_file(&self, path: &Path) -> PathBuf {
+        self.virtual_tracks
+            .get(path)
+            .map(|track| track.file.clone())
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Files of the album the current track belongs to, itself included. Tracks
+    /// belong to the same album when their album tags match and they either
+    /// share an album artist or, lacking one, a folder.
+    fn current_album_files(&self) -> Vec<PathBuf> {
+        let Some(current) = self.queue.current() else {
+            return Vec::new();
+        };
+        let folder = |path: &Path| self.audio_file(path).parent().map(Path::to_path_buf);
+        let mut files = BTreeSet::from([self.audio_file(current)]);
+        if let Some(md) = self.library_meta.get(current) {
+            for path in &self.library_tracks {
+                let Some(other) = self.library_meta.get(path) else {
+                    continue;
+                };
+                let same_album = match (&md.album, &other.album) {
+                    (Some(a), Some(b)) if a == b => match (&md.album_artist, &other.album_artist) {
+                        (Some(a), Some(b)) => a == b,
+                        _ => folder(current) == folder(path),
+                    },
+                    (None, None) => folder(current) == folder(path),
+                    _ => false,
+                };
+                if same_album {
+                    files.insert(self.audio_file(path));
+                }
+            }
+        }
+        files.into_iter().collect()
+    }
+
+    /// Look up the current track's cover art if it hasn't been yet.
+    fn load_current_artwork(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(path) = self.queue.current().cloned() else {
+            return Task::none();
+        };
+        if self.artwork.touch(&path) {
+            return Task::none();
+        }
+        self.artwork.insert(path.clone(), Artwork::default());
+        let file = self.audio_file(&path);
+        cosmic::task::future(async move {
+            // Reading the tags and decoding the image take a while for large covers
+            let loaded = tokio::task::spawn_blocking(move || {
+                let thumbnail = match artwork::read_cover(&file) {
+                    Ok(cover) => cover.and_then(|c| artwork::thumbnail(&c, ARTWORK_SIZE).ok()),
+                    Err(err) => {
+                        eprintln!("failed to read artwork of {:?}: {err}", file);
+                        None
+                    }
+                };
+                (thumbnail, file.parent().and_then(artwork::folder_cover))
+            })
+            .await;
+            let (thumbnail, folder_cover) = loaded.unwrap_or_default();
+            Message::ArtworkLoaded(path, thumbnail, folder_cover)
+        })
+    }
+
+    /// Look up the current track's lyrics if they haven't been yet. Tracks of
+    /// CUE sheets share their file, so only whole files have lyrics.
+    fn load_current_lyrics(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(path) = self.queue.current().cloned() else {
+            return Task::none();
+        };
+        if self.lyrics.contains_key(&path) {
+            return Task::none();
+        }
+        self.lyrics.insert(path.clone(), None);
+        if self.virtual_tracks.contains_key(&path) {
+            return Task::none();
+        }
+        cosmic::task::future(async move {
+            let lyrics = lyrics::read_lyrics(&path).unwrap_or_else(|err| {
+                eprintln!("failed to read lyrics of {:?}: {err}", path);
+                None
+            });
+            Message::LyricsLoaded(path, lyrics)
+        })
+    }
+
+    /// Lyrics of the current track, if it has any.
+    pub fn current_lyrics(&self) -> Option<&Lyrics> {
+        self.queue
+            .current()
+            .and_then(|p| self.lyrics.get(p))
+            .and_then(Option::as_ref)
+    }
+
+    /// The equalizer settings in use: the preset bound to what is playing, or
+    /// else the ones picked or made by hand.
+    pub fn current_equalizer(&self) -> &EqualizerPreset {
+        self.equalizer
+            .bound
+            .as_ref()
+            .unwrap_or(&self.config.equalizer)
+    }
+
+    /// The parametric profile in use: the one being edited, or else the
+    /// output device's, or else the one picked for every device.
+    pub fn current_parametric(&self) -> Option<&ParametricProfile> {
+        match &self.equalizer.profile_editor {
+            Some(editor) => Some(&editor.profile),
+            None => self
+                .config
+                .parametric_profile(self.equalizer.device.as_deref()),
+        }
+    }
+
+    /// Play with the equalizer settings and parametric profile in use, or
+    /// without those that are off.
+    fn apply_equalizer(&self) {
+        if let Some(player) = &self.audio {
+            let settings = self
+                .config
+                .equalizer_enabled
+                .then(|| self.current_equalizer());
+            player.set_equalizer(settings);
+            let profile = self
+                .config
+                .parametric_enabled
+                .then(|| self.current_parametric())
+                .flatten();
+            player.set_parametric(profile);
+        }
+    }
+
+    /// A filter of the profile being edited.
+    fn edited_filter(&mut self, index: usize) -> Option<&mut Filter> {
+        self.equalizer
+            .profile_editor
+            .as_mut()
+            .and_then(|editor| editor.profile.filters.get_mut(index))
+    }
+
+    /// Save `profile`, replacing the one of the same name and the one it was
+    /// opened as, and use it where that one was used.
+    fn save_parametric_profile(&mut self, profile: ParametricProfile, original: Option<String>) {
+        let replaced = |name: &String| *name == profile.name || Some(name) == original.as_ref();
+        let mut profiles = self.config.parametric_profiles.clone();
+        let at = profiles.iter().position(|p| replaced(&p.name));
+        profiles.retain(|p| !replaced(&p.name));
+        profiles.insert(at.unwrap_or(profiles.len()).min(profiles.len()), profile.clone());
+        let mut by_device = self.config.parametric_device_profiles.clone();
+        for name in by_device.values_mut() {
+            if replaced(name) {
+                *name = profile.name.clone();
+            }
+        }
+        // A new profile is used on every device without one of its own
+        let selected = match original {
+            Some(_) if !replaced(&self.config.parametric_profile) => {
+                self.config.parametric_profile.clone()
+            }
+            _ => profile.name.clone(),
+        };
+        self.save_config("parametric profiles", |config, context| {
+            config.set_parametric_profiles(context, profiles)?;
+            config.set_parametric_device_profiles(context, by_device)?;
+            config.set_parametric_profile(context, selected)
+        });
+        self.equalizer.profile_editor = None;
+        self.refresh_equalizer_presets();
+        self.apply_equalizer();
+    }
+
+    /// Use `settings` in place of any bound preset until the next track, without
+    /// saving them.
+    fn use_equalizer(&mut self, settings: EqualizerPreset) {
+        self.config.equalizer = settings;
+        self.equalizer.bound = None;
+        self.apply_equalizer();
+    }
+
+    fn save_equalizer(&mut self) {
+        let settings = self.config.equalizer.clone();
+        self.save_config("the equalizer settings", |config, context| {
+            config.set_equalizer(context, settings)
+        });
+    }
+
+    /// Work out which preset the playing track's genre or the output device
+    /// calls for, and play with it.
+    fn rebind_equalizer(&mut self) {
+        let genre = self
+            .equalizer
+            .track
+            .as_ref()
+            .and_then(|path| match self.virtual_tracks.get(path) {
+                Some(track) => track.metadata().genre,
+                None => self.library_meta.get(path).and_then(|md| md.genre.clone()),
+            });
+        self.equalizer.bound = equalizer::bound_preset(
+            genre.as_deref(),
+            self.equalizer.device.as_deref(),
+            &self.config.equalizer_genre_presets,
+            &self.config.equalizer_device_presets,
+        )
+        .and_then(|name| equalizer::find_preset(name, &self.config.equalizer_presets));
+        self.apply_equalizer();
+    }
+
+    /// List the built-in presets, save those the user replaced, then the
+    /// user's own.
+    fn refresh_equalizer_presets(&mut self) {
+        let user = &self.config.equalizer_presets;
+        let mut presets = equalizer::builtin_presets();
+        presets.retain(|builtin| !user.iter().any(|p| p.name == builtin.name));
+        presets.extend(user.iter().cloned());
+        self.equalizer.labels = presets.iter().map(|p| pages::preset_label(&p.name)).collect();
+        self.equalizer.presets = presets;
+        self.equalizer.profile_labels = self
+            .config
+            .parametric_profiles
+            .iter()
+            .map(|p| p.name.clone())
+            .collect();
+        self.equalizer.filter_kind_labels = pages::filter_kind_labels();
+    }
+
+    /// Persist a change to the configuration, made with its setters in `set`.
+    fn save_config(
+        &mut self,
+        what: &str,
+        set: impl FnOnce(&mut Config, &cosmic_config::Config) -> Result<bool, cosmic_config::Error>,
+    ) {
+        let app_id = <Self as cosmic::Application>::APP_ID;
+        if let Ok(context) = cosmic_config::Config::new(app_id, Config::VERSION) {
+            if let Err(err) = set(&mut self.config, &context) {
+                eprintln!("failed to save {what}: {err}");
+            }
+        }
+    }
+
+    /// Seek the current track to `position`, showing it right away.
+    fn seek_to(&mut self, position: Duration) {
+        let Some(player) = &self.audio else {
+            return;
+        };
+        if let Err(err) = player.seek(position) {
+            eprintln!("failed to seek: {err}");
+            return;
+        }
+        // Reflect immediately in UI
+        self.position_ms = position.as_millis() as u64;
+        if let Some(tx) = &self.mpris_tx {
+            let _ = tx.try_send(MprisCommand::SetPlayback {
+                playing: self.is_playing,
+                position: Some(position),
+            });
+        }
+    }
+
+    /// Cover art of the current track, once it has been looked up.
+    pub fn current_artwork(&self) -> Option<&Artwork> {
+        self.queue
+            .current()
+            .and_then(|p| self.artwork.get(p))
+            .filter(|a| a.loaded)
+    }
+
+    /// Tracks the organizer moves: the selection if there is one, otherwise
+    /// the whole library. CUE tracks stay with the file they share.
+    fn organize_tracks(&self) -> Vec<(PathBuf, TrackMetadata)> {
+        self.library_tracks
+            .iter()
+            .filter(|p| self.library_selection.is_empty() || self.library_selection.contains(*p))
+            .filter(|p| !self.virtual_tracks.contains_key(*p))
+            .filter_map(|p| Some((p.clone(), self.library_meta.get(p)?.clone())))
+            .collect()
+    }
+
+    /// Fingerprint the library tracks that have no fingerprint yet, in the
+    /// background, unless that is already being done.
+    fn fingerprint_library(&mut self) -> Task<cosmic::Action<Message>> {
+        if self.fingerprinting {
+            return Task::none();
+        }
+        // CUE tracks share their file, which is fingerprinted as a whole
+        let mut tracks: Vec<(PathBuf, TrackMetadata)> = self
+            .library_tracks
+            .iter()
+            .filter(|p| !self.virtual_tracks.contains_key(*p))
+            .filter_map(|p| Some((p.clone(), self.library_meta.get(p)?.clone())))
+            .filter(|(_, md)| md.fingerprint.is_none())
+            .collect();
+        if tracks.is_empty() {
+            return Task::none();
+        }
+        self.fingerprinting = true;
+        cosmic::task::future(async move {
+            let fingerprints =
+                tokio::task::spawn_blocking(move || fingerprint_tracks(&mut tracks)).await;
+            Message::FingerprintsComputed(fingerprints.unwrap_or_default())
+        })
+    }
+
+    /// Keep fingerprints computed in the background with the library metadata.
+    fn store_fingerprints(&mut self, fingerprints: Vec<(PathBuf, String)>) {
+        if fingerprints.is_empty() {
+            return;
+        }
+        for (path, fingerprint) in fingerprints {
+            // Tracks just found may not have their tags read yet; they keep
+            // the fingerprint when they do
+            self.library_meta.entry(path).or_default().fingerprint = Some(fingerprint);
+        }
+        self.save_library_meta();
+    }
+
+    /// Move the tracks not kept in one group of duplicates, or in all of them,
+    /// to the trash in the background.
+    fn trash_duplicates(&mut self, group: Option<usize>) -> Task<cosmic::Action<Message>> {
+        let Some(groups) = &self.duplicates.groups else {
+            return Task::none();
+        };
+        let mut losers = Vec::new();
+        for (index, (found, keep)) in groups.iter().zip(&self.duplicates.keep).enumerate() {
+            if group.is_some_and(|g| g != index) {
+                continue;
+            }
+            let Some(keeper) = found.tracks.get(*keep).map(|t| &t.path) else {
+                continue;
+            };
+            for track in &found.tracks {
+                if track.path != *keeper {
+                    losers.push((track.path.clone(), keeper.clone()));
+                }
+            }
+        }
+        if losers.is_empty() {
+            return Task::none();
+        }
+        self.duplicates.busy = true;
+        cosmic::task::future(async move {
+            // Trashing can take a while, on network drives especially
+            let done = tokio::task::spawn_blocking(move || {
+                let mut trashed = Vec::new();
+                let mut failed = Vec::new();
+                for (path, keeper) in losers {
+                    match trash::delete(&path) {
+                        Ok(()) => trashed.push((path, keeper)),
+                        Err(err) => {
+                            failed.push(format!("failed to move {:?} to the trash: {err}", path))
+                        }
+                    }
+                }
+                (trashed, failed)
+            })
+            .await;
+            let (trashed, failed) =
+                done.unwrap_or_else(|err| (Vec::new(), vec![err.to_string()]));
+            Message::DuplicatesTrashed(trashed, failed)
+        })
+    }
+
+    /// Drop tracks that were moved to the trash from the library, handing
+    /// their play statistics, playlist entries, history and place in the queue
+    /// to the copy kept.
+    fn remove_duplicates(&mut self, trashed: &[(PathBuf, PathBuf)]) {
+        if trashed.is_empty() {
+            return;
+        }
+        for (path, keeper) in trashed {
+            if let Some(md) = self.library_meta.remove(path) {
+                if let Some(kept) = self.library_meta.get_mut(keeper) {
+                    duplicates::merge_plays(kept, &md);
+                }
+            }
+            self.library_selection.remove(path);
+        }
+        let replaced: HashMap<PathBuf, PathBuf> = trashed.iter().cloned().collect();
+        self.library_tracks.retain(|p| !replaced.contains_key(p));
+
+        if let Some(groups) = self.duplicates.groups.take() {
+            let mut remaining = Vec::new();
+            let mut keep = Vec::new();
+            for (mut group, kept) in groups.into_iter().zip(&self.duplicates.keep) {
+                let keeper = group.tracks.get(*kept).map(|t| t.path.clone());
+                group.tracks.retain(|t| !replaced.contains_key(&t.path));
+                if group.tracks.len() > 1 {
+                    let index = group.tracks.iter().position(|t| Some(&t.path) == keeper.as_ref());
+                    keep.push(index.unwrap_or(0));
+                    remaining.push(group);
+                }
+            }
+            self.duplicates.groups = Some(remaining);
+            self.duplicates.keep = keep;
+        }
+
+        // References to the removed files now lead to the copies kept
+        self.follow_moved_files(&replaced);
+    }
+
+    /// Update every reference to files that were moved, so tracks keep their
+    /// place in the queue and playlists, their play statistics and history.
+    fn follow_moved_files(&mut self, moved: &HashMap<PathBuf, PathBuf>) {
+        if moved.is_empty() {
+            return;
+        }
+        for path in &mut self.library_tracks {
+            if let Some(to) = moved.get(&*path) {
+                *path = to.clone();
+            }
+        }
+        self.refresh_unsupported_tracks();
+        for (from, to) in moved {
+            if let Some(md) = self.library_meta.remove(from) {
+                self.library_meta.insert(to.clone(), md);
+            }
+            if self.library_selection.remove(from) {
+                self.library_selection.insert(to.clone());
+            }
+            // The folder cover may have moved too; look it up again
+            self.artwork.remove(from);
+        }
+        self.queue.move_paths(moved);
+        for path in [
+            self.listening.as_mut().map(|session| &mut session.path),
+            self.scrobble_announced.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(to) = moved.get(&*path) {
+                *path = to.clone();
+            }
+        }
+
+        self.save_library_meta();
+        if self.playlists.move_paths(moved) {
+            self.save_playlists();
+        }
+        if let Some(history) = &mut self.history {
+            if let Err(err) = history.move_paths(moved) {
+                eprintln!("failed to update listening history: {err}");
+            }
+        }
+        self.refresh_smart_playlists();
+    }
+
+    /// Open the tag editor for `paths`. CUE tracks are left out, as their tags
+    /// live in the sheet rather than the audio file.
+    fn open_tag_editor(&mut self, mut paths: Vec<PathBuf>) {
+        paths.retain(|p| !self.virtual_tracks.contains_key(p));
+        if paths.is_empty() {
+            return;
+        }
+        paths.sort();
+        let default = TrackMetadata::default();
+        let tracks: Vec<&TrackMetadata> = paths
+            .iter()
+            .map(|p| self.library_meta.get(p).unwrap_or(&default))
+            .collect();
+        let values = TagField::ALL
+            .iter()
+            .map(|field| (*field, tags::common_value(*field, tracks.iter().copied())))
+            .collect();
+        self.tag_editor = Some(TagEditor {
+            paths,
+            values,
+            changes: TagChanges::new(),
+            error: None,
+            saving: false,
+            identifying: false,
+        });
+    }
+
+    /// Trade the Last.fm username and password for a session key in the
+    /// background, if they are set and there is no key yet.
+    fn lastfm_login(&self) -> Task<cosmic::Action<Message>> {
+        let Some(client) = self.config.lastfm_login() else {
+            return Task::none();
+        };
+        cosmic::task::future(async move {
+            let key = tokio::task::spawn_blocking(move || client.session_key())
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|key| key.map_err(|err| err.to_string()));
+            Message::LastfmSession(key)
+        })
+    }
+
+    /// Compute statistics for the selected range in the background.
+    fn compute_statistics(&self) -> Task<cosmic::Action<Message>> {
+        let history = self
+            .history
+            .as_ref()
+            .map(|h| h.entries().to_vec())
+            .unwrap_or_default();
+        let library = self.library_meta.clone();
+        let range = self.stats_range;
+        cosmic::task::future(async move {
+            let now = unix_time(SystemTime::now());
+            // Sizing the library reads every file's metadata
+            let statistics = tokio::task::spawn_blocking(move || {
+                stats::compute(&history, &library, range, now)
+            })
+            .await
+            .unwrap_or_default();
+            Message::StatsReady(Box::new(statistics))
+        })
+    }
+
+    /// The listening history, if it could be loaded.
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
 
-        widget::column()
-            .push(icon)
-            .push(title)
-            .push(link)
-            .push(
-                widget::button::link(fl!(
-                    "git-description",
-                    hash = short_hash.as_str(),
-                    date = date
-                ))
-                .on_press(Message::LaunchUrl(format!("{REPOSITORY}/commits/{hash}")))
-                .padding(0),
-            )
-            .align_x(Alignment::Center)
-            .spacing(space_xxs)
-            .into()
+    /// Read-only access to the named and smart playlists.
+    pub fn playlists(&self) -> &PlaylistStore {
+        &self.playlists
     }
 
-    /// Updates the header and window titles.
-    pub fn update_title(&mut self) -> Task<cosmic::Action<Message>> {
-        let mut window_title = fl!("app-title");
+    /// The playlist to select when none is: the first named one, else the first smart one.
+    fn first_playlist(&self) -> Option<u32> {
+        let named = self.playlists.playlists().first().map(|p| p.id);
+        named.or_else(|| self.playlists.smart_playlists().first().map(|p| p.id))
+    }
 
-        if let Some(page) = self.nav.text(self.nav.active()) {
-            window_title.push_str(" — ");
-            window_title.push_str(page);
+    /// Tracks of a smart playlist as of its last evaluation.
+    pub fn smart_tracks(&self, id: u32) -> &[PathBuf] {
+        self.smart_results.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The library tracks of a named or smart playlist.
+    fn playlist_tracks(&self, id: u32) -> Option<Vec<PathBuf>> {
+        match self.playlists.get(id) {
+            Some(playlist) => Some(playlist.resolve(&self.library_meta)),
+            None => self.playlists.smart(id).map(|_| self.smart_tracks(id).to_vec()),
         }
+    }
 
-        if let Some(id) = self.core.main_window_id() {
-            self.set_window_title(window_title, id)
-        } else {
-            Task::none()
+    /// Follow playback of the current track, counting it as played once enough of
+    /// it has been heard. Switching tracks ends the previous listen.
+    fn track_listening(&mut self) {
+        let Some(player) = &self.audio else {
+            return;
+        };
+        let position = player.position();
+        let length = player.duration();
+
+        let current = self.queue.current().cloned();
+        if self.listening.as_ref().map(|s| &s.path) != current.as_ref() {
+            self.finish_listen(ListenEnd::Stopped);
+        }
+        let (Some(path), Some(position), true) = (current, position, self.is_playing) else {
+            return;
+        };
+
+        let now = unix_time(SystemTime::now());
+        let threshold = self.config.play_threshold();
+        let session = self
+            .listening
+            .get_or_insert_with(|| ListenSession::new(path.clone(), now));
+        if session.advance(position, length, &threshold) {
+            if let Some(md) = self.library_meta.get_mut(&path) {
+                session.record_play(md, now);
+                self.save_library_meta();
+                self.refresh_smart_playlists();
+            }
         }
+        self.track_scrobbling(length);
     }
 
-    /// Read-only access to scanned library tracks.
-    pub fn library_tracks(&self) -> &[PathBuf] {
-        &self.library_tracks
+    /// Announce the current track to the scrobbler once its tags are known, and
+    /// submit the listen once enough of it has been heard. Streams are left out,
+    /// since one stream plays many tracks.
+    fn track_scrobbling(&mut self, length: Option<Duration>) {
+        let (Some(tx), Some(session), Some(player)) =
+            (&self.scrobble_tx, &self.listening, &self.audio)
+        else {
+            return;
+        };
+        if playlist::is_remote_uri(&session.path.to_string_lossy()) {
+            return;
+        }
+
+        if self.scrobble_announced.as_ref() != Some(&session.path) {
+            let md = self.current_metadata(player);
+            let Some(scrobble) = Scrobble::new(&md, session.started_at) else {
+                return;
+            };
+            let _ = tx.send(ScrobbleCommand::NowPlaying(scrobble.clone()));
+            self.scrobble_announced = Some(session.path.clone());
+            self.scrobble_pending = Some(scrobble);
+        }
+
+        if scrobble::THRESHOLD.is_reached(session.listened, length) {
+            if let Some(scrobble) = self.scrobble_pending.take() {
+                let _ = tx.send(ScrobbleCommand::Listen(scrobble));
+            }
+        }
+    }
+
+    /// End the current listen, logging it in the history and adding its listening
+    /// time to the track's statistics.
+    fn finish_listen(&mut self, end: ListenEnd) {
+        let Some(session) = self.listening.take() else {
+            return;
+        };
+        // A listen not heard long enough by now is never submitted
+        self.scrobble_announced = None;
+        self.scrobble_pending = None;
+        if let Some(history) = self.history.as_mut().filter(|_| !session.listened.is_zero()) {
+            let source = if playlist::is_remote_uri(&session.path.to_string_lossy()) {
+                PlaySource::Radio
+            } else {
+                self.play_source
+            };
+            let md = self.library_meta.get(&session.path);
+            if let Err(err) = history.append(HistoryEntry::new(&session, end, source, md)) {
+                eprintln!("failed to record listen: {err}");
+            }
+        }
+        if let Some(md) = self.library_meta.get_mut(&session.path) {
+            session.record_end(md, end);
+            self.save_library_meta();
+            if end == ListenEnd::Skipped {
+                self.refresh_smart_playlists();
+            }
+        }
+    }
+
+    /// Save and apply a change the user made to a track's library metadata.
+    fn library_meta_changed(&mut self, path: &Path) {
+        self.save_library_meta();
+        self.refresh_smart_playlists();
+        if self.queue.current().is_some_and(|p| p == path) {
+            self.mpris_needs_metadata_flush = true;
+        }
+    }
+
+    /// Persist the library metadata and play statistics to the data directory
+    /// shortly, together with whatever else changes meanwhile.
+    fn save_library_meta(&mut self) {
+        self.library_meta_changed_at.get_or_insert_with(Instant::now);
+    }
+
+    /// Note the size and modification time of files the app just wrote, so
+    /// reading their tags again doesn't take the change for new audio and
+    /// drop their fingerprints.
+    fn refresh_file_stats<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) {
+        for path in paths {
+            if let Some(md) = self.library_meta.get_mut(path) {
+                md.file_modified = modified_time(path);
+                md.file_size = file_size(path);
+            }
+        }
+        self.save_library_meta();
+    }
+
+    /// Write the library metadata out in the background once it has been left
+    /// alone for a moment, unless a write is still going.
+    fn flush_library_meta(&mut self) -> Task<cosmic::Action<Message>> {
+        let due = self
+            .library_meta_changed_at
+            .is_some_and(|at| at.elapsed() >= LIBRARY_META_SAVE_DELAY);
+        if !due || self.library_meta_saving {
+            return Task::none();
+        }
+        let Some(file) = library_meta_file() else {
+            return Task::none();
+        };
+        self.library_meta_changed_at = None;
+        self.library_meta_saving = true;
+        let meta = self.all_library_meta();
+        cosmic::task::future(async move {
+            let written =
+                tokio::task::spawn_blocking(move || write_library_meta(&file, &meta)).await;
+            match written {
+                Ok(Err(err)) => eprintln!("failed to save library metadata: {err}"),
+                Err(err) => eprintln!("failed to save library metadata: {err}"),
+                Ok(Ok(())) => {}
+            }
+            Message::LibraryMetaSaved
+        })
+    }
+
+    /// The metadata of every track the library knows of, found by the last
+    /// scan or not.
+    fn all_library_meta(&self) -> HashMap<PathBuf, TrackMetadata> {
+        let mut meta = self.missing_meta.clone();
+        meta.extend(self.library_meta.iter().map(|(p, md)| (p.clone(), md.clone())));
+        meta
+    }
+
+    /// Library tracks with their index in the scan order, sorted by the chosen column.
+    pub fn sorted_library(&self) -> Vec<(usize, &PathBuf)> {
+        let mut tracks: Vec<(usize, &PathBuf)> = self.library_tracks.iter().enumerate().collect();
+        let meta = |path: &PathBuf| self.library_meta.get(path);
+        match self.library_sort {
+            LibrarySort::Title => tracks.sort_by_cached_key(|(_, p)| {
+                self.library_display_text(p).to_lowercase()
+            }),
+            LibrarySort::PlayCount => tracks.sort_by_key(|(_, p)| meta(p).map(|m| m.play_count)),
+            LibrarySort::SkipCount => tracks.sort_by_key(|(_, p)| meta(p).map(|m| m.skip_count)),
+            LibrarySort::LastPlayed => {
+                tracks.sort_by_key(|(_, p)| meta(p).and_then(|m| m.last_played))
+            }
+            LibrarySort::ListeningTime => {
+                tracks.sort_by_key(|(_, p)| meta(p).map(|m| m.listening_time))
+            }
+            LibrarySort::Rating => {
+                tracks.sort_by_key(|(_, p)| meta(p).map(|m| (m.rating, m.favorite)))
+            }
+        }
+        if self.library_sort_descending {
+            tracks.reverse();
+        }
+        tracks
+    }
+
+    /// Metadata and play statistics for a library track.
+    pub fn track_metadata(&self, path: &Path) -> Option<&TrackMetadata> {
+        self.library_meta.get(path)
+    }
+
+    /// Library tracks selected for editing together.
+    pub fn library_selection(&self) -> &HashSet<PathBuf> {
+        &self.library_selection
+    }
+
+    /// The library's sort column and whether it is descending.
+    pub fn library_sort(&self) -> (LibrarySort, bool) {
+        (self.library_sort, self.library_sort_descending)
+    }
+
+    /// Re-evaluate every smart playlist against the library metadata.
+    fn refresh_smart_playlists(&mut self) {
+        let now = unix_time(SystemTime::now());
+        self.smart_results = self
+            .playlists
+            .smart_playlists()
+            .iter()
+            .map(|p| (p.id, p.evaluate(&self.library_meta, now)))
+            .collect();
+    }
+
+    /// Reference a library or queue track for adding it to a playlist.
+    fn track_ref(&self, source: TrackSource) -> Option<TrackRef> {
+        let path = match source {
+            TrackSource::Library(index) => self.library_tracks.get(index),
+            TrackSource::Queue(index) => self.queue.tracks().get(index),
+        }?;
+        Some(TrackRef::new(path.clone(), self.library_meta.get(path)))
+    }
+
+    /// Context menu for a library or queue track, offering to add it to a
+    /// playlist or edit its tags.
+    pub fn track_menu(&self, source: TrackSource) -> Vec<menu::Tree<Message>> {
+        let mut items: Vec<menu::Item<MenuAction, String>> = self
+            .playlists
+            .playlists()
+            .iter()
+            .map(|p| {
+                menu::Item::Button(
+                    fl!("add-to-playlist", name = p.name.as_str()),
+                    None,
+                    MenuAction::AddToPlaylist(source, p.id),
+                )
+            })
+            .collect();
+        if !items.is_empty() {
+            items.push(menu::Item::Divider);
+        }
+        items.push(menu::Item::Button(
+            fl!("add-to-new-playlist"),
+            None,
+            MenuAction::AddToNewPlaylist(source),
+        ));
+        items.push(menu::Item::Divider);
+        items.push(menu::Item::Button(
+            fl!("edit-tags"),
+            None,
+            MenuAction::EditTags(source),
+        ));
+        menu::items(&self.key_binds, items)
+    }
+
+    /// Persist the named playlists to the data directory.
+    fn save_playlists(&self) {
+        if let Some(file) = playlists_file() {
+            if let Err(err) = self.playlists.save(&file) {
+                eprintln!("failed to save playlists: {err}");
+            }
+        }
+    }
+
+    /// Codec availability, once the registry inspection has finished.
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        self.diagnostics.as_ref()
+    }
+
+    /// Library files that can't be decoded with the installed GStreamer plugins.
+    pub fn unsupported_tracks(&self) -> &[PathBuf] {
+        &self.unsupported_tracks
+    }
+
+    /// Look for library files the installed plugins can't decode, once both
+    /// the library and the registry are known.
+    fn refresh_unsupported_tracks(&mut self) {
+        self.unsupported_tracks = self
+            .diagnostics
+            .as_ref()
+            .map(|d| d.unsupported_tracks(&self.library_tracks))
+            .unwrap_or_default()
+            .into_iter()
+            .cloned()
+            .collect();
+    }
+
+    /// Whether a library file can be decoded with the installed GStreamer plugins.
+    pub fn is_track_playable(&self, path: &Path) -> bool {
+        self.diagnostics
+            .as_ref()
+            .is_none_or(|d| d.is_playable(path))
     }
 
     /// Build a display label for a library item using metadata when available.
@@ -814,6 +4215,25 @@ impl AppModel {
             .unwrap_or_else(|| path.to_string_lossy().into_owned())
     }
 
+    /// Describe the queue as playlist entries, using library metadata for `#EXTINF`.
+    fn queue_playlist_entries(&self) -> Vec<PlaylistEntry> {
+        self.queue
+            .tracks()
+            .iter()
+            .map(|path| {
+                let md = self.library_meta.get(path);
+                PlaylistEntry {
+                    location: path.to_string_lossy().into_owned(),
+                    title: md.and_then(|m| m.title.clone()),
+                    artist: md.and_then(|m| m.artist.clone()),
+                    album: md.and_then(|m| m.album.clone()),
+                    duration: md.and_then(|m| m.duration),
+                    image: None,
+                }
+            })
+            .collect()
+    }
+
     /// The queue context page showing the current playback queue.
     /// TODO)) Add fallback when the queue are empty
     pub fn queue_context_view(&self) -> Element<'static, Message> {
@@ -821,7 +4241,7 @@ impl AppModel {
 
         let mut items = widget::column().spacing(4);
         let current = self.queue.current().cloned();
-        for path in self.queue.tracks() {
+        for (index, path) in self.queue.tracks().iter().enumerate() {
             let label = self.library_display_text(path);
 
             let is_current = current.as_ref().map(|p| p == path).unwrap_or(false);
@@ -853,20 +4273,90 @@ impl AppModel {
                     widget::button::icon(icon::from_name("media-playback-start-symbolic"))
                         .on_press(Message::LoadPath(path.to_string_lossy().into_owned())),
                 )
-                .push(widget::text(label.clone()))
+                .push(widget::text(label.clone()).width(Length::Fill))
                 .width(Length::Fill);
+            if let Some(md) = self.library_meta.get(path) {
+                row = row.push(pages::rating_controls(path, md));
+            }
 
             let container = widget::container(row).padding([4, 8]);
+            let menu = self.track_menu(TrackSource::Queue(index));
 
-            items = items.push(container);
+            items = items.push(widget::context_menu(container, Some(menu)));
         }
 
-        widget::column()
-            .spacing(12)
+        let mut column = widget::column().spacing(12);
+        if !self.unresolved_entries.is_empty() {
+            column = column.push(self.unresolved_view());
+        }
+        column
             .push(widget::scrollable(items))
             .width(Length::Fill)
             .into()
     }
+
+    /// Entries of the loaded playlist that no track was found for.
+    fn unresolved_view(&self) -> Element<'static, Message> {
+        const SHOWN: usize = 10;
+        let count = self.unresolved_entries.len();
+        let mut list = widget::column().spacing(2).push(
+            widget::row()
+                .spacing(8)
+                .align_y(Vertical::Center)
+                .push(
+                    widget::text(fl!("playlist-unresolved", count = count))
+                        .width(Length::Fill),
+                )
+                .push(
+                    widget::button::text(fl!("playlist-unresolved-dismiss"))
+                        .on_press(Message::DismissUnresolved),
+                ),
+        );
+        for location in self.unresolved_entries.iter().take(SHOWN) {
+            list = list.push(widget::text::caption(location.clone()));
+        }
+        if count > SHOWN {
+            list = list.push(widget::text::caption(fl!(
+                "playlist-unresolved-more",
+                count = count - SHOWN
+            )));
+        }
+        widget::container(list)
+            .padding([8, 8])
+            .class(cosmic::theme::Container::Card)
+            .into()
+    }
+}
+
+impl Drop for AppModel {
+    /// Save library metadata changed too recently to have been written out.
+    fn drop(&mut self) {
+        if self.library_meta_changed_at.is_none() {
+            return;
+        }
+        if let Some(file) = library_meta_file() {
+            if let Err(err) = write_library_meta(&file, &self.all_library_meta()) {
+                eprintln!("failed to save library metadata: {err}");
+            }
+        }
+    }
+}
+
+/// Apply `change` to each file, returning the files it succeeded on and the
+/// errors of the others.
+fn change_files(
+    files: Vec<PathBuf>,
+    change: impl Fn(&Path) -> anyhow::Result<()>,
+) -> (Vec<PathBuf>, Vec<String>) {
+    let mut written = Vec::new();
+    let mut failed = Vec::new();
+    for file in files {
+        match change(&file) {
+            Ok(()) => written.push(file),
+            Err(err) => failed.push(err.to_string()),
+        }
+    }
+    (written, failed)
 }
 
 /// Format milliseconds as m:ss or h:mm:ss
@@ -883,6 +4373,111 @@ fn format_time(ms: u64) -> String {
     }
 }
 
+/// Location of the named playlists in the app's data directory.
+fn playlists_file() -> Option<PathBuf> {
+    ProjectDirs::from("io.github", "bloomdevelop", "music-player")
+        .map(|pd| pd.data_dir().join("playlists.json"))
+}
+
+/// Location of the library metadata and play statistics in the app's data
+/// directory.
+fn library_meta_file() -> Option<PathBuf> {
+    ProjectDirs::from("io.github", "bloomdevelop", "music-player")
+        .map(|pd| pd.data_dir().join("library_meta.json"))
+}
+
+/// Write the library metadata through a temporary file, so a crash midway
+/// leaves the previous store in place.
+fn write_library_meta(file: &Path, meta: &HashMap<PathBuf, TrackMetadata>) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = file.with_extension("json.tmp");
+    let mut out = fs::File::create(&tmp)?;
+    out.write_all(&serde_json::to_vec(meta)?)?;
+    out.sync_all()?;
+    fs::rename(&tmp, file)?;
+    Ok(())
+}
+
+/// Location of the listening history in the app's data directory.
+fn history_file() -> Option<PathBuf> {
+    ProjectDirs::from("io.github", "bloomdevelop", "music-player")
+        .map(|pd| pd.data_dir().join("history.jsonl"))
+}
+
+/// Location of listens waiting to be scrobbled, one file per service, in the
+/// app's data directory.
+fn scrobble_queue_dir() -> Option<PathBuf> {
+    ProjectDirs::from("io.github", "bloomdevelop", "music-player")
+        .map(|pd| pd.data_dir().join("scrobble_queue"))
+}
+
+/// The folder the library is scanned from and organized in.
+fn music_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| String::from("."));
+    PathBuf::from(format!("{}/Music", home))
+}
+
+/// File dialog filter matching the supported playlist formats.
+fn playlist_filter() -> FileFilter {
+    FileFilter::new(fl!("playlist-files"))
+        .glob("*.m3u")
+        .glob("*.m3u8")
+        .glob("*.pls")
+        .glob("*.xspf")
+}
+
+/// Fingerprint the tracks that have no fingerprint yet, returning the new ones.
+fn fingerprint_tracks(tracks: &mut [(PathBuf, TrackMetadata)]) -> Vec<(PathBuf, String)> {
+    let mut computed = Vec::new();
+    for (path, md) in tracks.iter_mut().filter(|(_, md)| md.fingerprint.is_none()) {
+        match fingerprint::fingerprint_file(path) {
+            Ok(raw) => {
+                let fingerprint = encode_fingerprint(&raw);
+                md.fingerprint = Some(fingerprint.clone());
+                computed.push((path.clone(), fingerprint));
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+    computed
+}
+
+fn equalizer_filter() -> FileFilter {
+    FileFilter::new(fl!("parametric-files")).glob("*.txt")
+}
+
+/// A playback speed in percent, within the range the player takes.
+pub fn rate_percent(rate: f64) -> u32 {
+    (rate.clamp(MIN_RATE, MAX_RATE) * 100.0).round() as u32
+}
+
+/// The value that occurs most often, the first of them on a tie.
+fn most_common(values: impl Iterator<Item = String>) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    let max = counts.iter().map(|(_, count)| *count).max()?;
+    counts.into_iter().find(|(_, count)| *count == max).map(|(v, _)| v)
+}
+
+fn image_filter() -> FileFilter {
+    FileFilter::new(fl!("artwork-images"))
+        .glob("*.jpg")
+        .glob("*.jpeg")
+        .glob("*.png")
+        .glob("*.webp")
+        .glob("*.gif")
+        .glob("*.bmp")
+}
+
 // ...existing code...
 
 /// The page to display in the application.
@@ -890,6 +4485,388 @@ fn format_time(ms: u64) -> String {
 pub enum Page {
     Page1,
     Page2,
+    Page3,
+    Page4,
+    Page5,
+    Page6,
+    Page7,
+    Page8,
+}
+
+/// Pending changes to a smart playlist's rules, limit and sort order.
+#[derive(Clone, Debug)]
+pub struct SmartEditor {
+    pub draft: SmartPlaylist,
+    /// The limit as typed, applied to the draft whenever it parses
+    pub limit_value: String,
+}
+
+/// An edit made in the smart playlist editor. Indices select a rule, or an
+/// option in the corresponding dropdown.
+#[derive(Clone, Debug)]
+pub enum SmartEdit {
+    Match(usize),
+    AddRule,
+    RemoveRule(usize),
+    RuleField(usize, usize),
+    RuleOperator(usize, usize),
+    RuleValue(usize, String),
+    /// 0 for no limit, otherwise an index into `LIMIT_UNITS` plus one
+    LimitUnit(usize),
+    LimitValue(String),
+    Sort(usize),
+}
+
+impl SmartEditor {
+    fn apply(&mut self, edit: SmartEdit) {
+        let draft = &mut self.draft;
+        match edit {
+            SmartEdit::Match(i) => {
+                if let Some(mode) = pages::MATCH_MODES.get(i) {
+                    draft.match_mode = *mode;
+                }
+            }
+            SmartEdit::AddRule => {
+                let field = Field::Genre;
+                draft.rules.push(Rule::new(field, field.operators()[0], ""));
+            }
+            SmartEdit::RemoveRule(index) => {
+                if index < draft.rules.len() {
+                    draft.rules.remove(index);
+                }
+            }
+            SmartEdit::RuleField(index, i) => {
+                if let (Some(rule), Some(field)) = (draft.rules.get_mut(index), Field::ALL.get(i)) {
+                    rule.field = *field;
+                    // Keep the operator if the new field offers it
+                    if !field.operators().contains(&rule.operator) {
+                        rule.operator = field.operators()[0];
+                    }
+                }
+            }
+            SmartEdit::RuleOperator(index, i) => {
+                if let Some(rule) = draft.rules.get_mut(index) {
+                    if let Some(operator) = rule.field.operators().get(i) {
+                        rule.operator = *operator;
+                    }
+                }
+            }
+            SmartEdit::RuleValue(index, value) => {
+                if let Some(rule) = draft.rules.get_mut(index) {
+                    rule.value = value;
+                }
+            }
+            SmartEdit::LimitUnit(i) => {
+                draft.limit = i
+                    .checked_sub(1)
+                    .and_then(|i| pages::LIMIT_UNITS.get(i))
+                    .map(|unit| Limit {
+                        unit: *unit,
+                        value: self.limit_value.trim().parse().unwrap_or(25),
+                    });
+                if let Some(limit) = draft.limit {
+                    self.limit_value = limit.value.to_string();
+                }
+            }
+            SmartEdit::LimitValue(value) => {
+                if let (Some(limit), Ok(n)) = (&mut draft.limit, value.trim().parse()) {
+                    limit.value = n;
+                }
+                self.limit_value = value;
+            }
+            SmartEdit::Sort(i) => {
+                if let Some(sort) = SortOrder::ALL.get(i) {
+                    draft.sort = *sort;
+                }
+            }
+        }
+    }
+}
+
+/// The library organizer's template and what it would do.
+#[derive(Clone, Debug)]
+pub struct Organizer {
+    pub template: String,
+    /// The moves the template makes, once previewed
+    pub preview: Option<Plan>,
+    pub error: Option<String>,
+    /// Whether files are being moved
+    pub running: bool,
+}
+
+/// The duplicates finder's checks and what it last found.
+#[derive(Clone, Debug, Default)]
+pub struct DuplicateFinder {
+    pub options: DuplicateOptions,
+    /// Groups of duplicates, once searched for
+    pub groups: Option<Vec<DuplicateGroup>>,
+    /// Index of the track to keep in each group
+    pub keep: Vec<usize>,
+    /// Whether duplicates are being looked for or moved to the trash
+    pub busy: bool,
+    pub error: Option<String>,
+}
+
+/// The equalizer page's inputs, and the preset bound to what is playing.
+#[derive(Clone, Debug, Default)]
+pub struct EqualizerPanel {
+    /// Track the bound preset was worked out for
+    pub track: Option<PathBuf>,
+    /// Name of the default audio output device, once looked up
+    pub device: Option<String>,
+    /// Preset bound to the playing track's genre or to the output device,
+    /// used instead of the configured settings until they are changed
+    pub bound: Option<EqualizerPreset>,
+    /// Built-in and user presets, as listed in the dropdown
+    pub presets: Vec<EqualizerPreset>,
+    /// Dropdown labels for `presets`
+    pub labels: Vec<String>,
+    /// Name to save the settings as a preset under
+    pub preset_name: String,
+    /// Genre to bind the preset in use to
+    pub genre: String,
+    /// Dropdown labels for the parametric profiles
+    pub profile_labels: Vec<String>,
+    /// Dropdown labels for `FilterKind::ALL`
+    pub filter_kind_labels: Vec<String>,
+    /// The parametric profile being made or changed, played while it is
+    pub profile_editor: Option<ProfileEditor>,
+    /// What importing a profile skipped, or why it failed
+    pub parametric_notice: Option<String>,
+}
+
+/// A parametric profile being made or changed, with its numbers as typed.
+#[derive(Clone, Debug, Default)]
+pub struct ProfileEditor {
+    /// Name of the profile it was opened as, which saving replaces
+    pub original: Option<String>,
+    pub profile: ParametricProfile,
+    pub preamp: String,
+    /// Each filter's frequency, gain and Q, in the order of `FilterInput`
+    pub inputs: Vec<[String; 3]>,
+}
+
+impl ProfileEditor {
+    fn new(original: Option<String>, profile: ParametricProfile) -> Self {
+        Self {
+            original,
+            preamp: profile.preamp_db().to_string(),
+            inputs: profile.filters.iter().map(FilterInput::texts).collect(),
+            profile,
+        }
+    }
+}
+
+/// The A–B loop set on the playing track, and how far practicing it has got.
+#[derive(Clone, Debug, Default)]
+pub struct PracticeLoop {
+    /// Track the points were set on
+    pub track: Option<PathBuf>,
+    pub a: Option<Duration>,
+    pub b: Option<Duration>,
+    /// Times the loop has come back round, for the speed to rise with
+    pub repetition: u32,
+    /// When to go round again, while pausing before a repetition
+    pub resume_at: Option<Instant>,
+    /// Name to save the loop as a bookmark under
+    pub name: String,
+}
+
+impl PracticeLoop {
+    /// The section to loop, once both points are set far enough apart.
+    pub fn range(&self) -> Option<(Duration, Duration)> {
+        practice::loop_range(self.a?, self.b?)
+    }
+}
+
+/// What a playback speed is remembered for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RateScope {
+    Track,
+    /// Every track of the album, as for a podcast's episodes
+    Album,
+}
+
+/// A number of a parametric filter that is typed in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FilterInput {
+    Frequency,
+    Gain,
+    Q,
+}
+
+impl FilterInput {
+    /// The numbers of `filter` as text.
+    fn texts(filter: &Filter) -> [String; 3] {
+        [
+            filter.frequency.to_string(),
+            filter.gain_db().to_string(),
+            filter.q_factor().to_string(),
+        ]
+    }
+}
+
+/// Cover art of the last `ARTWORK_CACHE_SIZE` tracks looked up. When it is
+/// full, the art used longest ago makes room for new art.
+#[derive(Debug, Default)]
+struct ArtworkCache {
+    /// Least recently used first
+    entries: VecDeque<(PathBuf, Artwork)>,
+}
+
+impl ArtworkCache {
+    fn get(&self, path: &Path) -> Option<&Artwork> {
+        self.entries.iter().find(|(p, _)| p == path).map(|(_, a)| a)
+    }
+
+    fn contains_key(&self, path: &Path) -> bool {
+        self.get(path).is_some()
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &PathBuf> {
+        self.entries.iter().map(|(p, _)| p)
+    }
+
+    /// Mark the art of `path` as just used. Returns whether there is any.
+    fn touch(&mut self, path: &Path) -> bool {
+        let Some(index) = self.entries.iter().position(|(p, _)| p == path) else {
+            return false;
+        };
+        if let Some(entry) = self.entries.remove(index) {
+            self.entries.push_back(entry);
+        }
+        true
+    }
+
+    fn insert(&mut self, path: PathBuf, artwork: Artwork) {
+        self.remove(&path);
+        self.entries.push_back((path, artwork));
+        while self.entries.len() > ARTWORK_CACHE_SIZE {
+            self.entries.pop_front();
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.entries.retain(|(p, _)| p != path);
+    }
+}
+
+/// A track's cover art as shown on the now playing page.
+#[derive(Clone, Debug, Default)]
+pub struct Artwork {
+    /// Whether it has been looked up yet
+    pub loaded: bool,
+    /// The embedded cover, scaled down
+    pub thumbnail: Option<widget::image::Handle>,
+    /// A cover image in the track's folder, which can be embedded
+    pub folder_cover: Option<PathBuf>,
+}
+
+/// Releases MusicBrainz has for the selected tracks, and the one chosen.
+#[derive(Clone, Debug)]
+pub struct AlbumLookup {
+    /// The tracks, in disc and track order
+    pub paths: Vec<PathBuf>,
+    /// Releases that could be the album, best fit first
+    pub candidates: Vec<Candidate>,
+    /// Dropdown labels for the candidates
+    pub labels: Vec<String>,
+    pub selected: usize,
+    pub error: Option<String>,
+    /// Whether releases are being looked up
+    pub searching: bool,
+    /// Whether the tags are being written
+    pub applying: bool,
+}
+
+impl AlbumLookup {
+    /// Each track with the changes the selected release makes to its tags, or
+    /// `None` if the release has no track for it.
+    pub fn changes(
+        &self,
+        library_meta: &HashMap<PathBuf, TrackMetadata>,
+    ) -> Vec<(PathBuf, Option<TagChanges>)> {
+        let Some(candidate) = self.candidates.get(self.selected) else {
+            return Vec::new();
+        };
+        let release = &candidate.release;
+        let default = TrackMetadata::default();
+        let tracks: Vec<&TrackMetadata> = self
+            .paths
+            .iter()
+            .map(|p| library_meta.get(p).unwrap_or(&default))
+            .collect();
+        let matched = musicbrainz::match_tracks(release, &tracks);
+        self.paths
+            .iter()
+            .zip(tracks)
+            .zip(matched)
+            .map(|((path, md), index)| {
+                let changes = index
+                    .map(|i| musicbrainz::proposed_changes(release, &release.tracks[i], md));
+                (path.clone(), changes)
+            })
+            .collect()
+    }
+}
+
+/// Lyrics being written or timed for a track.
+pub struct LyricsEditor {
+    pub path: PathBuf,
+    /// The lyrics as LRC or plain text, while they are edited as text
+    pub text: widget::text_editor::Content,
+    /// The lines being timed, in the order of the text
+    pub draft: Lyrics,
+    /// Whether the lines are being timed rather than edited as text
+    pub timing: bool,
+    /// Line the next stamp times
+    pub selected: usize,
+    /// Whether the lyrics are being saved
+    pub saving: bool,
+    pub error: Option<String>,
+}
+
+/// Where timed lyrics are saved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LyricsTarget {
+    /// An `.lrc` file next to the track
+    Sidecar,
+    /// The track's lyrics tag
+    Embedded,
+}
+
+/// Pending tag changes for one or more tracks.
+#[derive(Clone, Debug)]
+pub struct TagEditor {
+    pub paths: Vec<PathBuf>,
+    /// What each field holds across the tracks, in `TagField::ALL` order
+    pub values: Vec<(TagField, FieldValue)>,
+    /// Fields the user changed, with the text as typed
+    pub changes: TagChanges,
+    /// Why the last save failed
+    pub error: Option<String>,
+    /// Whether the changes are being written
+    pub saving: bool,
+    /// Whether the track is being looked up on AcoustID
+    pub identifying: bool,
+}
+
+/// Library columns that can be sorted on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LibrarySort {
+    Title,
+    PlayCount,
+    SkipCount,
+    LastPlayed,
+    ListeningTime,
+    Rating,
+}
+
+/// Where a track offered in a context menu comes from, by index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrackSource {
+    Library(usize),
+    Queue(usize),
 }
 
 /// The context page to display in the context drawer.
@@ -903,6 +4880,12 @@ pub enum ContextPage {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
+    OpenPlaylist,
+    SaveQueue,
+    OrganizeLibrary,
+    AddToPlaylist(TrackSource, u32),
+    AddToNewPlaylist(TrackSource),
+    EditTags(TrackSource),
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -911,6 +4894,12 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
+            MenuAction::OpenPlaylist => Message::OpenPlaylist,
+            MenuAction::SaveQueue => Message::SaveQueue,
+            MenuAction::OrganizeLibrary => Message::OrganizeOpen,
+            MenuAction::AddToPlaylist(source, id) => Message::AddToPlaylist(*source, *id),
+            MenuAction::AddToNewPlaylist(source) => Message::AddToNewPlaylist(*source),
+            MenuAction::EditTags(source) => Message::EditTags(*source),
         }
     }
 }
@@ -0,0 +1,113 @@
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::Vertical;
+use music_player::audio::backend::TrackMetadata;
+use music_player::audio::tags::TagChanges;
+use music_player::musicbrainz::Candidate;
+
+use super::super::{AlbumLookup, AppModel, Message};
+use super::tag_editor::field_label;
+use crate::fl;
+
+const LABEL_WIDTH: f32 = 160.0;
+
+/// A release as offered in the dropdown, with how well it fits.
+pub fn candidate_label(candidate: &Candidate) -> String {
+    let release = &candidate.release;
+    let mut details = vec![fl!("album-lookup-tracks", count = release.tracks.len())];
+    details.extend(release.year.map(|year| year.to_string()));
+    details.extend(release.country.clone());
+    fl!(
+        "album-lookup-candidate",
+        title = release.title.clone(),
+        artist = release.artist.clone(),
+        details = details.join(", "),
+        score = (candidate.score * 100.0).round() as u32
+    )
+}
+
+fn diff_row(label: String, current: String, proposed: String) -> Element<'static, Message> {
+    widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(label).width(Length::Fixed(LABEL_WIDTH)))
+        .push(widget::text(current).width(Length::Fill))
+        .push(widget::text(proposed).width(Length::Fill))
+        .into()
+}
+
+/// The fields the release changes on a track, current values beside the new.
+fn track_diff(
+    name: String,
+    md: &TrackMetadata,
+    changes: Option<TagChanges>,
+) -> Element<'static, Message> {
+    let mut rows = widget::column()
+        .spacing(4)
+        .push(widget::text::heading(name));
+    match changes {
+        None => rows = rows.push(widget::text::caption(fl!("album-lookup-unmatched"))),
+        Some(changes) if changes.is_empty() => {
+            rows = rows.push(widget::text::caption(fl!("album-lookup-unchanged")))
+        }
+        Some(changes) => {
+            for (field, proposed) in changes {
+                rows = rows.push(diff_row(
+                    field_label(field),
+                    field.value(md).unwrap_or_default(),
+                    proposed.unwrap_or_default(),
+                ));
+            }
+        }
+    }
+    rows.into()
+}
+
+/// Dialog looking the selected tracks up on MusicBrainz as an album, showing
+/// what the chosen release would change on each track before anything is
+/// written.
+pub fn album_lookup_dialog<'a>(app: &'a AppModel, lookup: &'a AlbumLookup) -> Element<'a, Message> {
+    let mut control = widget::column().spacing(8);
+    if lookup.searching {
+        control = control.push(widget::text(fl!("album-lookup-searching")));
+    }
+
+    if !lookup.candidates.is_empty() {
+        control = control.push(widget::dropdown(
+            &lookup.labels,
+            Some(lookup.selected),
+            Message::AlbumCandidateSelect,
+        ));
+        let default = TrackMetadata::default();
+        let mut tracks = widget::column().spacing(12).push(diff_row(
+            String::new(),
+            fl!("album-lookup-current"),
+            fl!("album-lookup-proposed"),
+        ));
+        for (path, changes) in lookup.changes(&app.library_meta) {
+            let md = app.library_meta.get(&path).unwrap_or(&default);
+            tracks = tracks.push(track_diff(app.library_display_text(&path), md, changes));
+        }
+        control = control.push(widget::scrollable(tracks).height(Length::Fixed(360.0)));
+    }
+
+    if let Some(error) = &lookup.error {
+        control = control.push(widget::text(error.clone()));
+    }
+
+    let apply = widget::button::suggested(fl!("album-lookup-apply")).on_press_maybe(
+        (!lookup.applying && !lookup.candidates.is_empty())
+            .then_some(Message::AlbumLookupApply),
+    );
+    let cancel = widget::button::standard(fl!("album-lookup-cancel"))
+        .on_press_maybe((!lookup.applying).then_some(Message::AlbumLookupCancel));
+
+    widget::dialog()
+        .title(fl!("album-lookup-title"))
+        .body(fl!("album-lookup-body", count = lookup.paths.len()))
+        .control(control)
+        .primary_action(apply)
+        .secondary_action(cancel)
+        .into()
+}
@@ -0,0 +1,105 @@
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use music_player::audio::diagnostics::ElementInfo;
+
+use super::super::{AppModel, Message};
+use crate::fl;
+
+pub fn diagnostics_view(app: &AppModel) -> Element<'_, Message> {
+    let Some(diagnostics) = app.diagnostics() else {
+        return widget::text(fl!("diagnostics-inspecting"))
+            .apply(widget::container)
+            .center(Length::Fill)
+            .into();
+    };
+
+    let mut content = widget::column()
+        .spacing(12)
+        .push(widget::text::title3(fl!("nav-diagnostics-label")))
+        .push(widget::text(fl!(
+            "diagnostics-gstreamer-version",
+            version = diagnostics.gstreamer_version.as_str()
+        )));
+
+    // Formats accepted by the library scanner
+    let mut formats = widget::column().spacing(4);
+    for format in &diagnostics.formats {
+        let (icon_name, status) = if format.is_supported() {
+            ("emblem-ok-symbolic", fl!("diagnostics-format-supported"))
+        } else {
+            (
+                "dialog-warning-symbolic",
+                fl!("diagnostics-format-missing", caps = format.missing.join("; ")),
+            )
+        };
+
+        let row = widget::row()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(icon::from_name(icon_name).icon())
+            .push(widget::text(format!(".{}", format.extension)).width(Length::Fixed(64.0)))
+            .push(widget::text(format.description.clone()).width(Length::Fixed(160.0)))
+            .push(widget::text(status).width(Length::Fill));
+
+        formats = formats.push(widget::container(row).padding([4, 8]));
+    }
+    content = content
+        .push(widget::text::heading(fl!("diagnostics-formats")))
+        .push(formats);
+
+    // Library files that will fail to play
+    let unsupported = app.unsupported_tracks();
+    if !unsupported.is_empty() {
+        let mut files = widget::column().spacing(4);
+        for path in unsupported.iter().take(200) {
+            files = files.push(
+                widget::text(path.to_string_lossy().into_owned())
+                    .apply(widget::container)
+                    .padding([2, 8]),
+            );
+        }
+        content = content
+            .push(widget::text::heading(fl!(
+                "diagnostics-unsupported-files",
+                count = unsupported.len()
+            )))
+            .push(files);
+    }
+
+    content = content
+        .push(widget::text::heading(fl!(
+            "diagnostics-decoders",
+            count = diagnostics.decoders.len()
+        )))
+        .push(element_list(&diagnostics.decoders))
+        .push(widget::text::heading(fl!(
+            "diagnostics-audio-sinks",
+            count = diagnostics.audio_sinks.len()
+        )))
+        .push(element_list(&diagnostics.audio_sinks));
+
+    widget::scrollable(content.width(Length::Fill))
+        .apply(widget::container)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Left)
+        .align_y(Vertical::Top)
+        .into()
+}
+
+fn element_list(elements: &[ElementInfo]) -> Element<'_, Message> {
+    let mut list = widget::column().spacing(2);
+    for element in elements {
+        let plugin = element.plugin.as_deref().unwrap_or("-");
+        let row = widget::row()
+            .spacing(8)
+            .push(widget::text(element.name.clone()).width(Length::Fixed(200.0)))
+            .push(widget::text(element.long_name.clone()).width(Length::Fill))
+            .push(widget::text(plugin.to_string()).width(Length::Fixed(140.0)));
+        list = list.push(widget::container(row).padding([2, 8]));
+    }
+    list.into()
+}
@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use music_player::duplicates::{DuplicateGroup, DuplicateTrack, MatchKind};
+
+use super::super::{format_time, AppModel, Message};
+use super::stats::{section, size};
+use crate::fl;
+
+/// Length differences, in seconds, allowed between copies matched by tags
+pub const DUPLICATE_TOLERANCES: [u64; 4] = [1, 3, 5, 10];
+
+pub fn tolerance_labels() -> Vec<String> {
+    DUPLICATE_TOLERANCES
+        .iter()
+        .map(|seconds| fl!("duplicates-tolerance-seconds", seconds = *seconds))
+        .collect()
+}
+
+fn match_label(kind: MatchKind) -> String {
+    match kind {
+        MatchKind::FileHash => fl!("duplicates-matched-hash"),
+        MatchKind::Fingerprint => fl!("duplicates-matched-fingerprint"),
+        MatchKind::Tags => fl!("duplicates-matched-tags"),
+    }
+}
+
+fn track_row<'a>(
+    group: usize,
+    index: usize,
+    track: &'a DuplicateTrack,
+    keep: usize,
+) -> Element<'a, Message> {
+    let bitrate = track
+        .bitrate
+        .map(|kbps| format!("{kbps} kbps"))
+        .unwrap_or_else(|| fl!("stats-unknown"));
+    let duration = track
+        .duration
+        .map(|d| format_time(d.as_millis() as u64))
+        .unwrap_or_default();
+
+    widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::radio("", index, Some(keep), move |index| {
+            Message::DuplicatesKeep(group, index)
+        }))
+        .push(widget::text(track.format()).width(Length::Fixed(56.0)))
+        .push(widget::text(bitrate).width(Length::Fixed(80.0)))
+        .push(widget::text(duration).width(Length::Fixed(56.0)))
+        .push(widget::text(size(track.size)).width(Length::Fixed(80.0)))
+        .push(
+            widget::text(track.path.to_string_lossy().into_owned())
+                .width(Length::Fill),
+        )
+        .into()
+}
+
+fn group_card<'a>(
+    app: &'a AppModel,
+    index: usize,
+    group: &'a DuplicateGroup,
+    keep: usize,
+) -> Element<'a, Message> {
+    let first = &group.tracks[0].path;
+    let title = app.library_display_text(first);
+    let matched_by = group
+        .matched_by
+        .iter()
+        .map(|kind| match_label(*kind))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut rows = widget::column().spacing(4);
+    for (i, track) in group.tracks.iter().enumerate() {
+        rows = rows.push(track_row(index, i, track, keep));
+    }
+    let actions = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text::caption(matched_by).width(Length::Fill))
+        .push(
+            widget::button::standard(fl!("duplicates-trash"))
+                .leading_icon(icon::from_name("user-trash-symbolic"))
+                .on_press_maybe(
+                    (!app.duplicates.busy).then_some(Message::DuplicatesTrashGroup(index)),
+                ),
+        );
+
+    section(title, widget::column().spacing(8).push(rows).push(actions))
+}
+
+pub fn duplicates_view(app: &AppModel) -> Element<'_, Message> {
+    let finder = &app.duplicates;
+    let tolerance_index = DUPLICATE_TOLERANCES
+        .iter()
+        .position(|secs| Duration::from_secs(*secs) == finder.options.tolerance);
+
+    let header = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text::title3(fl!("nav-duplicates-label")).width(Length::Fill))
+        .push(
+            widget::button::standard(fl!("duplicates-trash-all"))
+                .leading_icon(icon::from_name("user-trash-symbolic"))
+                .on_press_maybe(
+                    (!finder.busy && finder.groups.as_ref().is_some_and(|g| !g.is_empty()))
+                        .then_some(Message::DuplicatesTrashAll),
+                ),
+        )
+        .push(
+            widget::button::suggested(fl!("duplicates-find"))
+                .on_press_maybe((!finder.busy).then_some(Message::DuplicatesFind)),
+        );
+
+    let checks = widget::row()
+        .spacing(16)
+        .align_y(Vertical::Center)
+        .push(
+            widget::checkbox(fl!("duplicates-by-hash"), finder.options.by_hash)
+                .on_toggle(Message::DuplicatesByHash),
+        )
+        .push(
+            widget::checkbox(fl!("duplicates-by-tags"), finder.options.by_tags)
+                .on_toggle(Message::DuplicatesByTags),
+        )
+        .push(widget::text(fl!("duplicates-tolerance")))
+        .push(widget::dropdown(
+            &app.duplicate_tolerance_labels,
+            tolerance_index,
+            Message::DuplicatesTolerance,
+        ))
+        .push(
+            widget::checkbox(fl!("duplicates-by-fingerprint"), finder.options.by_fingerprint)
+                .on_toggle(Message::DuplicatesByFingerprint),
+        );
+
+    let mut page = widget::column().spacing(12).push(header).push(checks);
+    if let Some(error) = &finder.error {
+        page = page.push(widget::text(error.clone()));
+    }
+
+    let message = match &finder.groups {
+        _ if finder.busy && finder.groups.is_none() => Some(fl!("duplicates-searching")),
+        None => Some(fl!("duplicates-hint")),
+        Some(groups) if groups.is_empty() => Some(fl!("duplicates-none")),
+        Some(_) => None,
+    };
+    if let Some(message) = message {
+        return page
+            .push(widget::text(message).apply(widget::container).center(Length::Fill))
+            .into();
+    }
+
+    let groups = finder.groups.as_deref().unwrap_or_default();
+    let extra: usize = groups.iter().map(|g| g.tracks.len() - 1).sum();
+    let mut list = widget::column().spacing(12).push(widget::text::heading(fl!(
+        "duplicates-summary",
+        groups = groups.len(),
+        extra = extra
+    )));
+    for (index, (group, keep)) in groups.iter().zip(&finder.keep).enumerate() {
+        list = list.push(group_card(app, index, group, *keep));
+    }
+
+    page.push(widget::scrollable(list).height(Length::Fill))
+        .apply(widget::container)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Left)
+        .align_y(Vertical::Top)
+        .into()
+}
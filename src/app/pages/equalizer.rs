@@ -0,0 +1,219 @@
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use cosmic::iced::widget::vertical_slider;
+use music_player::equalizer::{
+    genre_key, BAND_FREQUENCIES, MAX_GAIN, MAX_PREAMP, MIN_GAIN, MIN_PREAMP,
+};
+
+use super::super::{AppModel, Message};
+use super::parametric::parametric_section;
+use super::stats::section;
+use crate::fl;
+
+const SLIDER_HEIGHT: f32 = 200.0;
+const SLIDER_WIDTH: f32 = 48.0;
+
+/// How a preset is listed: built-in presets by their translated names, the
+/// user's by the names they were saved under.
+pub fn preset_label(name: &str) -> String {
+    match name {
+        "flat" => fl!("equalizer-preset-flat"),
+        "rock" => fl!("equalizer-preset-rock"),
+        "pop" => fl!("equalizer-preset-pop"),
+        "jazz" => fl!("equalizer-preset-jazz"),
+        "classical" => fl!("equalizer-preset-classical"),
+        "electronic" => fl!("equalizer-preset-electronic"),
+        "bass-boost" => fl!("equalizer-preset-bass-boost"),
+        "treble-boost" => fl!("equalizer-preset-treble-boost"),
+        "vocal" => fl!("equalizer-preset-vocal"),
+        _ => name.to_string(),
+    }
+}
+
+fn frequency_label(hz: u32) -> String {
+    if hz >= 1000 {
+        fl!("equalizer-khz", value = format!("{:.1}", f64::from(hz) / 1000.0))
+    } else {
+        fl!("equalizer-hz", value = hz)
+    }
+}
+
+fn decibels(tenths: i32) -> String {
+    format!("{:+.1}", f64::from(tenths) / 10.0)
+}
+
+/// A vertical slider with its value above it and what it sets below.
+fn slider_column<'a>(
+    label: String,
+    range: std::ops::RangeInclusive<i32>,
+    value: i32,
+    on_change: impl Fn(i32) -> Message + 'a,
+) -> Element<'a, Message> {
+    let slider = vertical_slider(range, value, on_change)
+        .on_release(Message::EqualizerSliderReleased)
+        .height(Length::Fixed(SLIDER_HEIGHT));
+    widget::column()
+        .spacing(4)
+        .width(Length::Fixed(SLIDER_WIDTH))
+        .align_x(Horizontal::Center)
+        .push(widget::text::caption(decibels(value)))
+        .push(slider)
+        .push(widget::text::caption(label))
+        .into()
+}
+
+/// The equalizer: a preamp and ten band sliders, presets to pick, save and
+/// delete, and the genres and output device presets are bound to, then the
+/// parametric equalizer.
+pub fn equalizer_view(app: &AppModel) -> Element<'_, Message> {
+    let settings = app.current_equalizer();
+    let named = !settings.name.is_empty();
+    let selected = app
+        .equalizer
+        .presets
+        .iter()
+        .position(|p| named && p.name == settings.name);
+
+    let header = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text::title3(fl!("nav-equalizer-label")).width(Length::Fill))
+        .push(
+            widget::toggler(app.config.equalizer_enabled)
+                .label(fl!("equalizer-enabled"))
+                .on_toggle(Message::EqualizerEnabled),
+        );
+
+    let mut page = widget::column().spacing(12).push(header);
+    if app.audio.as_ref().is_some_and(|player| !player.has_equalizer()) {
+        page = page.push(widget::text(fl!("equalizer-unavailable")));
+    }
+
+    let presets = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(fl!("equalizer-preset")))
+        .push(widget::dropdown(
+            &app.equalizer.labels,
+            selected,
+            Message::EqualizerPreset,
+        ))
+        .push(
+            widget::text_input(fl!("equalizer-preset-name"), &app.equalizer.preset_name)
+                .on_input(Message::EqualizerPresetName)
+                .on_submit(|_| Message::EqualizerSavePreset)
+                .width(Length::Fixed(200.0)),
+        )
+        .push(
+            widget::button::standard(fl!("equalizer-save-preset")).on_press_maybe(
+                (!app.equalizer.preset_name.trim().is_empty())
+                    .then_some(Message::EqualizerSavePreset),
+            ),
+        )
+        .push(
+            widget::button::icon(icon::from_name("user-trash-symbolic"))
+                .tooltip(fl!("equalizer-delete-preset"))
+                .on_press_maybe(
+                    (named
+                        && app
+                            .config
+                            .equalizer_presets
+                            .iter()
+                            .any(|p| p.name == settings.name))
+                        .then_some(Message::EqualizerDeletePreset),
+                ),
+        );
+    let mut presets = widget::column().spacing(8).push(presets);
+    if app.equalizer.bound.is_some() {
+        presets = presets.push(widget::text::caption(fl!("equalizer-bound-hint")));
+    }
+    page = page.push(presets);
+
+    let mut sliders = widget::row()
+        .spacing(4)
+        .push(slider_column(
+            fl!("equalizer-preamp"),
+            MIN_PREAMP..=MAX_PREAMP,
+            settings.preamp,
+            Message::EqualizerPreamp,
+        ))
+        .push(widget::Space::with_width(Length::Fixed(16.0)));
+    for (band, hz) in BAND_FREQUENCIES.iter().enumerate() {
+        sliders = sliders.push(slider_column(
+            frequency_label(*hz),
+            MIN_GAIN..=MAX_GAIN,
+            settings.gains[band],
+            move |gain| Message::EqualizerBand(band, gain),
+        ));
+    }
+    page = page.push(widget::scrollable::horizontal(sliders));
+
+    // Genre bindings, then the output device's
+    let mut genres = widget::column().spacing(4);
+    for (genre, preset) in &app.config.equalizer_genre_presets {
+        genres = genres.push(
+            widget::row()
+                .spacing(8)
+                .align_y(Vertical::Center)
+                .push(widget::text(genre.clone()).width(Length::Fill))
+                .push(widget::text(preset_label(preset)).width(Length::Fill))
+                .push(
+                    widget::button::icon(icon::from_name("edit-delete-symbolic"))
+                        .extra_small()
+                        .tooltip(fl!("equalizer-unbind"))
+                        .on_press(Message::EqualizerUnbindGenre(genre.clone())),
+                ),
+        );
+    }
+    let genre_input = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::text_input(fl!("equalizer-genre"), &app.equalizer.genre)
+                .on_input(Message::EqualizerGenre)
+                .on_submit(|_| Message::EqualizerBindGenre)
+                .width(Length::Fixed(200.0)),
+        )
+        .push(
+            widget::button::standard(fl!("equalizer-bind-genre")).on_press_maybe(
+                (named && !genre_key(&app.equalizer.genre).is_empty())
+                    .then_some(Message::EqualizerBindGenre),
+            ),
+        );
+    genres = genres.push(genre_input);
+
+    let device = app.equalizer.device.as_deref();
+    let device_preset = device.and_then(|d| app.config.equalizer_device_presets.get(d));
+    let device_label = match (device, device_preset) {
+        (None, _) => fl!("equalizer-device-unknown"),
+        (Some(device), None) => fl!("equalizer-device", device = device),
+        (Some(device), Some(preset)) => fl!(
+            "equalizer-device-bound",
+            device = device,
+            preset = preset_label(preset)
+        ),
+    };
+    let mut device_row = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(device_label).width(Length::Fill))
+        .push(
+            widget::button::standard(fl!("equalizer-bind-device"))
+                .on_press_maybe((named && device.is_some()).then_some(Message::EqualizerBindDevice)),
+        );
+    if device_preset.is_some() {
+        device_row = device_row.push(
+            widget::button::standard(fl!("equalizer-unbind"))
+                .on_press(Message::EqualizerUnbindDevice),
+        );
+    }
+
+    page.push(section(fl!("equalizer-genres"), genres))
+        .push(parametric_section(app))
+        .push(section(fl!("equalizer-output-device"), device_row))
+        .apply(widget::scrollable)
+        .into()
+}
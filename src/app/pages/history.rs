@@ -0,0 +1,149 @@
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use music_player::audio::listen::ListenEnd;
+use music_player::history::{HistoryEntry, PlaySource};
+
+use super::super::{format_time, AppModel, Message};
+use crate::fl;
+
+/// Ages, in days, the history can be pruned to
+pub const PRUNE_AGES: [u64; 3] = [30, 90, 365];
+
+/// Number of listens shown on the page
+const MAX_ENTRIES: usize = 500;
+
+pub fn prune_labels() -> Vec<String> {
+    PRUNE_AGES
+        .iter()
+        .map(|days| fl!("history-prune-age", days = *days))
+        .collect()
+}
+
+fn entry_row<'a>(app: &'a AppModel, entry: &'a HistoryEntry) -> Element<'a, Message> {
+    let location = entry.path.to_string_lossy().into_owned();
+    let label = match (&entry.title, &entry.artist) {
+        (Some(title), Some(artist)) => format!("{title} — {artist}"),
+        (Some(title), None) => title.clone(),
+        _ => app.library_display_text(&entry.path),
+    };
+
+    let source = match entry.source {
+        PlaySource::Library => fl!("history-source-library"),
+        PlaySource::Playlist => fl!("history-source-playlist"),
+        PlaySource::Radio => fl!("history-source-radio"),
+    };
+    let status: Element<'a, Message> = match entry.end {
+        ListenEnd::Completed => widget::Space::with_width(Length::Fixed(16.0)).into(),
+        ListenEnd::Skipped => widget::tooltip(
+            icon::from_name("media-skip-forward-symbolic").size(16).icon(),
+            widget::text(fl!("history-skipped")),
+            widget::tooltip::Position::Left,
+        )
+        .into(),
+        ListenEnd::Stopped => widget::tooltip(
+            icon::from_name("media-playback-stop-symbolic").size(16).icon(),
+            widget::text(fl!("history-stopped")),
+            widget::tooltip::Position::Left,
+        )
+        .into(),
+    };
+
+    let row = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::button::icon(icon::from_name("media-playback-start-symbolic"))
+                .tooltip(fl!("history-replay"))
+                .on_press(Message::LoadPath(location.clone())),
+        )
+        .push(
+            widget::button::icon(icon::from_name("list-add-symbolic"))
+                .tooltip(fl!("history-enqueue"))
+                .on_press(Message::Enqueue(location)),
+        )
+        .push(
+            widget::text(entry.started().format("%H:%M").to_string())
+                .width(Length::Fixed(48.0)),
+        )
+        .push(widget::text(label).width(Length::Fill))
+        .push(widget::text(source).width(Length::Fixed(80.0)))
+        .push(widget::text(format_time(entry.listened_ms)).width(Length::Fixed(64.0)))
+        .push(status);
+
+    widget::container(row).padding([4, 8]).into()
+}
+
+pub fn history_view(app: &AppModel) -> Element<'_, Message> {
+    let Some(history) = app.history() else {
+        return widget::text(fl!("history-unavailable"))
+            .apply(widget::container)
+            .center(Length::Fill)
+            .into();
+    };
+
+    let actions = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text::title3(fl!("nav-history-label")).width(Length::Fill))
+        .push(widget::text(fl!("history-prune")))
+        .push(widget::dropdown(
+            &app.history_prune_labels,
+            Some(app.history_prune_choice),
+            Message::HistoryPruneChoice,
+        ))
+        .push(
+            widget::button::standard(fl!("history-prune-button"))
+                .on_press_maybe((!history.entries().is_empty()).then_some(Message::HistoryPrune)),
+        )
+        .push(
+            widget::button::standard(fl!("history-export"))
+                .leading_icon(icon::from_name("document-save-as-symbolic"))
+                .on_press_maybe((!history.entries().is_empty()).then_some(Message::HistoryExport)),
+        );
+
+    if history.entries().is_empty() {
+        return widget::column()
+            .spacing(12)
+            .push(actions)
+            .push(
+                widget::text(fl!("history-empty"))
+                    .apply(widget::container)
+                    .center(Length::Fill),
+            )
+            .into();
+    }
+
+    // Listens grouped under a heading per day
+    let today = chrono::Local::now().date_naive();
+    let mut list = widget::column().spacing(4);
+    let mut shown = 0;
+    for (day, entries) in history.by_day() {
+        if shown >= MAX_ENTRIES {
+            break;
+        }
+        let heading = match (today - day).num_days() {
+            0 => fl!("library-today"),
+            1 => fl!("history-yesterday"),
+            _ => day.format("%A, %-d %B %Y").to_string(),
+        };
+        list = list.push(widget::container(widget::text::heading(heading)).padding([8, 8]));
+        for entry in entries.into_iter().take(MAX_ENTRIES - shown) {
+            list = list.push(entry_row(app, entry));
+            shown += 1;
+        }
+    }
+
+    widget::column()
+        .spacing(12)
+        .push(actions)
+        .push(widget::scrollable(list).height(Length::Fill))
+        .apply(widget::container)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Left)
+        .align_y(Vertical::Top)
+        .into()
+}
@@ -3,14 +3,107 @@ use cosmic::widget;
 use cosmic::widget::icon;
 use cosmic::iced::Length;
 use cosmic::iced::alignment::{Horizontal, Vertical};
+use std::time::SystemTime;
 
-use super::super::{AppModel, Message};
+use super::super::{format_time, AppModel, LibrarySort, Message, TrackSource};
+use super::rating_controls;
+use crate::fl;
+use music_player::audio::metadata::unix_time;
+
+const STAT_WIDTH: f32 = 90.0;
+
+/// Room for five stars and the favorite toggle
+const RATING_WIDTH: f32 = 150.0;
+
+/// A column header that sorts the library, marked with the current direction.
+fn sort_header(app: &AppModel, sort: LibrarySort, label: String) -> widget::Button<'_, Message> {
+    let (current, descending) = app.library_sort();
+    let button = if current == sort {
+        let arrow = if descending {
+            "go-down-symbolic"
+        } else {
+            "go-up-symbolic"
+        };
+        widget::button::text(label).trailing_icon(icon::from_name(arrow))
+    } else {
+        widget::button::text(label)
+    };
+    button.on_press(Message::LibrarySort(sort))
+}
+
+/// Describe a Unix timestamp relative to today.
+fn days_ago(timestamp: u64, now: u64) -> String {
+    let days = now.saturating_sub(timestamp) / (24 * 60 * 60);
+    match days {
+        0 => fl!("library-today"),
+        days => fl!("library-days-ago", days = days),
+    }
+}
 
 pub fn library_view(app: &AppModel) -> Element<'_, Message> {
+    let now = unix_time(SystemTime::now());
+
+    let selected = app.library_selection().len();
+    let toolbar = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::button::standard(fl!("library-edit-tags", count = selected))
+                .leading_icon(icon::from_name("document-edit-symbolic"))
+                .on_press_maybe((selected > 0).then_some(Message::EditSelectedTags)),
+        )
+        .push(
+            widget::button::standard(fl!("library-look-up-album"))
+                .leading_icon(icon::from_name("system-search-symbolic"))
+                .on_press_maybe((selected > 0).then_some(Message::LookUpAlbum)),
+        )
+        .push(
+            widget::button::text(fl!("library-select-none"))
+                .on_press_maybe((selected > 0).then_some(Message::LibrarySelectNone)),
+        );
+
+    let header = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::Space::with_width(Length::Fixed(112.0)))
+        .push(sort_header(app, LibrarySort::Title, fl!("library-title")).width(Length::Fill))
+        .push(
+            sort_header(app, LibrarySort::PlayCount, fl!("library-plays"))
+                .width(Length::Fixed(STAT_WIDTH)),
+        )
+        .push(
+            sort_header(app, LibrarySort::SkipCount, fl!("library-skips"))
+                .width(Length::Fixed(STAT_WIDTH)),
+        )
+        .push(
+            sort_header(app, LibrarySort::LastPlayed, fl!("library-last-played"))
+                .width(Length::Fixed(STAT_WIDTH + 30.0)),
+        )
+        .push(
+            sort_header(app, LibrarySort::ListeningTime, fl!("library-listened"))
+                .width(Length::Fixed(STAT_WIDTH)),
+        )
+        .push(
+            sort_header(app, LibrarySort::Rating, fl!("library-rating"))
+                .width(Length::Fixed(RATING_WIDTH)),
+        );
+
     // Rows
     let mut rows = widget::column().spacing(4);
-    for path in app.library_tracks().iter().take(200) {
+    for (index, path) in app.sorted_library().into_iter().take(200) {
         let label = app.library_display_text(path);
+        let md = app.track_metadata(path);
+        let plays = md.map_or(0, |m| m.play_count);
+        let skips = md.map_or(0, |m| m.skip_count);
+        let last_played = md
+            .and_then(|m| m.last_played)
+            .map(|t| days_ago(t, now))
+            .unwrap_or_else(|| fl!("library-never"));
+        let listened = md.map(|m| m.listening_time).unwrap_or_default();
+        let rating: Element<'_, Message> = match md {
+            Some(md) => rating_controls(path, md),
+            None => widget::Space::with_width(Length::Shrink).into(),
+        };
 
         let play_btn = widget::button::icon(icon::from_name("media-playback-start-symbolic"))
             .on_press(Message::LoadPath(path.to_string_lossy().into_owned()));
@@ -18,18 +111,46 @@ pub fn library_view(app: &AppModel) -> Element<'_, Message> {
         let add_btn = widget::button::icon(icon::from_name("list-add-symbolic"))
             .on_press(Message::Enqueue(path.to_string_lossy().into_owned()));
 
-        let row = widget::row()
+        let select_path = path.clone();
+        let select = widget::checkbox("", app.library_selection().contains(path))
+            .on_toggle(move |on| Message::LibrarySelect(select_path.clone(), on));
+
+        let mut row = widget::row()
             .spacing(8)
             .align_y(Vertical::Center)
+            .push(select)
             .push(play_btn)
             .push(add_btn)
             .push(widget::text(label).width(Length::Fill))
+            .push(widget::text(plays.to_string()).width(Length::Fixed(STAT_WIDTH)))
+            .push(widget::text(skips.to_string()).width(Length::Fixed(STAT_WIDTH)))
+            .push(widget::text(last_played).width(Length::Fixed(STAT_WIDTH + 30.0)))
+            .push(
+                widget::text(format_time(listened.as_millis() as u64))
+                    .width(Length::Fixed(STAT_WIDTH)),
+            )
+            .push(widget::container(rating).width(Length::Fixed(RATING_WIDTH)))
             .width(Length::Fill);
 
-        rows = rows.push(widget::container(row).padding([4, 8]));
+        // Flag files no installed decoder can handle
+        if !app.is_track_playable(path) {
+            row = row.push(widget::tooltip(
+                icon::from_name("dialog-warning-symbolic").icon(),
+                widget::text(fl!("library-unsupported-codec")),
+                widget::tooltip::Position::Left,
+            ));
+        }
+
+        let menu = app.track_menu(TrackSource::Library(index));
+        rows = rows.push(widget::context_menu(
+            widget::container(row).padding([4, 8]),
+            Some(menu),
+        ));
     }
 
     let library = widget::column()
+        .push(widget::container(toolbar).padding([0, 8, 8, 8]))
+        .push(widget::container(header).padding([0, 8]))
         .push(widget::scrollable(rows).height(Length::FillPortion(1)));
 
     widget::column()
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::Vertical;
+use music_player::lrc::{format_time, LyricLine};
+
+use super::super::{AppModel, LyricsEditor, LyricsTarget, Message};
+use crate::fl;
+
+/// How far the nudge buttons move a line's time, in milliseconds.
+const NUDGE_MS: i64 = 100;
+
+const TIME_WIDTH: f32 = 80.0;
+
+/// A line being timed: its time, which seeks there, buttons to nudge it, and
+/// its text, which selects it for the next stamp.
+fn line_row(
+    index: usize,
+    line: &LyricLine,
+    selected: bool,
+    sung: bool,
+) -> Element<'static, Message> {
+    let label = line.time.map(format_time).unwrap_or_else(|| "--:--.--".into());
+    let time = widget::button::text(label)
+        .width(Length::Fixed(TIME_WIDTH))
+        .on_press_maybe(line.time.map(Message::SeekToTime));
+    let nudge = |name, delta| {
+        widget::button::icon(icon::from_name(name))
+            .extra_small()
+            .on_press_maybe(line.time.map(|_| Message::LyricsEditorNudge(index, delta)))
+    };
+    let text = if selected {
+        widget::text::heading(line.text.clone())
+    } else {
+        widget::text::body(line.text.clone())
+    };
+    let text = if sung {
+        text.class(cosmic::theme::Text::Accent)
+    } else {
+        text
+    };
+
+    widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(time)
+        .push(nudge("go-previous-symbolic", -NUDGE_MS))
+        .push(nudge("go-next-symbolic", NUDGE_MS))
+        .push(
+            widget::mouse_area(text.width(Length::Fill))
+                .on_press(Message::LyricsEditorSelect(index)),
+        )
+        .into()
+}
+
+/// Editor for the playing track's lyrics: first as text, pasted or typed, then
+/// line by line, stamping each with the playback position as it is sung.
+pub fn lyrics_editor_view<'a>(app: &'a AppModel, editor: &'a LyricsEditor) -> Element<'a, Message> {
+    let cancel = widget::button::standard(fl!("lyrics-editor-cancel"))
+        .on_press_maybe((!editor.saving).then_some(Message::LyricsEditorCancel));
+
+    if !editor.timing {
+        let text = widget::text_editor(&editor.text)
+            .placeholder(fl!("lyrics-editor-placeholder"))
+            .on_action(Message::LyricsEditorText)
+            .height(Length::Fill);
+        let actions = widget::row()
+            .spacing(8)
+            .push(
+                widget::button::suggested(fl!("lyrics-editor-start-timing"))
+                    .on_press(Message::LyricsEditorStartTiming),
+            )
+            .push(cancel);
+        return widget::column()
+            .spacing(8)
+            .push(widget::text::caption(fl!("lyrics-editor-text-hint")))
+            .push(text)
+            .push(actions)
+            .into();
+    }
+
+    // Preview: the line the stamps so far put at the playback position
+    let position = Duration::from_millis(app.position_ms);
+    let sung = editor
+        .draft
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.time.is_some_and(|t| t <= position))
+        .max_by_key(|(_, line)| line.time)
+        .map(|(i, _)| i);
+    let mut lines = widget::column().spacing(4);
+    for (i, line) in editor.draft.lines.iter().enumerate() {
+        lines = lines.push(line_row(i, line, i == editor.selected, Some(i) == sung));
+    }
+
+    let timed = editor.draft.lines.iter().filter(|l| l.time.is_some()).count();
+    let mut page = widget::column()
+        .spacing(8)
+        .push(widget::text::caption(fl!("lyrics-editor-timing-hint")))
+        .push(widget::text::caption(fl!(
+            "lyrics-editor-progress",
+            timed = timed,
+            total = editor.draft.lines.len()
+        )))
+        .push(widget::scrollable(lines).height(Length::Fill));
+    if let Some(error) = &editor.error {
+        page = page.push(widget::text(error.clone()));
+    }
+
+    let can_save = !editor.saving && !editor.draft.lines.is_empty();
+    let actions = widget::row()
+        .spacing(8)
+        .push(
+            widget::button::suggested(fl!("lyrics-editor-save-lrc")).on_press_maybe(
+                can_save.then_some(Message::LyricsEditorSave(LyricsTarget::Sidecar)),
+            ),
+        )
+        .push(
+            widget::button::standard(fl!("lyrics-editor-embed")).on_press_maybe(
+                can_save.then_some(Message::LyricsEditorSave(LyricsTarget::Embedded)),
+            ),
+        )
+        .push(
+            widget::button::standard(fl!("lyrics-editor-edit-text"))
+                .on_press_maybe((!editor.saving).then_some(Message::LyricsEditorEditText)),
+        )
+        .push(cancel);
+    page.push(actions).into()
+}
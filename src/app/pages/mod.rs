@@ -1,9 +1,39 @@
+use std::path::Path;
+
+use cosmic::iced::alignment::Vertical;
 use cosmic::prelude::*;
+use cosmic::widget::{self, icon};
+use music_player::audio::backend::TrackMetadata;
+use music_player::audio::rating::MAX_STARS;
 
 use super::{AppModel, Message, Page};
+use crate::fl;
 
+mod album_lookup;
+mod diagnostics;
+mod duplicates;
+mod equalizer;
+mod history;
 mod library;
+mod lyrics_editor;
 mod now_playing;
+mod organizer;
+mod parametric;
+mod playlists;
+mod practice;
+mod stats;
+mod tag_editor;
+
+pub use album_lookup::{album_lookup_dialog, candidate_label};
+pub use duplicates::{tolerance_labels, DUPLICATE_TOLERANCES};
+pub use equalizer::preset_label;
+pub use history::{prune_labels, PRUNE_AGES};
+pub use organizer::organizer_dialog;
+pub use parametric::filter_kind_labels;
+pub use playlists::{SmartLabels, LIMIT_UNITS, MATCH_MODES};
+pub use practice::loop_markers;
+pub use stats::stats_range_labels;
+pub use tag_editor::tag_editor_dialog;
 
 pub fn page_view(app: &AppModel) -> Element<'_, Message> {
     let active_page = app
@@ -14,6 +44,45 @@ pub fn page_view(app: &AppModel) -> Element<'_, Message> {
 
     match active_page {
         Page::Page1 => library::library_view(app),
-        Page::Page2 => now_playing::now_playing_view(app)
+        Page::Page2 => now_playing::now_playing_view(app),
+        Page::Page3 => diagnostics::diagnostics_view(app),
+        Page::Page4 => playlists::playlists_view(app),
+        Page::Page5 => history::history_view(app),
+        Page::Page6 => stats::stats_view(app),
+        Page::Page7 => duplicates::duplicates_view(app),
+        Page::Page8 => equalizer::equalizer_view(app),
+    }
+}
+
+/// Stars to rate a library track, and a toggle to mark it as a favorite.
+pub fn rating_controls(path: &Path, md: &TrackMetadata) -> Element<'static, Message> {
+    let rating = md.rating.unwrap_or(0);
+    let mut row = widget::row().align_y(Vertical::Center);
+    for stars in 1..=MAX_STARS {
+        let name = if stars <= rating {
+            "starred-symbolic"
+        } else {
+            "non-starred-symbolic"
+        };
+        // Picking the current rating again clears it
+        let target = if stars == rating { 0 } else { stars };
+        row = row.push(
+            widget::button::icon(icon::from_name(name))
+                .extra_small()
+                .on_press(Message::RateTrack(path.to_path_buf(), target)),
+        );
     }
+    let favorite_tooltip = if md.favorite {
+        fl!("rating-unfavorite")
+    } else {
+        fl!("rating-favorite")
+    };
+    row.push(
+        widget::button::icon(icon::from_name("emblem-favorite-symbolic"))
+            .extra_small()
+            .selected(md.favorite)
+            .tooltip(favorite_tooltip)
+            .on_press(Message::ToggleFavorite(path.to_path_buf())),
+    )
+    .into()
 }
@@ -1,14 +1,204 @@
+use std::time::Duration;
+
 use cosmic::prelude::*;
 use cosmic::widget;
+use cosmic::widget::icon;
 use cosmic::iced::Length;
-use cosmic::iced::alignment::{Horizontal};
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use music_player::audio::backend::{MediaPlayer, MAX_RATE, MIN_RATE};
+use music_player::audio::effects::MAX_PITCH_SHIFT;
+use music_player::lrc::{LyricLine, Lyrics};
+
+use super::super::{rate_percent, AppModel, Artwork, Message, RateScope};
+use super::lyrics_editor::lyrics_editor_view;
+use super::practice::practice_section;
+use super::rating_controls;
+use crate::fl;
+
+/// Side of the cover shown on the page, in pixels.
+const COVER_SIZE: f32 = 240.0;
+
+const CONTROL_SLIDER_WIDTH: f32 = 240.0;
+
+/// Lines of synchronized lyrics shown before and after the one being sung.
+const LINES_BEFORE: usize = 3;
+const LINES_AFTER: usize = 5;
+
+/// The line being sung, with the words sung so far highlighted.
+fn current_line(line: &LyricLine, position: Duration) -> Element<'static, Message> {
+    let Some(sung) = line.word_at(position) else {
+        return widget::text::title4(line.text.clone()).into();
+    };
+    let mut row = widget::row();
+    for (i, word) in line.words.iter().enumerate() {
+        let text = widget::text::title4(word.text.clone());
+        row = row.push(if i <= sung {
+            text.class(cosmic::theme::Text::Accent)
+        } else {
+            text
+        });
+    }
+    row.into()
+}
+
+/// Lyrics of the playing track. Synchronized lyrics follow the track, the line
+/// being sung kept in view and highlighted; clicking a line seeks to it.
+fn lyrics_section(lyrics: &Lyrics, position: Duration) -> Element<'static, Message> {
+    let mut lines = widget::column().spacing(6).align_x(Horizontal::Center);
+    if !lyrics.is_synced() {
+        for line in &lyrics.lines {
+            lines = lines.push(widget::text::body(line.text.clone()));
+        }
+        return widget::scrollable(lines).height(Length::Fill).into();
+    }
+
+    let current = lyrics.line_at(position);
+    let first = current.map_or(0, |i| i.saturating_sub(LINES_BEFORE));
+    let last = current.map_or(LINES_AFTER, |i| i + LINES_AFTER);
+    for (i, line) in lyrics.lines.iter().enumerate().take(last + 1).skip(first) {
+        let Some(time) = line.time else {
+            continue;
+        };
+        let text = if Some(i) == current {
+            current_line(line, position)
+        } else {
+            widget::text::body(line.text.clone()).into()
+        };
+        lines = lines.push(widget::mouse_area(text).on_press(Message::SeekToTime(time)));
+    }
+    lines.into()
+}
+
+/// The cover of the playing track, with actions to change the album's artwork.
+fn artwork_section(artwork: &Artwork) -> Element<'_, Message> {
+    let cover: Element<'_, Message> = match &artwork.thumbnail {
+        Some(handle) => widget::image(handle.clone())
+            .width(Length::Fixed(COVER_SIZE))
+            .height(Length::Fixed(COVER_SIZE))
+            .into(),
+        None => widget::container(icon::from_name("folder-music-symbolic").size(96))
+            .center(Length::Fixed(COVER_SIZE))
+            .into(),
+    };
+    let has_cover = artwork.thumbnail.is_some();
+
+    let actions = widget::row()
+        .spacing(8)
+        .push(
+            widget::button::standard(fl!("artwork-set"))
+                .on_press(Message::SetCoverFromFile),
+        )
+        .push(
+            widget::button::standard(fl!("artwork-use-folder"))
+                .on_press_maybe(artwork.folder_cover.clone().map(Message::SetCover)),
+        )
+        .push(
+            widget::button::standard(fl!("artwork-extract"))
+                .on_press_maybe(has_cover.then_some(Message::ExtractCover)),
+        )
+        .push(
+            widget::button::destructive(fl!("artwork-remove"))
+                .on_press_maybe(has_cover.then_some(Message::RemoveArtwork)),
+        );
+
+    widget::column()
+        .spacing(8)
+        .align_x(Horizontal::Center)
+        .push(cover)
+        .push(actions)
+        .into()
+}
+
+/// Playback speed and pitch, and the speed remembered for the track or its album.
+fn speed_controls<'a>(app: &'a AppModel, player: &MediaPlayer) -> Element<'a, Message> {
+    let percent = rate_percent(player.rate());
+    let speed = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(fl!("speed")))
+        .push(
+            widget::slider(
+                rate_percent(MIN_RATE)..=rate_percent(MAX_RATE),
+                percent,
+                Message::PlaybackRate,
+            )
+            .step(5u32)
+            .width(Length::Fixed(CONTROL_SLIDER_WIDTH)),
+        )
+        .push(widget::text(fl!(
+            "speed-value",
+            speed = format!("{:.2}", f64::from(percent) / 100.0)
+        )))
+        .push(
+            widget::button::text(fl!("speed-normal"))
+                .on_press_maybe((percent != 100).then_some(Message::PlaybackRate(100))),
+        );
+
+    let current = app.queue.current();
+    let remembered = current.and_then(|p| app.remembered_rate(p));
+    let in_library = current.is_some_and(|p| app.library_meta.contains_key(p));
+    let has_album = current.is_some_and(|p| app.album_key(p).is_some());
+    let mut remember = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::button::standard(fl!("speed-remember-track"))
+                .on_press_maybe(in_library.then_some(Message::RememberRate(RateScope::Track))),
+        )
+        .push(
+            widget::button::standard(fl!("speed-remember-album"))
+                .on_press_maybe(has_album.then_some(Message::RememberRate(RateScope::Album))),
+        );
+    if let Some(remembered) = remembered {
+        remember = remember
+            .push(widget::text::caption(fl!(
+                "speed-remembered",
+                speed = format!("{:.2}", f64::from(remembered) / 100.0)
+            )))
+            .push(widget::button::text(fl!("speed-forget")).on_press(Message::ForgetRate));
+    }
+
+    let pitch: Element<'a, Message> = if player.has_pitch() {
+        widget::row()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(widget::text(fl!("pitch")))
+            .push(
+                widget::slider(
+                    -MAX_PITCH_SHIFT..=MAX_PITCH_SHIFT,
+                    app.pitch_shift,
+                    Message::PitchShift,
+                )
+                .step(1)
+                .width(Length::Fixed(CONTROL_SLIDER_WIDTH)),
+            )
+            .push(widget::text(fl!(
+                "pitch-value",
+                semitones = app.pitch_shift.abs(),
+                shift = format!("{:+}", app.pitch_shift)
+            )))
+            .push(
+                widget::button::text(fl!("speed-normal"))
+                    .on_press_maybe((app.pitch_shift != 0).then_some(Message::PitchShift(0))),
+            )
+            .into()
+    } else {
+        widget::text::caption(fl!("pitch-unavailable")).into()
+    };
 
-use super::super::{AppModel, Message};
+    widget::column()
+        .spacing(8)
+        .align_x(Horizontal::Center)
+        .push(speed)
+        .push(remember)
+        .push(pitch)
+        .into()
+}
 
 pub fn now_playing_view(app: &AppModel) -> Element<'_, Message> {
     // Read metadata for current track if available
     let (title, artist, album) = if let Some(player) = &app.audio {
-        let md = player.metadata();
+        let md = app.current_metadata(player);
         (
             md.title.unwrap_or_else(|| "Unknown Title".into()),
             md.artist.unwrap_or_else(|| "Unknown Artist".into()),
@@ -27,12 +217,48 @@ pub fn now_playing_view(app: &AppModel) -> Element<'_, Message> {
     let prev = widget::button::standard("Prev").on_press(Message::Prev);
     let next = widget::button::standard("Next").on_press(Message::Next);
 
-    widget::column()
+    let mut column = widget::column()
         .spacing(12)
-        .push(widget::text::title1("Now Playing"))
+        .push(widget::text::title1("Now Playing"));
+    if let Some(artwork) = app.current_artwork() {
+        column = column.push(artwork_section(artwork));
+    }
+    column = column
         .push(widget::text(format!("{}", title)))
-        .push(widget::text(format!("{} — {}", artist, album)))
-        .push(widget::row().spacing(8).push(prev).push(play).push(pause).push(stop).push(next))
+        .push(widget::text(format!("{} — {}", artist, album)));
+    if let Some((path, md)) = app
+        .queue
+        .current()
+        .and_then(|p| Some((p, app.track_metadata(p)?)))
+    {
+        column = column.push(rating_controls(path, md));
+    }
+    column = column
+        .push(widget::row().spacing(8).push(prev).push(play).push(pause).push(stop).push(next));
+    if let Some(player) = &app.audio {
+        column = column
+            .push(speed_controls(app, player))
+            .push(practice_section(app));
+    }
+    if let Some(editor) = &app.lyrics_editor {
+        column = column.push(lyrics_editor_view(app, editor));
+    } else {
+        let can_edit = app
+            .queue
+            .current()
+            .is_some_and(|p| !app.virtual_tracks.contains_key(p));
+        column = column.push(
+            widget::button::standard(fl!("lyrics-edit"))
+                .leading_icon(icon::from_name("document-edit-symbolic"))
+                .on_press_maybe(can_edit.then_some(Message::LyricsEdit)),
+        );
+        if let Some(lyrics) = app.current_lyrics() {
+            let position = Duration::from_millis(app.position_ms);
+            column = column.push(lyrics_section(lyrics, position));
+        }
+    }
+
+    column
         .width(Length::Fill)
         .height(Length::Fill)
         .align_x(Horizontal::Center)
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::iced::Length;
+use music_player::organize::Move;
+
+use super::super::{music_dir, AppModel, Message, Organizer};
+use crate::fl;
+
+/// Moves listed in the preview; the summary counts all of them.
+const MAX_PREVIEW: usize = 200;
+
+/// A path as shown in the preview, relative to the music folder when inside it.
+fn display_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn move_row(mv: &Move, root: &Path) -> Element<'static, Message> {
+    widget::column()
+        .push(widget::text::caption(display_path(&mv.from, root)))
+        .push(widget::text::body(format!("→ {}", display_path(&mv.to, root))))
+        .into()
+}
+
+/// Dialog for moving and renaming library files after a path template, with a
+/// preview of every move before anything is touched.
+pub fn organizer_dialog<'a>(app: &'a AppModel, organizer: &'a Organizer) -> Element<'a, Message> {
+    let root = music_dir();
+    let selected = app.library_selection().len();
+    let scope = if selected > 0 {
+        fl!("organize-scope-selection", count = selected)
+    } else {
+        fl!("organize-scope-library")
+    };
+
+    let mut control = widget::column()
+        .spacing(8)
+        .push(
+            widget::text_input(fl!("organize-template"), &organizer.template)
+                .on_input(Message::OrganizeTemplate)
+                .on_submit(|_| Message::OrganizePreview),
+        )
+        .push(widget::text::caption(fl!("organize-placeholders")));
+    if let Some(error) = &organizer.error {
+        control = control.push(widget::text(error.clone()));
+    }
+
+    if let Some(plan) = &organizer.preview {
+        control = control.push(widget::text::heading(fl!(
+            "organize-summary",
+            tracks = plan.tracks.len(),
+            companions = plan.companions.len(),
+            unchanged = plan.unchanged
+        )));
+        let mut moves = widget::column().spacing(6);
+        for mv in plan.moves().take(MAX_PREVIEW) {
+            moves = moves.push(move_row(mv, &root));
+        }
+        control = control.push(widget::scrollable(moves).height(Length::Fixed(300.0)));
+    }
+
+    let has_moves = organizer
+        .preview
+        .as_ref()
+        .is_some_and(|plan| plan.moves().next().is_some());
+    let organize = widget::button::suggested(fl!("organize-run"))
+        .on_press_maybe((has_moves && !organizer.running).then_some(Message::OrganizeRun));
+    let preview = widget::button::standard(fl!("organize-preview"))
+        .on_press_maybe((!organizer.running).then_some(Message::OrganizePreview));
+    let cancel = widget::button::standard(fl!("organize-cancel"))
+        .on_press_maybe((!organizer.running).then_some(Message::OrganizeCancel));
+
+    widget::dialog()
+        .title(fl!("organize-library"))
+        .body(scope)
+        .control(control)
+        .primary_action(organize)
+        .secondary_action(cancel)
+        .tertiary_action(preview)
+        .into()
+}
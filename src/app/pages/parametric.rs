@@ -0,0 +1,232 @@
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::Vertical;
+use music_player::parametric::{Filter, FilterKind};
+
+use super::super::{AppModel, FilterInput, Message, ProfileEditor};
+use super::stats::section;
+use crate::fl;
+
+const NUMBER_WIDTH: f32 = 90.0;
+
+pub fn filter_kind_labels() -> Vec<String> {
+    FilterKind::ALL
+        .iter()
+        .map(|kind| match kind {
+            FilterKind::Peak => fl!("parametric-peak"),
+            FilterKind::LowShelf => fl!("parametric-low-shelf"),
+            FilterKind::HighShelf => fl!("parametric-high-shelf"),
+            FilterKind::LowPass => fl!("parametric-low-pass"),
+            FilterKind::HighPass => fl!("parametric-high-pass"),
+        })
+        .collect()
+}
+
+/// A field for one of a filter's numbers, with its unit after it.
+fn number_input<'a>(
+    index: usize,
+    input: FilterInput,
+    text: &'a str,
+    unit: String,
+) -> Element<'a, Message> {
+    widget::row()
+        .spacing(4)
+        .align_y(Vertical::Center)
+        .push(
+            widget::text_input("", text)
+                .on_input(move |text| Message::ParametricFilterInput(index, input, text))
+                .width(Length::Fixed(NUMBER_WIDTH)),
+        )
+        .push(widget::text::caption(unit))
+        .into()
+}
+
+fn filter_row<'a>(
+    app: &'a AppModel,
+    index: usize,
+    filter: &Filter,
+    texts: &'a [String; 3],
+) -> Element<'a, Message> {
+    let kind = FilterKind::ALL.iter().position(|k| *k == filter.kind);
+    let mut row = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::checkbox("", filter.enabled)
+                .on_toggle(move |on| Message::ParametricFilterEnabled(index, on)),
+        )
+        .push(widget::dropdown(
+            &app.equalizer.filter_kind_labels,
+            kind,
+            move |kind| Message::ParametricFilterKind(index, kind),
+        ))
+        .push(number_input(
+            index,
+            FilterInput::Frequency,
+            &texts[0],
+            fl!("parametric-hz"),
+        ));
+    if filter.kind.has_gain() {
+        row = row.push(number_input(
+            index,
+            FilterInput::Gain,
+            &texts[1],
+            fl!("parametric-db"),
+        ));
+    }
+    row.push(number_input(index, FilterInput::Q, &texts[2], fl!("parametric-q")))
+        .push(widget::horizontal_space())
+        .push(
+            widget::button::icon(icon::from_name("edit-delete-symbolic"))
+                .extra_small()
+                .tooltip(fl!("parametric-remove-filter"))
+                .on_press(Message::ParametricFilterRemove(index)),
+        )
+        .into()
+}
+
+/// The profile being made or changed: its name, preamp and filters, which
+/// play as they are changed.
+fn editor_view<'a>(app: &'a AppModel, editor: &'a ProfileEditor) -> Element<'a, Message> {
+    let header = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::text_input(fl!("parametric-name"), &editor.profile.name)
+                .on_input(Message::ParametricEditorName)
+                .width(Length::Fixed(240.0)),
+        )
+        .push(widget::text(fl!("parametric-preamp")))
+        .push(
+            widget::text_input("", &editor.preamp)
+                .on_input(Message::ParametricEditorPreamp)
+                .width(Length::Fixed(NUMBER_WIDTH)),
+        )
+        .push(widget::text::caption(fl!("parametric-db")));
+
+    let mut filters = widget::column().spacing(4);
+    for (index, (filter, texts)) in editor
+        .profile
+        .filters
+        .iter()
+        .zip(&editor.inputs)
+        .enumerate()
+    {
+        filters = filters.push(filter_row(app, index, filter, texts));
+    }
+
+    let actions = widget::row()
+        .spacing(8)
+        .push(
+            widget::button::standard(fl!("parametric-add-filter"))
+                .leading_icon(icon::from_name("list-add-symbolic"))
+                .on_press(Message::ParametricFilterAdd),
+        )
+        .push(widget::horizontal_space())
+        .push(
+            widget::button::suggested(fl!("parametric-save")).on_press_maybe(
+                (!editor.profile.name.trim().is_empty())
+                    .then_some(Message::ParametricEditorSave),
+            ),
+        )
+        .push(
+            widget::button::standard(fl!("parametric-cancel"))
+                .on_press(Message::ParametricEditorCancel),
+        );
+
+    widget::column()
+        .spacing(8)
+        .push(header)
+        .push(filters)
+        .push(actions)
+        .into()
+}
+
+/// The parametric equalizer: the profile in use, profiles to pick, import,
+/// make and change, and the output device's own profile.
+pub fn parametric_section(app: &AppModel) -> Element<'_, Message> {
+    let title = fl!("parametric-title");
+    if let Some(editor) = &app.equalizer.profile_editor {
+        return section(title, editor_view(app, editor));
+    }
+
+    let profile = app.current_parametric();
+    let selected = profile.and_then(|profile| {
+        app.config
+            .parametric_profiles
+            .iter()
+            .position(|p| p.name == profile.name)
+    });
+    let profiles = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::toggler(app.config.parametric_enabled)
+                .label(fl!("equalizer-enabled"))
+                .on_toggle(Message::ParametricEnabled),
+        )
+        .push(widget::dropdown(
+            &app.equalizer.profile_labels,
+            selected,
+            Message::ParametricProfile,
+        ))
+        .push(
+            widget::button::standard(fl!("parametric-import"))
+                .leading_icon(icon::from_name("document-open-symbolic"))
+                .on_press(Message::ParametricImport),
+        )
+        .push(widget::button::standard(fl!("parametric-new")).on_press(Message::ParametricNew))
+        .push(
+            widget::button::standard(fl!("parametric-edit"))
+                .on_press_maybe(profile.is_some().then_some(Message::ParametricEdit)),
+        )
+        .push(
+            widget::button::icon(icon::from_name("user-trash-symbolic"))
+                .tooltip(fl!("parametric-delete"))
+                .on_press_maybe(profile.is_some().then_some(Message::ParametricDelete)),
+        );
+
+    let mut content = widget::column().spacing(8).push(profiles);
+    if let Some(profile) = profile {
+        let enabled = profile.filters.iter().filter(|f| f.enabled).count();
+        content = content.push(widget::text::caption(fl!(
+            "parametric-summary",
+            filters = enabled,
+            preamp = format!("{:+.1}", profile.preamp_db())
+        )));
+    }
+    if let Some(notice) = &app.equalizer.parametric_notice {
+        content = content.push(widget::text::caption(notice.clone()));
+    }
+
+    let device = app.equalizer.device.as_deref();
+    let device_profile = device.and_then(|d| app.config.parametric_device_profiles.get(d));
+    let device_label = match (device, device_profile) {
+        (None, _) => fl!("equalizer-device-unknown"),
+        (Some(device), None) => fl!("parametric-device", device = device),
+        (Some(device), Some(profile)) => fl!(
+            "equalizer-device-bound",
+            device = device,
+            preset = profile.clone()
+        ),
+    };
+    let mut device_row = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(device_label).width(Length::Fill))
+        .push(
+            widget::button::standard(fl!("equalizer-bind-device")).on_press_maybe(
+                (device.is_some() && profile.is_some()).then_some(Message::ParametricBindDevice),
+            ),
+        );
+    if device_profile.is_some() {
+        device_row = device_row.push(
+            widget::button::standard(fl!("equalizer-unbind"))
+                .on_press(Message::ParametricUnbindDevice),
+        );
+    }
+
+    section(title, content.push(device_row))
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use music_player::playlist::smart::{Field, LimitUnit, Match, Operator, SmartPlaylist, SortOrder};
+use music_player::playlist::store::NamedPlaylist;
+
+use super::super::{AppModel, Message, SmartEdit};
+use crate::fl;
+
+/// Labels for the smart playlist editor's dropdowns, built once so the
+/// widgets can borrow them.
+pub struct SmartLabels {
+    pub match_modes: Vec<String>,
+    pub fields: Vec<String>,
+    /// Operators offered for each field, in `Field::operators` order
+    pub operators: HashMap<Field, Vec<String>>,
+    /// "No limit" followed by the `LimitUnit`s in `LIMIT_UNITS` order
+    pub limits: Vec<String>,
+    pub sorts: Vec<String>,
+}
+
+pub const MATCH_MODES: [Match; 2] = [Match::All, Match::Any];
+pub const LIMIT_UNITS: [LimitUnit; 3] = [LimitUnit::Tracks, LimitUnit::Minutes, LimitUnit::Hours];
+
+impl Default for SmartLabels {
+    fn default() -> Self {
+        Self {
+            match_modes: vec![fl!("smart-match-all"), fl!("smart-match-any")],
+            fields: Field::ALL.iter().map(|f| field_label(*f)).collect(),
+            operators: Field::ALL
+                .iter()
+                .map(|f| (*f, f.operators().iter().map(|o| operator_label(*o)).collect()))
+                .collect(),
+            limits: vec![
+                fl!("smart-no-limit"),
+                fl!("smart-limit-tracks"),
+                fl!("smart-limit-minutes"),
+                fl!("smart-limit-hours"),
+            ],
+            sorts: SortOrder::ALL.iter().map(|s| sort_label(*s)).collect(),
+        }
+    }
+}
+
+fn field_label(field: Field) -> String {
+    match field {
+        Field::Title => fl!("smart-field-title"),
+        Field::Artist => fl!("smart-field-artist"),
+        Field::Album => fl!("smart-field-album"),
+        Field::Genre => fl!("smart-field-genre"),
+        Field::Year => fl!("smart-field-year"),
+        Field::Duration => fl!("smart-field-duration"),
+        Field::PlayCount => fl!("smart-field-play-count"),
+        Field::SkipCount => fl!("smart-field-skip-count"),
+        Field::Rating => fl!("smart-field-rating"),
+        Field::Favorite => fl!("smart-field-favorite"),
+        Field::DateAdded => fl!("smart-field-date-added"),
+        Field::FirstPlayed => fl!("smart-field-first-played"),
+        Field::LastPlayed => fl!("smart-field-last-played"),
+    }
+}
+
+fn operator_label(operator: Operator) -> String {
+    match operator {
+        Operator::Is => fl!("smart-op-is"),
+        Operator::IsNot => fl!("smart-op-is-not"),
+        Operator::Contains => fl!("smart-op-contains"),
+        Operator::DoesNotContain => fl!("smart-op-does-not-contain"),
+        Operator::LessThan => fl!("smart-op-less-than"),
+        Operator::AtMost => fl!("smart-op-at-most"),
+        Operator::GreaterThan => fl!("smart-op-greater-than"),
+        Operator::AtLeast => fl!("smart-op-at-least"),
+        Operator::InLast => fl!("smart-op-in-last"),
+        Operator::NotInLast => fl!("smart-op-not-in-last"),
+        Operator::IsEmpty => fl!("smart-op-is-empty"),
+        Operator::IsSet => fl!("smart-op-is-set"),
+        Operator::IsNotSet => fl!("smart-op-is-not-set"),
+    }
+}
+
+fn sort_label(sort: SortOrder) -> String {
+    match sort {
+        SortOrder::Random => fl!("smart-sort-random"),
+        SortOrder::MostPlayed => fl!("smart-sort-most-played"),
+        SortOrder::LeastPlayed => fl!("smart-sort-least-played"),
+        SortOrder::RecentlyAdded => fl!("smart-sort-recently-added"),
+        SortOrder::RecentlyPlayed => fl!("smart-sort-recently-played"),
+        SortOrder::HighestRated => fl!("smart-sort-highest-rated"),
+        SortOrder::Artist => fl!("smart-sort-artist"),
+        SortOrder::Title => fl!("smart-sort-title"),
+        SortOrder::Year => fl!("smart-sort-year"),
+    }
+}
+
+pub fn playlists_view(app: &AppModel) -> Element<'_, Message> {
+    let detail = match app.selected_playlist {
+        Some(id) => {
+            if let Some(playlist) = app.playlists().get(id) {
+                Some(named_detail(app, playlist))
+            } else if let Some(editor) = app.smart_editor.as_ref().filter(|e| e.draft.id == id) {
+                Some(smart_editor(app, &editor.draft, &editor.limit_value))
+            } else {
+                app.playlists().smart(id).map(|p| smart_detail(app, p))
+            }
+        }
+        None => None,
+    };
+    let detail = detail.unwrap_or_else(|| {
+        widget::text(fl!("playlist-empty-selection"))
+            .apply(widget::container)
+            .center(Length::Fill)
+            .into()
+    });
+
+    widget::row()
+        .spacing(12)
+        .push(sidebar(app))
+        .push(detail)
+        .apply(widget::container)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Left)
+        .align_y(Vertical::Top)
+        .into()
+}
+
+/// Sidebar listing every named playlist, then the smart playlists
+fn sidebar(app: &AppModel) -> Element<'_, Message> {
+    let entry = |id: u32, label: String| {
+        let button = if app.selected_playlist == Some(id) {
+            widget::button::suggested(label)
+        } else {
+            widget::button::text(label)
+        };
+        button
+            .width(Length::Fill)
+            .on_press(Message::PlaylistSelect(id))
+    };
+
+    let mut list = widget::column().spacing(4).push(
+        widget::button::standard(fl!("playlist-new"))
+            .leading_icon(icon::from_name("list-add-symbolic"))
+            .on_press(Message::PlaylistCreate),
+    );
+    for playlist in app.playlists().playlists() {
+        list = list.push(entry(
+            playlist.id,
+            format!("{} ({})", playlist.name, playlist.len()),
+        ));
+    }
+
+    list = list
+        .push(widget::text::heading(fl!("smart-playlists")))
+        .push(
+            widget::button::standard(fl!("smart-playlist-new"))
+                .leading_icon(icon::from_name("list-add-symbolic"))
+                .on_press(Message::SmartPlaylistCreate),
+        );
+    for playlist in app.playlists().smart_playlists() {
+        let count = app.smart_tracks(playlist.id).len();
+        list = list.push(entry(playlist.id, format!("{} ({count})", playlist.name)));
+    }
+
+    widget::scrollable(list).width(Length::Fixed(220.0)).into()
+}
+
+/// Title, or a name field while renaming
+fn title<'a>(app: &'a AppModel, id: u32, current: &str) -> Element<'a, Message> {
+    match &app.renaming_playlist {
+        Some((rename_id, name)) if *rename_id == id => {
+            widget::text_input(fl!("playlist-name"), name)
+                .on_input(Message::PlaylistNameInput)
+                .on_submit(|_| Message::PlaylistRenameSubmit)
+                .width(Length::Fill)
+                .into()
+        }
+        _ => widget::text::title3(current.to_string())
+            .width(Length::Fill)
+            .into(),
+    }
+}
+
+/// Play and append buttons, followed by the playlist-wide actions in `extra`
+fn actions<'a>(
+    id: u32,
+    is_empty: bool,
+    extra: impl IntoIterator<Item = Element<'a, Message>>,
+) -> widget::Row<'a, Message> {
+    let mut row = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::button::suggested(fl!("playlist-play"))
+                .leading_icon(icon::from_name("media-playback-start-symbolic"))
+                .on_press_maybe((!is_empty).then_some(Message::PlaylistPlay(id))),
+        )
+        .push(
+            widget::button::standard(fl!("playlist-append"))
+                .leading_icon(icon::from_name("list-add-symbolic"))
+                .on_press_maybe((!is_empty).then_some(Message::PlaylistAppend(id))),
+        )
+        .push(widget::horizontal_space());
+    for element in extra {
+        row = row.push(element);
+    }
+    row.push(
+        widget::button::icon(icon::from_name("document-edit-symbolic"))
+            .tooltip(fl!("playlist-rename"))
+            .on_press(Message::PlaylistRename(id)),
+    )
+    .push(
+        widget::button::icon(icon::from_name("edit-copy-symbolic"))
+            .tooltip(fl!("playlist-duplicate"))
+            .on_press(Message::PlaylistDuplicate(id)),
+    )
+    .push(
+        widget::button::icon(icon::from_name("user-trash-symbolic"))
+            .tooltip(fl!("playlist-delete"))
+            .on_press(Message::PlaylistDelete(id)),
+    )
+}
+
+fn named_detail<'a>(app: &'a AppModel, playlist: &'a NamedPlaylist) -> Element<'a, Message> {
+    let id = playlist.id;
+
+    // Entries with reorder and remove controls
+    let mut rows = widget::column().spacing(4);
+    let last = playlist.len().saturating_sub(1);
+    for (index, track) in playlist.tracks.iter().enumerate() {
+        let label = app.library_display_text(&track.path);
+
+        let up = widget::button::icon(icon::from_name("go-up-symbolic"))
+            .on_press_maybe((index > 0).then(|| Message::PlaylistMoveTrack(id, index, index - 1)));
+        let down = widget::button::icon(icon::from_name("go-down-symbolic"))
+            .on_press_maybe((index < last).then(|| Message::PlaylistMoveTrack(id, index, index + 1)));
+        let remove = widget::button::icon(icon::from_name("list-remove-symbolic"))
+            .on_press(Message::PlaylistRemoveTrack(id, index));
+
+        let row = widget::row()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(widget::text(format!("{}.", index + 1)).width(Length::Fixed(32.0)))
+            .push(widget::text(label).width(Length::Fill))
+            .push(up)
+            .push(down)
+            .push(remove);
+
+        rows = rows.push(widget::container(row).padding([4, 8]));
+    }
+
+    widget::column()
+        .spacing(12)
+        .push(title(app, id, &playlist.name))
+        .push(actions(id, playlist.is_empty(), []))
+        .push(widget::scrollable(rows).height(Length::Fill))
+        .width(Length::Fill)
+        .into()
+}
+
+fn smart_detail<'a>(app: &'a AppModel, playlist: &'a SmartPlaylist) -> Element<'a, Message> {
+    let id = playlist.id;
+    let tracks = app.smart_tracks(id);
+
+    let mut extra: Vec<Element<'a, Message>> = vec![widget::button::icon(icon::from_name(
+        "preferences-system-symbolic",
+    ))
+    .tooltip(fl!("smart-edit-rules"))
+    .on_press(Message::SmartPlaylistEdit(id))
+    .into()];
+    if playlist.sort == SortOrder::Random {
+        extra.push(
+            widget::button::icon(icon::from_name("media-playlist-shuffle-symbolic"))
+                .tooltip(fl!("smart-reshuffle"))
+                .on_press(Message::SmartPlaylistReshuffle(id))
+                .into(),
+        );
+    }
+
+    let mut rows = widget::column().spacing(4);
+    for (index, path) in tracks.iter().enumerate() {
+        let row = widget::row()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(widget::text(format!("{}.", index + 1)).width(Length::Fixed(32.0)))
+            .push(widget::text(app.library_display_text(path)).width(Length::Fill));
+        rows = rows.push(widget::container(row).padding([4, 8]));
+    }
+
+    widget::column()
+        .spacing(12)
+        .push(title(app, id, &playlist.name))
+        .push(actions(id, tracks.is_empty(), extra))
+        .push(widget::scrollable(rows).height(Length::Fill))
+        .width(Length::Fill)
+        .into()
+}
+
+fn smart_editor<'a>(
+    app: &'a AppModel,
+    draft: &'a SmartPlaylist,
+    limit_value: &'a str,
+) -> Element<'a, Message> {
+    let labels = &app.smart_labels;
+
+    let match_mode = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(fl!("smart-match")))
+        .push(widget::dropdown(
+            &labels.match_modes,
+            MATCH_MODES.iter().position(|m| *m == draft.match_mode),
+            move |i| Message::SmartPlaylistEdited(SmartEdit::Match(i)),
+        ))
+        .push(widget::text(fl!("smart-match-suffix")));
+
+    let mut rules = widget::column().spacing(8);
+    for (index, rule) in draft.rules.iter().enumerate() {
+        let operators = rule.field.operators();
+        let needs_value = rule.operator.needs_value();
+        let mut row = widget::row()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(widget::dropdown(
+                &labels.fields,
+                Field::ALL.iter().position(|f| *f == rule.field),
+                move |i| Message::SmartPlaylistEdited(SmartEdit::RuleField(index, i)),
+            ))
+            .push(widget::dropdown(
+                &labels.operators[&rule.field],
+                operators.iter().position(|o| *o == rule.operator),
+                move |i| Message::SmartPlaylistEdited(SmartEdit::RuleOperator(index, i)),
+            ));
+        if needs_value {
+            row = row.push(
+                widget::text_input("", &rule.value)
+                    .on_input(move |v| Message::SmartPlaylistEdited(SmartEdit::RuleValue(index, v)))
+                    .width(Length::Fill),
+            );
+        } else {
+            row = row.push(widget::horizontal_space());
+        }
+        row = row.push(
+            widget::button::icon(icon::from_name("list-remove-symbolic"))
+                .tooltip(fl!("smart-remove-rule"))
+                .on_press(Message::SmartPlaylistEdited(SmartEdit::RemoveRule(index))),
+        );
+        rules = rules.push(row);
+    }
+    rules = rules.push(
+        widget::button::standard(fl!("smart-add-rule"))
+            .leading_icon(icon::from_name("list-add-symbolic"))
+            .on_press(Message::SmartPlaylistEdited(SmartEdit::AddRule)),
+    );
+
+    let limit_index = draft
+        .limit
+        .and_then(|l| LIMIT_UNITS.iter().position(|u| *u == l.unit))
+        .map_or(0, |i| i + 1);
+    let mut limit = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(fl!("smart-limit")))
+        .push(widget::dropdown(&labels.limits, Some(limit_index), move |i| {
+            Message::SmartPlaylistEdited(SmartEdit::LimitUnit(i))
+        }));
+    if draft.limit.is_some() {
+        limit = limit.push(
+            widget::text_input("", limit_value)
+                .on_input(move |v| Message::SmartPlaylistEdited(SmartEdit::LimitValue(v)))
+                .width(Length::Fixed(80.0)),
+        );
+    }
+
+    let sort = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(fl!("smart-sort")))
+        .push(widget::dropdown(
+            &labels.sorts,
+            SortOrder::ALL.iter().position(|s| *s == draft.sort),
+            move |i| Message::SmartPlaylistEdited(SmartEdit::Sort(i)),
+        ));
+
+    let buttons = widget::row()
+        .spacing(8)
+        .push(widget::horizontal_space())
+        .push(widget::button::standard(fl!("cancel")).on_press(Message::SmartPlaylistCancel))
+        .push(widget::button::suggested(fl!("save")).on_press(Message::SmartPlaylistSave));
+
+    widget::column()
+        .spacing(16)
+        .push(widget::text::title3(draft.name.clone()))
+        .push(match_mode)
+        .push(widget::scrollable(rules).height(Length::Fill))
+        .push(limit)
+        .push(sort)
+        .push(buttons)
+        .width(Length::Fill)
+        .into()
+}
+
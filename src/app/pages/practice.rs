@@ -0,0 +1,279 @@
+use std::time::{Duration, Instant};
+
+use cosmic::prelude::*;
+use cosmic::theme;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::Vertical;
+use music_player::audio::backend::{MAX_RATE, MIN_RATE};
+use music_player::practice::{self, PracticeSettings, MAX_GAP, MAX_STEP_EVERY};
+
+use super::super::{format_time, rate_percent, AppModel, Message};
+use super::stats::section;
+use crate::fl;
+
+const SLIDER_WIDTH: f32 = 200.0;
+
+/// Height of a loop's marker under the seek bar
+const MARKER_HEIGHT: f32 = 3.0;
+
+/// Parts a marker row is divided into, across the length of the track
+const MARKER_PARTS: f32 = 1000.0;
+
+fn millis(d: Duration) -> u64 {
+    d.as_millis() as u64
+}
+
+/// A slider for one of the practice settings, saved when it is let go.
+fn setting_slider<'a>(
+    label: String,
+    range: std::ops::RangeInclusive<u32>,
+    step: u32,
+    value: u32,
+    value_label: String,
+    change: fn(&mut PracticeSettings, u32),
+    settings: PracticeSettings,
+) -> Element<'a, Message> {
+    widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(label).width(Length::Fixed(SLIDER_WIDTH)))
+        .push(
+            widget::slider(range, value, move |value| {
+                let mut settings = settings;
+                change(&mut settings, value);
+                Message::Practice(settings)
+            })
+            .step(step)
+            .on_release(Message::PracticeSave)
+            .width(Length::Fixed(SLIDER_WIDTH)),
+        )
+        .push(widget::text(value_label))
+        .into()
+}
+
+fn speed_label(percent: u32) -> String {
+    fl!(
+        "speed-value",
+        speed = format!("{:.2}", f64::from(percent) / 100.0)
+    )
+}
+
+/// The pause before each repetition, and how the speed rises.
+fn settings_view(settings: PracticeSettings) -> Element<'static, Message> {
+    let gap_label = if settings.gap == 0 {
+        fl!("practice-off")
+    } else {
+        fl!("practice-seconds", seconds = settings.gap)
+    };
+    let step_label = if settings.rate_step == 0 {
+        fl!("practice-off")
+    } else {
+        fl!("practice-step-value", step = settings.rate_step)
+    };
+    let mut column = widget::column()
+        .spacing(4)
+        .push(setting_slider(
+            fl!("practice-gap"),
+            0..=MAX_GAP,
+            1,
+            settings.gap,
+            gap_label,
+            |s, gap| s.gap = gap,
+            settings,
+        ))
+        .push(setting_slider(
+            fl!("practice-step"),
+            0..=25,
+            1,
+            settings.rate_step,
+            step_label,
+            |s, step| s.rate_step = step,
+            settings,
+        ));
+    if settings.rate_step > 0 {
+        let rates = rate_percent(MIN_RATE)..=rate_percent(MAX_RATE);
+        column = column
+            .push(setting_slider(
+                fl!("practice-step-every"),
+                1..=MAX_STEP_EVERY,
+                1,
+                settings.step_every,
+                fl!("practice-repetitions", count = settings.step_every),
+                |s, every| s.step_every = every,
+                settings,
+            ))
+            .push(setting_slider(
+                fl!("practice-start-rate"),
+                rates.clone(),
+                5,
+                settings.start_rate,
+                speed_label(settings.start_rate),
+                |s, rate| s.start_rate = rate,
+                settings,
+            ))
+            .push(setting_slider(
+                fl!("practice-target-rate"),
+                rates,
+                5,
+                settings.target_rate,
+                speed_label(settings.target_rate),
+                |s, rate| s.target_rate = rate,
+                settings,
+            ));
+    }
+    column.into()
+}
+
+/// Loops saved with the playing track, and saving the one set.
+fn bookmarks_view(app: &AppModel) -> Element<'_, Message> {
+    let in_library = app
+        .queue
+        .current()
+        .is_some_and(|p| app.library_meta.contains_key(p));
+    let can_save =
+        in_library && app.practice.range().is_some() && !app.practice.name.trim().is_empty();
+    let mut column = widget::column().spacing(4).push(
+        widget::row()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(
+                widget::text_input(fl!("practice-bookmark-name"), &app.practice.name)
+                    .on_input(Message::LoopName)
+                    .on_submit(|_| Message::LoopSave)
+                    .width(Length::Fixed(SLIDER_WIDTH)),
+            )
+            .push(
+                widget::button::standard(fl!("practice-bookmark-save"))
+                    .on_press_maybe(can_save.then_some(Message::LoopSave)),
+            ),
+    );
+    for (index, bookmark) in app.current_loops().iter().enumerate() {
+        column = column.push(
+            widget::row()
+                .spacing(8)
+                .align_y(Vertical::Center)
+                .push(
+                    widget::button::icon(icon::from_name("media-playlist-repeat-symbolic"))
+                        .extra_small()
+                        .tooltip(fl!("practice-bookmark-loop"))
+                        .on_press(Message::LoopLoad(index)),
+                )
+                .push(widget::text(bookmark.name.clone()).width(Length::Fill))
+                .push(widget::text::caption(format!(
+                    "{} – {}",
+                    format_time(bookmark.start),
+                    format_time(bookmark.end)
+                )))
+                .push(
+                    widget::button::icon(icon::from_name("edit-delete-symbolic"))
+                        .extra_small()
+                        .tooltip(fl!("practice-bookmark-delete"))
+                        .on_press(Message::LoopDelete(index)),
+                ),
+        );
+    }
+    column.into()
+}
+
+/// Setting A and B to loop, how the loop is practiced, and the loops saved
+/// with the track.
+pub fn practice_section(app: &AppModel) -> Element<'_, Message> {
+    let point = |label: String, point: Option<Duration>| match point {
+        Some(point) => format!("{label} {}", format_time(millis(point))),
+        None => label,
+    };
+    let points = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(
+            widget::button::standard(point(fl!("practice-set-a"), app.practice.a))
+                .on_press(Message::LoopSetA),
+        )
+        .push(
+            widget::button::standard(point(fl!("practice-set-b"), app.practice.b))
+                .on_press(Message::LoopSetB),
+        )
+        .push(widget::button::text(fl!("practice-clear")).on_press_maybe(
+            (app.practice.a.is_some() || app.practice.b.is_some()).then_some(Message::LoopClear),
+        ));
+
+    let status = match (app.practice.range(), app.practice.resume_at) {
+        (Some(_), Some(at)) => fl!(
+            "practice-count-in",
+            seconds = at.saturating_duration_since(Instant::now()).as_secs() + 1
+        ),
+        (Some((start, end)), None) => fl!(
+            "practice-looping",
+            start = format_time(millis(start)),
+            end = format_time(millis(end)),
+            count = app.practice.repetition
+        ),
+        (None, _) => fl!("practice-hint"),
+    };
+
+    let content = widget::column()
+        .spacing(8)
+        .push(points)
+        .push(widget::text::caption(status))
+        .push(settings_view(app.config.practice))
+        .push(bookmarks_view(app));
+    section(fl!("practice-title"), content)
+}
+
+/// A bar along part of the seek bar's length.
+fn marker<'a>((start, end): (f32, f32), alpha: f32) -> Element<'a, Message> {
+    let mut color: cosmic::iced::Color = theme::active().cosmic().accent.base.into();
+    color.a *= alpha;
+    let portion = |fraction: f32| (fraction * MARKER_PARTS).round() as u16;
+    let before = portion(start);
+    let span = portion(end).saturating_sub(before).max(1);
+    let after = (MARKER_PARTS as u16).saturating_sub(before + span);
+    let bar = widget::container(widget::Space::new(
+        Length::Fill,
+        Length::Fixed(MARKER_HEIGHT),
+    ))
+    .width(Length::FillPortion(span))
+    .style(move |_| {
+        let mut s = cosmic::iced::widget::container::Style::default();
+        s.background = Some(cosmic::iced::Background::Color(color));
+        s
+    });
+    let mut row = widget::row();
+    if before > 0 {
+        row = row.push(widget::Space::with_width(Length::FillPortion(before)));
+    }
+    row = row.push(bar);
+    if after > 0 {
+        row = row.push(widget::Space::with_width(Length::FillPortion(after)));
+    }
+    row.width(Length::Fill).into()
+}
+
+/// Markers under the footer's seek bar for the loop being played and the
+/// loops saved with the track, if there are any.
+pub fn loop_markers(app: &AppModel) -> Option<Element<'_, Message>> {
+    let duration = Duration::from_millis(app.duration_ms);
+    let active = app.practice.range();
+    let mut spans: Vec<_> = active
+        .and_then(|range| practice::span_fractions(range, duration))
+        .map(|span| (span, 1.0))
+        .into_iter()
+        .collect();
+    spans.extend(
+        app.current_loops()
+            .iter()
+            .filter(|bookmark| Some(bookmark.range()) != active)
+            .filter_map(|bookmark| practice::span_fractions(bookmark.range(), duration))
+            .map(|span| (span, 0.4)),
+    );
+    if spans.is_empty() {
+        return None;
+    }
+    let mut column = widget::column().spacing(1).width(Length::Fill);
+    for (span, alpha) in spans {
+        column = column.push(marker(span, alpha));
+    }
+    Some(column.into())
+}
@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use cosmic::prelude::*;
+use cosmic::theme;
+use cosmic::widget;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::{Horizontal, Vertical};
+use music_player::stats::{Ranked, StatsRange, BITRATE_BUCKETS};
+
+use super::super::{AppModel, Message};
+use crate::fl;
+
+/// Height of the tallest histogram bar
+const HISTOGRAM_HEIGHT: f32 = 96.0;
+
+/// Width of the longest horizontal bar
+const BAR_WIDTH: f32 = 160.0;
+
+pub fn stats_range_labels() -> Vec<String> {
+    StatsRange::ALL
+        .iter()
+        .map(|range| match range {
+            StatsRange::Week => fl!("stats-range-week"),
+            StatsRange::Month => fl!("stats-range-month"),
+            StatsRange::Year => fl!("stats-range-year"),
+            StatsRange::All => fl!("stats-range-all"),
+        })
+        .collect()
+}
+
+/// A bar in the system accent color.
+fn bar<'a>(width: f32, height: f32) -> Element<'a, Message> {
+    let accent: cosmic::iced::Color = theme::active().cosmic().accent.base.into();
+    widget::container(widget::Space::new(Length::Fixed(width), Length::Fixed(height)))
+        .style(move |_| {
+            let mut s = cosmic::iced::widget::container::Style::default();
+            s.background = Some(cosmic::iced::Background::Color(accent));
+            s
+        })
+        .into()
+}
+
+fn fraction(part: f64, whole: f64) -> f32 {
+    if whole > 0.0 {
+        (part / whole) as f32
+    } else {
+        0.0
+    }
+}
+
+fn hours(duration: Duration) -> String {
+    format!("{:.1}", duration.as_secs_f64() / 3600.0)
+}
+
+pub fn size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+pub fn section<'a>(title: String, content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    widget::column()
+        .spacing(8)
+        .push(widget::text::heading(title))
+        .push(content)
+        .apply(widget::container)
+        .padding(12)
+        .width(Length::Fill)
+        .class(cosmic::theme::Container::Card)
+        .into()
+}
+
+/// Label, proportional bar and value on one line.
+fn bar_row<'a>(label: String, share: f32, value: String) -> Element<'a, Message> {
+    widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text(label).width(Length::Fill))
+        .push(bar((share * BAR_WIDTH).max(1.0), 8.0))
+        .push(
+            widget::text(value)
+                .width(Length::Fixed(72.0))
+                .align_x(Horizontal::Right),
+        )
+        .into()
+}
+
+fn ranked_list<'a>(title: String, ranked: &[Ranked]) -> Element<'a, Message> {
+    if ranked.is_empty() {
+        return section(title, widget::text(fl!("stats-none")));
+    }
+    let mut list = widget::column().spacing(4);
+    for (index, entry) in ranked.iter().enumerate() {
+        list = list.push(
+            widget::row()
+                .spacing(8)
+                .push(widget::text(format!("{}.", index + 1)).width(Length::Fixed(24.0)))
+                .push(widget::text(entry.name.clone()).width(Length::Fill))
+                .push(widget::text(fl!("stats-plays", plays = entry.plays))),
+        );
+    }
+    section(title, list)
+}
+
+/// Vertical bars, one per bucket, labelled underneath.
+fn histogram<'a>(buckets: &[Duration], labels: Vec<String>) -> Element<'a, Message> {
+    let most = buckets.iter().max().copied().unwrap_or_default();
+    let mut row = widget::row().spacing(4).align_y(Vertical::Bottom);
+    for (duration, label) in buckets.iter().zip(labels) {
+        let share = fraction(duration.as_secs_f64(), most.as_secs_f64());
+        row = row.push(
+            widget::column()
+                .spacing(4)
+                .align_x(Horizontal::Center)
+                .width(Length::Fill)
+                .push(bar(12.0, (share * HISTOGRAM_HEIGHT).max(1.0)))
+                .push(widget::text::caption(label)),
+        );
+    }
+    row.into()
+}
+
+pub fn stats_view(app: &AppModel) -> Element<'_, Message> {
+    let range_index = StatsRange::ALL
+        .iter()
+        .position(|r| *r == app.stats_range)
+        .unwrap_or(0);
+    let header = widget::row()
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .push(widget::text::title3(fl!("nav-statistics-label")).width(Length::Fill))
+        .push(widget::dropdown(
+            &app.stats_range_labels,
+            Some(range_index),
+            Message::StatsRange,
+        ));
+
+    let Some(statistics) = &app.statistics else {
+        return widget::column()
+            .spacing(12)
+            .push(header)
+            .push(
+                widget::text(fl!("stats-loading"))
+                    .apply(widget::container)
+                    .center(Length::Fill),
+            )
+            .into();
+    };
+    let listening = &statistics.listening;
+    let library = &statistics.library;
+
+    let totals = widget::row()
+        .spacing(24)
+        .push(widget::text::title4(fl!(
+            "stats-hours-listened",
+            hours = hours(listening.total_listened)
+        )))
+        .push(widget::text::title4(fl!(
+            "stats-listens",
+            listens = listening.listens
+        )));
+
+    let tops = widget::row()
+        .spacing(12)
+        .push(ranked_list(fl!("stats-top-artists"), &listening.top_artists))
+        .push(ranked_list(fl!("stats-top-albums"), &listening.top_albums))
+        .push(ranked_list(fl!("stats-top-tracks"), &listening.top_tracks));
+
+    let hour_labels = (0..24)
+        .map(|h| if h % 3 == 0 { h.to_string() } else { String::new() })
+        .collect();
+    let weekday_labels = [
+        fl!("stats-monday"),
+        fl!("stats-tuesday"),
+        fl!("stats-wednesday"),
+        fl!("stats-thursday"),
+        fl!("stats-friday"),
+        fl!("stats-saturday"),
+        fl!("stats-sunday"),
+    ]
+    .into();
+    let histograms = widget::row()
+        .spacing(12)
+        .push(section(
+            fl!("stats-by-hour"),
+            histogram(&listening.by_hour, hour_labels),
+        ))
+        .push(section(
+            fl!("stats-by-weekday"),
+            histogram(&listening.by_weekday, weekday_labels),
+        ));
+
+    let mut genres = widget::column().spacing(4);
+    if listening.genres.is_empty() {
+        genres = genres.push(widget::text(fl!("stats-none")));
+    }
+    for genre in &listening.genres {
+        genres = genres.push(bar_row(
+            genre.name.clone(),
+            fraction(
+                genre.listened.as_secs_f64(),
+                listening.total_listened.as_secs_f64(),
+            ),
+            format!("{} h", hours(genre.listened)),
+        ));
+    }
+
+    let mut formats = widget::column().spacing(4);
+    for (format, count) in &library.formats {
+        let label = if format.is_empty() {
+            fl!("stats-unknown")
+        } else {
+            format.to_uppercase()
+        };
+        formats = formats.push(bar_row(
+            label,
+            fraction(*count as f64, library.tracks as f64),
+            count.to_string(),
+        ));
+    }
+    let mut bitrates = widget::column().spacing(4);
+    let most_bitrate = library.bitrates.iter().max().copied().unwrap_or(0);
+    for ((_, label), count) in BITRATE_BUCKETS.iter().zip(library.bitrates) {
+        bitrates = bitrates.push(bar_row(
+            label.to_string(),
+            fraction(count as f64, most_bitrate as f64),
+            count.to_string(),
+        ));
+    }
+    let composition = widget::column()
+        .spacing(12)
+        .push(
+            widget::row()
+                .spacing(24)
+                .push(widget::text(fl!("stats-tracks", tracks = library.tracks)))
+                .push(widget::text(fl!(
+                    "stats-library-duration",
+                    hours = hours(library.total_duration)
+                )))
+                .push(widget::text(fl!(
+                    "stats-library-size",
+                    size = size(library.total_size)
+                ))),
+        )
+        .push(
+            widget::row()
+                .spacing(24)
+                .push(formats.width(Length::Fill))
+                .push(bitrates.width(Length::Fill)),
+        );
+
+    let content = widget::column()
+        .spacing(12)
+        .push(totals)
+        .push(tops)
+        .push(histograms)
+        .push(section(fl!("stats-genres"), genres))
+        .push(section(fl!("stats-library"), composition));
+
+    widget::column()
+        .spacing(12)
+        .push(header)
+        .push(widget::scrollable(content).height(Length::Fill))
+        .apply(widget::container)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Left)
+        .align_y(Vertical::Top)
+        .into()
+}
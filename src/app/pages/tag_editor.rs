@@ -0,0 +1,90 @@
+use cosmic::prelude::*;
+use cosmic::widget;
+use cosmic::widget::icon;
+use cosmic::iced::Length;
+use cosmic::iced::alignment::Vertical;
+use music_player::audio::tags::{FieldValue, TagField};
+
+use super::super::{AppModel, Message, TagEditor};
+use crate::fl;
+
+const LABEL_WIDTH: f32 = 110.0;
+
+pub fn field_label(field: TagField) -> String {
+    match field {
+        TagField::Title => fl!("tag-title"),
+        TagField::Artist => fl!("tag-artist"),
+        TagField::Album => fl!("tag-album"),
+        TagField::AlbumArtist => fl!("tag-album-artist"),
+        TagField::Track => fl!("tag-track"),
+        TagField::Disc => fl!("tag-disc"),
+        TagField::Year => fl!("tag-year"),
+        TagField::Genre => fl!("tag-genre"),
+        TagField::Comment => fl!("tag-comment"),
+        TagField::MusicBrainzRecordingId => fl!("tag-musicbrainz-recording"),
+        TagField::MusicBrainzReleaseId => fl!("tag-musicbrainz-release"),
+        TagField::MusicBrainzReleaseGroupId => fl!("tag-musicbrainz-release-group"),
+        TagField::MusicBrainzArtistId => fl!("tag-musicbrainz-artist"),
+    }
+}
+
+/// Dialog editing the tags of one or more tracks. Fields the tracks disagree on
+/// stay empty, marked as mixed, until they are changed. A single track can be
+/// identified by its sound, which proposes tags to review.
+pub fn tag_editor_dialog<'a>(app: &'a AppModel, editor: &'a TagEditor) -> Element<'a, Message> {
+    let mut fields = widget::column().spacing(8);
+    for (field, value) in &editor.values {
+        let field = *field;
+        let changed = editor.changes.get(&field);
+        let (text, placeholder) = match (changed, value) {
+            (Some(text), _) => (text.clone().unwrap_or_default(), String::new()),
+            (None, FieldValue::Same(value)) => (value.clone().unwrap_or_default(), String::new()),
+            (None, FieldValue::Mixed) => (String::new(), fl!("tag-editor-mixed")),
+        };
+        let input = widget::text_input(placeholder, text)
+            .on_input(move |text| Message::TagEditorInput(field, text))
+            .width(Length::Fill);
+        let revert = widget::button::icon(icon::from_name("edit-undo-symbolic"))
+            .extra_small()
+            .tooltip(fl!("tag-editor-revert"))
+            .on_press_maybe(changed.map(|_| Message::TagEditorRevert(field)));
+        fields = fields.push(
+            widget::row()
+                .spacing(8)
+                .align_y(Vertical::Center)
+                .push(widget::text(field_label(field)).width(Length::Fixed(LABEL_WIDTH)))
+                .push(input)
+                .push(revert),
+        );
+    }
+    if let Some(error) = &editor.error {
+        fields = fields.push(widget::text(error.clone()));
+    }
+
+    let save = widget::button::suggested(fl!("tag-editor-save")).on_press_maybe(
+        (!editor.saving && !editor.changes.is_empty()).then_some(Message::TagEditorSave),
+    );
+    let cancel = widget::button::standard(fl!("tag-editor-cancel"))
+        .on_press_maybe((!editor.saving).then_some(Message::TagEditorCancel));
+    let can_identify = editor.paths.len() == 1 && app.config.acoustid().is_some();
+    let identify_label = if editor.identifying {
+        fl!("tag-editor-identifying")
+    } else {
+        fl!("tag-editor-identify")
+    };
+    let identify = widget::button::standard(identify_label).on_press_maybe(
+        (can_identify && !editor.identifying && !editor.saving)
+            .then_some(Message::TagEditorIdentify),
+    );
+
+    let mut dialog = widget::dialog()
+        .title(fl!("tag-editor-title", count = editor.paths.len()))
+        .control(fields)
+        .primary_action(save)
+        .secondary_action(cancel)
+        .tertiary_action(identify);
+    if let [path] = editor.paths.as_slice() {
+        dialog = dialog.body(path.to_string_lossy().into_owned());
+    }
+    dialog.into()
+}
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Cover art: reading, embedding, extracting and stripping the pictures in
+//! audio files' tags, and finding the cover image kept in an album's folder.
+
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use lofty::picture::{MimeType, Picture, PictureType};
+use lofty::prelude::*;
+use lofty::probe::Probe;
+
+use super::tags;
+
+/// Names of cover images in album folders, without extension, most common first.
+const FOLDER_COVER_NAMES: [&str; 4] = ["cover", "folder", "front", "album"];
+
+const FOLDER_COVER_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// Quality of covers recompressed as JPEG.
+const JPEG_QUALITY: u8 = 90;
+
+/// A decoded cover image, scaled down for display.
+#[derive(Clone)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels as 8-bit RGBA, row by row
+    pub rgba: Vec<u8>,
+}
+
+impl std::fmt::Debug for Thumbnail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Thumbnail({}x{})", self.width, self.height)
+    }
+}
+
+/// The cover embedded in the file at `path`: its front cover, or else the
+/// first picture it has.
+pub fn read_cover(path: &Path) -> Result<Option<Picture>> {
+    let tagged = Probe::open(path)?.guess_file_type()?.read()?;
+    let pictures = || tagged.tags().iter().flat_map(|tag| tag.pictures());
+    Ok(pictures()
+        .find(|p| p.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures().next())
+        .cloned())
+}
+
+/// The cover image kept in `dir`, such as `cover.jpg` or `Folder.png`.
+pub fn folder_cover(dir: &Path) -> Option<PathBuf> {
+    let files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    let matches = |path: &PathBuf, name: &str| {
+        let stem = path.file_stem().and_then(|s| s.to_str());
+        let ext = path.extension().and_then(|s| s.to_str());
+        stem.is_some_and(|s| s.eq_ignore_ascii_case(name))
+            && ext.is_some_and(|e| FOLDER_COVER_EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x)))
+    };
+    FOLDER_COVER_NAMES
+        .iter()
+        .find_map(|name| files.iter().find(|p| matches(p, name)))
+        .cloned()
+}
+
+/// Read the image in `file` as a front cover to embed. Images wider or taller
+/// than `max_size` are scaled down, and they and images in formats players
+/// rarely support are recompressed as JPEG. JPEG and PNG images that fit are
+/// embedded as they are.
+pub fn load_image(file: &Path, max_size: Option<u32>) -> Result<Picture> {
+    let data = fs::read(file).map_err(|e| anyhow!("failed to read {:?}: {e}", file))?;
+    let image = image::load_from_memory(&data)
+        .map_err(|e| anyhow!("{:?} is not a supported image: {e}", file))?;
+
+    let too_large = max_size.is_some_and(|max| image.width() > max || image.height() > max);
+    let mime = Picture::from_reader(&mut Cursor::new(&data))
+        .ok()
+        .and_then(|p| p.mime_type().cloned());
+    let (mime, data) = match mime {
+        Some(mime @ (MimeType::Jpeg | MimeType::Png)) if !too_large => (mime, data),
+        _ => {
+            let image = match max_size {
+                Some(max) if too_large => image.resize(max, max, FilterType::Lanczos3),
+                _ => image,
+            };
+            (MimeType::Jpeg, encode_jpeg(&image)?)
+        }
+    };
+    Ok(Picture::new_unchecked(
+        PictureType::CoverFront,
+        Some(mime),
+        None,
+        data,
+    ))
+}
+
+fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+    Ok(data)
+}
+
+/// Embed `cover` in the file at `path`, replacing its front covers. Other
+/// pictures, such as a back cover or artist photo, are kept.
+pub fn embed_cover(path: &Path, cover: &Picture) -> Result<()> {
+    tags::edit_tag(path, |tag| {
+        tag.remove_picture_type(PictureType::CoverFront);
+        let mut cover = cover.clone();
+        cover.set_pic_type(PictureType::CoverFront);
+        tag.push_picture(cover);
+    })
+    .map_err(|e| anyhow!("failed to embed cover in {:?}: {e}", path))
+}
+
+/// Remove every picture from the file at `path`.
+pub fn remove_artwork(path: &Path) -> Result<()> {
+    tags::edit_tag(path, |tag| {
+        while !tag.pictures().is_empty() {
+            tag.remove_picture(0);
+        }
+    })
+    .map_err(|e| anyhow!("failed to remove artwork from {:?}: {e}", path))
+}
+
+/// Write the cover embedded in the file at `path` to `dest`, adding the
+/// extension for the image's format if `dest` has none. Returns where it was
+/// written.
+pub fn extract_cover(path: &Path, dest: &Path) -> Result<PathBuf> {
+    let cover = read_cover(path)?.ok_or_else(|| anyhow!("{:?} has no embedded cover", path))?;
+    let dest = match (dest.extension(), cover.mime_type()) {
+        (None, Some(mime)) => dest.with_extension(extension(mime)),
+        _ => dest.to_path_buf(),
+    };
+    fs::write(&dest, cover.data()).map_err(|e| anyhow!("failed to write {:?}: {e}", dest))?;
+    Ok(dest)
+}
+
+/// The usual file extension for images of type `mime`.
+pub fn extension(mime: &MimeType) -> &'static str {
+    match mime {
+        MimeType::Png => "png",
+        MimeType::Gif => "gif",
+        MimeType::Bmp => "bmp",
+        MimeType::Tiff => "tiff",
+        _ => "jpg",
+    }
+}
+
+/// Decode `picture` and scale it to fit within `size` pixels for display.
+pub fn thumbnail(picture: &Picture, size: u32) -> Result<Thumbnail> {
+    let image = image::load_from_memory(picture.data())?.thumbnail(size, size);
+    Ok(Thumbnail {
+        width: image.width(),
+        height: image.height(),
+        rgba: image.into_rgba8().into_raw(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("artwork-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn finds_folder_covers_by_name() {
+        let dir = temp_dir("folder");
+        assert_eq!(folder_cover(&dir), None);
+
+        for name in ["back.jpg", "cover.txt", "folder.png", "Cover.JPG"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        // A directory is never a cover, whatever its name
+        fs::create_dir_all(dir.join("cover.png")).unwrap();
+        assert_eq!(folder_cover(&dir), Some(dir.join("Cover.JPG")));
+
+        fs::remove_file(dir.join("Cover.JPG")).unwrap();
+        assert_eq!(folder_cover(&dir), Some(dir.join("folder.png")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn embeds_small_images_as_they_are() {
+        let dir = temp_dir("small");
+        let file = dir.join("cover.png");
+        RgbImage::new(40, 30).save(&file).unwrap();
+
+        let cover = load_image(&file, Some(100)).unwrap();
+        assert_eq!(cover.pic_type(), PictureType::CoverFront);
+        assert_eq!(cover.mime_type(), Some(&MimeType::Png));
+        assert_eq!(cover.data(), fs::read(&file).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recompresses_large_and_unusual_images() {
+        let dir = temp_dir("large");
+        let large = dir.join("large.png");
+        RgbImage::new(400, 200).save(&large).unwrap();
+        let bitmap = dir.join("cover.bmp");
+        RgbImage::new(40, 30).save(&bitmap).unwrap();
+
+        let cover = load_image(&large, Some(100)).unwrap();
+        assert_eq!(cover.mime_type(), Some(&MimeType::Jpeg));
+        let image = image::load_from_memory(cover.data()).unwrap();
+        assert_eq!((image.width(), image.height()), (100, 50));
+
+        let cover = load_image(&bitmap, None).unwrap();
+        assert_eq!(cover.mime_type(), Some(&MimeType::Jpeg));
+        let image = image::load_from_memory(cover.data()).unwrap();
+        assert_eq!((image.width(), image.height()), (40, 30));
+
+        let text = dir.join("notes.png");
+        fs::write(&text, b"not an image").unwrap();
+        assert!(load_image(&text, None).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
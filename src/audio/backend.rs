@@ -6,14 +6,114 @@ use std::path::Path;
 use std::thread;
 use std::time::Duration;
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::effects::Effects;
+use crate::equalizer::EqualizerPreset;
+use crate::parametric::ParametricProfile;
+use crate::playlist::is_remote_uri;
+use crate::practice::LoopBookmark;
 // Backend focuses purely on GStreamer playback. MPRIS is handled by a separate module.
 
+/// Range of playback speeds, as multiples of normal speed.
+pub const MIN_RATE: f64 = 0.5;
+pub const MAX_RATE: f64 = 3.0;
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TrackMetadata {
     pub title: Option<String>,
     pub album: Option<String>,
     pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub duration: Option<Duration>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    /// Track number on its disc
+    pub track: Option<u32>,
+    pub disc: Option<u32>,
+    pub comment: Option<String>,
+    /// MusicBrainz IDs of the recording, the release it is on, that release's
+    /// group and the recording's artist
+    pub musicbrainz_recording_id: Option<String>,
+    pub musicbrainz_release_id: Option<String>,
+    pub musicbrainz_release_group_id: Option<String>,
+    pub musicbrainz_artist_id: Option<String>,
+    /// Average bitrate in kbps
+    pub bitrate: Option<u32>,
+    /// When the track first appeared in the library, in seconds since the Unix epoch
+    pub date_added: Option<u64>,
+    pub play_count: u32,
+    /// Times the track was skipped before it counted as played
+    pub skip_count: u32,
+    /// Seconds since the Unix epoch
+    pub first_played: Option<u64>,
+    /// Seconds since the Unix epoch
+    pub last_played: Option<u64>,
+    /// Total time spent playing the track
+    pub listening_time: Duration,
+    /// Star rating from 1 to 5; unrated tracks have none
+    pub rating: Option<u8>,
+    pub favorite: bool,
+    /// Chromaprint fingerprint of the audio, compressed and encoded as AcoustID takes it
+    pub fingerprint: Option<String>,
+    /// Modification time, in seconds since the Unix epoch, and size in bytes
+    /// of the file when its tags were read
+    pub file_modified: Option<u64>,
+    pub file_size: Option<u64>,
+    /// Speed the track is played at, in percent, if one was remembered for it
+    pub playback_rate: Option<u32>,
+    /// A–B loops saved for practicing parts of the track
+    pub loops: Vec<LoopBookmark>,
+}
+
+impl TrackMetadata {
+    /// Carry over the fields the library keeps about a track (when it was added,
+    /// play history, rating, favorite, fingerprint, speed, loops) from a previous entry, so
+    /// re-reading tags doesn't lose them. The library's rating wins over the
+    /// one in the file, even when it was cleared. The fingerprint is dropped
+    /// when the file changed since it was last read, as the audio may have too.
+    pub fn keep_library_fields(&mut self, previous: &TrackMetadata) {
+        self.date_added = previous.date_added.or(self.date_added);
+        self.play_count = previous.play_count;
+        self.skip_count = previous.skip_count;
+        self.first_played = previous.first_played;
+        self.last_played = previous.last_played;
+        self.listening_time = previous.listening_time;
+        self.rating = previous.rating;
+        self.favorite = previous.favorite;
+        // Entries from before file sizes were recorded can't tell
+        let unknown = previous.file_modified.is_none() && previous.file_size.is_none();
+        let unchanged = (self.file_modified, self.file_size)
+            == (previous.file_modified, previous.file_size);
+        if unknown || unchanged {
+            self.fingerprint = previous.fingerprint.clone();
+        }
+        self.playback_rate = previous.playback_rate;
+        self.loops = previous.loops.clone();
+    }
+}
+
+/// A section of the loaded file played as if it were a track of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Segment {
+    start: Duration,
+    end: Option<Duration>,
+    /// Keep playing past `end` instead of stopping there, so the following
+    /// segment of the same file starts without a gap.
+    play_through: bool,
+}
+
+/// A section of the file played over and over, with positions in the file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Loop {
+    start: Duration,
+    end: Duration,
+    /// Stop at the start after each time through instead of playing on, until
+    /// played again
+    hold: bool,
+    /// Speed to play the next time through at
+    next_rate: Option<f64>,
 }
 
 #[derive(Clone)]
@@ -21,6 +121,26 @@ pub struct MediaPlayer {
     playbin: gst::Element,
     eos_flag: Arc<AtomicBool>,
     metadata: Arc<Mutex<TrackMetadata>>, // updated from bus tag messages
+    segment: Arc<Mutex<Option<Segment>>>,
+    /// Equalizers and preamps, unless their GStreamer plugins are missing
+    effects: Option<Effects>,
+    /// Playback speed, kept across tracks and seeks
+    rate: Arc<Mutex<f64>>,
+    /// Set when a track is loaded, for the bus watch to apply the playback
+    /// speed once it has prerolled
+    rate_pending: Arc<AtomicBool>,
+    ab_loop: Arc<Mutex<Option<Loop>>>,
+    /// Set when a loop is set before the track has prerolled, for the bus
+    /// watch to seek into it
+    loop_pending: Arc<AtomicBool>,
+    /// Times the loop came back round since they were last taken
+    loop_repeats: Arc<AtomicU32>,
+    /// Set when a segment is loaded, for the bus watch to seek to its start
+    /// once the file has prerolled
+    segment_pending: Arc<AtomicBool>,
+    /// Set when playback is asked for before that seek, for the bus watch to
+    /// start playing after it
+    play_pending: Arc<AtomicBool>,
 }
 
 impl MediaPlayer {
@@ -29,13 +149,90 @@ impl MediaPlayer {
         let playbin = gst::ElementFactory::make("playbin")
             .build()
             .map_err(|_| anyhow!("Failed to create playbin element"))?;
+        // Playback works without effects, just without equalizers and with
+        // the pitch changing along with the speed
+        let effects = match Effects::new() {
+            Ok(effects) => {
+                playbin.set_property("audio-filter", effects.element());
+                Some(effects)
+            }
+            Err(err) => {
+                eprintln!("audio effects are unavailable: {err}");
+                None
+            }
+        };
         Ok(Self {
             playbin,
             eos_flag: Arc::new(AtomicBool::new(false)),
             metadata: Arc::new(Mutex::new(TrackMetadata::default())),
+            segment: Arc::new(Mutex::new(None)),
+            effects,
+            rate: Arc::new(Mutex::new(1.0)),
+            rate_pending: Arc::new(AtomicBool::new(false)),
+            ab_loop: Arc::new(Mutex::new(None)),
+            loop_pending: Arc::new(AtomicBool::new(false)),
+            loop_repeats: Arc::new(AtomicU32::new(0)),
+            segment_pending: Arc::new(AtomicBool::new(false)),
+            play_pending: Arc::new(AtomicBool::new(false)),
         })
     }
 
+    /// Whether playback runs through the equalizer.
+    pub fn has_equalizer(&self) -> bool {
+        self.effects.is_some()
+    }
+
+    /// Apply equalizer settings to playback right away, or turn the equalizer
+    /// off for `None`.
+    pub fn set_equalizer(&self, preset: Option<&EqualizerPreset>) {
+        if let Some(effects) = &self.effects {
+            effects.set_equalizer(preset);
+        }
+    }
+
+    /// Apply a parametric equalizer profile to playback right away, or turn
+    /// the parametric equalizer off for `None`.
+    pub fn set_parametric(&self, profile: Option<&ParametricProfile>) {
+        if let Some(effects) = &self.effects {
+            effects.set_parametric(profile);
+        }
+    }
+
+    /// Whether the pitch can be shifted apart from the speed.
+    pub fn has_pitch(&self) -> bool {
+        self.effects.as_ref().is_some_and(Effects::has_pitch)
+    }
+
+    /// Shift the pitch by `semitones` without changing the speed.
+    pub fn set_pitch(&self, semitones: i32) {
+        if let Some(effects) = &self.effects {
+            effects.set_pitch(semitones);
+        }
+    }
+
+    /// Playback speed as a multiple of normal speed.
+    pub fn rate(&self) -> f64 {
+        self.rate.lock().map(|guard| *guard).unwrap_or(1.0)
+    }
+
+    /// Play at `rate` times normal speed, between [`MIN_RATE`] and [`MAX_RATE`].
+    /// The pitch stays the same when the effects are available. The speed is
+    /// kept for tracks loaded afterwards.
+    pub fn set_rate(&self, rate: f64) -> Result<()> {
+        let rate = rate.clamp(MIN_RATE, MAX_RATE);
+        if let Ok(mut guard) = self.rate.lock() {
+            *guard = rate;
+        }
+        // Until a track has prerolled, the bus watch applies the rate
+        let Some(position) = self.raw_position() else {
+            return Ok(());
+        };
+        match self.ab_loop() {
+            Some(ab) => self.seek_loop(&ab, position),
+            None => apply_rate(&self.playbin, rate, position),
+        }
+    }
+
     pub fn path_to_uri(path: &Path) -> Result<String> {
         let abs = std::fs::canonicalize(path)?;
         let s = abs.to_str().ok_or_else(|| anyhow!("Invalid Path"))?;
@@ -46,15 +243,165 @@ impl MediaPlayer {
 
     pub fn set_uri(&self, uri: &str) -> Result<()> {
         self.playbin.set_property("uri", &uri);
+        self.rate_pending.store(true, Ordering::SeqCst);
         Ok(())
     }
 
+    /// Load a local file, or a stream URI such as one imported from a playlist.
     pub fn load_path(&self, path: &Path) -> Result<()> {
+        self.set_segment(None);
+        self.set_ab_loop(None);
+        self.segment_pending.store(false, Ordering::SeqCst);
+        self.play_pending.store(false, Ordering::SeqCst);
+        if let Some(s) = path.to_str().filter(|s| is_remote_uri(s)) {
+            return self.set_uri(s);
+        }
         let uri = Self::path_to_uri(path)?;
         self.set_uri(&uri)
     }
 
+    /// Load a section of a file, e.g. a CUE sheet track, and cue it up at `start`.
+    ///
+    /// Unless `play_through` is set, playback ends with EOS at `end`. Position,
+    /// duration and seeking are relative to the segment afterwards.
+    ///
+    /// Returns without waiting for the file to preroll: the bus watch seeks
+    /// to `start` once it has, and playback asked for meanwhile begins there.
+    pub fn load_segment(
+        &self,
+        path: &Path,
+        start: Duration,
+        end: Option<Duration>,
+        play_through: bool,
+    ) -> Result<()> {
+        self.load_path(path)?;
+        self.set_segment(Some(Segment { start, end, play_through }));
+        self.segment_pending.store(true, Ordering::SeqCst);
+
+        // Seeking needs a prerolled pipeline
+        self.playbin
+            .set_state(gst::State::Paused)
+            .map_err(|e| anyhow!("Failed to set state to Paused: {}", e))?;
+        Ok(())
+    }
+
+    /// Switch to the next segment of the already playing file without seeking.
+    /// Only valid when it starts where the current one ends.
+    pub fn continue_segment(&self, start: Duration, end: Option<Duration>, play_through: bool) {
+        self.set_segment(Some(Segment { start, end, play_through }));
+    }
+
+    /// Whether a play-through segment has been played past its end.
+    pub fn segment_ended(&self) -> bool {
+        let Some(segment) = self.segment() else {
+            return false;
+        };
+        match (segment.play_through, segment.end, self.raw_position()) {
+            (true, Some(end), Some(pos)) => pos >= end,
+            _ => false,
+        }
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        self.segment.lock().ok().and_then(|guard| *guard)
+    }
+
+    fn set_segment(&self, segment: Option<Segment>) {
+        if let Ok(mut guard) = self.segment.lock() {
+            *guard = segment;
+        }
+    }
+
+    /// Play the section between `start` and `end` of the track over and over,
+    /// going straight back to `start` each time without a gap, or stop
+    /// looping for `None`. With `hold`, playback pauses at `start` after each
+    /// time through until [`MediaPlayer::play`] is called.
+    pub fn set_loop(&self, range: Option<(Duration, Duration)>, hold: bool) -> Result<()> {
+        let offset = self.segment().map(|s| s.start).unwrap_or_default();
+        let ab = range.map(|(start, end)| Loop {
+            start: offset + start,
+            end: offset + end,
+            hold,
+            next_rate: None,
+        });
+        let previous = self.ab_loop();
+        self.set_ab_loop(ab);
+        self.loop_repeats.store(0, Ordering::SeqCst);
+        let Some(position) = self.raw_position() else {
+            self.loop_pending.store(ab.is_some(), Ordering::SeqCst);
+            return Ok(());
+        };
+        match ab {
+            // Changing only how the loop repeats needs no seek
+            Some(ab) if previous.is_some_and(|p| (p.start, p.end) == (ab.start, ab.end)) => Ok(()),
+            Some(ab) => self.seek_loop(&ab, position),
+            // Play on past the loop's end again
+            None if previous.is_some() => {
+                let stop = self.segment().and_then(|s| s.stop());
+                self.seek_absolute(position, stop)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.ab_loop().is_some()
+    }
+
+    /// Play the next time through the loop at `rate` times normal speed.
+    pub fn set_loop_rate(&self, rate: f64) {
+        if let Ok(mut guard) = self.ab_loop.lock() {
+            if let Some(ab) = guard.as_mut() {
+                ab.next_rate = Some(rate.clamp(MIN_RATE, MAX_RATE));
+            }
+        }
+    }
+
+    /// Times the loop has come back round since this was last called.
+    pub fn take_loop_repeats(&self) -> u32 {
+        self.loop_repeats.swap(0, Ordering::SeqCst)
+    }
+
+    fn ab_loop(&self) -> Option<Loop> {
+        self.ab_loop.lock().ok().and_then(|guard| *guard)
+    }
+
+    fn set_ab_loop(&self, ab: Option<Loop>) {
+        if let Ok(mut guard) = self.ab_loop.lock() {
+            *guard = ab;
+        }
+    }
+
+    /// Seek to `position` in the file, or to the loop's start from outside
+    /// it, with a segment seek that ends at the loop's end.
+    fn seek_loop(&self, ab: &Loop, position: Duration) -> Result<()> {
+        let position = if (ab.start..ab.end).contains(&position) {
+            position
+        } else {
+            ab.start
+        };
+        seek_segment(&self.playbin, self.rate(), ab, position, true)
+    }
+
+    fn raw_position(&self) -> Option<Duration> {
+        self.playbin
+            .query_position::<gst::ClockTime>()
+            .map(|ct| Duration::from_nanos(ct.nseconds()))
+    }
+
+    fn seek_absolute(&self, start: Duration, stop: Option<Duration>) -> Result<()> {
+        seek_range(&self.playbin, self.rate(), start, stop)
+    }
+
     pub fn play(&self) -> Result<()> {
+        // A segment still waiting for its seek starts playing after it, not
+        // from the start of the file
+        self.play_pending.store(true, Ordering::SeqCst);
+        if self.segment_pending.load(Ordering::SeqCst) {
+            self.eos_flag.store(false, Ordering::SeqCst);
+            return Ok(());
+        }
+        self.play_pending.store(false, Ordering::SeqCst);
         self.playbin
             .set_state(gst::State::Playing)
             .map_err(|e| anyhow!("Failed to set state to Playing: {}", e))?;
@@ -64,6 +411,7 @@ impl MediaPlayer {
     }
 
     pub fn pause(&self) -> Result<()> {
+        self.play_pending.store(false, Ordering::SeqCst);
         self.playbin
             .set_state(gst::State::Paused)
             .map_err(|e| anyhow!("Failed to set state to Playing: {}", e))?;
@@ -71,6 +419,7 @@ impl MediaPlayer {
     }
 
     pub fn stop(&self) -> Result<()> {
+        self.play_pending.store(false, Ordering::SeqCst);
         self.playbin
             .set_state(gst::State::Ready)
             .map_err(|e| anyhow!("Failed to set state to Null: {}", e))?;
@@ -79,25 +428,44 @@ impl MediaPlayer {
 
     /// Query the current playback position.
     pub fn position(&self) -> Option<Duration> {
-        self.playbin
-            .query_position::<gst::ClockTime>()
-            .map(|ct| Duration::from_nanos(ct.nseconds()))
+        let pos = self.raw_position()?;
+        match self.segment() {
+            Some(segment) => Some(pos.saturating_sub(segment.start)),
+            None => Some(pos),
+        }
     }
 
     /// Query the total duration of the currently loaded media.
     pub fn duration(&self) -> Option<Duration> {
-        self.playbin
+        let total = self
+            .playbin
             .query_duration::<gst::ClockTime>()
-            .map(|ct| Duration::from_nanos(ct.nseconds()))
+            .map(|ct| Duration::from_nanos(ct.nseconds()));
+        match self.segment() {
+            Some(segment) => Some(segment.end.or(total)?.saturating_sub(segment.start)),
+            None => total,
+        }
     }
 
-    /// Seek to the specified absolute position.
+    /// Seek to the specified absolute position, keeping the playback speed.
+    /// While looping, positions outside the loop go to its start.
     pub fn seek(&self, position: Duration) -> Result<()> {
-        let clock_time = gst::ClockTime::from_nseconds(position.as_nanos() as u64);
+        if let Some(ab) = self.ab_loop() {
+            let offset = self.segment().map(|s| s.start).unwrap_or_default();
+            return self.seek_loop(&ab, offset + position);
+        }
+        if let Some(segment) = self.segment() {
+            return self.seek_absolute(segment.start + position, segment.stop());
+        }
+
         self.playbin
-            .seek_simple(
+            .seek(
+                self.rate(),
                 gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
-                clock_time,
+                gst::SeekType::Set,
+                Some(to_clock(position)),
+                gst::SeekType::None,
+                gst::ClockTime::NONE,
             )
             .map_err(|e| anyhow!("Failed to seek: {}", e))?;
         Ok(())
@@ -108,6 +476,14 @@ impl MediaPlayer {
         let playbin = self.playbin.clone();
         let eos_flag = self.eos_flag.clone();
         let metadata = self.metadata.clone();
+        let rate = self.rate.clone();
+        let rate_pending = self.rate_pending.clone();
+        let ab_loop = self.ab_loop.clone();
+        let loop_repeats = self.loop_repeats.clone();
+        let loop_pending = self.loop_pending.clone();
+        let segment = self.segment.clone();
+        let segment_pending = self.segment_pending.clone();
+        let play_pending = self.play_pending.clone();
 
         thread::spawn(move || {
             for msg in bus.iter_timed(gst::ClockTime::NONE) {
@@ -119,6 +495,74 @@ impl MediaPlayer {
                         let _ = playbin.set_state(gst::State::Ready);
                     }
 
+                    // A newly loaded track prerolled: play it at the speed set,
+                    // from the start of the loop if one was set meanwhile, or
+                    // of the segment loaded
+                    gst::MessageView::AsyncDone(..) => {
+                        let rate = rate.lock().map(|guard| *guard).unwrap_or(1.0);
+                        let position = playbin
+                            .query_position::<gst::ClockTime>()
+                            .map(|ct| Duration::from_nanos(ct.nseconds()));
+                        let ab = ab_loop.lock().ok().and_then(|guard| *guard);
+                        let segment = segment.lock().ok().and_then(|guard| *guard);
+                        let rate_pending = rate_pending.swap(false, Ordering::SeqCst);
+                        let segment_pending = segment_pending.swap(false, Ordering::SeqCst);
+                        let loop_pending = loop_pending.swap(false, Ordering::SeqCst);
+                        let result = match (loop_pending, ab, segment) {
+                            (true, Some(ab), _) => {
+                                seek_segment(&playbin, rate, &ab, ab.start, true)
+                            }
+                            (_, _, Some(segment)) if segment_pending => {
+                                seek_range(&playbin, rate, segment.start, segment.stop())
+                            }
+                            _ if rate_pending && rate != 1.0 => match position {
+                                Some(position) => apply_rate(&playbin, rate, position),
+                                None => Ok(()),
+                            },
+                            _ => Ok(()),
+                        };
+                        if let Err(err) = result {
+                            eprintln!("{err}");
+                        }
+                        if play_pending.swap(false, Ordering::SeqCst) {
+                            let _ = playbin.set_state(gst::State::Playing);
+                        }
+                    }
+
+                    // The end of the loop: go back round to its start
+                    gst::MessageView::SegmentDone(..) => {
+                        let ab = ab_loop.lock().ok().and_then(|guard| *guard);
+                        let current = rate.lock().map(|guard| *guard).unwrap_or(1.0);
+                        if let Some(ab) = ab {
+                            let rate = match ab.next_rate {
+                                Some(next) => {
+                                    if let Ok(mut guard) = rate.lock() {
+                                        *guard = next;
+                                    }
+                                    next
+                                }
+                                None => current,
+                            };
+                            if ab.hold {
+                                let _ = playbin.set_state(gst::State::Paused);
+                            }
+                            // Unless holding there, the seek doesn't flush, so
+                            // the start follows on from the end without a gap
+                            if let Err(err) = seek_segment(&playbin, rate, &ab, ab.start, ab.hold) {
+                                eprintln!("{err}");
+                            }
+                            loop_repeats.fetch_add(1, Ordering::SeqCst);
+                        } else if let Some(position) = playbin
+                            .query_position::<gst::ClockTime>()
+                            .map(|ct| Duration::from_nanos(ct.nseconds()))
+                        {
+                            // The loop was stopped as it ended: play on
+                            if let Err(err) = apply_rate(&playbin, current, position) {
+                                eprintln!("{err}");
+                            }
+                        }
+                    }
+
                     gst::MessageView::Tag(tag_msg) => {
                         let tags = tag_msg.tags();
                         if let Ok(mut guard) = metadata.lock() {
@@ -169,8 +613,181 @@ impl MediaPlayer {
     // MPRIS is managed by audio::mpris
 }
 
+fn to_clock(d: Duration) -> gst::ClockTime {
+    gst::ClockTime::from_nseconds(d.as_nanos() as u64)
+}
+
+/// Seek to `start` at `rate`, ending at `stop` or the end of the file.
+fn seek_range(
+    playbin: &gst::Element,
+    rate: f64,
+    start: Duration,
+    stop: Option<Duration>,
+) -> Result<()> {
+    playbin
+        .seek(
+            rate,
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            gst::SeekType::Set,
+            Some(to_clock(start)),
+            gst::SeekType::Set,
+            stop.map(to_clock),
+        )
+        .map_err(|e| anyhow!("Failed to seek: {}", e))?;
+    Ok(())
+}
+
+/// Change the playback speed with a seek to where `playbin` is. The end of the
+/// segment being played stays as it is.
+fn apply_rate(playbin: &gst::Element, rate: f64, position: Duration) -> Result<()> {
+    playbin
+        .seek(
+            rate,
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            gst::SeekType::Set,
+            Some(to_clock(position)),
+            gst::SeekType::None,
+            gst::ClockTime::NONE,
+        )
+        .map_err(|e| anyhow!("Failed to change playback speed: {}", e))?;
+    Ok(())
+}
+
+/// Seek to `position` for a loop. A segment seek posts `SegmentDone` at the
+/// loop's end instead of playing on, for the bus watch to seek back round.
+fn seek_segment(
+    playbin: &gst::Element,
+    rate: f64,
+    ab: &Loop,
+    position: Duration,
+    flush: bool,
+) -> Result<()> {
+    let mut flags = gst::SeekFlags::SEGMENT | gst::SeekFlags::ACCURATE;
+    if flush {
+        flags |= gst::SeekFlags::FLUSH;
+    }
+    playbin
+        .seek(
+            rate,
+            flags,
+            gst::SeekType::Set,
+            Some(to_clock(position)),
+            gst::SeekType::Set,
+            Some(to_clock(ab.end)),
+        )
+        .map_err(|e| anyhow!("Failed to seek in the loop: {}", e))?;
+    Ok(())
+}
+
+/// Watch the default audio output device from a background thread, as the
+/// sound server reports it. Its name is sent once the monitor is up and again
+/// whenever another device becomes the default; the thread stops once the
+/// receiver is dropped and the next device event arrives.
+pub fn watch_output_device() -> crossbeam_channel::Receiver<Option<String>> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        if let Err(err) = gst::init() {
+            eprintln!("not watching the output device: {err}");
+            return;
+        }
+        let monitor = gst::DeviceMonitor::new();
+        let _ = monitor.add_filter(Some("Audio/Sink"), None);
+        let bus = monitor.bus();
+        if let Err(err) = monitor.start() {
+            eprintln!("not watching the output device: {err}");
+            return;
+        }
+        let mut current = default_output_device(&monitor);
+        if tx.send(current.clone()).is_ok() {
+            for msg in bus.iter_timed(gst::ClockTime::NONE) {
+                match msg.view() {
+                    gst::MessageView::DeviceAdded(..)
+                    | gst::MessageView::DeviceRemoved(..)
+                    | gst::MessageView::DeviceChanged(..) => {}
+                    _ => continue,
+                }
+                let device = default_output_device(&monitor);
+                if device == current {
+                    continue;
+                }
+                current = device;
+                if tx.send(current.clone()).is_err() {
+                    break;
+                }
+            }
+        }
+        monitor.stop();
+    });
+    rx
+}
+
+/// Name of the monitor's default device, or its first when none is marked.
+fn default_output_device(monitor: &gst::DeviceMonitor) -> Option<String> {
+    let devices = monitor.devices();
+    let is_default = |device: &&gst::Device| {
+        device
+            .properties()
+            .and_then(|p| p.get::<bool>("is-default").ok())
+            .unwrap_or(false)
+    };
+    devices
+        .iter()
+        .find(is_default)
+        .or_else(|| devices.iter().next())
+        .map(|device| device.display_name().to_string())
+}
+
+impl Segment {
+    /// The stop position for segment seeks; `None` when playing through.
+    fn stop(&self) -> Option<Duration> {
+        if self.play_through {
+            None
+        } else {
+            self.end
+        }
+    }
+}
+
 impl Drop for MediaPlayer {
     fn drop(&mut self) {
         let _ = self.playbin.set_state(gst::State::Null);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_fingerprints_of_unchanged_files() {
+        let previous = TrackMetadata {
+            fingerprint: Some("AQAA".into()),
+            file_modified: Some(100),
+            file_size: Some(2000),
+            play_count: 4,
+            ..Default::default()
+        };
+        let reread = |modified: u64, size: u64| {
+            let mut md = TrackMetadata {
+                file_modified: Some(modified),
+                file_size: Some(size),
+                ..Default::default()
+            };
+            md.keep_library_fields(&previous);
+            md
+        };
+        assert_eq!(reread(100, 2000).fingerprint.as_deref(), Some("AQAA"));
+        let edited = reread(150, 2000);
+        assert_eq!(edited.fingerprint, None);
+        assert_eq!(edited.play_count, 4);
+        assert_eq!(reread(100, 2500).fingerprint, None);
+
+        // Entries saved before file sizes were recorded keep theirs
+        let legacy = TrackMetadata {
+            fingerprint: Some("AQAA".into()),
+            ..Default::default()
+        };
+        let mut md = reread(100, 2000);
+        md.keep_library_fields(&legacy);
+        assert_eq!(md.fingerprint.as_deref(), Some("AQAA"));
+    }
+}
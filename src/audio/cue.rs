@@ -0,0 +1,472 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! CUE sheet parsing and expansion of single-file album rips into virtual tracks.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use lofty::config::ParseOptions;
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::ItemKey;
+
+use super::backend::TrackMetadata;
+use crate::playlist::decode_text;
+
+/// CUE sheet timestamps count 75 frames per second.
+const FRAMES_PER_SECOND: u64 = 75;
+
+/// File types that commonly carry an embedded `CUESHEET` tag.
+const EMBEDDED_CUE_EXTENSIONS: &[&str] = &["flac", "ape", "wv", "wav"];
+
+/// A parsed CUE sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    pub tracks: Vec<CueSheetTrack>,
+}
+
+/// A `TRACK` block as written in the sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueSheetTrack {
+    pub number: u32,
+    /// The `FILE` the track belongs to, as written in the sheet.
+    pub file: String,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    /// Offset of `INDEX 01` within the file.
+    pub start: Duration,
+}
+
+/// A track carved out of a larger audio file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CueTrack {
+    pub number: u32,
+    /// The audio file holding the track.
+    pub file: PathBuf,
+    pub start: Duration,
+    /// Where the next track starts, or `None` to play to the end of the file.
+    pub end: Option<Duration>,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+}
+
+impl CueTrack {
+    /// Library metadata for the virtual track.
+    pub fn metadata(&self) -> TrackMetadata {
+        TrackMetadata {
+            title: self.title.clone(),
+            album: self.album.clone(),
+            artist: self.performer.clone(),
+            genre: self.genre.clone(),
+            year: self.year,
+            track: Some(self.number),
+            duration: self.end.map(|end| end.saturating_sub(self.start)),
+            ..Default::default()
+        }
+    }
+
+    /// Whether `next` picks up exactly where this track ends in the same file,
+    /// so playback can continue without reloading or seeking.
+    pub fn is_followed_by(&self, next: &CueTrack) -> bool {
+        self.file == next.file && self.end == Some(next.start)
+    }
+}
+
+/// Parse the text of a CUE sheet. Unknown commands are ignored.
+pub fn parse(text: &str) -> CueSheet {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut sheet = CueSheet::default();
+    let mut file = String::new();
+    let mut current: Option<CueSheetTrack> = None;
+    let mut has_index = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => file = unquote(strip_file_type(rest)),
+            "TRACK" => {
+                if let Some(track) = current.take().filter(|_| has_index) {
+                    sheet.tracks.push(track);
+                }
+                has_index = false;
+                let number = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(sheet.tracks.len() as u32 + 1);
+                current = Some(CueSheetTrack {
+                    number,
+                    file: file.clone(),
+                    ..Default::default()
+                });
+            }
+            "TITLE" => match &mut current {
+                Some(track) => track.title = Some(unquote(rest)),
+                None => sheet.title = Some(unquote(rest)),
+            },
+            "PERFORMER" => match &mut current {
+                Some(track) => track.performer = Some(unquote(rest)),
+                None => sheet.performer = Some(unquote(rest)),
+            },
+            "REM" => {
+                let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                match key.to_ascii_uppercase().as_str() {
+                    "GENRE" => sheet.genre = Some(unquote(value.trim())),
+                    "DATE" => sheet.date = Some(unquote(value.trim())),
+                    _ => {}
+                }
+            }
+            "INDEX" => {
+                let mut parts = rest.split_whitespace();
+                let is_01 = parts.next().and_then(|n| n.parse::<u32>().ok()) == Some(1);
+                if let (true, Some(track), Some(time)) =
+                    (is_01, &mut current, parts.next().and_then(parse_timestamp))
+                {
+                    // INDEX 01 may sit in a new FILE when pregaps are kept in the previous one
+                    track.file = file.clone();
+                    track.start = time;
+                    has_index = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(track) = current.filter(|_| has_index) {
+        sheet.tracks.push(track);
+    }
+
+    sheet
+}
+
+/// Parse an `mm:ss:ff` timestamp.
+fn parse_timestamp(value: &str) -> Option<Duration> {
+    let mut parts = value.split(':').map(|p| p.parse::<u64>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || frames >= FRAMES_PER_SECOND {
+        return None;
+    }
+    let total_frames = (minutes * 60 + seconds) * FRAMES_PER_SECOND + frames;
+    Some(Duration::from_nanos(total_frames * 1_000_000_000 / FRAMES_PER_SECOND))
+}
+
+fn strip_file_type(rest: &str) -> &str {
+    // `FILE "name.flac" WAVE`: drop the trailing type keyword
+    match rest.rsplit_once(char::is_whitespace) {
+        Some((name, kind))
+            if ["WAVE", "MP3", "AIFF", "BINARY", "MOTOROLA", "FLAC"]
+                .iter()
+                .any(|k| k.eq_ignore_ascii_case(kind)) =>
+        {
+            name.trim()
+        }
+        _ => rest,
+    }
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+impl CueSheet {
+    /// The release year from `REM DATE`, which may be a full date.
+    pub fn year(&self) -> Option<u32> {
+        let date = self.date.as_deref()?;
+        date.get(..4).and_then(|y| y.parse().ok())
+    }
+
+    /// Turn the sheet into virtual tracks, mapping each `FILE` through `locate`.
+    /// Tracks whose file cannot be located are dropped.
+    pub fn virtual_tracks(&self, mut locate: impl FnMut(&str) -> Option<PathBuf>) -> Vec<CueTrack> {
+        let mut tracks: Vec<CueTrack> = self
+            .tracks
+            .iter()
+            .filter_map(|t| {
+                Some(CueTrack {
+                    number: t.number,
+                    file: locate(&t.file)?,
+                    start: t.start,
+                    end: None,
+                    title: t.title.clone(),
+                    performer: t.performer.clone().or_else(|| self.performer.clone()),
+                    album: self.title.clone(),
+                    genre: self.genre.clone(),
+                    year: self.year(),
+                })
+            })
+            .collect();
+
+        for i in 1..tracks.len() {
+            if tracks[i].file == tracks[i - 1].file {
+                tracks[i - 1].end = Some(tracks[i].start);
+            }
+        }
+
+        tracks
+    }
+}
+
+/// The library path used for a virtual track: the audio file with `#NN` appended.
+pub fn virtual_path(file: &Path, number: u32) -> PathBuf {
+    let mut path = OsString::from(file.as_os_str());
+    path.push(format!("#{number:02}"));
+    PathBuf::from(path)
+}
+
+/// The audio file behind a library path: the file a virtual track was carved
+/// from, or the path itself for regular tracks.
+pub fn source_file(path: &Path) -> PathBuf {
+    if let Some((file, number)) = path.to_str().and_then(|p| p.rsplit_once('#')) {
+        if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) && !path.exists() {
+            return PathBuf::from(file);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Read a `CUESHEET` tag embedded in the file, if any.
+pub fn read_embedded(path: &Path) -> Option<CueSheet> {
+    let tagged = Probe::open(path)
+        .ok()?
+        .options(ParseOptions::new().read_properties(false))
+        .read()
+        .ok()?;
+
+    tagged.tags().iter().find_map(|tag| {
+        tag.items()
+            .find(|item| {
+                matches!(item.key(), ItemKey::Unknown(key) if key.eq_ignore_ascii_case("cuesheet"))
+            })
+            .and_then(|item| item.value().text())
+            .map(parse)
+    })
+}
+
+/// Replace single-file album rips in `tracks` with the virtual tracks described by
+/// sidecar `.cue` files or embedded `CUESHEET` tags.
+///
+/// Returns the new track list and the virtual tracks keyed by their library path.
+pub fn expand_cue_sheets(tracks: Vec<PathBuf>) -> (Vec<PathBuf>, HashMap<PathBuf, CueTrack>) {
+    let known: HashSet<&PathBuf> = tracks.iter().collect();
+    let mut by_file: HashMap<PathBuf, Vec<CueTrack>> = HashMap::new();
+
+    // Sidecar sheets next to the audio files
+    let dirs: HashSet<&Path> = tracks.iter().filter_map(|p| p.parent()).collect();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let sheets = entries.flatten().map(|e| e.path()).filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("cue"))
+        });
+
+        for sheet_path in sheets {
+            let Ok(bytes) = fs::read(&sheet_path) else {
+                continue;
+            };
+            let sheet = parse(&decode_text(&bytes));
+            let locate = |name: &str| locate_file(dir, name, &known);
+            for track in sheet.virtual_tracks(locate) {
+                by_file.entry(track.file.clone()).or_default().push(track);
+            }
+        }
+    }
+
+    // Embedded sheets in files not covered by a sidecar
+    for path in &tracks {
+        let embeddable = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| EMBEDDED_CUE_EXTENSIONS.iter().any(|x| x.eq_ignore_ascii_case(e)));
+        if !embeddable || by_file.contains_key(path) {
+            continue;
+        }
+        if let Some(sheet) = read_embedded(path) {
+            let virtual_tracks = sheet.virtual_tracks(|_| Some(path.clone()));
+            if !virtual_tracks.is_empty() {
+                by_file.insert(path.clone(), virtual_tracks);
+            }
+        }
+    }
+
+    // A sheet listing one track per file describes a regular rip, nothing to split
+    by_file.retain(|_, cue_tracks| cue_tracks.len() > 1);
+
+    let mut expanded = Vec::with_capacity(tracks.len());
+    let mut virtual_tracks = HashMap::new();
+    for path in tracks {
+        match by_file.remove(&path) {
+            Some(mut cue_tracks) => {
+                cue_tracks.sort_by_key(|t| t.start);
+                cue_tracks.dedup_by_key(|t| t.number);
+                for track in cue_tracks {
+                    let vpath = virtual_path(&path, track.number);
+                    expanded.push(vpath.clone());
+                    virtual_tracks.insert(vpath, track);
+                }
+            }
+            None => expanded.push(path),
+        }
+    }
+
+    (expanded, virtual_tracks)
+}
+
+/// Map a sheet's `FILE` name to a scanned track. Rips are often re-encoded after the
+/// sheet was written, so a `.wav` reference also matches a file with the same stem.
+fn locate_file(dir: &Path, name: &str, known: &HashSet<&PathBuf>) -> Option<PathBuf> {
+    let exact = dir.join(name.replace('\\', "/"));
+    if known.contains(&exact) {
+        return Some(exact);
+    }
+
+    let stem = exact.file_stem()?;
+    let parent = exact.parent()?;
+    known
+        .iter()
+        .find(|p| p.parent() == Some(parent) && p.file_stem() == Some(stem))
+        .map(|p| (*p).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn starts_tracks_at_index_01() {
+        let sheet = parse(
+            "REM GENRE Jazz\n\
+             REM DATE 1959-08-17\n\
+             PERFORMER \"Band\"\n\
+             TITLE \"Album\"\n\
+             FILE \"album.flac\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             TITLE \"One\"\n\
+             INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n\
+             TITLE \"Two\"\n\
+             PERFORMER \"Guest\"\n\
+             INDEX 00 04:58:00\n\
+             INDEX 01 05:02:37\n\
+             TRACK 03 AUDIO\n\
+             TITLE \"No index\"\n",
+        );
+        assert_eq!(sheet.title.as_deref(), Some("Album"));
+        assert_eq!(sheet.genre.as_deref(), Some("Jazz"));
+        assert_eq!(sheet.year(), Some(1959));
+        assert_eq!(sheet.tracks.len(), 2);
+        assert_eq!(sheet.tracks[0].file, "album.flac");
+        assert_eq!(sheet.tracks[0].start, Duration::ZERO);
+        // 37 frames of 75 are 493.33 ms
+        assert_eq!(sheet.tracks[1].start.as_millis(), 302_493);
+        assert_eq!(sheet.tracks[1].performer.as_deref(), Some("Guest"));
+
+        let tracks = sheet.virtual_tracks(|name| Some(PathBuf::from("/music").join(name)));
+        assert_eq!(tracks[0].end, Some(sheet.tracks[1].start));
+        assert_eq!(tracks[0].performer.as_deref(), Some("Band"));
+        assert_eq!(tracks[1].end, None);
+        assert!(tracks[0].is_followed_by(&tracks[1]));
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        assert_eq!(parse_timestamp("01:02:03"), Some(ms(62_040)));
+        assert_eq!(parse_timestamp("01:60:00"), None);
+        assert_eq!(parse_timestamp("01:02:75"), None);
+        assert_eq!(parse_timestamp("01:02"), None);
+    }
+
+    #[test]
+    fn ends_tracks_at_the_end_of_their_file() {
+        let sheet = parse(
+            "FILE \"side a.wav\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n\
+             INDEX 01 03:00:00\n\
+             FILE \"side b.wav\" WAVE\n\
+             TRACK 03 AUDIO\n\
+             INDEX 01 00:00:00\n\
+             TRACK 04 AUDIO\n\
+             INDEX 01 04:00:00\n",
+        );
+        let files: Vec<_> = sheet.tracks.iter().map(|t| t.file.as_str()).collect();
+        assert_eq!(files, ["side a.wav", "side a.wav", "side b.wav", "side b.wav"]);
+
+        let tracks = sheet.virtual_tracks(|name| Some(PathBuf::from(name)));
+        let ends: Vec<_> = tracks.iter().map(|t| t.end).collect();
+        assert_eq!(ends, [Some(ms(180_000)), None, Some(ms(240_000)), None]);
+        assert!(!tracks[1].is_followed_by(&tracks[2]));
+
+        // Tracks of files that can't be found are left out
+        let tracks = sheet.virtual_tracks(|name| (name == "side b.wav").then(|| name.into()));
+        let numbers: Vec<_> = tracks.iter().map(|t| t.number).collect();
+        assert_eq!(numbers, [3, 4]);
+    }
+
+    #[test]
+    fn pregaps_in_the_previous_file_belong_to_the_next() {
+        let sheet = parse(
+            "FILE \"01.wav\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n\
+             INDEX 00 04:58:50\n\
+             FILE \"02.wav\" WAVE\n\
+             INDEX 01 00:00:00\n",
+        );
+        assert_eq!(sheet.tracks[0].file, "01.wav");
+        assert_eq!(sheet.tracks[1].file, "02.wav");
+        assert_eq!(sheet.tracks[1].start, Duration::ZERO);
+
+        // The first file plays to its end, pregap included
+        let tracks = sheet.virtual_tracks(|name| Some(PathBuf::from(name)));
+        assert_eq!(tracks[0].end, None);
+    }
+
+    #[test]
+    fn strips_file_types_and_quotes() {
+        let sheet =
+            parse("\u{feff}FILE \"My Album.flac\" FLAC\nTRACK 1 AUDIO\nINDEX 01 00:00:00\n");
+        assert_eq!(sheet.tracks[0].file, "My Album.flac");
+        let sheet = parse("FILE album.ape WAVE\nTRACK 1 AUDIO\nINDEX 01 00:00:00\n");
+        assert_eq!(sheet.tracks[0].file, "album.ape");
+    }
+
+    #[test]
+    fn maps_virtual_paths_back_to_their_file() {
+        let file = Path::new("/nonexistent/album.flac");
+        let path = virtual_path(file, 7);
+        assert_eq!(path, Path::new("/nonexistent/album.flac#07"));
+        assert_eq!(source_file(&path), file);
+        assert_eq!(source_file(file), file);
+        assert_eq!(
+            source_file(Path::new("/nonexistent/#hashtag")),
+            Path::new("/nonexistent/#hashtag")
+        );
+    }
+}
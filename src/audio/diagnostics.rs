@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use gst::prelude::*;
+use gstreamer as gst;
+
+/// What an element must do with a stream for its format to play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    /// Split a container into its streams, or unwrap raw audio
+    Demuxer,
+    /// Turn compressed audio into samples
+    Decoder,
+}
+
+use Role::{Decoder, Demuxer};
+
+/// Caps each supported extension needs to be decodable, from container down to the
+/// compressed audio stream, with the kind of element each needs. A format is
+/// playable when every entry can be sunk by at least one element of that kind in
+/// the registry. Parsers don't count: they frame a stream without decoding it.
+const FORMAT_CAPS: &[(&str, &str, &[(&str, Role)])] = &[
+    ("mp3", "MPEG-1 Layer 3", &[("audio/mpeg, mpegversion=(int)1, layer=(int)3", Decoder)]),
+    ("flac", "FLAC", &[("audio/x-flac", Decoder)]),
+    ("wav", "WAVE", &[("audio/x-wav", Demuxer)]),
+    ("ogg", "Ogg Vorbis", &[("application/ogg", Demuxer), ("audio/x-vorbis", Decoder)]),
+    // Ogg audio may hold Vorbis, Opus or FLAC
+    (
+        "oga",
+        "Ogg Audio",
+        &[("application/ogg", Demuxer), ("audio/x-vorbis; audio/x-opus; audio/x-flac", Decoder)],
+    ),
+    ("opus", "Opus", &[("application/ogg", Demuxer), ("audio/x-opus", Decoder)]),
+    (
+        "m4a",
+        "MPEG-4 AAC",
+        &[("video/quicktime", Demuxer), ("audio/mpeg, mpegversion=(int)4", Decoder)],
+    ),
+    ("aac", "ADTS AAC", &[("audio/mpeg, mpegversion=(int)4", Decoder)]),
+    ("aiff", "AIFF", &[("audio/x-aiff", Demuxer)]),
+    ("aif", "AIFF", &[("audio/x-aiff", Demuxer)]),
+    ("wv", "WavPack", &[("audio/x-wavpack", Decoder)]),
+    // The tag demuxer for APE tags sinks the same caps as the container; only
+    // a decoder for the audio itself makes the format playable
+    (
+        "ape",
+        "Monkey's Audio",
+        &[("application/x-ape", Demuxer), ("audio/x-ffmpeg-parsed-ape", Decoder)],
+    ),
+    ("mpc", "Musepack", &[("audio/x-musepack", Decoder)]),
+    ("dsf", "DSD Stream File", &[("audio/x-dsf", Demuxer)]),
+    ("wma", "Windows Media Audio", &[("video/x-ms-asf", Demuxer), ("audio/x-wma", Decoder)]),
+    ("mka", "Matroska Audio", &[("audio/x-matroska", Demuxer)]),
+];
+
+/// Whether an element of class `klass`, such as `Codec/Decoder/Audio`, can
+/// play the part of `role`.
+fn fills_role(klass: &str, role: Role) -> bool {
+    let wanted = match role {
+        Demuxer => "Demuxer",
+        Decoder => "Decoder",
+    };
+    klass.split('/').any(|part| part == wanted)
+}
+
+/// The entries of `caps` no element can handle, where `handles` tells whether
+/// some element of the given role sinks the caps.
+fn missing_caps(caps: &[(&str, Role)], handles: impl Fn(&str, Role) -> bool) -> Vec<String> {
+    caps.iter()
+        .filter(|(caps, role)| !handles(caps, *role))
+        .map(|(caps, _)| caps.to_string())
+        .collect()
+}
+
+/// Decoding support for a single file extension.
+#[derive(Clone, Debug)]
+pub struct FormatSupport {
+    pub extension: String,
+    pub description: String,
+    /// Caps no installed element can handle; empty when the format is playable.
+    pub missing: Vec<String>,
+}
+
+impl FormatSupport {
+    pub fn is_supported(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// A registry element listed on the diagnostics page.
+#[derive(Clone, Debug)]
+pub struct ElementInfo {
+    pub name: String,
+    pub long_name: String,
+    pub plugin: Option<String>,
+}
+
+/// Snapshot of what the local GStreamer installation can play.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    pub gstreamer_version: String,
+    pub formats: Vec<FormatSupport>,
+    pub decoders: Vec<ElementInfo>,
+    pub audio_sinks: Vec<ElementInfo>,
+}
+
+impl Diagnostics {
+    /// Inspect the GStreamer registry for the given library extensions.
+    /// Initializes GStreamer if needed.
+    pub fn inspect(extensions: &[String]) -> Result<Self> {
+        gst::init()?;
+
+        let decodable = gst::ElementFactory::factories_with_type(
+            gst::ElementFactoryType::DECODABLE,
+            gst::Rank::MARGINAL,
+        );
+
+        let formats = extensions
+            .iter()
+            .map(|ext| {
+                let (description, caps) = FORMAT_CAPS
+                    .iter()
+                    .find(|(e, _, _)| e.eq_ignore_ascii_case(ext))
+                    .map(|(_, d, c)| (*d, *c))
+                    .unwrap_or((ext.as_str(), &[]));
+
+                let missing = missing_caps(caps, |caps, role| {
+                    let Ok(caps) = caps.parse::<gst::Caps>() else {
+                        return false;
+                    };
+                    decodable
+                        .iter()
+                        .any(|f| fills_role(f.klass(), role) && f.can_sink_any_caps(&caps))
+                });
+
+                FormatSupport {
+                    extension: ext.to_string(),
+                    description: description.to_string(),
+                    missing,
+                }
+            })
+            .collect();
+
+        let decoders = list_elements(
+            gst::ElementFactoryType::DECODER | gst::ElementFactoryType::MEDIA_AUDIO,
+        );
+        let audio_sinks =
+            list_elements(gst::ElementFactoryType::SINK | gst::ElementFactoryType::MEDIA_AUDIO);
+
+        Ok(Self {
+            gstreamer_version: gst::version_string().to_string(),
+            formats,
+            decoders,
+            audio_sinks,
+        })
+    }
+
+    /// Look up the support entry for a file based on its extension.
+    pub fn format_for(&self, path: &Path) -> Option<&FormatSupport> {
+        let ext = path.extension().and_then(|e| e.to_str())?;
+        self.formats
+            .iter()
+            .find(|f| f.extension.eq_ignore_ascii_case(ext))
+    }
+
+    /// Whether the file's format can be decoded. Unknown formats are assumed playable
+    /// so that files accepted by other means are not flagged spuriously.
+    pub fn is_playable(&self, path: &Path) -> bool {
+        self.format_for(path).is_none_or(FormatSupport::is_supported)
+    }
+
+    /// Library files whose codec is not decodable with the installed plugins.
+    pub fn unsupported_tracks<'a>(&self, paths: &'a [PathBuf]) -> Vec<&'a PathBuf> {
+        paths.iter().filter(|p| !self.is_playable(p)).collect()
+    }
+}
+
+fn list_elements(kind: gst::ElementFactoryType) -> Vec<ElementInfo> {
+    let mut elements: Vec<ElementInfo> =
+        gst::ElementFactory::factories_with_type(kind, gst::Rank::NONE)
+            .iter()
+            .map(|f| ElementInfo {
+                name: f.name().to_string(),
+                long_name: f
+                    .metadata(gst::ELEMENT_METADATA_LONGNAME)
+                    .unwrap_or_default()
+                    .to_string(),
+                plugin: f.plugin_name().map(|p| p.to_string()),
+            })
+            .collect();
+    elements.sort_by(|a, b| a.name.cmp(&b.name));
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsers_are_not_decoders() {
+        for parser in ["Codec/Parser/Audio", "Codec/Parser/Converter/Audio"] {
+            assert!(!fills_role(parser, Decoder));
+            assert!(!fills_role(parser, Demuxer));
+        }
+        assert!(fills_role("Codec/Decoder/Audio", Decoder));
+        assert!(!fills_role("Codec/Decoder/Audio", Demuxer));
+        assert!(fills_role("Codec/Demuxer", Demuxer));
+        assert!(fills_role("Codec/Demuxer/Audio", Demuxer));
+    }
+
+    fn caps_of(extension: &str) -> &'static [(&'static str, Role)] {
+        FORMAT_CAPS
+            .iter()
+            .find(|(e, _, _)| *e == extension)
+            .map(|(_, _, caps)| *caps)
+            .unwrap()
+    }
+
+    #[test]
+    fn tag_demuxers_dont_make_ape_playable() {
+        // Only `apedemux`, a tag demuxer, is installed
+        let tags_only = |caps: &str, role| caps == "application/x-ape" && role == Demuxer;
+        assert_eq!(
+            missing_caps(caps_of("ape"), tags_only),
+            ["audio/x-ffmpeg-parsed-ape"]
+        );
+
+        let with_decoder = |caps: &str, role| {
+            tags_only(caps, role) || (caps == "audio/x-ffmpeg-parsed-ape" && role == Decoder)
+        };
+        assert!(missing_caps(caps_of("ape"), with_decoder).is_empty());
+    }
+
+    #[test]
+    fn lists_tracks_in_unsupported_formats() {
+        let format = |extension: &str, missing: &[&str]| FormatSupport {
+            extension: extension.into(),
+            description: String::new(),
+            missing: missing.iter().map(|m| m.to_string()).collect(),
+        };
+        let diagnostics = Diagnostics {
+            formats: vec![format("flac", &[]), format("ape", &["audio/x-ffmpeg-parsed-ape"])],
+            ..Default::default()
+        };
+        let paths: Vec<PathBuf> = ["/m/a.flac", "/m/b.APE", "/m/c.xyz", "/m/d"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        // Formats nothing was learned about are given the benefit of the doubt
+        assert_eq!(diagnostics.unsupported_tracks(&paths), [&paths[1]]);
+    }
+}
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The chain of effects playback runs through, set as playbin's audio filter:
+//! time stretching that keeps the pitch when playing faster or slower, a pitch
+//! shift, a preamp and a ten-band graphic equalizer, then a parametric
+//! equalizer with its own preamp. Their settings are element properties, so
+//! changing them takes effect while playing.
+
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+
+use crate::equalizer::{EqualizerPreset, BANDS};
+use crate::parametric::{FilterKind, ParametricProfile};
+
+/// Most bands `equalizer-nbands` takes.
+const MAX_PARAMETRIC_BANDS: usize = 64;
+
+/// Gain range of `equalizer-nbands` bands in dB.
+const PARAMETRIC_GAIN: (f64, f64) = (-24.0, 12.0);
+
+/// Cutoffs at which `audiocheblimit` lets everything through, as a high-pass
+/// and as a low-pass filter.
+const OPEN_HIGH_PASS: f32 = 0.0;
+const OPEN_LOW_PASS: f32 = 100_000.0;
+
+/// Range of the pitch shift in semitones.
+pub const MAX_PITCH_SHIFT: i32 = 12;
+
+#[derive(Clone)]
+pub struct Effects {
+    bin: gst::Bin,
+    /// SoundTouch's `pitch`, from the optional Bad plugins
+    pitch: Option<gst::Element>,
+    preamp: gst::Element,
+    equalizer: gst::Element,
+    parametric_preamp: gst::Element,
+    /// Peaking and shelving filters
+    parametric: gst::Element,
+    high_pass: gst::Element,
+    low_pass: gst::Element,
+}
+
+impl Effects {
+    pub fn new() -> Result<Self> {
+        let make = |factory: &str| {
+            gst::ElementFactory::make(factory)
+                .build()
+                .map_err(|_| anyhow!("GStreamer element {factory} is missing"))
+        };
+        // The equalizer only takes some sample formats
+        let convert_in = make("audioconvert")?;
+        let tempo = make("scaletempo")?;
+        let pitch = match make("pitch") {
+            Ok(pitch) => Some(pitch),
+            Err(err) => {
+                eprintln!("pitch shifting is unavailable: {err}");
+                None
+            }
+        };
+        let preamp = make("volume")?;
+        let equalizer = make("equalizer-10bands")?;
+        let parametric_preamp = make("volume")?;
+        let parametric = make("equalizer-nbands")?;
+        let high_pass = make("audiocheblimit")?;
+        let low_pass = make("audiocheblimit")?;
+        let convert_out = make("audioconvert")?;
+        // Two poles without ripple: the Butterworth filters parametric
+        // equalizers use
+        for (filter, mode) in [(&high_pass, "high-pass"), (&low_pass, "low-pass")] {
+            filter.set_property_from_str("mode", mode);
+            filter.set_property("poles", 2i32);
+            filter.set_property("ripple", 0f32);
+        }
+
+        let bin = gst::Bin::builder().name("effects").build();
+        let mut chain = vec![convert_in.clone(), tempo];
+        if let Some(pitch) = &pitch {
+            chain.extend([make("audioconvert")?, pitch.clone(), make("audioconvert")?]);
+        }
+        chain.extend([
+            preamp.clone(),
+            equalizer.clone(),
+            parametric_preamp.clone(),
+            parametric.clone(),
+            high_pass.clone(),
+            low_pass.clone(),
+            convert_out.clone(),
+        ]);
+        bin.add_many(&chain)?;
+        gst::Element::link_many(&chain)?;
+        for (element, name) in [(&convert_in, "sink"), (&convert_out, "src")] {
+            let pad = element
+                .static_pad(name)
+                .ok_or_else(|| anyhow!("audioconvert has no {name} pad"))?;
+            bin.add_pad(&gst::GhostPad::with_target(&pad)?)?;
+        }
+
+        let effects = Self {
+            bin,
+            pitch,
+            preamp,
+            equalizer,
+            parametric_preamp,
+            parametric,
+            high_pass,
+            low_pass,
+        };
+        effects.set_parametric(None);
+        Ok(effects)
+    }
+
+    /// The bin to set as playbin's `audio-filter`.
+    pub fn element(&self) -> &gst::Element {
+        self.bin.upcast_ref()
+    }
+
+    pub fn has_pitch(&self) -> bool {
+        self.pitch.is_some()
+    }
+
+    /// Shift the pitch by `semitones`, up to [`MAX_PITCH_SHIFT`] either way.
+    pub fn set_pitch(&self, semitones: i32) {
+        if let Some(pitch) = &self.pitch {
+            let semitones = semitones.clamp(-MAX_PITCH_SHIFT, MAX_PITCH_SHIFT);
+            let ratio = 2f32.powf(semitones as f32 / 12.0);
+            pitch.set_property("pitch", ratio);
+        }
+    }
+
+    /// Apply equalizer settings, or leave the sound untouched for `None`.
+    pub fn set_equalizer(&self, preset: Option<&EqualizerPreset>) {
+        let flat = EqualizerPreset::default();
+        let preset = preset.unwrap_or(&flat);
+        for band in 0..BANDS {
+            self.equalizer
+                .set_property(&format!("band{band}"), preset.gain_db(band));
+        }
+        self.preamp.set_property("volume", volume(preset.preamp_db()));
+    }
+
+    /// Apply a parametric profile, or leave the sound untouched for `None`.
+    /// Peaking and shelving filters become bands of `equalizer-nbands`; of
+    /// several low-pass or high-pass filters, only the strictest is used.
+    pub fn set_parametric(&self, profile: Option<&ParametricProfile>) {
+        let filters: Vec<_> = profile
+            .map(|p| p.filters.iter().filter(|f| f.enabled).collect())
+            .unwrap_or_default();
+        let bands: Vec<_> = filters
+            .iter()
+            .filter(|f| f.kind.has_gain())
+            .take(MAX_PARAMETRIC_BANDS)
+            .collect();
+
+        // The element has at least one band, left flat when there are none
+        self.parametric
+            .set_property("num-bands", bands.len().max(1) as u32);
+        let proxy = self.parametric.dynamic_cast_ref::<gst::ChildProxy>();
+        for index in 0..bands.len().max(1) {
+            let Some(band) = proxy.and_then(|p| p.child_by_index(index as u32)) else {
+                continue;
+            };
+            let Some(filter) = bands.get(index) else {
+                band.set_property("gain", 0f64);
+                continue;
+            };
+            let kind = match filter.kind {
+                FilterKind::LowShelf => "low-shelf",
+                FilterKind::HighShelf => "high-shelf",
+                _ => "peak",
+            };
+            band.set_property_from_str("type", kind);
+            band.set_property("freq", f64::from(filter.frequency));
+            band.set_property("bandwidth", filter.bandwidth_hz());
+            let (min, max) = PARAMETRIC_GAIN;
+            band.set_property("gain", filter.gain_db().clamp(min, max));
+        }
+
+        let cutoff = |kind| {
+            filters
+                .iter()
+                .filter(|f| f.kind == kind)
+                .map(|f| f.frequency as f32)
+        };
+        let high_pass = cutoff(FilterKind::HighPass).fold(OPEN_HIGH_PASS, f32::max);
+        let low_pass = cutoff(FilterKind::LowPass).fold(OPEN_LOW_PASS, f32::min);
+        self.high_pass.set_property("cutoff", high_pass);
+        self.low_pass.set_property("cutoff", low_pass);
+
+        let preamp = profile.map(ParametricProfile::preamp_db).unwrap_or(0.0);
+        self.parametric_preamp.set_property("volume", volume(preamp));
+    }
+}
+
+/// Linear volume of a gain in dB.
+fn volume(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
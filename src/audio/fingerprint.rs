@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Chromaprint fingerprints of tracks, computed from audio decoded by GStreamer.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gstreamer_app as gst_app;
+use gst::prelude::*;
+use rusty_chromaprint::{Configuration, Fingerprinter};
+
+/// How much of a track is fingerprinted: its first two minutes, as AcoustID
+/// expects.
+pub const FINGERPRINT_LENGTH: Duration = Duration::from_secs(120);
+
+/// Chromaprint works on mono audio at this rate; GStreamer converts to it so
+/// the fingerprinter doesn't have to.
+const SAMPLE_RATE: u32 = 11025;
+
+/// How long to wait for decoded audio before giving up on a file.
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the bus is checked for errors while waiting for audio.
+const POLL_INTERVAL: gst::ClockTime = gst::ClockTime::from_mseconds(100);
+
+/// The raw Chromaprint fingerprint of the file at `path`.
+pub fn fingerprint_file(path: &Path) -> Result<Vec<u32>> {
+    gst::init()?;
+    let uri = gst::glib::filename_to_uri(path, None)?;
+    let pipeline = gst::parse::launch(&format!(
+        "uridecodebin name=decoder ! audioconvert ! audioresample \
+         ! audio/x-raw,format=S16LE,channels=1,rate={SAMPLE_RATE} \
+         ! appsink name=sink sync=false"
+    ))?
+    .downcast::<gst::Pipeline>()
+    .map_err(|_| anyhow!("failed to build a decoding pipeline"))?;
+    let decoder = pipeline
+        .by_name("decoder")
+        .ok_or_else(|| anyhow!("decoding pipeline has no decoder"))?;
+    decoder.set_property("uri", uri.as_str());
+    let sink = pipeline
+        .by_name("sink")
+        .and_then(|e| e.downcast::<gst_app::AppSink>().ok())
+        .ok_or_else(|| anyhow!("decoding pipeline has no sink"))?;
+
+    let result = decode(&pipeline, &sink);
+    let _ = pipeline.set_state(gst::State::Null);
+    result.map_err(|e| anyhow!("failed to fingerprint {:?}: {e}", path))
+}
+
+fn decode(pipeline: &gst::Pipeline, sink: &gst_app::AppSink) -> Result<Vec<u32>> {
+    let config = Configuration::preset_test2();
+    let mut printer = Fingerprinter::new(&config);
+    printer
+        .start(SAMPLE_RATE, 1)
+        .map_err(|e| anyhow!("failed to start fingerprinting: {e:?}"))?;
+
+    let bus = pipeline
+        .bus()
+        .ok_or_else(|| anyhow!("decoding pipeline has no bus"))?;
+    pipeline.set_state(gst::State::Playing)?;
+    let wanted = (FINGERPRINT_LENGTH.as_secs() * u64::from(SAMPLE_RATE)) as usize;
+    let mut consumed = 0;
+    let mut last_sample = Instant::now();
+    // A decoder that failed never ends the stream, so waiting for samples
+    // stops now and then to look for errors
+    while consumed < wanted {
+        let Some(sample) = sink.try_pull_sample(POLL_INTERVAL) else {
+            if let Some(msg) = bus.pop_filtered(&[gst::MessageType::Error]) {
+                if let gst::MessageView::Error(err) = msg.view() {
+                    return Err(anyhow!("{}", err.error()));
+                }
+            }
+            if sink.is_eos() {
+                break;
+            }
+            if last_sample.elapsed() > SAMPLE_TIMEOUT {
+                return Err(anyhow!("no audio decoded for {} seconds", SAMPLE_TIMEOUT.as_secs()));
+            }
+            continue;
+        };
+        last_sample = Instant::now();
+        let Some(buffer) = sample.buffer() else {
+            continue;
+        };
+        let map = buffer.map_readable()?;
+        let samples: Vec<i16> = map
+            .chunks_exact(2)
+            .take(wanted - consumed)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        consumed += samples.len();
+        printer.consume(&samples);
+    }
+
+    if let Some(msg) = bus.pop_filtered(&[gst::MessageType::Error]) {
+        if let gst::MessageView::Error(err) = msg.view() {
+            return Err(anyhow!("{}", err.error()));
+        }
+    }
+    if consumed == 0 {
+        return Err(anyhow!("no audio decoded"));
+    }
+    printer.finish();
+    Ok(printer.fingerprint().to_vec())
+}
@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Tracking how long a track is listened to, and when that counts as a play.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::backend::TrackMetadata;
+
+/// Position jumps larger than this between two updates are seeks, not listening.
+const MAX_STEP: Duration = Duration::from_secs(2);
+
+/// When a listen counts as a play: after `fraction` of the track has been heard,
+/// or after `max`, whichever comes first. Tracks of `min_length` or shorter
+/// never count.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlayThreshold {
+    pub fraction: f64,
+    pub max: Duration,
+    pub min_length: Duration,
+}
+
+impl Default for PlayThreshold {
+    fn default() -> Self {
+        Self {
+            fraction: 0.5,
+            max: Duration::from_secs(4 * 60),
+            min_length: Duration::ZERO,
+        }
+    }
+}
+
+impl PlayThreshold {
+    pub fn is_reached(&self, listened: Duration, length: Option<Duration>) -> bool {
+        if length.is_some_and(|l| !l.is_zero() && l <= self.min_length) {
+            return false;
+        }
+        listened >= self.max
+            || length.is_some_and(|l| !l.is_zero() && listened >= l.mul_f64(self.fraction))
+    }
+}
+
+/// How a listen came to an end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenEnd {
+    /// The track played to its end
+    Completed,
+    /// The user moved on to the next track
+    Skipped,
+    /// Playback was stopped or another track was picked
+    Stopped,
+}
+
+/// A track that is currently being listened to.
+#[derive(Clone, Debug)]
+pub struct ListenSession {
+    pub path: PathBuf,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    /// Time actually spent playing, excluding seeks and pauses
+    pub listened: Duration,
+    /// Whether the listen has already been counted as a play
+    pub counted: bool,
+    last_position: Option<Duration>,
+}
+
+impl ListenSession {
+    pub fn new(path: PathBuf, started_at: u64) -> Self {
+        Self {
+            path,
+            started_at,
+            listened: Duration::ZERO,
+            counted: false,
+            last_position: None,
+        }
+    }
+
+    /// Feed the current playback position. Returns `true` when this update makes
+    /// the listen count as a play; it does so only once per session.
+    pub fn advance(
+        &mut self,
+        position: Duration,
+        length: Option<Duration>,
+        threshold: &PlayThreshold,
+    ) -> bool {
+        if let Some(last) = self.last_position {
+            if position > last && position - last <= MAX_STEP {
+                self.listened += position - last;
+            }
+        }
+        self.last_position = Some(position);
+
+        if !self.counted && threshold.is_reached(self.listened, length) {
+            self.counted = true;
+            return true;
+        }
+        false
+    }
+
+    /// Record a play of the track at `at` (seconds since the Unix epoch).
+    pub fn record_play(&self, md: &mut TrackMetadata, at: u64) {
+        md.play_count += 1;
+        md.first_played.get_or_insert(at);
+        md.last_played = Some(at);
+    }
+
+    /// Add the session's listening time to the track, counting a skip if the
+    /// user moved on before it counted as a play.
+    pub fn record_end(&self, md: &mut TrackMetadata, end: ListenEnd) {
+        md.listening_time += self.listened;
+        if end == ListenEnd::Skipped && !self.counted {
+            md.skip_count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn counts_half_the_track_or_four_minutes() {
+        let threshold = PlayThreshold::default();
+        let song = Some(secs(200));
+        assert!(!threshold.is_reached(secs(99), song));
+        assert!(threshold.is_reached(secs(100), song));
+
+        // Long tracks count after four minutes, whatever their length
+        let mix = Some(secs(60 * 60));
+        assert!(!threshold.is_reached(secs(239), mix));
+        assert!(threshold.is_reached(secs(240), mix));
+
+        // Without a length, only the cap applies
+        assert!(!threshold.is_reached(secs(200), None));
+        assert!(!threshold.is_reached(secs(200), Some(Duration::ZERO)));
+        assert!(threshold.is_reached(secs(240), None));
+    }
+
+    #[test]
+    fn never_counts_tracks_of_the_minimum_length() {
+        let threshold = PlayThreshold {
+            min_length: secs(30),
+            ..Default::default()
+        };
+        assert!(!threshold.is_reached(secs(30), Some(secs(30))));
+        assert!(threshold.is_reached(secs(16), Some(secs(31))));
+        // The default has no minimum
+        assert!(PlayThreshold::default().is_reached(secs(10), Some(secs(20))));
+    }
+
+    #[test]
+    fn counts_listening_not_seeking() {
+        let threshold = PlayThreshold::default();
+        let length = Some(secs(20));
+        let mut session = ListenSession::new(PathBuf::from("/music/a.flac"), 0);
+
+        assert!(!session.advance(secs(0), length, &threshold));
+        assert!(!session.advance(secs(1), length, &threshold));
+        // A seek ahead and one back add nothing
+        assert!(!session.advance(secs(15), length, &threshold));
+        assert!(!session.advance(secs(2), length, &threshold));
+        assert_eq!(session.listened, secs(1));
+
+        let counted: Vec<bool> = (3..=12)
+            .map(|s| session.advance(secs(s), length, &threshold))
+            .collect();
+        assert_eq!(session.listened, secs(11));
+        // Counted once, as soon as half the track was heard
+        assert_eq!(counted.iter().filter(|c| **c).count(), 1);
+        assert!(counted[8]);
+        assert!(session.counted);
+    }
+
+    #[test]
+    fn records_plays_and_skips() {
+        let mut md = TrackMetadata::default();
+        let mut session = ListenSession::new(PathBuf::from("/music/a.flac"), 100);
+        session.listened = secs(30);
+
+        session.record_end(&mut md, ListenEnd::Skipped);
+        assert_eq!((md.skip_count, md.listening_time), (1, secs(30)));
+        session.record_end(&mut md, ListenEnd::Stopped);
+        assert_eq!(md.skip_count, 1);
+
+        session.counted = true;
+        session.record_play(&mut md, 100);
+        session.record_play(&mut md, 200);
+        session.record_end(&mut md, ListenEnd::Skipped);
+        assert_eq!(md.play_count, 2);
+        assert_eq!((md.first_played, md.last_played), (Some(100), Some(200)));
+        assert_eq!(md.skip_count, 1);
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Finding a track's lyrics: in an `.lrc` file next to it, in an ID3 `SYLT`
+//! frame, or in its unsynchronized lyrics tag (`USLT`, `LYRICS`, `©lyr`),
+//! which often holds LRC text too.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use lofty::config::ParseOptions;
+use lofty::file::FileType;
+use lofty::id3::v2::{Frame, SynchronizedTextFrame, TimestampFormat};
+use lofty::mpeg::MpegFile;
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::ItemKey;
+
+use super::tags;
+use crate::lrc::Lyrics;
+
+/// The `.lrc` file kept next to the track at `path`, if there is one.
+pub fn sidecar_file(path: &Path) -> Option<PathBuf> {
+    ["lrc", "LRC"]
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|p| p.is_file())
+}
+
+/// The lyrics of the track at `path`. Synchronized lyrics are preferred over
+/// plain ones wherever they are found; otherwise the sidecar file comes
+/// before the lyrics tag, and that before `SYLT` frames.
+pub fn read_lyrics(path: &Path) -> Result<Option<Lyrics>> {
+    let mut found = Vec::new();
+    if let Some(sidecar) = sidecar_file(path) {
+        let bytes = fs::read(&sidecar).map_err(|e| anyhow!("failed to read {:?}: {e}", sidecar))?;
+        found.push(Lyrics::parse(&String::from_utf8_lossy(&bytes)));
+    }
+
+    let tagged = Probe::open(path)?.guess_file_type()?.read()?;
+    for tag in tagged.tags() {
+        if let Some(text) = tag.get_string(&ItemKey::Lyrics) {
+            found.push(Lyrics::parse(text));
+        }
+    }
+    if tagged.file_type() == FileType::Mpeg {
+        found.extend(synchronized_frames(path)?);
+    }
+
+    found.retain(|lyrics| !lyrics.lines.is_empty());
+    let synced = found.iter().position(Lyrics::is_synced);
+    Ok(match synced {
+        Some(index) => Some(found.swap_remove(index)),
+        None => found.into_iter().next(),
+    })
+}
+
+/// Write `lyrics` as LRC to the `.lrc` file next to the track at `path`,
+/// replacing the one there is.
+pub fn save_sidecar(path: &Path, lyrics: &Lyrics) -> Result<()> {
+    let sidecar = sidecar_file(path).unwrap_or_else(|| path.with_extension("lrc"));
+    fs::write(&sidecar, lyrics.to_lrc()).map_err(|e| anyhow!("failed to write {:?}: {e}", sidecar))
+}
+
+/// Store `lyrics` as LRC in the lyrics tag of the file at `path`. An `.lrc`
+/// file next to the track would still be read first, so it is replaced too.
+pub fn embed_lyrics(path: &Path, lyrics: &Lyrics) -> Result<()> {
+    let text = lyrics.to_lrc();
+    tags::edit_tag(path, |tag| {
+        tag.insert_text(ItemKey::Lyrics, text.clone());
+    })
+    .map_err(|e| anyhow!("failed to embed lyrics in {:?}: {e}", path))?;
+    if sidecar_file(path).is_some() {
+        save_sidecar(path, lyrics)?;
+    }
+    Ok(())
+}
+
+/// Lyrics in the `SYLT` frames of an MP3 file's ID3v2 tag. Frames timed in
+/// MPEG frames rather than milliseconds are skipped.
+fn synchronized_frames(path: &Path) -> Result<Vec<Lyrics>> {
+    let mut file = File::open(path)?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new())?;
+    let Some(id3) = mpeg.id3v2() else {
+        return Ok(Vec::new());
+    };
+    let mut found = Vec::new();
+    for frame in id3 {
+        let Frame::Binary(binary) = frame else {
+            continue;
+        };
+        if frame.id().as_str() != "SYLT" {
+            continue;
+        }
+        let sylt = match SynchronizedTextFrame::parse(&binary.data, frame.flags()) {
+            Ok(sylt) => sylt,
+            Err(err) => {
+                eprintln!("invalid synchronized lyrics in {:?}: {err}", path);
+                continue;
+            }
+        };
+        if sylt.timestamp_format != TimestampFormat::MS {
+            continue;
+        }
+        let pieces: Vec<(Duration, String)> = sylt
+            .content
+            .into_iter()
+            .map(|(ms, text)| (Duration::from_millis(u64::from(ms)), text))
+            .collect();
+        found.push(Lyrics::from_timed_text(&pieces));
+    }
+    Ok(found)
+}
@@ -1,43 +1,174 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::SystemTime;
 
 use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gstreamer_pbutils as gst_pbutils;
 use lofty::prelude::*;
 use lofty::probe::Probe;
+use lofty::tag::{ItemKey, Tag};
 
 use super::backend::TrackMetadata;
+use super::rating;
 
 /// Parse metadata for a single audio file using the `lofty` crate.
+///
+/// Formats lofty cannot read (DSF, WMA, Matroska, Ogg FLAC, ...) fall back to
+/// GStreamer's discoverer, which extracts tags through the installed demuxers.
 pub fn parse_file_metadata(path: &Path) -> Result<TrackMetadata> {
+    let mut md = match parse_with_lofty(path) {
+        Ok(md) => md,
+        Err(lofty_err) => parse_with_discoverer(path).map_err(|e| anyhow!("{lofty_err}; {e}"))?,
+    };
+    // Until the library has seen the file, the best guess for when it was added
+    // is when it was last written.
+    md.date_added = modified_time(path);
+    md.file_modified = md.date_added;
+    md.file_size = file_size(path);
+    Ok(md)
+}
+
+/// Seconds since the Unix epoch for `time`, clamping times before it to zero.
+pub fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The file's modification time in seconds since the Unix epoch.
+pub fn modified_time(path: &Path) -> Option<u64> {
+    fs::metadata(path).and_then(|m| m.modified()).ok().map(unix_time)
+}
+
+/// The file's size in bytes.
+pub fn file_size(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().map(|m| m.len())
+}
+
+fn parse_with_lofty(path: &Path) -> Result<TrackMetadata> {
+    // Detect the file type from content so mislabeled files use the right reader
     let tagged = Probe::open(path)
         .map_err(|e| anyhow!("failed to open {:?}: {e}", path))?
+        .guess_file_type()
+        .map_err(|e| anyhow!("failed to probe {:?}: {e}", path))?
         .read()
         .map_err(|e| anyhow!("failed to read tags for {:?}: {e}", path))?;
 
-    let tag = tagged.primary_tag().or_else(|| tagged.first_tag());
+    // Prefer the format's primary tag (ID3v2, Vorbis comments, APE, MP4 ilst, ...)
+    // but fill gaps from any secondary tag such as ID3v1 or RIFF INFO.
+    let primary = tagged.primary_tag();
+    let tags: Vec<&Tag> = primary
+        .into_iter()
+        .chain(
+            tagged
+                .tags()
+                .iter()
+                .filter(|t| Some(t.tag_type()) != primary.map(Tag::tag_type)),
+        )
+        .collect();
     let props = tagged.properties();
 
     let mut md = TrackMetadata::default();
 
-    if let Some(t) = tag.and_then(|t| t.title()) {
-        md.title = Some(t.to_string());
-    }
-    if let Some(a) = tag.and_then(|t| t.album()) {
-        md.album = Some(a.to_string());
-    }
-    if let Some(ar) = tag.and_then(|t| t.artist()) {
-        md.artist = Some(ar.to_string());
-    }
+    md.title = tags.iter().find_map(|t| non_empty(t.title()));
+    md.album = tags.iter().find_map(|t| non_empty(t.album()));
+    md.artist = tags.iter().find_map(|t| non_empty(t.artist()));
+    md.album_artist = tags
+        .iter()
+        .find_map(|t| non_empty(t.get_string(&ItemKey::AlbumArtist).map(Cow::Borrowed)));
+    md.genre = tags.iter().find_map(|t| non_empty(t.genre()));
+    md.year = tags.iter().find_map(|t| t.year()).filter(|y| *y > 0);
+    md.track = tags.iter().find_map(|t| t.track()).filter(|n| *n > 0);
+    md.disc = tags.iter().find_map(|t| t.disk()).filter(|n| *n > 0);
+    md.comment = tags.iter().find_map(|t| non_empty(t.comment()));
+    let text = |key: ItemKey| {
+        tags.iter().find_map(|t| non_empty(t.get_string(&key).map(Cow::Borrowed)))
+    };
+    md.musicbrainz_recording_id = text(ItemKey::MusicBrainzRecordingId);
+    md.musicbrainz_release_id = text(ItemKey::MusicBrainzReleaseId);
+    md.musicbrainz_release_group_id = text(ItemKey::MusicBrainzReleaseGroupId);
+    md.musicbrainz_artist_id = text(ItemKey::MusicBrainzArtistId);
+    md.rating = rating::read_rating(path, &tagged);
+
+    // Playback still queries the backend for duration; this one feeds the library.
+    md.duration = Some(props.duration()).filter(|d| !d.is_zero());
+    md.bitrate = props.audio_bitrate().or(props.overall_bitrate()).filter(|b| *b > 0);
+
+    Ok(md)
+}
+
+thread_local! {
+    /// The discoverer for files lofty can't read, set up once per scanning thread
+    /// rather than for every file.
+    static DISCOVERER: OnceCell<Option<gst_pbutils::Discoverer>> = const { OnceCell::new() };
+}
+
+fn parse_with_discoverer(path: &Path) -> Result<TrackMetadata> {
+    let uri = gst::glib::filename_to_uri(path, None)?;
+    let info = DISCOVERER.with(|discoverer| {
+        let discoverer = discoverer
+            .get_or_init(|| {
+                gst::init().ok()?;
+                gst_pbutils::Discoverer::new(gst::ClockTime::from_seconds(5)).ok()
+            })
+            .as_ref()
+            .ok_or_else(|| anyhow!("GStreamer's discoverer is unavailable"))?;
+        Ok::<_, anyhow::Error>(discoverer.discover_uri(&uri)?)
+    })?;
 
-    // Duration is optional in TrackMetadata (backend), keep using backend's duration query for playback.
-    let _duration: Option<Duration> = Some(props.duration());
+    let mut md = TrackMetadata::default();
+    if let Some(tags) = info.tags() {
+        md.title = tags.get::<gst::tags::Title>().map(|v| v.get().to_string());
+        md.album = tags.get::<gst::tags::Album>().map(|v| v.get().to_string());
+        md.artist = tags.get::<gst::tags::Artist>().map(|v| v.get().to_string());
+        md.album_artist = tags
+            .get::<gst::tags::AlbumArtist>()
+            .map(|v| v.get().to_string());
+        md.genre = tags.get::<gst::tags::Genre>().map(|v| v.get().to_string());
+        md.track = tags.get::<gst::tags::TrackNumber>().map(|v| v.get());
+        md.disc = tags
+            .get::<gst::tags::AlbumVolumeNumber>()
+            .map(|v| v.get());
+        md.comment = tags.get::<gst::tags::Comment>().map(|v| v.get().to_string());
+        // GStreamer calls recordings tracks and releases albums
+        md.musicbrainz_recording_id = tags
+            .get::<gst::tags::MusicbrainzTrackid>()
+            .map(|v| v.get().to_string());
+        md.musicbrainz_release_id = tags
+            .get::<gst::tags::MusicbrainzAlbumid>()
+            .map(|v| v.get().to_string());
+        md.musicbrainz_artist_id = tags
+            .get::<gst::tags::MusicbrainzArtistid>()
+            .map(|v| v.get().to_string());
+        md.year = tags
+            .get::<gst::tags::DateTime>()
+            .map(|v| v.get().year())
+            .and_then(|y| u32::try_from(y).ok());
+    }
+    md.duration = info
+        .duration()
+        .map(|d| std::time::Duration::from_nanos(d.nseconds()));
+    md.bitrate = info
+        .audio_streams()
+        .first()
+        .map(|stream| stream.bitrate() / 1000)
+        .filter(|b| *b > 0);
 
     Ok(md)
 }
 
+fn non_empty(value: Option<Cow<'_, str>>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
 /// Parse metadata for a list of files.
 pub fn parse_files_metadata(paths: &[PathBuf]) -> Vec<(PathBuf, TrackMetadata)> {
     paths
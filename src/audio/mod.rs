@@ -1,4 +1,13 @@
 pub mod backend;
 pub mod queue;
 pub mod mpris;
-pub mod metadata;
\ No newline at end of file
+pub mod metadata;
+pub mod cue;
+pub mod diagnostics;
+pub mod listen;
+pub mod rating;
+pub mod tags;
+pub mod artwork;
+pub mod fingerprint;
+pub mod lyrics;
+pub mod effects;
@@ -4,6 +4,8 @@ use std::thread;
 use mpris_server::{Metadata, Player, Time};
 use tokio::sync::mpsc;
 
+use super::backend::{MAX_RATE, MIN_RATE};
+
 #[derive(Debug, Clone)]
 pub enum MprisCommand {
     SetPlayback { playing: bool, position: Option<Duration> },
@@ -12,7 +14,11 @@ pub enum MprisCommand {
         artist: Option<String>,
         album: Option<String>,
         length: Option<Duration>,
+        /// Star rating out of 5, sent as `xesam:userRating`
+        rating: Option<u8>,
     },
+    /// Playback speed as a multiple of normal speed
+    SetRate(f64),
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +28,9 @@ pub enum MprisEvent {
     Next,
     Previous,
     SeekTo(Duration),
+    /// Seek forward, or back for negative offsets, by milliseconds
+    SeekBy(i64),
+    SetRate(f64),
 }
 
 pub struct MprisHandle {
@@ -50,6 +59,9 @@ pub fn start(app_id: &str) -> MprisHandle {
                 .can_go_next(true)
                 .can_go_previous(true)
                 .can_seek(true)
+                .rate(1.0)
+                .minimum_rate(MIN_RATE)
+                .maximum_rate(MAX_RATE)
                 .identity("COSMIC Music Player")
                 .build()
                 .await
@@ -83,11 +95,21 @@ pub fn start(app_id: &str) -> MprisHandle {
             });
             
             let tx = evt_tx.clone();
-            player.connect_seek(move |_p, pos| {
-                let dur = Duration::from_micros(pos.as_micros() as u64);
+            player.connect_seek(move |_p, offset| {
+                let _ = tx.try_send(MprisEvent::SeekBy(offset.as_millis()));
+            });
+
+            let tx = evt_tx.clone();
+            player.connect_set_position(move |_p, _track, pos| {
+                let dur = Duration::from_micros(pos.as_micros().max(0) as u64);
                 let _ = tx.try_send(MprisEvent::SeekTo(dur));
             });
 
+            let tx = evt_tx.clone();
+            player.connect_set_rate(move |_p, rate| {
+                let _ = tx.try_send(MprisEvent::SetRate(rate));
+            });
+
             // Run event loop for mpris_server on the local set
             tokio::task::spawn_local(player.run());
 
@@ -112,14 +134,20 @@ pub fn start(app_id: &str) -> MprisHandle {
                         artist,
                         album,
                         length,
+                        rating,
                     } => {
                         let mut builder = Metadata::builder();
                         if let Some(t) = title { builder = builder.title(t); }
                         if let Some(a) = album { builder = builder.album(a); }
                         if let Some(ar) = artist { builder = builder.artist([ar]); }
                         if let Some(d) = length { builder = builder.length(Time::from_micros(d.as_micros() as i64)); }
+                        if let Some(r) = rating { builder = builder.user_rating(f64::from(r) / 5.0); }
                         let _ = player.set_metadata(builder.build()).await;
                     }
+
+                    MprisCommand::SetRate(rate) => {
+                        let _ = player.set_rate(rate).await;
+                    }
                 }
             }
         });
@@ -1,8 +1,11 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use std::path::{PathBuf};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::fs;
 
+use lofty::probe::Probe;
+
 /// A simple queue/playlist manager.
 #[derive(Debug, Default, Clone)]
 pub struct Queue {
@@ -19,6 +22,17 @@ impl Queue {
         Self { tracks: v, index: 0 }
     }
 
+    /// Replace the queue's contents, starting again from the first track.
+    pub fn replace(&mut self, tracks: impl IntoIterator<Item = PathBuf>) {
+        self.tracks = tracks.into_iter().collect();
+        self.index = 0;
+    }
+
+    /// Add tracks to the end of the queue, keeping the current position.
+    pub fn append(&mut self, tracks: impl IntoIterator<Item = PathBuf>) {
+        self.tracks.extend(tracks);
+    }
+
     pub fn push(&mut self, path: PathBuf) {
         self.tracks.push(path);
     }
@@ -55,6 +69,15 @@ impl Queue {
         self.tracks.get(self.index)
     }
 
+    /// The track `next` would move to, without advancing.
+    pub fn peek_next(&self) -> Option<&PathBuf> {
+        if self.tracks.is_empty() {
+            return None;
+        }
+
+        self.tracks.get((self.index + 1) % self.tracks.len())
+    }
+
     pub fn current(&self) -> Option<&PathBuf> {
         self.tracks.get(self.index)
     }
@@ -72,6 +95,15 @@ impl Queue {
         self.index = 0;
     }
 
+    /// Replace the paths of tracks whose files were moved.
+    pub fn move_paths(&mut self, moved: &HashMap<PathBuf, PathBuf>) {
+        for track in &mut self.tracks {
+            if let Some(to) = moved.get(&*track) {
+                *track = to.clone();
+            }
+        }
+    }
+
     /// Return the internal tracks slice for read-only iteration in the UI.
     pub fn tracks(&self) -> &[PathBuf] {
         &self.tracks
@@ -94,30 +126,156 @@ impl Queue {
     }
 }
 
-/// Recursively scan a directory for common audio file extensions.
-pub fn scan_music_dir(dir: impl Into<PathBuf>) -> Vec<PathBuf> {
+/// Audio file extensions picked up by [`scan_music_dir`] by default.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "wav", "ogg", "oga", "opus", "m4a", "aac", "aiff", "aif", "wv", "ape", "mpc",
+    "dsf", "wma", "mka",
+];
+
+/// Extensions that are never audio, or are video; skipped without sniffing their
+/// contents, as lofty reads the audio of video containers too.
+const NON_AUDIO_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "cue", "log", "txt", "nfo", "m3u", "m3u8",
+    "pls", "xspf", "lrc", "pdf", "db", "ini", "sfv", "md5", "accurip", "mp4", "m4v", "mov",
+    "mkv", "webm", "avi", "wmv", "flv", "3gp",
+];
+
+/// Controls which files [`scan_music_dir`] accepts.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Accepted file extensions, compared case-insensitively.
+    pub extensions: Vec<String>,
+    /// Probe the contents of extension-less or unrecognized files to detect audio.
+    /// Off by default, as it opens every other file in the music directory.
+    pub sniff_content: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            extensions: SUPPORTED_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            sniff_content: false,
+        }
+    }
+}
+
+impl ScanOptions {
+    fn accepts(&self, path: &Path) -> bool {
+        let ext = path.extension().and_then(|e| e.to_str());
+
+        if let Some(ext) = ext {
+            if self.extensions.iter().any(|x| x.eq_ignore_ascii_case(ext)) {
+                return true;
+            }
+            if NON_AUDIO_EXTENSIONS.iter().any(|x| x.eq_ignore_ascii_case(ext)) {
+                return false;
+            }
+        }
+
+        self.sniff_content && sniff_audio(path)
+    }
+}
+
+/// Guess whether a file holds audio lofty understands by looking at its header.
+fn sniff_audio(path: &Path) -> bool {
+    let Ok(probe) = Probe::open(path) else {
+        return false;
+    };
+    probe
+        .guess_file_type()
+        .map(|p| p.file_type().is_some())
+        .unwrap_or(false)
+}
+
+/// Recursively scan a directory for audio files accepted by `options`.
+pub fn scan_music_dir(dir: impl Into<PathBuf>, options: &ScanOptions) -> Vec<PathBuf> {
     let dir = dir.into();
     let mut found = Vec::new();
 
-    let exts = ["mp3", "flac", "wav", "ogg", "m4a"];
-
-    fn visit(path: &PathBuf, exts: &[&str], out: &mut Vec<PathBuf>) {
+    fn visit(path: &PathBuf, options: &ScanOptions, out: &mut Vec<PathBuf>) {
         if let Ok(metadata) = fs::metadata(path) {
             if metadata.is_dir() {
                 if let Ok(mut entries) = fs::read_dir(path) {
                     while let Some(Ok(entry)) = entries.next() {
-                        visit(&entry.path().to_path_buf(), exts, out);
+                        visit(&entry.path().to_path_buf(), options, out);
                     }
                 }
-            } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                if exts.iter().any(|x| x.eq_ignore_ascii_case(ext)) {
-                    out.push(path.to_path_buf());
-                }
+            } else if options.accepts(path) {
+                out.push(path.to_path_buf());
             }
         }
     }
 
-    visit(&dir, &exts, &mut found);
+    visit(&dir, options, &mut found);
 
     found
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn wraps_around_both_ways() {
+        let mut queue = Queue::from_vec(paths(&["a", "b", "c"]));
+        assert_eq!(queue.peek_next(), Some(&PathBuf::from("b")));
+        assert_eq!(queue.prev(), Some(&PathBuf::from("c")));
+        assert_eq!(queue.next(), Some(&PathBuf::from("a")));
+        queue.next();
+        assert_eq!(queue.next(), Some(&PathBuf::from("c")));
+        assert_eq!(queue.peek_next(), Some(&PathBuf::from("a")));
+        assert_eq!(Queue::new().next(), None);
+    }
+
+    #[test]
+    fn selects_or_appends_tracks() {
+        let mut queue = Queue::from_vec(paths(&["a", "b"]));
+        queue.select_or_push(PathBuf::from("b"));
+        assert_eq!(queue.current(), Some(&PathBuf::from("b")));
+        queue.select_or_push(PathBuf::from("c"));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.current(), Some(&PathBuf::from("c")));
+
+        let moved = HashMap::from([(PathBuf::from("a"), PathBuf::from("z"))]);
+        queue.move_paths(&moved);
+        assert_eq!(queue.tracks(), paths(&["z", "b", "c"]));
+    }
+
+    #[test]
+    fn accepts_listed_extensions_only_unless_sniffing() {
+        let options = ScanOptions::default();
+        assert!(!options.sniff_content);
+        assert!(options.accepts(Path::new("/music/Song.FLAC")));
+        assert!(options.accepts(Path::new("/music/song.oga")));
+        assert!(!options.accepts(Path::new("/music/cover.jpg")));
+        assert!(!options.accepts(Path::new("/music/no-extension")));
+
+        let sniffing = ScanOptions {
+            sniff_content: true,
+            ..ScanOptions::default()
+        };
+        // Video and other known non-audio files are never opened
+        assert!(!sniffing.accepts(Path::new("/music/concert.mp4")));
+        assert!(!sniffing.accepts(Path::new("/music/notes.txt")));
+        // Nor is a file that can't be read
+        assert!(!sniffing.accepts(Path::new("/nonexistent/track")));
+    }
+
+    #[test]
+    fn scans_directories_recursively() {
+        let dir = std::env::temp_dir().join(format!("queue-scan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("album")).unwrap();
+        for name in ["album/01.mp3", "album/02.opus", "album/cover.jpg", "notes.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let mut found = scan_music_dir(&dir, &ScanOptions::default());
+        found.sort();
+        assert_eq!(found, [dir.join("album/01.mp3"), dir.join("album/02.opus")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Star ratings as stored in file tags: POPM frames in ID3v2, `FMPS_RATING` and
+//! `RATING` in Vorbis comments, and the `rate` atom in MP4.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use lofty::config::{ParseOptions, WriteOptions};
+use lofty::file::{FileType, TaggedFile};
+use lofty::id3::v2::{Frame, FrameId, Id3v2Tag, PopularimeterFrame};
+use lofty::mpeg::MpegFile;
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::{ItemKey, Tag, TagType};
+
+use super::tags;
+
+/// Highest star rating.
+pub const MAX_STARS: u8 = 5;
+
+/// POPM e-mail ratings are stored under. Most players read any POPM frame.
+const POPM_EMAIL: &str = "music-player";
+
+/// The POPM byte for a star rating, using the scale of Windows Media Player that
+/// most other players follow.
+fn stars_to_popm(stars: u8) -> u8 {
+    [0, 1, 64, 128, 196, 255][usize::from(stars.min(MAX_STARS))]
+}
+
+fn popm_to_stars(value: u8) -> Option<u8> {
+    match value {
+        0 => None,
+        1..=31 => Some(1),
+        32..=95 => Some(2),
+        96..=159 => Some(3),
+        160..=223 => Some(4),
+        _ => Some(5),
+    }
+}
+
+/// Stars for a rating out of `max`, rounded to the nearest star.
+fn scaled_to_stars(value: f64, max: f64) -> Option<u8> {
+    if value.is_nan() || value <= 0.0 {
+        return None;
+    }
+    let stars = (value / max * f64::from(MAX_STARS)).round();
+    Some(stars.clamp(1.0, f64::from(MAX_STARS)) as u8)
+}
+
+fn unknown(key: &str) -> ItemKey {
+    ItemKey::Unknown(key.to_string())
+}
+
+/// Read the rating from a file's tags, if it has one.
+pub fn read_rating(path: &Path, tagged: &TaggedFile) -> Option<u8> {
+    if tagged.file_type() == FileType::Mpeg {
+        return read_id3v2(path).ok().flatten();
+    }
+    let tag = tagged.primary_tag()?;
+    match tag.tag_type() {
+        TagType::VorbisComments => {
+            let fmps = tag.get_string(&unknown("FMPS_RATING"));
+            let rating = tag.get_string(&unknown("RATING"));
+            // RATING is out of 100 for most taggers, but some store stars
+            let rating = rating.and_then(|v| {
+                let value: f64 = v.trim().parse().ok()?;
+                let max = if value <= f64::from(MAX_STARS) { 5.0 } else { 100.0 };
+                scaled_to_stars(value, max)
+            });
+            fmps.and_then(|v| scaled_to_stars(v.trim().parse().ok()?, 1.0))
+                .or(rating)
+        }
+        TagType::Mp4Ilst => tag
+            .get_string(&unknown("rate"))
+            .and_then(|v| scaled_to_stars(v.trim().parse().ok()?, 100.0)),
+        _ => None,
+    }
+}
+
+fn read_id3v2(path: &Path) -> Result<Option<u8>> {
+    let mut file = File::open(path)?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new())?;
+    let popm = FrameId::Valid(Cow::Borrowed("POPM"));
+    Ok(mpeg
+        .id3v2()
+        .and_then(|tag| tag.get(&popm))
+        .and_then(|frame| match frame {
+            Frame::Popularimeter(popm) => popm_to_stars(popm.rating),
+            _ => None,
+        }))
+}
+
+/// Write `stars` into the file's tags, or remove the rating for `None`. Other
+/// tag items are left untouched. Formats without a rating field are an error.
+pub fn write_rating(path: &Path, stars: Option<u8>) -> Result<()> {
+    let stars = stars.filter(|s| *s > 0).map(|s| s.min(MAX_STARS));
+    tags::write_atomically(path, |copy| write_rating_in(copy, stars))
+}
+
+fn write_rating_in(path: &Path, stars: Option<u8>) -> Result<()> {
+    let tagged = Probe::open(path)
+        .map_err(|e| anyhow!("failed to open {:?}: {e}", path))?
+        .guess_file_type()?
+        .read()
+        .map_err(|e| anyhow!("failed to read tags for {:?}: {e}", path))?;
+
+    if tagged.file_type() == FileType::Mpeg {
+        return write_id3v2(path, stars);
+    }
+
+    let tag_type = tagged.primary_tag_type();
+    let mut tag = tagged
+        .primary_tag()
+        .cloned()
+        .unwrap_or_else(|| Tag::new(tag_type));
+    match tag_type {
+        TagType::VorbisComments => {
+            tag.remove_key(&unknown("FMPS_RATING"));
+            tag.remove_key(&unknown("RATING"));
+            if let Some(stars) = stars {
+                let fraction = f64::from(stars) / f64::from(MAX_STARS);
+                tag.insert_text(unknown("FMPS_RATING"), format!("{fraction:.1}"));
+                tag.insert_text(unknown("RATING"), (u32::from(stars) * 20).to_string());
+            }
+        }
+        TagType::Mp4Ilst => {
+            tag.remove_key(&unknown("rate"));
+            if let Some(stars) = stars {
+                tag.insert_text(unknown("rate"), (u32::from(stars) * 20).to_string());
+            }
+        }
+        other => return Err(anyhow!("{other:?} tags can't hold a rating")),
+    }
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|e| anyhow!("failed to write rating to {:?}: {e}", path))
+}
+
+fn write_id3v2(path: &Path, stars: Option<u8>) -> Result<()> {
+    let mut file = File::open(path)?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new())?;
+    drop(file);
+    let mut tag = mpeg.id3v2().cloned().unwrap_or_else(Id3v2Tag::new);
+
+    // Every POPM frame is replaced so players reading any of them agree; the
+    // play counter another player kept is carried over
+    let popm = FrameId::Valid(Cow::Borrowed("POPM"));
+    let counter = match tag.remove(&popm).next() {
+        Some(Frame::Popularimeter(frame)) => frame.counter,
+        _ => 0,
+    };
+    if let Some(stars) = stars {
+        tag.insert(Frame::Popularimeter(PopularimeterFrame::new(
+            Cow::Borrowed(POPM_EMAIL),
+            stars_to_popm(stars),
+            counter,
+        )));
+    }
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|e| anyhow!("failed to write rating to {:?}: {e}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popm_round_trips_every_rating() {
+        for stars in 1..=MAX_STARS {
+            assert_eq!(popm_to_stars(stars_to_popm(stars)), Some(stars));
+        }
+        assert_eq!(stars_to_popm(0), 0);
+        assert_eq!(popm_to_stars(0), None);
+    }
+
+    #[test]
+    fn reads_popm_values_of_other_players() {
+        // Ranges taken by players that don't use the Windows Media Player values
+        let cases = [(31, 1), (32, 2), (95, 2), (96, 3), (159, 3), (160, 4), (223, 4), (224, 5)];
+        for (value, stars) in cases {
+            assert_eq!(popm_to_stars(value), Some(stars), "{value}");
+        }
+    }
+
+    #[test]
+    fn scales_ratings_to_stars() {
+        assert_eq!(scaled_to_stars(0.6, 1.0), Some(3));
+        assert_eq!(scaled_to_stars(100.0, 100.0), Some(5));
+        assert_eq!(scaled_to_stars(50.0, 100.0), Some(3));
+        // Any rating at all is at least one star, and none is more than five
+        assert_eq!(scaled_to_stars(1.0, 100.0), Some(1));
+        assert_eq!(scaled_to_stars(7.0, 5.0), Some(5));
+        assert_eq!(scaled_to_stars(0.0, 100.0), None);
+        assert_eq!(scaled_to_stars(-1.0, 100.0), None);
+        assert_eq!(scaled_to_stars(f64::NAN, 1.0), None);
+    }
+}
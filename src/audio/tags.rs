@@ -0,0 +1,511 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Editing the common tags of audio files. Changes are written to a copy of
+//! the file that then replaces it, so a failed write never leaves a damaged
+//! file behind, and tag items the editor doesn't know about are kept.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use lofty::ape::ApeFile;
+use lofty::config::{ParseOptions, WriteOptions};
+use lofty::error::LoftyError;
+use lofty::file::FileType;
+use lofty::flac::FlacFile;
+use lofty::mp4::Mp4File;
+use lofty::mpeg::MpegFile;
+use lofty::ogg::{OpusFile, SpeexFile, VorbisFile};
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::{ItemKey, Tag};
+use lofty::wavpack::WavPackFile;
+
+use super::backend::TrackMetadata;
+
+/// A tag the editor can change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Track,
+    Disc,
+    Year,
+    Genre,
+    Comment,
+    MusicBrainzRecordingId,
+    MusicBrainzReleaseId,
+    MusicBrainzReleaseGroupId,
+    MusicBrainzArtistId,
+}
+
+impl TagField {
+    pub const ALL: [TagField; 9] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::AlbumArtist,
+        TagField::Track,
+        TagField::Disc,
+        TagField::Year,
+        TagField::Genre,
+        TagField::Comment,
+    ];
+
+    /// The MusicBrainz IDs, which are set by looking tracks up rather than
+    /// edited by hand.
+    pub const MUSICBRAINZ: [TagField; 4] = [
+        TagField::MusicBrainzRecordingId,
+        TagField::MusicBrainzReleaseId,
+        TagField::MusicBrainzReleaseGroupId,
+        TagField::MusicBrainzArtistId,
+    ];
+
+    /// Whether the field holds a positive number.
+    pub fn is_number(self) -> bool {
+        matches!(self, TagField::Track | TagField::Disc | TagField::Year)
+    }
+
+    /// The field's value in `md` as text.
+    pub fn value(self, md: &TrackMetadata) -> Option<String> {
+        match self {
+            TagField::Title => md.title.clone(),
+            TagField::Artist => md.artist.clone(),
+            TagField::Album => md.album.clone(),
+            TagField::AlbumArtist => md.album_artist.clone(),
+            TagField::Track => md.track.map(|n| n.to_string()),
+            TagField::Disc => md.disc.map(|n| n.to_string()),
+            TagField::Year => md.year.map(|n| n.to_string()),
+            TagField::Genre => md.genre.clone(),
+            TagField::Comment => md.comment.clone(),
+            TagField::MusicBrainzRecordingId => md.musicbrainz_recording_id.clone(),
+            TagField::MusicBrainzReleaseId => md.musicbrainz_release_id.clone(),
+            TagField::MusicBrainzReleaseGroupId => md.musicbrainz_release_group_id.clone(),
+            TagField::MusicBrainzArtistId => md.musicbrainz_artist_id.clone(),
+        }
+    }
+
+    /// Set the field in `md`, or clear it for `None`. Numbers that don't parse
+    /// clear the field; [`normalize`] catches them before anything is written.
+    pub fn set(self, md: &mut TrackMetadata, value: Option<&str>) {
+        let text = value.map(str::to_string);
+        let number = value.and_then(|v| v.parse().ok());
+        match self {
+            TagField::Title => md.title = text,
+            TagField::Artist => md.artist = text,
+            TagField::Album => md.album = text,
+            TagField::AlbumArtist => md.album_artist = text,
+            TagField::Track => md.track = number,
+            TagField::Disc => md.disc = number,
+            TagField::Year => md.year = number,
+            TagField::Genre => md.genre = text,
+            TagField::Comment => md.comment = text,
+            TagField::MusicBrainzRecordingId => md.musicbrainz_recording_id = text,
+            TagField::MusicBrainzReleaseId => md.musicbrainz_release_id = text,
+            TagField::MusicBrainzReleaseGroupId => md.musicbrainz_release_group_id = text,
+            TagField::MusicBrainzArtistId => md.musicbrainz_artist_id = text,
+        }
+    }
+}
+
+/// What a field holds across the tracks being edited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldValue {
+    /// Every track has this value, or none has the field
+    Same(Option<String>),
+    /// The tracks disagree
+    Mixed,
+}
+
+/// What `field` holds across `tracks`.
+pub fn common_value<'a>(
+    field: TagField,
+    tracks: impl IntoIterator<Item = &'a TrackMetadata>,
+) -> FieldValue {
+    let mut values = tracks.into_iter().map(|md| field.value(md));
+    let Some(first) = values.next() else {
+        return FieldValue::Same(None);
+    };
+    if values.all(|v| v == first) {
+        FieldValue::Same(first)
+    } else {
+        FieldValue::Mixed
+    }
+}
+
+/// New values for the fields being changed; `None` removes the field. Fields
+/// not in the map are left as they are.
+pub type TagChanges = BTreeMap<TagField, Option<String>>;
+
+/// Trim the new values, turning empty ones into removals, and check that
+/// numeric fields hold positive numbers.
+pub fn normalize(changes: &TagChanges) -> Result<TagChanges> {
+    changes
+        .iter()
+        .map(|(field, value)| {
+            let value = value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string);
+            if let Some(v) = value.as_deref().filter(|_| field.is_number()) {
+                if !v.parse::<u32>().is_ok_and(|n| n > 0) {
+                    return Err(anyhow!("{field:?} must be a positive number, not {v:?}"));
+                }
+            }
+            Ok((*field, value))
+        })
+        .collect()
+}
+
+/// Apply `changes` to the metadata the library keeps for a track.
+pub fn apply_to_metadata(md: &mut TrackMetadata, changes: &TagChanges) {
+    for (field, value) in changes {
+        field.set(md, value.as_deref());
+    }
+}
+
+/// Write `changes` into the tags of the file at `path`.
+pub fn write_tags(path: &Path, changes: &TagChanges) -> Result<()> {
+    let changes = normalize(changes)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+    edit_tag(path, |tag| apply_to_tag(tag, &changes))
+        .map_err(|e| anyhow!("failed to write tags to {:?}: {e}", path))
+}
+
+/// Change every tag of the file at `path` through `edit`, creating the
+/// format's main tag if the file has none, and save them safely with
+/// [`write_atomically`].
+///
+/// The generic tag loses frames, atoms and fields it has no item for, so the
+/// tags of common formats are edited in their own format: what the generic
+/// tag can't represent is split off and merged back after the edit.
+pub fn edit_tag(path: &Path, edit: impl Fn(&mut Tag)) -> Result<()> {
+    write_atomically(path, |copy| {
+        let tagged = Probe::open(copy)?.guess_file_type()?.read()?;
+        match tagged.file_type() {
+            FileType::Mpeg => {
+                let mpeg: MpegFile = read_file(copy)?;
+                edit_split(copy, mpeg.id3v2(), true, &edit)?;
+                edit_split(copy, mpeg.id3v1(), false, &edit)?;
+                edit_split(copy, mpeg.ape(), false, &edit)
+            }
+            FileType::Flac => {
+                let flac: FlacFile = read_file(copy)?;
+                edit_split(copy, flac.vorbis_comments(), true, &edit)
+            }
+            FileType::Mp4 => {
+                let mp4: Mp4File = read_file(copy)?;
+                edit_split(copy, mp4.ilst(), true, &edit)
+            }
+            FileType::Ape => {
+                let ape: ApeFile = read_file(copy)?;
+                edit_split(copy, ape.ape(), true, &edit)?;
+                edit_split(copy, ape.id3v1(), false, &edit)
+            }
+            FileType::WavPack => {
+                let wavpack: WavPackFile = read_file(copy)?;
+                edit_split(copy, wavpack.ape(), true, &edit)?;
+                edit_split(copy, wavpack.id3v1(), false, &edit)
+            }
+            FileType::Vorbis => {
+                let vorbis: VorbisFile = read_file(copy)?;
+                edit_split(copy, Some(vorbis.vorbis_comments()), true, &edit)
+            }
+            FileType::Opus => {
+                let opus: OpusFile = read_file(copy)?;
+                edit_split(copy, Some(opus.vorbis_comments()), true, &edit)
+            }
+            FileType::Speex => {
+                let speex: SpeexFile = read_file(copy)?;
+                edit_split(copy, Some(speex.vorbis_comments()), true, &edit)
+            }
+            _ => {
+                let mut tags = tagged.tags().to_vec();
+                if tags.is_empty() {
+                    tags.push(Tag::new(tagged.primary_tag_type()));
+                }
+                for mut tag in tags {
+                    edit(&mut tag);
+                    tag.save_to_path(copy, WriteOptions::default())?;
+                }
+                Ok(())
+            }
+        }
+    })
+}
+
+fn read_file<F: AudioFile>(path: &Path) -> Result<F> {
+    let mut file = File::open(path)?;
+    Ok(F::read_from(&mut file, ParseOptions::new())?)
+}
+
+/// Edit one tag of the file at `path` through the generic tag, keeping what
+/// that can't hold. A missing tag is created when `create` is set, and
+/// otherwise left missing.
+fn edit_split<T>(path: &Path, tag: Option<&T>, create: bool, edit: &impl Fn(&mut Tag)) -> Result<()>
+where
+    T: SplitTag + TagExt<Err = LoftyError> + Clone + Default,
+{
+    let tag = match tag {
+        Some(tag) => tag.clone(),
+        None if create => T::default(),
+        None => return Ok(()),
+    };
+    let (remainder, mut generic) = tag.split_tag();
+    edit(&mut generic);
+    remainder
+        .merge_tag(generic)
+        .save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+fn apply_to_tag(tag: &mut Tag, changes: &TagChanges) {
+    for (field, value) in changes {
+        let number = value.as_deref().and_then(|v| v.parse::<u32>().ok());
+        match (field, value.clone()) {
+            (TagField::Title, Some(v)) => tag.set_title(v),
+            (TagField::Title, None) => tag.remove_title(),
+            (TagField::Artist, Some(v)) => tag.set_artist(v),
+            (TagField::Artist, None) => tag.remove_artist(),
+            (TagField::Album, Some(v)) => tag.set_album(v),
+            (TagField::Album, None) => tag.remove_album(),
+            (TagField::AlbumArtist, Some(v)) => {
+                tag.insert_text(ItemKey::AlbumArtist, v);
+            }
+            (TagField::AlbumArtist, None) => tag.remove_key(&ItemKey::AlbumArtist),
+            (TagField::Genre, Some(v)) => tag.set_genre(v),
+            (TagField::Genre, None) => tag.remove_genre(),
+            (TagField::Comment, Some(v)) => tag.set_comment(v),
+            (TagField::Comment, None) => tag.remove_comment(),
+            (TagField::MusicBrainzRecordingId, v) => {
+                set_text(tag, ItemKey::MusicBrainzRecordingId, v)
+            }
+            (TagField::MusicBrainzReleaseId, v) => set_text(tag, ItemKey::MusicBrainzReleaseId, v),
+            (TagField::MusicBrainzReleaseGroupId, v) => {
+                set_text(tag, ItemKey::MusicBrainzReleaseGroupId, v)
+            }
+            (TagField::MusicBrainzArtistId, v) => set_text(tag, ItemKey::MusicBrainzArtistId, v),
+            (TagField::Track, _) => match number {
+                Some(n) => tag.set_track(n),
+                None => tag.remove_track(),
+            },
+            (TagField::Disc, _) => match number {
+                Some(n) => tag.set_disk(n),
+                None => tag.remove_disk(),
+            },
+            (TagField::Year, _) => match number {
+                Some(n) => tag.set_year(n),
+                None => tag.remove_year(),
+            },
+        }
+    }
+}
+
+fn set_text(tag: &mut Tag, key: ItemKey, value: Option<String>) {
+    match value {
+        Some(v) => {
+            tag.insert_text(key, v);
+        }
+        None => tag.remove_key(&key),
+    }
+}
+
+/// Change the file at `path` by letting `edit` work on a copy of it in the
+/// same directory, which then replaces the original in one rename. When `edit`
+/// fails the copy is removed and the original is left untouched. The copy is
+/// flushed to disk first, so a crash can't leave a truncated file in its place.
+pub fn write_atomically(path: &Path, edit: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let copy = temp_path(path);
+    let result = fs::copy(path, &copy)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| edit(&copy))
+        .and_then(|()| File::open(&copy)?.sync_all().map_err(anyhow::Error::from))
+        .and_then(|()| fs::rename(&copy, path).map_err(anyhow::Error::from));
+    if result.is_err() {
+        let _ = fs::remove_file(&copy);
+    }
+    result
+}
+
+/// A hidden file next to `path`, so renaming it over `path` stays on one file system.
+fn temp_path(path: &Path) -> PathBuf {
+    // Keeping the extension lets format detection work on the copy as well
+    let mut name = OsString::from(".tags-tmp-");
+    name.push(path.file_name().unwrap_or_default());
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tags-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A 4-byte ID3v2.4 frame size or tag size, 7 bits per byte.
+    fn syncsafe(size: usize) -> [u8; 4] {
+        let size = size as u32;
+        [21, 14, 7, 0].map(|shift| (size >> shift) as u8 & 0x7f)
+    }
+
+    fn id3v2_frame(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend(syncsafe(data.len()));
+        frame.extend([0, 0]);
+        frame.extend(data);
+        frame
+    }
+
+    /// An MP3 whose ID3v2 tag has a title and a private frame no tag item
+    /// stands for, followed by a few silent frames.
+    fn mp3_with_private_frame(path: &Path) {
+        let mut frames = id3v2_frame(b"TIT2", b"\x03Old title");
+        frames.extend(id3v2_frame(b"PRIV", b"music-player-test\0\x01\x02\x03"));
+        let mut file = b"ID3\x04\x00\x00".to_vec();
+        file.extend(syncsafe(frames.len()));
+        file.extend(frames);
+        for _ in 0..10 {
+            // MPEG-1 Layer III, 128 kbps, 44.1 kHz: 417 bytes a frame
+            file.extend([0xff, 0xfb, 0x90, 0x00]);
+            file.extend([0; 413]);
+        }
+        fs::write(path, file).unwrap();
+    }
+
+    /// A FLAC file with only its stream info and a Vorbis comment block
+    /// holding `comments`.
+    fn flac_with_comments(path: &Path, comments: &[&str]) {
+        let mut file = b"fLaC\x00\x00\x00\x22".to_vec();
+        file.extend([0x10, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        file.extend([0x0a, 0xc4, 0x42, 0xf0, 0x00, 0x00, 0x00, 0x00]);
+        file.extend([0; 16]);
+
+        let vendor = b"test";
+        let mut block = (vendor.len() as u32).to_le_bytes().to_vec();
+        block.extend(vendor);
+        block.extend((comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            block.extend((comment.len() as u32).to_le_bytes());
+            block.extend(comment.as_bytes());
+        }
+        // Last metadata block, of type VORBIS_COMMENT
+        file.push(0x84);
+        file.extend(&(block.len() as u32).to_be_bytes()[1..]);
+        file.extend(block);
+        fs::write(path, file).unwrap();
+    }
+
+    fn changes(pairs: &[(TagField, Option<&str>)]) -> TagChanges {
+        pairs.iter().map(|(f, v)| (*f, v.map(str::to_string))).collect()
+    }
+
+    #[test]
+    fn normalizes_changes() {
+        let normalized = normalize(&changes(&[
+            (TagField::Title, Some("  Song  ")),
+            (TagField::Album, Some("   ")),
+            (TagField::Track, Some(" 7 ")),
+            (TagField::Year, None),
+        ]))
+        .unwrap();
+        assert_eq!(
+            normalized,
+            changes(&[
+                (TagField::Title, Some("Song")),
+                (TagField::Album, None),
+                (TagField::Track, Some("7")),
+                (TagField::Year, None),
+            ])
+        );
+
+        for bad in ["0", "-3", "seven", "1.5"] {
+            assert!(normalize(&changes(&[(TagField::Disc, Some(bad))])).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn finds_common_values() {
+        let track = |artist: Option<&str>, year: Option<u32>| TrackMetadata {
+            artist: artist.map(str::to_string),
+            year,
+            ..Default::default()
+        };
+        let tracks = [track(Some("A"), Some(2001)), track(Some("A"), None)];
+        assert_eq!(
+            common_value(TagField::Artist, &tracks),
+            FieldValue::Same(Some("A".into()))
+        );
+        assert_eq!(common_value(TagField::Year, &tracks), FieldValue::Mixed);
+        assert_eq!(common_value(TagField::Genre, &tracks), FieldValue::Same(None));
+        assert_eq!(common_value(TagField::Title, std::iter::empty()), FieldValue::Same(None));
+    }
+
+    #[test]
+    fn replaces_files_only_when_the_edit_succeeds() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("track.mp3");
+        fs::write(&path, b"original").unwrap();
+
+        let result = write_atomically(&path, |copy| {
+            fs::OpenOptions::new().append(true).open(copy)?.write_all(b" and more")?;
+            Err(anyhow!("failed halfway"))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert!(!temp_path(&path).exists());
+
+        write_atomically(&path, |copy| Ok(fs::write(copy, b"edited")?)).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"edited");
+        assert!(!temp_path(&path).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_id3v2_frames_it_does_not_edit() {
+        let dir = temp_dir("id3v2");
+        let path = dir.join("track.mp3");
+        mp3_with_private_frame(&path);
+
+        write_tags(&path, &changes(&[(TagField::Title, Some("New title"))])).unwrap();
+
+        let mpeg: MpegFile = read_file(&path).unwrap();
+        let id3 = mpeg.id3v2().unwrap();
+        assert_eq!(id3.title().as_deref(), Some("New title"));
+        assert!(id3.into_iter().any(|frame| frame.id().as_str() == "PRIV"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_vorbis_comments_it_does_not_edit() {
+        let dir = temp_dir("vorbis");
+        let path = dir.join("track.flac");
+        flac_with_comments(&path, &["TITLE=Old title", "ARTIST=Someone", "CUSTOMFIELD=kept"]);
+
+        write_tags(
+            &path,
+            &changes(&[(TagField::Title, Some("New title")), (TagField::Artist, None)]),
+        )
+        .unwrap();
+
+        let tagged = Probe::open(&path).unwrap().read().unwrap();
+        let tag = tagged.primary_tag().unwrap();
+        assert_eq!(tag.title().as_deref(), Some("New title"));
+        assert_eq!(tag.artist(), None);
+        assert_eq!(
+            tag.get_string(&ItemKey::Unknown("CUSTOMFIELD".into())),
+            Some("kept")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
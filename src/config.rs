@@ -1,9 +1,241 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use music_player::acoustid::AcoustId;
+use music_player::audio::listen::PlayThreshold;
+use music_player::audio::queue::{ScanOptions, SUPPORTED_EXTENSIONS};
+use music_player::equalizer::EqualizerPreset;
+use music_player::musicbrainz::MusicBrainz;
+use music_player::organize;
+use music_player::parametric::ParametricProfile;
+use music_player::practice::PracticeSettings;
+use music_player::scrobble::audioscrobbler::Credentials;
+use music_player::scrobble::{AudioScrobbler, ListenBrainz, Scrobbler};
+use std::collections::BTreeMap;
+use std::time::Duration;
 
-#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
 pub struct Config {
     demo: String,
+    /// File extensions the library scanner accepts.
+    pub library_extensions: Vec<String>,
+    /// Probe extension-less or unrecognized files for audio content.
+    pub sniff_content: bool,
+    /// Percentage of a track that has to be heard for it to count as played.
+    pub play_threshold_percent: u32,
+    /// Seconds after which a track counts as played, however long it is.
+    pub play_threshold_secs: u32,
+    /// Also store ratings in the audio files' tags.
+    pub write_ratings_to_files: bool,
+    /// Largest width or height of cover art embedded in files; larger images
+    /// are scaled down. 0 embeds images at their original size.
+    pub artwork_max_size: u32,
+    /// Where the library organizer moves tracks, relative to the music folder.
+    pub organize_template: String,
+    /// ListenBrainz user token; listens are only submitted when it is set.
+    pub listenbrainz_token: String,
+    /// Root of the ListenBrainz API, for self-hosted servers.
+    pub listenbrainz_url: String,
+    /// Root of the Last.fm API, or of a compatible server such as Libre.fm or Maloja.
+    pub lastfm_url: String,
+    /// API account key and secret calls to the Last.fm API are signed with.
+    pub lastfm_api_key: String,
+    pub lastfm_api_secret: String,
+    /// Last.fm session key. When empty, the username and password are used to
+    /// log in once, and the password is cleared when the key has been saved.
+    pub lastfm_session_key: String,
+    pub lastfm_username: String,
+    pub lastfm_password: String,
+    /// Root of the AcoustID API, for mirrors.
+    pub acoustid_url: String,
+    /// AcoustID application key; tracks are only identified when it is set.
+    pub acoustid_key: String,
+    /// Root of the MusicBrainz web service, for mirrors.
+    pub musicbrainz_url: String,
+    /// Play through the equalizer.
+    pub equalizer_enabled: bool,
+    /// The equalizer settings picked or made by hand.
+    pub equalizer: EqualizerPreset,
+    /// Presets the user saved.
+    pub equalizer_presets: Vec<EqualizerPreset>,
+    /// Names of presets used for tracks of a genre, keyed by lowercased genre.
+    pub equalizer_genre_presets: BTreeMap<String, String>,
+    /// Names of presets used on an output device, keyed by device name.
+    pub equalizer_device_presets: BTreeMap<String, String>,
+    /// Play through the parametric equalizer.
+    pub parametric_enabled: bool,
+    /// Name of the parametric profile used where no device has its own.
+    pub parametric_profile: String,
+    /// Parametric profiles, made by hand or imported.
+    pub parametric_profiles: Vec<ParametricProfile>,
+    /// Names of parametric profiles used on an output device, keyed by device name.
+    pub parametric_device_profiles: BTreeMap<String, String>,
+    /// Speeds remembered for albums, such as a podcast's episodes, in
+    /// percent. Keyed by lowercased artist and album.
+    pub album_rates: BTreeMap<String, u32>,
+    /// How A–B loops are practiced: the pause before each repetition and how
+    /// the speed rises.
+    pub practice: PracticeSettings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            demo: String::new(),
+            library_extensions: SUPPORTED_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            sniff_content: false,
+            play_threshold_percent: 50,
+            play_threshold_secs: 240,
+            write_ratings_to_files: false,
+            artwork_max_size: 1200,
+            organize_template: organize::DEFAULT_TEMPLATE.to_string(),
+            listenbrainz_token: String::new(),
+            listenbrainz_url: ListenBrainz::DEFAULT_API_ROOT.to_string(),
+            lastfm_url: AudioScrobbler::LASTFM_API_ROOT.to_string(),
+            lastfm_api_key: String::new(),
+            lastfm_api_secret: String::new(),
+            lastfm_session_key: String::new(),
+            lastfm_username: String::new(),
+            lastfm_password: String::new(),
+            acoustid_url: AcoustId::DEFAULT_API_ROOT.to_string(),
+            acoustid_key: String::new(),
+            musicbrainz_url: MusicBrainz::DEFAULT_API_ROOT.to_string(),
+            equalizer_enabled: false,
+            equalizer: EqualizerPreset::default(),
+            equalizer_presets: Vec::new(),
+            equalizer_genre_presets: BTreeMap::new(),
+            equalizer_device_presets: BTreeMap::new(),
+            parametric_enabled: false,
+            parametric_profile: String::new(),
+            parametric_profiles: Vec::new(),
+            parametric_device_profiles: BTreeMap::new(),
+            album_rates: BTreeMap::new(),
+            practice: PracticeSettings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Library scanner options derived from this configuration.
+    pub fn scan_options(&self) -> ScanOptions {
+        ScanOptions {
+            extensions: self.library_extensions.clone(),
+            sniff_content: self.sniff_content,
+        }
+    }
+
+    /// When a listen counts towards a track's play count.
+    pub fn play_threshold(&self) -> PlayThreshold {
+        PlayThreshold {
+            fraction: f64::from(self.play_threshold_percent.min(100)) / 100.0,
+            max: Duration::from_secs(u64::from(self.play_threshold_secs)),
+            ..Default::default()
+        }
+    }
+
+    /// The ListenBrainz client to submit listens with, if a token is set.
+    pub fn listenbrainz(&self) -> Option<ListenBrainz> {
+        if self.listenbrainz_token.trim().is_empty() {
+            return None;
+        }
+        Some(ListenBrainz::new(&self.listenbrainz_url, &self.listenbrainz_token))
+    }
+
+    /// The Last.fm-compatible client to submit listens with, if an API account
+    /// and a session key are set.
+    pub fn audioscrobbler(&self) -> Option<AudioScrobbler> {
+        if self.lastfm_session_key.trim().is_empty() {
+            return None;
+        }
+        self.lastfm_client(Credentials::SessionKey(self.lastfm_session_key.clone()))
+    }
+
+    /// A Last.fm-compatible client to get a session key with, if there is a
+    /// username and password to log in with and no key yet.
+    pub fn lastfm_login(&self) -> Option<AudioScrobbler> {
+        if !self.lastfm_session_key.trim().is_empty()
+            || self.lastfm_username.trim().is_empty()
+            || self.lastfm_password.is_empty()
+        {
+            return None;
+        }
+        self.lastfm_client(Credentials::Password {
+            username: self.lastfm_username.trim().to_string(),
+            password: self.lastfm_password.clone(),
+        })
+    }
+
+    fn lastfm_client(&self, credentials: Credentials) -> Option<AudioScrobbler> {
+        if self.lastfm_api_key.trim().is_empty() || self.lastfm_api_secret.trim().is_empty() {
+            return None;
+        }
+        Some(AudioScrobbler::new(
+            &self.lastfm_url,
+            &self.lastfm_api_key,
+            &self.lastfm_api_secret,
+            credentials,
+        ))
+    }
+
+    /// The AcoustID client to identify tracks with, if a key is set.
+    pub fn acoustid(&self) -> Option<AcoustId> {
+        if self.acoustid_key.trim().is_empty() {
+            return None;
+        }
+        Some(AcoustId::new(&self.acoustid_url, &self.acoustid_key))
+    }
+
+    /// The MusicBrainz client to look albums up with.
+    pub fn musicbrainz(&self) -> MusicBrainz {
+        MusicBrainz::new(&self.musicbrainz_url)
+    }
+
+    /// Every scrobbling service that is set up.
+    pub fn scrobblers(&self) -> Vec<Box<dyn Scrobbler>> {
+        let mut scrobblers: Vec<Box<dyn Scrobbler>> = Vec::new();
+        if let Some(client) = self.listenbrainz() {
+            scrobblers.push(Box::new(client));
+        }
+        if let Some(client) = self.audioscrobbler() {
+            scrobblers.push(Box::new(client));
+        }
+        scrobblers
+    }
+
+    /// Whether `other` sets up the scrobbling services differently.
+    pub fn scrobblers_differ(&self, other: &Config) -> bool {
+        let settings = |c: &Config| {
+            [
+                c.listenbrainz_token.clone(),
+                c.listenbrainz_url.clone(),
+                c.lastfm_url.clone(),
+                c.lastfm_api_key.clone(),
+                c.lastfm_api_secret.clone(),
+                c.lastfm_session_key.clone(),
+                c.lastfm_username.clone(),
+                c.lastfm_password.clone(),
+            ]
+        };
+        settings(self) != settings(other)
+    }
+
+    /// Whether `other` has other equalizer presets or parametric profiles, or
+    /// binds them differently.
+    pub fn equalizer_bindings_differ(&self, other: &Config) -> bool {
+        self.equalizer_presets != other.equalizer_presets
+            || self.equalizer_genre_presets != other.equalizer_genre_presets
+            || self.equalizer_device_presets != other.equalizer_device_presets
+            || self.parametric_profiles != other.parametric_profiles
+            || self.parametric_device_profiles != other.parametric_device_profiles
+    }
+
+    /// The parametric profile for the output device `device`, or else the one
+    /// picked for every device.
+    pub fn parametric_profile(&self, device: Option<&str>) -> Option<&ParametricProfile> {
+        let name = device
+            .and_then(|d| self.parametric_device_profiles.get(d))
+            .unwrap_or(&self.parametric_profile);
+        self.parametric_profiles.iter().find(|p| p.name == *name)
+    }
 }
@@ -0,0 +1,614 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Finding tracks that are in the library more than once: identical files,
+//! the same song in another format or rip, or the same recording by sound.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::acoustid::decode_fingerprint;
+use crate::audio::backend::TrackMetadata;
+
+/// How far apart the lengths of the same song may be when matching by tags.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(3);
+
+/// Share of fingerprint bits that have to agree for two tracks to sound alike.
+const FINGERPRINT_MATCH: f64 = 0.85;
+
+/// Fewest fingerprint items two tracks have to overlap by to be compared.
+const MIN_FINGERPRINT_OVERLAP: usize = 20;
+
+/// Largest shift, in fingerprint items, tried when lining up two fingerprints;
+/// about ten seconds of silence or a different intro.
+const MAX_FINGERPRINT_OFFSET: usize = 80;
+
+/// Largest length difference of tracks compared by fingerprint. Fingerprints
+/// only cover the first two minutes, so a longer difference makes another
+/// version, such as an extended mix.
+const FINGERPRINT_LENGTH_DIFFERENCE: Duration = Duration::from_secs(10);
+
+/// What showed that tracks are the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MatchKind {
+    /// The files are byte for byte identical
+    FileHash,
+    /// They sound the same
+    Fingerprint,
+    /// Artist and title agree and the lengths are close
+    Tags,
+}
+
+/// Which checks to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicateOptions {
+    pub by_hash: bool,
+    pub by_tags: bool,
+    pub by_fingerprint: bool,
+    /// Length difference allowed when matching by tags
+    pub tolerance: Duration,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            by_hash: true,
+            by_tags: true,
+            by_fingerprint: false,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+/// A track in a group of duplicates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateTrack {
+    pub path: PathBuf,
+    /// File size in bytes
+    pub size: u64,
+    /// Average bitrate in kbps
+    pub bitrate: Option<u32>,
+    pub duration: Option<Duration>,
+}
+
+impl DuplicateTrack {
+    /// The file's format, from its extension.
+    pub fn format(&self) -> String {
+        self.path
+            .extension()
+            .map(|e| e.to_string_lossy().to_uppercase())
+            .unwrap_or_default()
+    }
+
+    fn is_lossless(&self) -> bool {
+        LOSSLESS_EXTENSIONS.contains(&self.format().to_lowercase().as_str())
+    }
+}
+
+const LOSSLESS_EXTENSIONS: [&str; 7] = ["flac", "wav", "aiff", "aif", "ape", "wv", "alac"];
+
+/// Tracks found to be the same, best copy first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub tracks: Vec<DuplicateTrack>,
+    /// The checks that linked the tracks
+    pub matched_by: Vec<MatchKind>,
+}
+
+/// Find groups of duplicates among `tracks`. Tracks without a fingerprint are
+/// left out of comparing by sound. Groups list the copy most worth keeping
+/// first: lossless before lossy, then by bitrate and size. Also returns why
+/// files that couldn't be read were left out of comparing by content.
+pub fn find_duplicates(
+    tracks: &[(PathBuf, &TrackMetadata)],
+    options: &DuplicateOptions,
+) -> (Vec<DuplicateGroup>, Vec<String>) {
+    let sizes: Vec<u64> = tracks
+        .iter()
+        .map(|(path, _)| fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+        .collect();
+    let mut links = Links::new(tracks.len());
+    let mut failed = Vec::new();
+
+    if options.by_hash {
+        let (groups, unreadable) = same_content(tracks, &sizes);
+        for group in groups {
+            links.join_all(&group, MatchKind::FileHash);
+        }
+        failed = unreadable;
+    }
+    if options.by_tags {
+        for group in same_tags(tracks, options.tolerance) {
+            links.join_all(&group, MatchKind::Tags);
+        }
+    }
+    if options.by_fingerprint {
+        for (a, b) in same_sound(tracks) {
+            links.join_all(&[a, b], MatchKind::Fingerprint);
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = links
+        .groups()
+        .into_iter()
+        .map(|(members, mut matched_by)| {
+            matched_by.sort();
+            matched_by.dedup();
+            let mut tracks: Vec<DuplicateTrack> = members
+                .into_iter()
+                .map(|i| DuplicateTrack {
+                    path: tracks[i].0.clone(),
+                    size: sizes[i],
+                    bitrate: tracks[i].1.bitrate,
+                    duration: tracks[i].1.duration,
+                })
+                .collect();
+            tracks.sort_by(|a, b| {
+                (b.is_lossless(), b.bitrate, b.size, &a.path).cmp(&(
+                    a.is_lossless(),
+                    a.bitrate,
+                    a.size,
+                    &b.path,
+                ))
+            });
+            DuplicateGroup { tracks, matched_by }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.tracks[0].path.cmp(&b.tracks[0].path));
+    (groups, failed)
+}
+
+/// Add the play statistics of a copy that is being removed to the copy kept,
+/// so listens of either count for the track.
+pub fn merge_plays(keep: &mut TrackMetadata, other: &TrackMetadata) {
+    keep.play_count += other.play_count;
+    keep.skip_count += other.skip_count;
+    keep.listening_time += other.listening_time;
+    keep.first_played = earliest(keep.first_played, other.first_played);
+    keep.last_played = keep.last_played.max(other.last_played);
+    keep.date_added = earliest(keep.date_added, other.date_added);
+    keep.rating = keep.rating.or(other.rating);
+    keep.favorite |= other.favorite;
+}
+
+/// The earlier of two times, either of which may be unknown.
+fn earliest(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        _ => a.or(b),
+    }
+}
+
+/// Union-find over track indices, remembering what joined each set.
+struct Links {
+    parent: Vec<usize>,
+    kinds: Vec<(usize, MatchKind)>,
+}
+
+impl Links {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            kinds: Vec::new(),
+        }
+    }
+
+    fn root(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn join_all(&mut self, members: &[usize], kind: MatchKind) {
+        let Some((&first, rest)) = members.split_first() else {
+            return;
+        };
+        for &other in rest {
+            let (a, b) = (self.root(first), self.root(other));
+            self.parent[b] = a;
+        }
+        self.kinds.push((first, kind));
+    }
+
+    /// Sets with more than one member, with the kinds of links inside them.
+    fn groups(mut self) -> Vec<(Vec<usize>, Vec<MatchKind>)> {
+        let mut sets: BTreeMap<usize, (Vec<usize>, Vec<MatchKind>)> = BTreeMap::new();
+        for i in 0..self.parent.len() {
+            let root = self.root(i);
+            sets.entry(root).or_default().0.push(i);
+        }
+        for (member, kind) in std::mem::take(&mut self.kinds) {
+            let root = self.root(member);
+            sets.entry(root).or_default().1.push(kind);
+        }
+        sets.into_values().filter(|(m, _)| m.len() > 1).collect()
+    }
+}
+
+/// Indices of files with identical content, and why the files that couldn't
+/// be read were skipped. Only files of equal size are read.
+fn same_content(
+    tracks: &[(PathBuf, &TrackMetadata)],
+    sizes: &[u64],
+) -> (Vec<Vec<usize>>, Vec<String>) {
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, size) in sizes.iter().enumerate().filter(|(_, s)| **s > 0) {
+        by_size.entry(*size).or_default().push(i);
+    }
+    let mut groups = Vec::new();
+    let mut failed = Vec::new();
+    for candidates in by_size.into_values().filter(|c| c.len() > 1) {
+        let mut by_hash: HashMap<[u8; 16], Vec<usize>> = HashMap::new();
+        for i in candidates {
+            match hash_file(&tracks[i].0) {
+                Ok(hash) => by_hash.entry(hash).or_default().push(i),
+                Err(err) => failed.push(format!("failed to read {:?}: {err}", tracks[i].0)),
+            }
+        }
+        groups.extend(by_hash.into_values().filter(|g| g.len() > 1));
+    }
+    (groups, failed)
+}
+
+fn hash_file(path: &Path) -> std::io::Result<[u8; 16]> {
+    let mut file = File::open(path)?;
+    let mut context = md5::Context::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        context.consume(&buffer[..read]);
+    }
+    Ok(context.compute().0)
+}
+
+/// Lowercase words of a tag, ignoring punctuation and spacing.
+pub(crate) fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Indices of tracks with the same artist and title whose lengths are within
+/// `tolerance` of the next shorter one. Tracks without a length match any.
+fn same_tags(tracks: &[(PathBuf, &TrackMetadata)], tolerance: Duration) -> Vec<Vec<usize>> {
+    let mut by_key: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for (i, (_, md)) in tracks.iter().enumerate() {
+        let (Some(artist), Some(title)) = (&md.artist, &md.title) else {
+            continue;
+        };
+        let key = (normalize(artist), normalize(title));
+        if !key.0.is_empty() && !key.1.is_empty() {
+            by_key.entry(key).or_default().push(i);
+        }
+    }
+
+    let mut groups = Vec::new();
+    for mut members in by_key.into_values().filter(|m| m.len() > 1) {
+        let duration = |i: usize| tracks[i].1.duration;
+        members.sort_by_key(|i| duration(*i));
+        // Tracks of unknown length can't be matched by it; they make a group of their own
+        let (unknown, known): (Vec<usize>, Vec<usize>) =
+            members.into_iter().partition(|i| duration(*i).is_none());
+        let mut clusters: Vec<Vec<usize>> = vec![unknown];
+        let mut previous: Option<Duration> = None;
+        for i in known {
+            let length = duration(i).unwrap_or_default();
+            match (previous, clusters.last_mut()) {
+                (Some(prev), Some(cluster)) if length - prev <= tolerance => cluster.push(i),
+                _ => clusters.push(vec![i]),
+            }
+            previous = Some(length);
+        }
+        groups.extend(clusters.into_iter().filter(|cluster| cluster.len() > 1));
+    }
+    groups
+}
+
+/// The raw fingerprint stored for a track, if it has a valid one.
+fn fingerprint(md: &TrackMetadata) -> Option<Vec<u32>> {
+    decode_fingerprint(md.fingerprint.as_deref()?).ok()
+}
+
+/// Pairs of tracks whose fingerprints agree. Only tracks of similar length are
+/// compared, which keeps this from comparing every pair in a large library.
+fn same_sound(tracks: &[(PathBuf, &TrackMetadata)]) -> Vec<(usize, usize)> {
+    let mut candidates: Vec<(Duration, usize, Vec<u32>)> = tracks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, md))| Some((md.duration.unwrap_or_default(), i, fingerprint(md)?)))
+        .collect();
+    candidates.sort_by_key(|(duration, i, _)| (*duration, *i));
+
+    let mut pairs = Vec::new();
+    for (n, (duration, a, fingerprint)) in candidates.iter().enumerate() {
+        for (other_duration, b, other) in &candidates[n + 1..] {
+            if *other_duration - *duration > FINGERPRINT_LENGTH_DIFFERENCE {
+                break;
+            }
+            if fingerprint_similarity(fingerprint, other) >= FINGERPRINT_MATCH {
+                pairs.push((*a, *b));
+            }
+        }
+    }
+    pairs
+}
+
+/// Match tracks that left the library with tracks that appeared in it and
+/// sound the same, as when an album was re-encoded or replaced by a better rip.
+/// Returns the new path of each old track that has one; every new track
+/// replaces at most one old track, the one it sounds most like.
+pub fn find_reencodes(
+    gone: &[(PathBuf, &TrackMetadata)],
+    added: &[(PathBuf, &TrackMetadata)],
+) -> HashMap<PathBuf, PathBuf> {
+    let fingerprints = |tracks: &[(PathBuf, &TrackMetadata)]| -> Vec<(usize, Vec<u32>)> {
+        tracks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, md))| Some((i, fingerprint(md)?)))
+            .collect()
+    };
+    let (old, new) = (fingerprints(gone), fingerprints(added));
+
+    let mut candidates = Vec::new();
+    for (i, a) in &old {
+        for (j, b) in &new {
+            let lengths = (gone[*i].1.duration, added[*j].1.duration);
+            if let (Some(x), Some(y)) = lengths {
+                if x.abs_diff(y) > FINGERPRINT_LENGTH_DIFFERENCE {
+                    continue;
+                }
+            }
+            let similarity = fingerprint_similarity(a, b);
+            if similarity >= FINGERPRINT_MATCH {
+                candidates.push((similarity, *i, *j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut moved = HashMap::new();
+    let mut taken = vec![false; added.len()];
+    for (_, i, j) in candidates {
+        if !taken[j] && !moved.contains_key(&gone[i].0) {
+            taken[j] = true;
+            moved.insert(gone[i].0.clone(), added[j].0.clone());
+        }
+    }
+    moved
+}
+
+/// Share of bits that agree between two raw Chromaprint fingerprints, at the
+/// alignment where they agree most. 0.0 when they barely overlap.
+pub fn fingerprint_similarity(a: &[u32], b: &[u32]) -> f64 {
+    let mut best = 0.0f64;
+    for offset in -(MAX_FINGERPRINT_OFFSET as isize)..=(MAX_FINGERPRINT_OFFSET as isize) {
+        let (a, b) = if offset >= 0 {
+            (a.get(offset as usize..).unwrap_or_default(), b)
+        } else {
+            (a, b.get(offset.unsigned_abs()..).unwrap_or_default())
+        };
+        let overlap = a.len().min(b.len());
+        if overlap < MIN_FINGERPRINT_OVERLAP {
+            continue;
+        }
+        let differing: u32 = a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum();
+        let similarity = 1.0 - f64::from(differing) / (overlap as f64 * 32.0);
+        best = best.max(similarity);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acoustid::encode_fingerprint;
+
+    fn track(artist: &str, title: &str, secs: u64, bitrate: u32) -> TrackMetadata {
+        TrackMetadata {
+            artist: Some(artist.into()),
+            title: Some(title.into()),
+            duration: Some(Duration::from_secs(secs)),
+            bitrate: Some(bitrate),
+            ..Default::default()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("duplicates-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn paths(group: &DuplicateGroup) -> Vec<&str> {
+        group
+            .tracks
+            .iter()
+            .map(|t| t.path.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn groups_identical_files() {
+        let dir = temp_dir("hash");
+        fs::write(dir.join("a.mp3"), "same bytes").unwrap();
+        fs::write(dir.join("copy of a.mp3"), "same bytes").unwrap();
+        fs::write(dir.join("b.mp3"), "other byte").unwrap();
+
+        let md = TrackMetadata::default();
+        let tracks: Vec<(PathBuf, &TrackMetadata)> = ["a.mp3", "copy of a.mp3", "b.mp3"]
+            .iter()
+            .map(|name| (dir.join(name), &md))
+            .collect();
+        let (groups, failed) = find_duplicates(&tracks, &DuplicateOptions::default());
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(paths(&groups[0]), ["a.mp3", "copy of a.mp3"]);
+        assert_eq!(groups[0].matched_by, [MatchKind::FileHash]);
+        assert!(failed.is_empty());
+    }
+
+    #[test]
+    fn reports_files_it_could_not_read() {
+        let dir = temp_dir("unreadable");
+        // Folders have a size but can't be read as files
+        for name in ["a.mp3", "b.mp3"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+
+        let md = TrackMetadata::default();
+        let tracks = vec![(dir.join("a.mp3"), &md), (dir.join("b.mp3"), &md)];
+        let (groups, failed) = find_duplicates(&tracks, &DuplicateOptions::default());
+
+        assert!(groups.is_empty());
+        assert_eq!(failed.len(), 2);
+        assert!(failed[0].starts_with("failed to read"));
+    }
+
+    #[test]
+    fn groups_the_same_song_in_other_formats() {
+        let flac = track("The Band", "Song (Live)", 200, 900);
+        let mp3 = track("the band", "song live", 201, 320);
+        let edit = track("The Band", "Song (Live)", 240, 320);
+        let other = track("The Band", "Another", 200, 320);
+        let tracks = vec![
+            (PathBuf::from("/m/song.mp3"), &mp3),
+            (PathBuf::from("/m/song.flac"), &flac),
+            (PathBuf::from("/m/edit.mp3"), &edit),
+            (PathBuf::from("/m/another.mp3"), &other),
+        ];
+        let (groups, _) = find_duplicates(&tracks, &DuplicateOptions::default());
+
+        assert_eq!(groups.len(), 1);
+        // The lossless copy is the one to keep
+        assert_eq!(paths(&groups[0]), ["song.flac", "song.mp3"]);
+        assert_eq!(groups[0].matched_by, [MatchKind::Tags]);
+    }
+
+    #[test]
+    fn groups_tracks_of_unknown_length_apart() {
+        let short = track("The Band", "Song", 200, 320);
+        let long = track("The Band", "Song", 400, 320);
+        let unknown = TrackMetadata {
+            duration: None,
+            ..track("The Band", "Song", 0, 320)
+        };
+        let tracks = vec![
+            (PathBuf::from("/m/short.mp3"), &short),
+            (PathBuf::from("/m/long.mp3"), &long),
+            (PathBuf::from("/m/a.mp3"), &unknown),
+            (PathBuf::from("/m/b.mp3"), &unknown),
+        ];
+        let (groups, _) = find_duplicates(&tracks, &DuplicateOptions::default());
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(paths(&groups[0]), ["a.mp3", "b.mp3"]);
+    }
+
+    #[test]
+    fn merges_play_statistics_into_the_copy_kept() {
+        let mut keep = TrackMetadata {
+            play_count: 3,
+            first_played: Some(500),
+            last_played: Some(900),
+            listening_time: Duration::from_secs(600),
+            ..Default::default()
+        };
+        let other = TrackMetadata {
+            play_count: 2,
+            skip_count: 1,
+            first_played: Some(100),
+            last_played: Some(700),
+            date_added: Some(50),
+            listening_time: Duration::from_secs(400),
+            rating: Some(4),
+            favorite: true,
+            ..Default::default()
+        };
+        merge_plays(&mut keep, &other);
+
+        assert_eq!((keep.play_count, keep.skip_count), (5, 1));
+        assert_eq!(
+            (keep.first_played, keep.last_played),
+            (Some(100), Some(900))
+        );
+        assert_eq!(keep.date_added, Some(50));
+        assert_eq!(keep.listening_time, Duration::from_secs(1000));
+        assert_eq!((keep.rating, keep.favorite), (Some(4), true));
+    }
+
+    /// A track three minutes long with the fingerprint `fingerprint`.
+    fn fingerprinted(fingerprint: &[u32]) -> TrackMetadata {
+        TrackMetadata {
+            duration: Some(Duration::from_secs(180)),
+            fingerprint: Some(encode_fingerprint(fingerprint)),
+            ..Default::default()
+        }
+    }
+
+    fn sounds() -> (Vec<u32>, Vec<u32>, Vec<u32>) {
+        let a: Vec<u32> = (0..200u32).map(|i| i.wrapping_mul(2_654_435_761)).collect();
+        // The same audio with a few items of extra lead-in and some noise
+        let mut b: Vec<u32> = vec![7, 7, 7, 7, 7];
+        b.extend(a.iter().map(|x| x ^ 1));
+        let c: Vec<u32> = (0..200u32).map(|i| i.wrapping_mul(40_503)).collect();
+        (a, b, c)
+    }
+
+    #[test]
+    fn matches_fingerprints_despite_an_offset() {
+        let (a, b, c) = sounds();
+        assert!(fingerprint_similarity(&a, &b) > 0.95);
+        assert!(fingerprint_similarity(&a, &c) < FINGERPRINT_MATCH);
+
+        let (a, b, c) = (fingerprinted(&a), fingerprinted(&b), fingerprinted(&c));
+        let tracks = vec![
+            (PathBuf::from("/m/a.ogg"), &a),
+            (PathBuf::from("/m/b.ogg"), &b),
+            (PathBuf::from("/m/c.ogg"), &c),
+        ];
+        let options = DuplicateOptions {
+            by_fingerprint: true,
+            ..Default::default()
+        };
+        let (groups, _) = find_duplicates(&tracks, &options);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(paths(&groups[0]), ["a.ogg", "b.ogg"]);
+        assert_eq!(groups[0].matched_by, [MatchKind::Fingerprint]);
+    }
+
+    #[test]
+    fn finds_reencoded_tracks() {
+        let (a, b, c) = sounds();
+        let (a, b, c) = (fingerprinted(&a), fingerprinted(&b), fingerprinted(&c));
+        let unknown = TrackMetadata::default();
+        let gone = vec![
+            (PathBuf::from("/m/song.mp3"), &a),
+            (PathBuf::from("/m/other.mp3"), &c),
+        ];
+        let added = vec![
+            (PathBuf::from("/m/new.ogg"), &unknown),
+            (PathBuf::from("/m/song.flac"), &b),
+        ];
+
+        let moved = find_reencodes(&gone, &added);
+        assert_eq!(
+            moved,
+            HashMap::from([(PathBuf::from("/m/song.mp3"), PathBuf::from("/m/song.flac"))])
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Graphic equalizer settings: ten band gains and a preamp, the presets that
+//! come with the player, and which preset a track's genre or the output
+//! device calls for.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+pub const BANDS: usize = 10;
+
+/// Centre frequencies of the bands in Hz, as GStreamer's `equalizer-10bands`
+/// has them.
+pub const BAND_FREQUENCIES: [u32; BANDS] = [29, 59, 119, 237, 474, 947, 1889, 3770, 7523, 15011];
+
+/// Range of band gains in tenths of a decibel, as `equalizer-10bands` takes them.
+pub const MIN_GAIN: i32 = -240;
+pub const MAX_GAIN: i32 = 120;
+
+/// Range of the preamp in tenths of a decibel.
+pub const MIN_PREAMP: i32 = -120;
+pub const MAX_PREAMP: i32 = 120;
+
+/// Equalizer settings. Gains and preamp are in tenths of a decibel, which
+/// keeps them exact in the configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqualizerPreset {
+    /// Name of the preset, or empty for settings made by hand
+    pub name: String,
+    pub preamp: i32,
+    pub gains: [i32; BANDS],
+}
+
+impl EqualizerPreset {
+    fn builtin(name: &str, gains: [i32; BANDS]) -> Self {
+        Self {
+            name: name.to_string(),
+            preamp: headroom(&gains),
+            gains,
+        }
+    }
+
+    /// Whether the settings leave the sound as it is.
+    pub fn is_flat(&self) -> bool {
+        self.preamp == 0 && self.gains.iter().all(|g| *g == 0)
+    }
+
+    pub fn gain_db(&self, band: usize) -> f64 {
+        f64::from(self.gains[band]) / 10.0
+    }
+
+    pub fn preamp_db(&self) -> f64 {
+        f64::from(self.preamp) / 10.0
+    }
+
+    /// The settings with gains and preamp brought into their ranges.
+    pub fn clamped(mut self) -> Self {
+        for gain in &mut self.gains {
+            *gain = (*gain).clamp(MIN_GAIN, MAX_GAIN);
+        }
+        self.preamp = self.preamp.clamp(MIN_PREAMP, MAX_PREAMP);
+        self
+    }
+}
+
+/// Preamp that keeps `gains` from clipping: the largest boost, taken off.
+pub fn headroom(gains: &[i32; BANDS]) -> i32 {
+    -gains.iter().copied().max().unwrap_or(0).max(0)
+}
+
+/// The presets that come with the player. Their names are IDs the interface
+/// translates.
+pub fn builtin_presets() -> Vec<EqualizerPreset> {
+    vec![
+        EqualizerPreset::builtin("flat", [0; BANDS]),
+        EqualizerPreset::builtin("rock", [50, 40, 30, 10, -10, -10, 10, 30, 40, 50]),
+        EqualizerPreset::builtin("pop", [-10, 0, 20, 40, 50, 40, 20, 0, -10, -10]),
+        EqualizerPreset::builtin("jazz", [30, 20, 10, 20, -10, -10, 0, 10, 20, 30]),
+        EqualizerPreset::builtin("classical", [40, 30, 20, 10, -10, -10, 0, 20, 30, 40]),
+        EqualizerPreset::builtin("electronic", [50, 40, 10, 0, -20, 20, 10, 10, 40, 50]),
+        EqualizerPreset::builtin("bass-boost", [60, 50, 40, 20, 0, 0, 0, 0, 0, 0]),
+        EqualizerPreset::builtin("treble-boost", [0, 0, 0, 0, 0, 10, 30, 40, 50, 60]),
+        EqualizerPreset::builtin("vocal", [-20, -30, -30, 10, 40, 40, 30, 10, 0, -20]),
+    ]
+}
+
+/// The preset called `name`: one of `user`'s, or else a built-in one.
+pub fn find_preset(name: &str, user: &[EqualizerPreset]) -> Option<EqualizerPreset> {
+    user.iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .or_else(|| builtin_presets().into_iter().find(|p| p.name == name))
+}
+
+/// How a genre is keyed in genre bindings.
+pub fn genre_key(genre: &str) -> String {
+    genre.trim().to_lowercase()
+}
+
+/// The preset bound to a track's genre, or else to the output device. Tracks
+/// with several genres, such as `Rock; Pop`, use the first one bound.
+pub fn bound_preset<'a>(
+    genre: Option<&str>,
+    device: Option<&str>,
+    by_genre: &'a BTreeMap<String, String>,
+    by_device: &'a BTreeMap<String, String>,
+) -> Option<&'a str> {
+    genre
+        .into_iter()
+        .flat_map(|g| g.split([';', ',', '/']))
+        .find_map(|g| by_genre.get(&genre_key(g)))
+        .or_else(|| device.and_then(|d| by_device.get(d)))
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_presets_leave_headroom() {
+        for preset in builtin_presets() {
+            let loudest = preset.gains.iter().max().unwrap();
+            assert!(preset.preamp + loudest <= 0, "{} clips", preset.name);
+            assert_eq!(preset.clone().clamped(), preset);
+        }
+        assert!(find_preset("flat", &[]).unwrap().is_flat());
+    }
+
+    #[test]
+    fn user_presets_shadow_builtin_ones() {
+        let mine = EqualizerPreset {
+            name: "rock".into(),
+            preamp: -30,
+            gains: [30; BANDS],
+        };
+        let user = [mine.clone()];
+        assert_eq!(find_preset("rock", &user), Some(mine));
+        assert_eq!(find_preset("rock", &[]).unwrap().gains[0], 50);
+        assert_eq!(find_preset("missing", &[]), None);
+    }
+
+    #[test]
+    fn clamps_to_the_element_ranges() {
+        let preset = EqualizerPreset {
+            name: String::new(),
+            preamp: 500,
+            gains: [-300, 200, 0, 0, 0, 0, 0, 0, 0, 15],
+        }
+        .clamped();
+        assert_eq!(preset.preamp, MAX_PREAMP);
+        assert_eq!(preset.gains[..2], [MIN_GAIN, MAX_GAIN]);
+        assert_eq!(preset.gain_db(9), 1.5);
+    }
+
+    #[test]
+    fn binds_presets_to_genres_before_devices() {
+        let by_genre = BTreeMap::from([("pop".to_string(), "pop".to_string())]);
+        let by_device = BTreeMap::from([("Headphones".to_string(), "bass-boost".to_string())]);
+
+        let bound = |genre, device| bound_preset(genre, device, &by_genre, &by_device);
+        assert_eq!(bound(Some("Rock; Pop "), Some("Headphones")), Some("pop"));
+        assert_eq!(bound(Some("Rock"), Some("Headphones")), Some("bass-boost"));
+        assert_eq!(bound(None, Some("Speakers")), None);
+    }
+}
@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Append-only log of listens, stored as JSON lines in the app's data directory.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::backend::TrackMetadata;
+use crate::audio::listen::{ListenEnd, ListenSession};
+
+/// Where a listened track was started from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaySource {
+    Library,
+    Playlist,
+    /// A remote stream
+    Radio,
+}
+
+/// One listen of a track.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub path: PathBuf,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    /// Time actually spent playing, in milliseconds
+    pub listened_ms: u64,
+    pub end: ListenEnd,
+    /// Whether enough was heard for the listen to count as a play
+    #[serde(default)]
+    pub counted: bool,
+    pub source: PlaySource,
+    /// Tags at the time of the listen, so entries stay readable after the file is gone
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        session: &ListenSession,
+        end: ListenEnd,
+        source: PlaySource,
+        md: Option<&TrackMetadata>,
+    ) -> Self {
+        Self {
+            path: session.path.clone(),
+            started_at: session.started_at,
+            listened_ms: session.listened.as_millis() as u64,
+            end,
+            counted: session.counted,
+            source,
+            title: md.and_then(|m| m.title.clone()),
+            artist: md.and_then(|m| m.artist.clone()),
+            album: md.and_then(|m| m.album.clone()),
+        }
+    }
+
+    pub fn listened(&self) -> Duration {
+        Duration::from_millis(self.listened_ms)
+    }
+
+    /// When the listen started, in local time.
+    pub fn started(&self) -> DateTime<Local> {
+        Local
+            .timestamp_opt(self.started_at as i64, 0)
+            .single()
+            .unwrap_or_default()
+    }
+}
+
+/// The listening history, oldest entry first.
+#[derive(Debug, Clone)]
+pub struct History {
+    file: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Load the history kept in `file`; a missing file yields an empty history.
+    /// Lines that fail to parse, such as one cut short by a crash, are skipped.
+    pub fn load(file: impl Into<PathBuf>) -> Result<Self> {
+        let file = file.into();
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(anyhow!("failed to read {:?}: {e}", file)),
+        };
+
+        let mut entries = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => eprintln!("skipping history line {} in {:?}: {e}", number + 1, file),
+            }
+        }
+
+        Ok(Self { file, entries })
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Add an entry, appending it to the file.
+    pub fn append(&mut self, entry: HistoryEntry) -> Result<()> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.file)?;
+        // Start on a line of its own after a line cut short by a crash, which
+        // would otherwise swallow the entry
+        let mut line = String::new();
+        if file.metadata()?.len() > 0 {
+            let mut last = [0];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                line.push('\n');
+            }
+        }
+        line.push_str(&serde_json::to_string(&entry)?);
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Drop entries that started before `cutoff` (seconds since the Unix epoch)
+    /// and rewrite the file. Returns how many were removed.
+    pub fn prune_before(&mut self, cutoff: u64) -> Result<usize> {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.started_at >= cutoff);
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.rewrite()?;
+        }
+        Ok(removed)
+    }
+
+    /// Point entries of files that were moved at their new path and rewrite
+    /// the file. Returns how many entries changed.
+    pub fn move_paths(&mut self, moved: &HashMap<PathBuf, PathBuf>) -> Result<usize> {
+        let mut changed = 0;
+        for entry in &mut self.entries {
+            if let Some(to) = moved.get(&entry.path) {
+                entry.path = to.clone();
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.rewrite()?;
+        }
+        Ok(changed)
+    }
+
+    fn rewrite(&self) -> Result<()> {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        let tmp = self.file.with_extension("jsonl.tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, &self.file)?;
+        Ok(())
+    }
+
+    /// Entries grouped by the local day they started on, most recent day and
+    /// listen first.
+    pub fn by_day(&self) -> Vec<(NaiveDate, Vec<&HistoryEntry>)> {
+        let mut days: Vec<(NaiveDate, Vec<&HistoryEntry>)> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            let day = entry.started().date_naive();
+            match days.last_mut() {
+                Some((last, entries)) if *last == day => entries.push(entry),
+                _ => days.push((day, vec![entry])),
+            }
+        }
+        days
+    }
+
+    /// Write the history as a JSON array.
+    pub fn export_json(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(&self.entries)?)?;
+        Ok(())
+    }
+
+    /// Write the history as CSV, one listen per row, with RFC 3339 start times.
+    pub fn export_csv(&self, path: &Path) -> Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record([
+            "started", "path", "title", "artist", "album", "listened_ms", "end", "counted", "source",
+        ])?;
+        for entry in &self.entries {
+            writer.write_record([
+                entry.started().to_rfc3339(),
+                entry.path.to_string_lossy().into_owned(),
+                entry.title.clone().unwrap_or_default(),
+                entry.artist.clone().unwrap_or_default(),
+                entry.album.clone().unwrap_or_default(),
+                entry.listened_ms.to_string(),
+                variant_name(&entry.end),
+                entry.counted.to_string(),
+                variant_name(&entry.source),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The serialized name of a unit enum variant, as used in the JSON log.
+fn variant_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, started_at: u64, end: ListenEnd) -> HistoryEntry {
+        HistoryEntry {
+            path: PathBuf::from(path),
+            started_at,
+            listened_ms: 90_000,
+            end,
+            counted: true,
+            source: PlaySource::Library,
+            title: Some("Song, \"live\"".into()),
+            artist: None,
+            album: None,
+        }
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("history-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("history.jsonl")
+    }
+
+    #[test]
+    fn appends_and_reloads_skipping_broken_lines() {
+        let file = temp_file("reload");
+        let _ = fs::remove_file(&file);
+
+        let mut history = History::load(&file).unwrap();
+        history.append(entry("/m/a.flac", 100, ListenEnd::Completed)).unwrap();
+        history.append(entry("/m/b.flac", 200, ListenEnd::Skipped)).unwrap();
+        // A write cut short by a crash
+        OpenOptions::new()
+            .append(true)
+            .open(&file)
+            .unwrap()
+            .write_all(b"{\"path\":\"/m/c")
+            .unwrap();
+
+        let reloaded = History::load(&file).unwrap();
+        assert_eq!(reloaded.entries(), history.entries());
+        assert_eq!(reloaded.entries()[1].end, ListenEnd::Skipped);
+    }
+
+    #[test]
+    fn prunes_old_entries_from_disk() {
+        let file = temp_file("prune");
+        let _ = fs::remove_file(&file);
+
+        let mut history = History::load(&file).unwrap();
+        for (i, started_at) in [100, 200, 300].into_iter().enumerate() {
+            history
+                .append(entry(&format!("/m/{i}.flac"), started_at, ListenEnd::Completed))
+                .unwrap();
+        }
+
+        assert_eq!(history.prune_before(200).unwrap(), 1);
+        let reloaded = History::load(&file).unwrap();
+        let starts: Vec<u64> = reloaded.entries().iter().map(|e| e.started_at).collect();
+        assert_eq!(starts, [200, 300]);
+    }
+
+    #[test]
+    fn follows_moved_files() {
+        let file = temp_file("move");
+        let _ = fs::remove_file(&file);
+        let mut history = History::load(&file).unwrap();
+        for path in ["/m/a.flac", "/m/b.flac", "/m/a.flac"] {
+            history
+                .append(entry(path, 0, ListenEnd::Completed))
+                .unwrap();
+        }
+
+        let moved = HashMap::from([(PathBuf::from("/m/a.flac"), PathBuf::from("/n/a.flac"))]);
+        assert_eq!(history.move_paths(&moved).unwrap(), 2);
+        let reloaded = History::load(&file).unwrap();
+        let paths: Vec<&Path> = reloaded
+            .entries()
+            .iter()
+            .map(|e| e.path.as_path())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("/n/a.flac"),
+                Path::new("/m/b.flac"),
+                Path::new("/n/a.flac")
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_quoted_csv() {
+        let file = temp_file("csv");
+        let _ = fs::remove_file(&file);
+        let mut history = History::load(&file).unwrap();
+        history.append(entry("/m/a.flac", 0, ListenEnd::Stopped)).unwrap();
+
+        let csv_file = file.with_extension("csv");
+        history.export_csv(&csv_file).unwrap();
+        let text = fs::read_to_string(&csv_file).unwrap();
+        let mut lines = text.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("started,path,title,artist,album,listened_ms,end,counted,source")
+        );
+        let row = lines.next().unwrap();
+        assert!(row.ends_with(",/m/a.flac,\"Song, \"\"live\"\"\",,,90000,stopped,true,library"));
+    }
+
+    #[test]
+    fn appends_after_a_cut_off_line() {
+        let file = temp_file("cut-off");
+        fs::write(&file, "{\"path\":\"/m/c").unwrap();
+
+        let mut history = History::load(&file).unwrap();
+        assert!(history.entries().is_empty());
+        history.append(entry("/m/a.flac", 100, ListenEnd::Completed)).unwrap();
+
+        let reloaded = History::load(&file).unwrap();
+        assert_eq!(reloaded.entries(), history.entries());
+    }
+}
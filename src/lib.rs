@@ -1 +1,13 @@
-pub mod audio;
\ No newline at end of file
+pub mod acoustid;
+pub mod audio;
+pub mod duplicates;
+pub mod equalizer;
+pub mod history;
+pub mod lrc;
+pub mod musicbrainz;
+pub mod organize;
+pub mod parametric;
+pub mod playlist;
+pub mod practice;
+pub mod scrobble;
+pub mod stats;
@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Lyrics in the LRC format: lines stamped with the time they are sung at,
+//! down to single words in the enhanced format, or plain lines without times.
+
+use std::time::Duration;
+
+/// A word of an enhanced LRC line, sung from `time` on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Word {
+    pub time: Duration,
+    /// The word with the spacing that follows it
+    pub text: String,
+}
+
+/// A line of lyrics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LyricLine {
+    /// When the line is sung, for synchronized lyrics
+    pub time: Option<Duration>,
+    pub text: String,
+    /// The line's words with their own times, if it has them
+    pub words: Vec<Word>,
+}
+
+impl LyricLine {
+    /// Time the line at `time`, moving its word times along with it.
+    pub fn set_time(&mut self, time: Duration) {
+        if let Some(old) = self.time {
+            let delta = time.as_millis() as i64 - old.as_millis() as i64;
+            for word in &mut self.words {
+                word.time = shift(word.time, delta);
+            }
+        }
+        self.time = Some(time);
+    }
+
+    /// Index of the word being sung at `position`.
+    pub fn word_at(&self, position: Duration) -> Option<usize> {
+        self.words
+            .partition_point(|w| w.time <= position)
+            .checked_sub(1)
+    }
+}
+
+/// The lyrics of a track.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    /// Lines in the order they are sung. Synchronized lyrics only keep the
+    /// lines that have a time.
+    pub lines: Vec<LyricLine>,
+    /// ID tags such as `ar` and `ti`, in file order, without `offset`, which
+    /// is already applied to the times
+    pub tags: Vec<(String, String)>,
+}
+
+impl Lyrics {
+    /// Parse LRC text. Text without timestamps gives unsynchronized lyrics, one
+    /// line per line of text.
+    pub fn parse(text: &str) -> Self {
+        let mut lyrics = Self::parse_draft(text);
+        if lyrics.is_synced() {
+            lyrics.lines.retain(|line| line.time.is_some());
+            lyrics.lines.sort_by_key(|line| line.time);
+        } else {
+            // Drop the blank lines around plain lyrics, keeping those between verses
+            let last = lyrics.lines.iter().rposition(|l| !l.text.is_empty());
+            lyrics.lines.truncate(last.map_or(0, |i| i + 1));
+            let first = lyrics.lines.iter().position(|l| !l.text.is_empty());
+            lyrics.lines.drain(..first.unwrap_or(0));
+        }
+        lyrics
+    }
+
+    /// Parse LRC text keeping every line where it is, timed or not, as lyrics
+    /// are while they are being timed.
+    pub fn parse_draft(text: &str) -> Self {
+        let mut lyrics = Lyrics::default();
+        let mut offset = 0i64;
+        'lines: for raw in text.lines() {
+            let mut rest = raw.trim();
+            let mut times = Vec::new();
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some(end) = tag.find(']') else {
+                    break;
+                };
+                let inner = &tag[..end];
+                if let Some(time) = parse_time(inner) {
+                    times.push(time);
+                } else if let Some((key, value)) = id_tag(inner).filter(|_| times.is_empty()) {
+                    if key == "offset" {
+                        offset = value.parse().unwrap_or(0);
+                    } else {
+                        lyrics.tags.push((key, value.to_string()));
+                    }
+                    continue 'lines;
+                } else {
+                    break;
+                }
+                rest = &tag[end + 1..];
+            }
+
+            let (text, words) = parse_words(rest);
+            if times.is_empty() {
+                lyrics.lines.push(LyricLine {
+                    time: None,
+                    text,
+                    words: Vec::new(),
+                });
+                continue;
+            }
+            // A line stamped with several times is sung again at each of them;
+            // its word times only hold for the first
+            for (i, time) in times.into_iter().enumerate() {
+                lyrics.lines.push(LyricLine {
+                    time: Some(time),
+                    text: text.clone(),
+                    words: if i == 0 { words.clone() } else { Vec::new() },
+                });
+            }
+        }
+
+        // A positive offset shows the lyrics earlier
+        for line in &mut lyrics.lines {
+            if let Some(time) = line.time {
+                line.set_time(shift(time, -offset));
+            }
+        }
+        lyrics
+    }
+
+    /// The lyrics as LRC text, with word times for the lines that have them.
+    /// Lines without a time are written as plain text.
+    pub fn to_lrc(&self) -> String {
+        let mut text = String::new();
+        for (key, value) in &self.tags {
+            text.push_str(&format!("[{key}:{value}]\n"));
+        }
+        for line in &self.lines {
+            if let Some(time) = line.time {
+                text.push_str(&format!("[{}]", format_time(time)));
+            }
+            if line.words.is_empty() {
+                text.push_str(&line.text);
+            } else {
+                for word in &line.words {
+                    text.push_str(&format!("<{}>{}", format_time(word.time), word.text));
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Synchronized lyrics from timed pieces of text, as ID3 `SYLT` frames hold
+    /// them. Pieces starting with a line break begin a new line and the others
+    /// continue the current one, as words; without any line breaks, every piece
+    /// is a line of its own.
+    pub fn from_timed_text(pieces: &[(Duration, String)]) -> Self {
+        let by_words = pieces
+            .iter()
+            .any(|(_, text)| text.starts_with(['\n', '\r']));
+        let mut lines: Vec<LyricLine> = Vec::new();
+        for (time, text) in pieces {
+            let starts_line = !text.starts_with(['\n', '\r']);
+            let text = text.trim_start_matches(['\n', '\r']);
+            match lines.last_mut() {
+                Some(line) if by_words && starts_line => {
+                    line.text.push_str(text);
+                    line.words.push(Word {
+                        time: *time,
+                        text: text.to_string(),
+                    });
+                }
+                _ => lines.push(LyricLine {
+                    time: Some(*time),
+                    text: text.to_string(),
+                    words: if by_words {
+                        vec![Word {
+                            time: *time,
+                            text: text.to_string(),
+                        }]
+                    } else {
+                        Vec::new()
+                    },
+                }),
+            }
+        }
+        for line in &mut lines {
+            line.text = line.text.trim().to_string();
+        }
+        lines.sort_by_key(|line| line.time);
+        Lyrics {
+            lines,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Whether the lines have times to follow the track with.
+    pub fn is_synced(&self) -> bool {
+        self.lines.iter().any(|line| line.time.is_some())
+    }
+
+    /// Index of the line being sung at `position`, for synchronized lyrics.
+    pub fn line_at(&self, position: Duration) -> Option<usize> {
+        if !self.is_synced() {
+            return None;
+        }
+        self.lines
+            .partition_point(|line| line.time.is_some_and(|t| t <= position))
+            .checked_sub(1)
+    }
+}
+
+/// `time` moved by `delta` milliseconds, no earlier than the start.
+fn shift(time: Duration, delta: i64) -> Duration {
+    Duration::from_millis((time.as_millis() as i64 + delta).max(0) as u64)
+}
+
+/// A time as LRC timestamps have it, in minutes, seconds and hundredths.
+pub fn format_time(time: Duration) -> String {
+    let hundredths = time.as_millis() / 10;
+    format!(
+        "{:02}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// The key, in lowercase, and value of an ID tag such as `ar: Someone`.
+fn id_tag(text: &str) -> Option<(String, &str)> {
+    let (key, value) = text.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty() || !key.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((key.to_ascii_lowercase(), value.trim()))
+}
+
+/// A timestamp such as `01:23.45`, `1:23.456`, `01:23:45` or `01:23`.
+fn parse_time(text: &str) -> Option<Duration> {
+    let (minutes, rest) = text.trim().split_once(':')?;
+    let (seconds, fraction) = rest.split_once(['.', ':']).unwrap_or((rest, ""));
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !digits(minutes) || !digits(seconds) || !(fraction.is_empty() || digits(fraction)) {
+        return None;
+    }
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.parse().ok()?;
+    // Hundredths in most files, milliseconds in some
+    let millis = format!("{fraction:0<3}")[..3].parse::<u64>().ok()?;
+    Some(Duration::from_millis(
+        (minutes * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+/// The text of an enhanced LRC line with its `<mm:ss.xx>` word times taken
+/// out, and the words they time.
+fn parse_words(line: &str) -> (String, Vec<Word>) {
+    let mut text = String::new();
+    let mut words: Vec<Word> = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let tag = rest
+            .strip_prefix('<')
+            .and_then(|tag| Some((tag, tag.find('>')?)))
+            .and_then(|(tag, end)| Some((parse_time(&tag[..end])?, &tag[end + 1..])));
+        if let Some((time, after)) = tag {
+            words.push(Word {
+                time,
+                text: String::new(),
+            });
+            rest = after;
+            continue;
+        }
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| *c == '<')
+            .map_or(rest.len(), |(i, _)| i);
+        let piece = &rest[..end];
+        text.push_str(piece);
+        if let Some(word) = words.last_mut() {
+            word.text.push_str(piece);
+        }
+        rest = &rest[end..];
+    }
+    // A closing time marks where the last word ends, and times nothing
+    words.retain(|word| !word.text.trim().is_empty());
+    (text.trim().to_string(), words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_time("01:23.45"), Some(ms(83_450)));
+        assert_eq!(parse_time("1:23.456"), Some(ms(83_456)));
+        assert_eq!(parse_time("01:23:4"), Some(ms(83_400)));
+        assert_eq!(parse_time("120:00"), Some(ms(7_200_000)));
+        assert_eq!(parse_time("ar:Someone"), None);
+        assert_eq!(parse_time("01:2x"), None);
+    }
+
+    #[test]
+    fn parses_synchronized_lyrics() {
+        let lyrics = Lyrics::parse(
+            "[ar: The Band]\n\
+             [ti:Song]\n\
+             [00:12.00]First line\r\n\
+             [00:20.50][01:20.50]Chorus\n\
+             not a timed line\n\
+             [00:16.00]\n\
+             [00:18.00]Second line",
+        );
+        assert!(lyrics.is_synced());
+        assert_eq!(
+            lyrics.tags,
+            [
+                ("ar".into(), "The Band".into()),
+                ("ti".into(), "Song".into())
+            ]
+        );
+        let lines: Vec<(Option<Duration>, &str)> = lyrics
+            .lines
+            .iter()
+            .map(|l| (l.time, l.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (Some(ms(12_000)), "First line"),
+                (Some(ms(16_000)), ""),
+                (Some(ms(18_000)), "Second line"),
+                (Some(ms(20_500)), "Chorus"),
+                (Some(ms(80_500)), "Chorus"),
+            ]
+        );
+
+        assert_eq!(lyrics.line_at(ms(5_000)), None);
+        assert_eq!(lyrics.line_at(ms(12_000)), Some(0));
+        assert_eq!(lyrics.line_at(ms(19_999)), Some(2));
+        assert_eq!(lyrics.line_at(ms(300_000)), Some(4));
+    }
+
+    #[test]
+    fn applies_offsets() {
+        let lyrics = Lyrics::parse("[00:10.00]Line\n[offset:+500]\n[00:00.20]Early");
+        assert_eq!(lyrics.lines[0].time, Some(Duration::ZERO));
+        assert_eq!(lyrics.lines[1].time, Some(ms(9_500)));
+        assert!(lyrics.tags.is_empty());
+
+        let lyrics = Lyrics::parse("[offset:-250]\n[00:10.00]<00:10.00>Line");
+        assert_eq!(lyrics.lines[0].time, Some(ms(10_250)));
+        assert_eq!(lyrics.lines[0].words[0].time, ms(10_250));
+    }
+
+    #[test]
+    fn parses_word_times() {
+        let lyrics =
+            Lyrics::parse("[00:01.00]<00:01.00>Hello <00:01.50>there, <00:02.25>world<00:03.00>");
+        let line = &lyrics.lines[0];
+        assert_eq!(line.text, "Hello there, world");
+        let words: Vec<(Duration, &str)> = line
+            .words
+            .iter()
+            .map(|w| (w.time, w.text.as_str()))
+            .collect();
+        assert_eq!(
+            words,
+            [
+                (ms(1_000), "Hello "),
+                (ms(1_500), "there, "),
+                (ms(2_250), "world"),
+            ]
+        );
+        assert_eq!(line.word_at(ms(900)), None);
+        assert_eq!(line.word_at(ms(1_700)), Some(1));
+        assert_eq!(line.word_at(ms(9_000)), Some(2));
+    }
+
+    #[test]
+    fn keeps_plain_lyrics() {
+        let lyrics = Lyrics::parse("\nFirst verse\nstill first\n\nSecond <verse>\n\n");
+        assert!(!lyrics.is_synced());
+        let lines: Vec<&str> = lyrics.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(lines, ["First verse", "still first", "", "Second <verse>"]);
+        assert_eq!(lyrics.line_at(ms(1_000)), None);
+    }
+
+    #[test]
+    fn writes_lrc() {
+        let text = "[ar:The Band]\n\
+                    [00:01.00]<00:01.00>Hello <00:01.50>there\n\
+                    [00:04.20]\n\
+                    [61:05.07]Much later\n";
+        let lyrics = Lyrics::parse(text);
+        assert_eq!(lyrics.to_lrc(), text);
+        assert_eq!(Lyrics::parse(&lyrics.to_lrc()), lyrics);
+        assert_eq!(format_time(ms(83_459)), "01:23.45");
+    }
+
+    #[test]
+    fn keeps_untimed_lines_in_drafts() {
+        let mut draft = Lyrics::parse_draft("[00:02.00]Second\nThird\n[00:01.00]<00:01.50>First");
+        let texts: Vec<&str> = draft.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["Second", "Third", "First"]);
+        assert_eq!(draft.lines[1].time, None);
+
+        draft.lines[2].set_time(ms(1_200));
+        assert_eq!(draft.lines[2].words[0].time, ms(1_700));
+        assert_eq!(
+            draft.to_lrc(),
+            "[00:02.00]Second\nThird\n[00:01.20]<00:01.70>First\n"
+        );
+    }
+
+    #[test]
+    fn reads_timed_text() {
+        let lines =
+            Lyrics::from_timed_text(&[(ms(2_000), "Second".into()), (ms(1_000), "First".into())]);
+        assert_eq!(lines.lines[0].text, "First");
+        assert!(lines.lines[0].words.is_empty());
+
+        let words = Lyrics::from_timed_text(&[
+            (ms(1_000), "Hel".into()),
+            (ms(1_200), "lo ".into()),
+            (ms(1_500), "you".into()),
+            (ms(3_000), "\nNext".into()),
+        ]);
+        assert_eq!(words.lines.len(), 2);
+        assert_eq!(words.lines[0].text, "Hello you");
+        assert_eq!(words.lines[0].words.len(), 3);
+        assert_eq!(words.lines[1].time, Some(ms(3_000)));
+        assert_eq!(words.lines[1].text, "Next");
+    }
+}
@@ -0,0 +1,526 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Looking albums up on MusicBrainz: finding the releases that match the
+//! tracks of an album, and the tags and IDs they would give each track.
+
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::audio::backend::TrackMetadata;
+use crate::audio::tags::{TagChanges, TagField};
+use crate::duplicates::normalize;
+
+/// Releases fetched in full and scored per lookup.
+const MAX_CANDIDATES: usize = 5;
+
+/// Length difference up to which tracks count as the same length.
+const SAME_LENGTH: Duration = Duration::from_secs(3);
+
+/// Length difference from which tracks count as different lengths altogether.
+const OTHER_LENGTH: Duration = Duration::from_secs(15);
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A release with its tracks, as listed on MusicBrainz.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Release {
+    pub id: String,
+    pub title: String,
+    /// The release's artists as credited together
+    pub artist: String,
+    pub artist_id: Option<String>,
+    pub release_group_id: Option<String>,
+    pub year: Option<u32>,
+    pub country: Option<String>,
+    pub tracks: Vec<ReleaseTrack>,
+}
+
+/// A track of a release.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReleaseTrack {
+    pub disc: u32,
+    /// Position on its disc
+    pub number: u32,
+    pub title: String,
+    pub artist: String,
+    pub artist_id: Option<String>,
+    pub recording_id: String,
+    pub length: Option<Duration>,
+}
+
+impl Release {
+    /// Whether the release spans more than one disc.
+    fn has_discs(&self) -> bool {
+        self.tracks.iter().any(|t| t.disc > 1)
+    }
+}
+
+/// A release that might be the album being looked up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub release: Release,
+    /// How well the release fits the album's tracks, from 0 to 1
+    pub score: f64,
+}
+
+/// A MusicBrainz web service: musicbrainz.org, a mirror, or a mock.
+#[derive(Debug, Clone)]
+pub struct MusicBrainz {
+    api_root: String,
+    agent: ureq::Agent,
+    /// Time to leave between requests; musicbrainz.org allows one a second
+    interval: Duration,
+}
+
+impl MusicBrainz {
+    pub const DEFAULT_API_ROOT: &'static str = "https://musicbrainz.org/ws/2";
+
+    /// A client for the web service at `api_root`. Requests are only spaced
+    /// out for musicbrainz.org itself.
+    pub fn new(api_root: &str) -> Self {
+        let api_root = api_root.trim_end_matches('/').to_string();
+        let agent = ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!(
+                "music-player/",
+                env!("CARGO_PKG_VERSION"),
+                " ( ",
+                env!("CARGO_PKG_REPOSITORY"),
+                " )"
+            ))
+            .build();
+        let interval = if api_root == Self::DEFAULT_API_ROOT {
+            Duration::from_secs(1)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            api_root,
+            agent,
+            interval,
+        }
+    }
+
+    fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let mut request = self.agent.get(&format!("{}/{path}", self.api_root));
+        for (name, value) in query {
+            request = request.query(name, value);
+        }
+        match request.query("fmt", "json").call() {
+            Ok(response) => Ok(response.into_json()?),
+            Err(ureq::Error::Status(status, response)) => {
+                let body: Value = response.into_json().unwrap_or_default();
+                let message = body["error"].as_str().map(str::to_string);
+                Err(anyhow!(
+                    "MusicBrainz request failed: {}",
+                    message.unwrap_or_else(|| status.to_string())
+                ))
+            }
+            Err(err) => Err(anyhow!("MusicBrainz is unreachable: {err}")),
+        }
+    }
+
+    /// Releases that could be the album `album` by `artist` made of `tracks`,
+    /// best fit first. Releases that fail to load are left out; only when all
+    /// of them do is that an error.
+    pub fn find_releases(
+        &self,
+        artist: Option<&str>,
+        album: &str,
+        tracks: &[&TrackMetadata],
+    ) -> Result<Vec<Candidate>> {
+        let mut query = format!("release:\"{}\"", escape(album));
+        if let Some(artist) = artist {
+            query.push_str(&format!(" AND artist:\"{}\"", escape(artist)));
+        }
+        let limit = MAX_CANDIDATES.to_string();
+        let found = self.get("release", &[("query", &query), ("limit", &limit)])?;
+
+        let mut candidates = Vec::new();
+        let mut failure = None;
+        for summary in found["releases"].as_array().into_iter().flatten() {
+            let Some(id) = summary["id"].as_str() else {
+                continue;
+            };
+            thread::sleep(self.interval);
+            let release = match self.release(id) {
+                Ok(release) => release,
+                Err(err) => {
+                    eprintln!("failed to load MusicBrainz release {id}: {err}");
+                    failure = Some(err);
+                    continue;
+                }
+            };
+            let score = score(&release, tracks);
+            candidates.push(Candidate { release, score });
+        }
+        if let (true, Some(err)) = (candidates.is_empty(), failure) {
+            return Err(err);
+        }
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(candidates)
+    }
+
+    /// The release with MusicBrainz ID `id`, with its tracks.
+    pub fn release(&self, id: &str) -> Result<Release> {
+        let release = self.get(
+            &format!("release/{id}"),
+            &[("inc", "recordings artist-credits release-groups")],
+        )?;
+        parse_release(&release).ok_or_else(|| anyhow!("MusicBrainz sent an invalid release"))
+    }
+}
+
+/// Quote `text` for a MusicBrainz search query.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Credited names joined as MusicBrainz shows them, and the first artist's ID.
+fn artist_credit(credit: &Value) -> (String, Option<String>) {
+    let credits = credit.as_array().map(Vec::as_slice).unwrap_or_default();
+    let name = credits
+        .iter()
+        .map(|c| {
+            let name = c["name"].as_str().unwrap_or_default();
+            format!("{name}{}", c["joinphrase"].as_str().unwrap_or_default())
+        })
+        .collect();
+    let id = credits
+        .first()
+        .and_then(|c| c["artist"]["id"].as_str())
+        .map(str::to_string);
+    (name, id)
+}
+
+fn parse_release(release: &Value) -> Option<Release> {
+    let (artist, artist_id) = artist_credit(&release["artist-credit"]);
+    let mut tracks = Vec::new();
+    for (index, medium) in release["media"].as_array()?.iter().enumerate() {
+        let disc = medium["position"].as_u64().unwrap_or(index as u64 + 1) as u32;
+        for (index, track) in medium["tracks"].as_array()?.iter().enumerate() {
+            let recording = &track["recording"];
+            // Track credits differ from the recording's on some releases
+            let credit = match &track["artist-credit"] {
+                Value::Null => &recording["artist-credit"],
+                credit => credit,
+            };
+            let (track_artist, track_artist_id) = artist_credit(credit);
+            let title = track["title"].as_str().or(recording["title"].as_str())?;
+            tracks.push(ReleaseTrack {
+                disc,
+                number: track["position"].as_u64().unwrap_or(index as u64 + 1) as u32,
+                title: title.to_string(),
+                artist: track_artist,
+                artist_id: track_artist_id,
+                recording_id: recording["id"].as_str()?.to_string(),
+                length: track["length"]
+                    .as_u64()
+                    .or(recording["length"].as_u64())
+                    .map(Duration::from_millis),
+            });
+        }
+    }
+    Some(Release {
+        id: release["id"].as_str()?.to_string(),
+        title: release["title"].as_str()?.to_string(),
+        artist,
+        artist_id,
+        release_group_id: release["release-group"]["id"].as_str().map(str::to_string),
+        year: release["date"]
+            .as_str()
+            .and_then(|d| d.get(..4))
+            .and_then(|y| y.parse().ok()),
+        country: release["country"].as_str().map(str::to_string),
+        tracks,
+    })
+}
+
+/// How alike two titles are, from 0 to 1, ignoring case and punctuation.
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+    let a: Vec<&str> = a.split(' ').collect();
+    let b: Vec<&str> = b.split(' ').collect();
+    let shared = a.iter().filter(|word| b.contains(word)).count();
+    shared as f64 / a.len().max(b.len()) as f64
+}
+
+/// How alike two lengths are, from 0 to 1. Unknown lengths are neutral.
+fn length_similarity(a: Option<Duration>, b: Option<Duration>) -> f64 {
+    let (Some(a), Some(b)) = (a, b) else {
+        return 0.5;
+    };
+    let difference = a.abs_diff(b);
+    if difference <= SAME_LENGTH {
+        1.0
+    } else if difference >= OTHER_LENGTH {
+        0.0
+    } else {
+        (OTHER_LENGTH - difference).as_secs_f64() / (OTHER_LENGTH - SAME_LENGTH).as_secs_f64()
+    }
+}
+
+/// The release track each of `tracks` is, if any: the one at the same disc and
+/// position when the tracks are numbered, otherwise the one with the most
+/// similar title and length. No release track is given to two tracks.
+pub fn match_tracks(release: &Release, tracks: &[&TrackMetadata]) -> Vec<Option<usize>> {
+    let mut matched = vec![None; tracks.len()];
+    let mut taken = vec![false; release.tracks.len()];
+
+    for (i, md) in tracks.iter().enumerate() {
+        let Some(number) = md.track else {
+            continue;
+        };
+        // Single-disc albums are often tagged without a disc number
+        let disc = md.disc.unwrap_or(1);
+        let found = release
+            .tracks
+            .iter()
+            .position(|t| t.number == number && (t.disc == disc || !release.has_discs()));
+        if let Some(j) = found.filter(|j| !taken[*j]) {
+            matched[i] = Some(j);
+            taken[j] = true;
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (i, md) in tracks.iter().enumerate() {
+        if matched[i].is_some() {
+            continue;
+        }
+        let title = md.title.as_deref().unwrap_or_default();
+        for (j, track) in release.tracks.iter().enumerate() {
+            if taken[j] {
+                continue;
+            }
+            let similarity = title_similarity(title, &track.title)
+                + length_similarity(md.duration, track.length) / 2.0;
+            if similarity >= 1.0 {
+                pairs.push((similarity, i, j));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (_, i, j) in pairs {
+        if matched[i].is_none() && !taken[j] {
+            matched[i] = Some(j);
+            taken[j] = true;
+        }
+    }
+    matched
+}
+
+/// How well `release` fits `tracks`, from 0 to 1: whether it has as many
+/// tracks, and how alike the titles and lengths of matched tracks are.
+pub fn score(release: &Release, tracks: &[&TrackMetadata]) -> f64 {
+    if tracks.is_empty() || release.tracks.is_empty() {
+        return 0.0;
+    }
+    let counts = (tracks.len(), release.tracks.len());
+    let count = counts.0.min(counts.1) as f64 / counts.0.max(counts.1) as f64;
+
+    let (mut titles, mut lengths) = (0.0, 0.0);
+    for (md, matched) in tracks.iter().zip(match_tracks(release, tracks)) {
+        if let Some(track) = matched.map(|j| &release.tracks[j]) {
+            titles += title_similarity(md.title.as_deref().unwrap_or_default(), &track.title);
+            lengths += length_similarity(md.duration, track.length);
+        }
+    }
+    let n = tracks.len() as f64;
+    0.3 * count + 0.35 * titles / n + 0.35 * lengths / n
+}
+
+/// The tags the release would give `md` as track `track` of it, with the
+/// fields that would change.
+pub fn proposed_changes(release: &Release, track: &ReleaseTrack, md: &TrackMetadata) -> TagChanges {
+    let proposed = [
+        (TagField::Title, Some(track.title.clone())),
+        (TagField::Artist, Some(track.artist.clone())),
+        (TagField::Album, Some(release.title.clone())),
+        (TagField::AlbumArtist, Some(release.artist.clone())),
+        (TagField::Track, Some(track.number.to_string())),
+        (
+            TagField::Disc,
+            // Keep single-disc albums without a disc number if they have none
+            (release.has_discs() || md.disc.is_some()).then(|| track.disc.to_string()),
+        ),
+        (TagField::Year, release.year.map(|y| y.to_string())),
+        (
+            TagField::MusicBrainzRecordingId,
+            Some(track.recording_id.clone()),
+        ),
+        (TagField::MusicBrainzReleaseId, Some(release.id.clone())),
+        (
+            TagField::MusicBrainzReleaseGroupId,
+            release.release_group_id.clone(),
+        ),
+        (TagField::MusicBrainzArtistId, track.artist_id.clone()),
+    ];
+    proposed
+        .into_iter()
+        .filter(|(field, value)| value.is_some() && field.value(md) != *value)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobble::test_server;
+
+    const RELEASE: &str = r#"{
+        "id": "rel-1", "title": "Record", "date": "1997-05-21", "country": "GB",
+        "artist-credit": [{"name": "The Band", "joinphrase": " & ", "artist": {"id": "art-1"}},
+                          {"name": "Guest", "joinphrase": "", "artist": {"id": "art-2"}}],
+        "release-group": {"id": "rg-1"},
+        "media": [{"position": 1, "tracks": [
+            {"position": 1, "title": "Opening", "length": 200000,
+             "recording": {"id": "rec-1", "title": "Opening",
+                           "artist-credit": [{"name": "The Band", "artist": {"id": "art-1"}}]}},
+            {"position": 2, "title": "Second Song", "length": 180000,
+             "artist-credit": [{"name": "Guest", "artist": {"id": "art-2"}}],
+             "recording": {"id": "rec-2", "title": "Second Song"}}
+        ]}]
+    }"#;
+
+    fn release() -> Release {
+        parse_release(&serde_json::from_str(RELEASE).unwrap()).unwrap()
+    }
+
+    fn track(title: &str, number: Option<u32>, secs: u64) -> TrackMetadata {
+        TrackMetadata {
+            title: Some(title.into()),
+            track: number,
+            duration: Some(Duration::from_secs(secs)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_releases() {
+        let release = release();
+        assert_eq!(release.artist, "The Band & Guest");
+        assert_eq!(release.artist_id.as_deref(), Some("art-1"));
+        assert_eq!(release.release_group_id.as_deref(), Some("rg-1"));
+        assert_eq!(release.year, Some(1997));
+        assert_eq!(release.tracks.len(), 2);
+        assert_eq!(release.tracks[0].artist, "The Band");
+        assert_eq!(release.tracks[1].artist, "Guest");
+        assert_eq!(release.tracks[1].length, Some(Duration::from_secs(180)));
+    }
+
+    #[test]
+    fn matches_tracks_by_number_then_title() {
+        let release = release();
+        let numbered = [
+            track("Whatever", Some(2), 180),
+            track("Opening", Some(1), 200),
+        ];
+        let numbered: Vec<&TrackMetadata> = numbered.iter().collect();
+        assert_eq!(match_tracks(&release, &numbered), [Some(1), Some(0)]);
+
+        let untagged = [track("second song", None, 181), track("Bonus", None, 60)];
+        let untagged: Vec<&TrackMetadata> = untagged.iter().collect();
+        assert_eq!(match_tracks(&release, &untagged), [Some(1), None]);
+    }
+
+    #[test]
+    fn scores_the_fitting_release_higher() {
+        let release = release();
+        let album = [
+            track("Opening", Some(1), 201),
+            track("Second Song", Some(2), 179),
+        ];
+        let album: Vec<&TrackMetadata> = album.iter().collect();
+        let other = [
+            track("Something", Some(1), 320),
+            track("Else", Some(2), 90),
+            track("Entirely", Some(3), 100),
+        ];
+        let other: Vec<&TrackMetadata> = other.iter().collect();
+
+        assert!(score(&release, &album) > 0.95);
+        assert!(score(&release, &other) < 0.5);
+    }
+
+    #[test]
+    fn proposes_only_changed_tags() {
+        let release = release();
+        let mut md = track("Opening", Some(1), 200);
+        md.album = Some("Record".into());
+        let changes = proposed_changes(&release, &release.tracks[0], &md);
+
+        assert_eq!(changes.get(&TagField::Title), None);
+        assert_eq!(changes.get(&TagField::Album), None);
+        assert_eq!(changes.get(&TagField::Disc), None);
+        assert_eq!(changes[&TagField::Artist].as_deref(), Some("The Band"));
+        assert_eq!(
+            changes[&TagField::AlbumArtist].as_deref(),
+            Some("The Band & Guest")
+        );
+        assert_eq!(changes[&TagField::Year].as_deref(), Some("1997"));
+        assert_eq!(
+            changes[&TagField::MusicBrainzRecordingId].as_deref(),
+            Some("rec-1")
+        );
+        assert_eq!(
+            changes[&TagField::MusicBrainzReleaseGroupId].as_deref(),
+            Some("rg-1")
+        );
+    }
+
+    #[test]
+    fn finds_releases() {
+        let search = r#"{"releases": [{"id": "rel-1", "score": 100}]}"#;
+        let (root, requests) = test_server::serve(vec![("200 OK", search), ("200 OK", RELEASE)]);
+        let album = [
+            track("Opening", Some(1), 200),
+            track("Second Song", Some(2), 180),
+        ];
+        let album: Vec<&TrackMetadata> = album.iter().collect();
+
+        let candidates = MusicBrainz::new(&root)
+            .find_releases(Some("The \"Band\""), "Record", &album)
+            .unwrap();
+
+        let search = requests.recv().unwrap();
+        assert!(search.head.starts_with("GET /release?"));
+        assert!(search.head.contains("fmt=json"));
+        let lookup = requests.recv().unwrap();
+        assert!(lookup.head.starts_with("GET /release/rel-1?"));
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].release.title, "Record");
+        assert!(candidates[0].score > 0.95);
+    }
+
+    #[test]
+    fn skips_releases_that_fail_to_load() {
+        let search = r#"{"releases": [{"id": "gone"}, {"id": "rel-1"}]}"#;
+        let missing = r#"{"error": "Not Found"}"#;
+        let (root, _requests) = test_server::serve(vec![
+            ("200 OK", search),
+            ("404 Not Found", missing),
+            ("200 OK", RELEASE),
+        ]);
+        let album = [track("Opening", Some(1), 200)];
+        let album: Vec<&TrackMetadata> = album.iter().collect();
+
+        let candidates = MusicBrainz::new(&root)
+            .find_releases(None, "Record", &album)
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].release.id, "rel-1");
+
+        // With nothing loaded, the failure is the result
+        let search = r#"{"releases": [{"id": "gone"}]}"#;
+        let (root, _requests) =
+            test_server::serve(vec![("200 OK", search), ("404 Not Found", missing)]);
+        let result = MusicBrainz::new(&root).find_releases(None, "Record", &album);
+        assert!(result.unwrap_err().to_string().contains("Not Found"));
+    }
+}
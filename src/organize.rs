@@ -0,0 +1,674 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Moving and renaming library files to paths built from their tags, such as
+//! `{albumartist}/{year} - {album}/{disc}-{track:02} {title}.{ext}`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::audio::backend::TrackMetadata;
+
+/// The template suggested to new users.
+pub const DEFAULT_TEMPLATE: &str = "{albumartist}/{year} - {album}/{disc}-{track:02} {title}.{ext}";
+
+/// Extensions of files that belong with an album's tracks and move along with them.
+const COMPANION_EXTENSIONS: [&str; 7] = ["cue", "log", "jpg", "jpeg", "png", "gif", "webp"];
+
+/// Extensions of lyrics files kept next to a single track, which are renamed with it.
+const SIDECAR_EXTENSIONS: [&str; 2] = ["lrc", "LRC"];
+
+/// Longest file or folder name written, in bytes; most file systems allow 255.
+const MAX_NAME_LEN: usize = 240;
+
+/// Stands in for a folder or file name that would otherwise be empty.
+const UNKNOWN: &str = "Unknown";
+
+/// A value a template can insert.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placeholder {
+    /// The album artist, or the track artist when there is none
+    AlbumArtist,
+    Artist,
+    Album,
+    /// The title, or the file name when there is none
+    Title,
+    Year,
+    Track,
+    Disc,
+    Genre,
+    /// The file's current extension
+    Ext,
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "albumartist" => Placeholder::AlbumArtist,
+            "artist" => Placeholder::Artist,
+            "album" => Placeholder::Album,
+            "title" => Placeholder::Title,
+            "year" => Placeholder::Year,
+            "track" => Placeholder::Track,
+            "disc" => Placeholder::Disc,
+            "genre" => Placeholder::Genre,
+            "ext" => Placeholder::Ext,
+            _ => return None,
+        })
+    }
+
+    fn value(self, md: &TrackMetadata, source: &Path) -> Option<String> {
+        let file_part =
+            |part: Option<&std::ffi::OsStr>| part.map(|p| p.to_string_lossy().into_owned());
+        match self {
+            Placeholder::AlbumArtist => md.album_artist.clone().or_else(|| md.artist.clone()),
+            Placeholder::Artist => md.artist.clone(),
+            Placeholder::Album => md.album.clone(),
+            Placeholder::Title => md.title.clone().or_else(|| file_part(source.file_stem())),
+            Placeholder::Year => md.year.map(|n| n.to_string()),
+            Placeholder::Track => md.track.map(|n| n.to_string()),
+            Placeholder::Disc => md.disc.map(|n| n.to_string()),
+            Placeholder::Genre => md.genre.clone(),
+            Placeholder::Ext => file_part(source.extension()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    /// A value, padded with zeros to `width` digits when it is a number
+    Value(Placeholder, usize),
+}
+
+/// A parsed path template. `/` separates folders; `{name}` inserts a tag and
+/// `{name:02}` pads a number to two digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = text.trim();
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| anyhow!("unclosed {{ in template"))?
+                + start;
+            let spec = &rest[start + 1..end];
+            let (name, width) = match spec.split_once(':') {
+                Some((name, width)) => (
+                    name,
+                    width
+                        .parse()
+                        .map_err(|_| anyhow!("invalid width in {{{spec}}}"))?,
+                ),
+                None => (spec, 0),
+            };
+            let placeholder = Placeholder::parse(name.trim())
+                .ok_or_else(|| anyhow!("unknown placeholder {{{name}}}"))?;
+            parts.push(Part::Value(placeholder, width));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        if parts.is_empty() {
+            return Err(anyhow!("empty template"));
+        }
+        Ok(Self { parts })
+    }
+
+    /// The path for the track at `source` with tags `md`, relative to the
+    /// library root. Tag values can't add folders: characters that aren't
+    /// allowed in file names are replaced. Separators left dangling by missing
+    /// values are trimmed, and the extension is kept even if the template
+    /// leaves it out.
+    pub fn render(&self, md: &TrackMetadata, source: &Path) -> PathBuf {
+        let mut text = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(t) => text.push_str(t),
+                Part::Value(placeholder, width) => {
+                    let value = placeholder.value(md, source).unwrap_or_default();
+                    let value = match value.trim().parse::<u64>() {
+                        Ok(n) if *width > 0 => format!("{n:0width$}"),
+                        _ => value,
+                    };
+                    text.push_str(&sanitize(&value));
+                }
+            }
+        }
+        let has_ext = self
+            .parts
+            .iter()
+            .any(|p| matches!(p, Part::Value(Placeholder::Ext, _)));
+        if !has_ext {
+            if let Some(ext) = source.extension() {
+                text.push('.');
+                text.push_str(&ext.to_string_lossy());
+            }
+        }
+
+        let components: Vec<&str> = text.split('/').collect();
+        let last = components.len() - 1;
+        components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| clean_component(component, i == last))
+            .collect()
+    }
+}
+
+/// Replace characters that are invalid in file names on common file systems.
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Tidy one folder or file name: collapse runs of spaces, trim separators
+/// left over from missing values, and shorten names that are too long. The
+/// extension of the file name is preserved.
+fn clean_component(component: &str, is_file: bool) -> String {
+    let (stem, ext) = match component.rsplit_once('.') {
+        Some((stem, ext)) if is_file && !ext.is_empty() && !ext.contains(' ') => (stem, Some(ext)),
+        _ => (component, None),
+    };
+    let trim = |c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.');
+    let stem = stem.split_whitespace().collect::<Vec<_>>().join(" ");
+    let stem = stem.trim_matches(trim);
+    let stem = if stem.is_empty() { UNKNOWN } else { stem };
+
+    let room = MAX_NAME_LEN.saturating_sub(ext.map_or(0, |e| e.len() + 1));
+    let mut end = stem.len().min(room);
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    let stem = stem[..end].trim_end_matches(trim);
+    match ext {
+        Some(ext) => format!("{stem}.{ext}"),
+        None => stem.to_string(),
+    }
+}
+
+/// A file to move, and where to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// What organizing the library would do.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// Tracks to move or rename
+    pub tracks: Vec<Move>,
+    /// Covers, CUE sheets and logs following their album to its new folder,
+    /// and lyrics files following their track
+    pub companions: Vec<Move>,
+    /// Tracks already where the template puts them
+    pub unchanged: usize,
+}
+
+impl Plan {
+    /// Every move, tracks first.
+    pub fn moves(&self) -> impl Iterator<Item = &Move> {
+        self.tracks.iter().chain(&self.companions)
+    }
+}
+
+/// Work out where `template` puts each of `tracks` under `root`, without
+/// touching any files. Targets already taken, on disk or by an earlier track,
+/// get a number appended. Companion files of a folder move along when all of
+/// its tracks in `library` are moving and end up in the same new folder.
+pub fn plan(
+    tracks: &[(PathBuf, &TrackMetadata)],
+    library: &[PathBuf],
+    root: &Path,
+    template: &Template,
+) -> Plan {
+    let mut plan = Plan::default();
+    let mut taken = HashSet::new();
+    // Destination folders of each source folder's tracks; `None` once a track stays
+    let mut destinations: BTreeMap<PathBuf, Option<HashSet<PathBuf>>> = BTreeMap::new();
+    let moving: HashSet<&Path> = tracks.iter().map(|(path, _)| path.as_path()).collect();
+    for path in library.iter().filter(|p| !moving.contains(p.as_path())) {
+        let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        destinations.insert(folder, None);
+    }
+
+    for (from, md) in tracks {
+        let target = root.join(template.render(md, from));
+        let to = free_path(&target, &taken, Some(from));
+        let folder = from.parent().map(Path::to_path_buf).unwrap_or_default();
+        let entry = destinations
+            .entry(folder)
+            .or_insert_with(|| Some(HashSet::new()));
+        if &to == from {
+            plan.unchanged += 1;
+            taken.insert(to);
+            *entry = None;
+            continue;
+        }
+        if let (Some(dirs), Some(dir)) = (entry.as_mut(), to.parent()) {
+            dirs.insert(dir.to_path_buf());
+        }
+        taken.insert(to.clone());
+        for sidecar in sidecar_files(from) {
+            let ext = sidecar.extension().unwrap_or_default();
+            let target = free_path(&to.with_extension(ext), &taken, None);
+            taken.insert(target.clone());
+            plan.companions.push(Move {
+                from: sidecar,
+                to: target,
+            });
+        }
+        plan.tracks.push(Move {
+            from: from.clone(),
+            to,
+        });
+    }
+
+    for (folder, dirs) in destinations {
+        let Some(dest) = dirs
+            .filter(|d| d.len() == 1)
+            .and_then(|d| d.into_iter().next())
+        else {
+            continue;
+        };
+        for from in companion_files(&folder) {
+            let Some(name) = from.file_name() else {
+                continue;
+            };
+            let to = free_path(&dest.join(name), &taken, None);
+            taken.insert(to.clone());
+            plan.companions.push(Move { from, to });
+        }
+    }
+    plan
+}
+
+/// Files in `folder` that belong with its tracks, in name order.
+fn companion_files(folder: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                COMPANION_EXTENSIONS
+                    .iter()
+                    .any(|x| e.eq_ignore_ascii_case(x))
+            })
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Lyrics files named after the track at `path`.
+fn sidecar_files(path: &Path) -> Vec<PathBuf> {
+    SIDECAR_EXTENSIONS
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// `target`, or the first of `name (2).ext`, `name (3).ext`, ... that is
+/// neither on disk nor in `taken`. The file being moved, `source`, doesn't
+/// count as taking its own path, so organizing twice changes nothing.
+fn free_path(target: &Path, taken: &HashSet<PathBuf>, source: Option<&Path>) -> PathBuf {
+    let is_free = |p: &Path| source == Some(p) || (!taken.contains(p) && !p.exists());
+    if is_free(target) {
+        return target.to_path_buf();
+    }
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = target
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| target.with_file_name(format!("{stem} ({n}){ext}")))
+        .find(|p| is_free(p))
+        .unwrap()
+}
+
+/// Carry out `plan`, never overwriting a file. CUE sheets that moved are
+/// pointed at the new names of their folder's tracks. Folders left empty are
+/// removed, up to `root`. Returns the moves that were made, and why the others
+/// failed.
+pub fn execute(plan: &Plan, root: &Path) -> (Vec<Move>, Vec<String>) {
+    let mut done = Vec::new();
+    let mut failed = Vec::new();
+    for mv in plan.moves() {
+        match move_file(&mv.from, &mv.to) {
+            Ok(()) => done.push(mv.clone()),
+            Err(err) => failed.push(format!(
+                "failed to move {:?} to {:?}: {err}",
+                mv.from, mv.to
+            )),
+        }
+    }
+
+    let is_cue = |path: &Path| path.extension().is_some_and(|e| e.eq_ignore_ascii_case("cue"));
+    for cue in done.iter().filter(|m| is_cue(&m.from)) {
+        let renamed: HashMap<String, String> = done
+            .iter()
+            .filter(|m| m.from.parent() == cue.from.parent())
+            .filter_map(|m| Some((file_name(&m.from)?, file_name(&m.to)?)))
+            .filter(|(from, to)| from != to)
+            .collect();
+        if renamed.is_empty() {
+            continue;
+        }
+        if let Err(err) = rewrite_cue(&cue.to, &renamed) {
+            failed.push(format!("failed to update {:?}: {err}", cue.to));
+        }
+    }
+
+    let folders: HashSet<&Path> = done.iter().filter_map(|m| m.from.parent()).collect();
+    for folder in folders {
+        remove_empty_folders(folder, root);
+    }
+    (done, failed)
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name()?.to_str().map(str::to_string)
+}
+
+/// Point the `FILE` lines of the CUE sheet at `path` to the new names of the
+/// files in `renamed`, which is keyed by their old names.
+fn rewrite_cue(path: &Path, renamed: &HashMap<String, String>) -> Result<()> {
+    let text = fs::read_to_string(path)?;
+    let mut changed = false;
+    let lines: Vec<String> = text
+        .split_inclusive('\n')
+        .map(|line| match renamed_cue_line(line, renamed) {
+            Some(line) => {
+                changed = true;
+                line
+            }
+            None => line.to_string(),
+        })
+        .collect();
+    if changed {
+        fs::write(path, lines.concat())?;
+    }
+    Ok(())
+}
+
+/// `line` naming the new file, if it is a `FILE` line for one in `renamed`.
+fn renamed_cue_line(line: &str, renamed: &HashMap<String, String>) -> Option<String> {
+    let trimmed = line.trim_start();
+    let (command, rest) = trimmed.split_once(char::is_whitespace)?;
+    if !command.eq_ignore_ascii_case("FILE") {
+        return None;
+    }
+    let rest = rest.trim_start();
+    // The name is quoted, or runs up to the file type
+    let (name, after) = match rest.strip_prefix('"') {
+        Some(quoted) => {
+            let end = quoted.find('"')?;
+            (&quoted[..end], &quoted[end + 1..])
+        }
+        None => {
+            let name = rest.split_whitespace().next()?;
+            (name, &rest[name.len()..])
+        }
+    };
+    let new = renamed.get(name)?;
+    let indent = &line[..line.len() - trimmed.len()];
+    Some(format!("{indent}{command} \"{new}\"{after}"))
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        return Err(anyhow!("the target already exists"));
+    }
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Renaming fails across file systems; copy the file over instead
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        if let Err(err) = fs::remove_file(from) {
+            let _ = fs::remove_file(to);
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
+
+/// Remove `folder` and its parents while they are empty, stopping at `root`.
+fn remove_empty_folders(folder: &Path, root: &Path) {
+    let mut folder = Some(folder);
+    while let Some(dir) = folder.filter(|d| d.starts_with(root) && *d != root) {
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        folder = dir.parent();
+    }
+}
+
+/// Where each moved track went, for updating the library, playlists and history.
+pub fn moved_paths(moves: &[Move]) -> HashMap<PathBuf, PathBuf> {
+    moves
+        .iter()
+        .map(|m| (m.from.clone(), m.to.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track() -> TrackMetadata {
+        TrackMetadata {
+            title: Some("Intro".into()),
+            artist: Some("Guest".into()),
+            album_artist: Some("Band".into()),
+            album: Some("First: Live".into()),
+            year: Some(1999),
+            track: Some(3),
+            disc: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("organize-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn renders_templates() {
+        let template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        let path = template.render(&track(), Path::new("/in/x.FLAC"));
+        assert_eq!(path, Path::new("Band/1999 - First_ Live/1-03 Intro.FLAC"));
+
+        // Missing values leave no stray separators behind
+        let md = TrackMetadata {
+            title: Some("AC/DC  tribute".into()),
+            ..Default::default()
+        };
+        let path = template.render(&md, Path::new("/in/x.mp3"));
+        assert_eq!(path, Path::new("Unknown/Unknown/AC_DC tribute.mp3"));
+
+        let template = Template::parse("{artist}/{title}").unwrap();
+        let path = template.render(&track(), Path::new("/in/x.ogg"));
+        assert_eq!(path, Path::new("Guest/Intro.ogg"));
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert!(Template::parse("{artist").is_err());
+        assert!(Template::parse("{composer}/{title}").is_err());
+        assert!(Template::parse("{track:xx}").is_err());
+        assert!(Template::parse("  ").is_err());
+    }
+
+    #[test]
+    fn shortens_long_names_keeping_the_extension() {
+        let name = clean_component(&format!("{}.flac", "é".repeat(200)), true);
+        assert!(name.len() <= MAX_NAME_LEN);
+        assert!(name.ends_with("é.flac"));
+
+        // An extension longer than a name may be leaves no room for the stem
+        let ext = "x".repeat(MAX_NAME_LEN);
+        assert_eq!(clean_component(&format!("song.{ext}"), true), format!(".{ext}"));
+    }
+
+    #[test]
+    fn plans_moves_with_companions_and_collisions() {
+        let root = temp_dir("plan");
+        let source = root.join("incoming");
+        fs::create_dir_all(&source).unwrap();
+        for name in ["a.flac", "b.flac", "cover.jpg", "rip.log", "notes.md"] {
+            fs::write(source.join(name), name).unwrap();
+        }
+        // Already taken on disk
+        let dest = root.join("Band/1999 - First_ Live");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("1-03 Intro.flac"), "old").unwrap();
+
+        let md = track();
+        let tracks = vec![(source.join("a.flac"), &md), (source.join("b.flac"), &md)];
+        let template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        let library = [source.join("a.flac"), source.join("b.flac")];
+        let plan = plan(&tracks, &library, &root, &template);
+
+        let targets: Vec<&Path> = plan.tracks.iter().map(|m| m.to.as_path()).collect();
+        assert_eq!(
+            targets,
+            [
+                dest.join("1-03 Intro (2).flac"),
+                dest.join("1-03 Intro (3).flac")
+            ]
+        );
+        let companions: Vec<&Path> = plan.companions.iter().map(|m| m.to.as_path()).collect();
+        assert_eq!(companions, [dest.join("cover.jpg"), dest.join("rip.log")]);
+        assert_eq!(plan.unchanged, 0);
+
+        let (done, failed) = execute(&plan, &root);
+        assert!(failed.is_empty(), "{failed:?}");
+        assert_eq!(done.len(), 4);
+        assert_eq!(
+            fs::read_to_string(dest.join("1-03 Intro (3).flac")).unwrap(),
+            "b.flac"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("1-03 Intro.flac")).unwrap(),
+            "old"
+        );
+        // The folder still holds a file that isn't a companion
+        assert!(source.join("notes.md").exists());
+
+        // Planning again leaves the organized tracks where they are
+        let moved = moved_paths(&done);
+        let tracks: Vec<(PathBuf, &TrackMetadata)> = tracks
+            .iter()
+            .map(|(p, md)| (moved[p].clone(), *md))
+            .collect();
+        let library: Vec<PathBuf> = tracks.iter().map(|(p, _)| p.clone()).collect();
+        let again = super::plan(&tracks, &library, &root, &template);
+        assert!(again.tracks.is_empty());
+        assert_eq!(again.unchanged, 2);
+    }
+
+    #[test]
+    fn removes_emptied_folders_up_to_the_root() {
+        let root = temp_dir("cleanup");
+        let source = root.join("a/b");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("x.mp3"), "x").unwrap();
+
+        let md = track();
+        let template = Template::parse("{title}").unwrap();
+        let library = [source.join("x.mp3")];
+        let plan = plan(&[(library[0].clone(), &md)], &library, &root, &template);
+        let (done, failed) = execute(&plan, &root);
+        assert!(failed.is_empty());
+        assert_eq!(done[0].to, root.join("Intro.mp3"));
+        assert!(!root.join("a").exists());
+        assert!(root.exists());
+    }
+
+    #[test]
+    fn leaves_companions_of_folders_with_tracks_staying() {
+        let root = temp_dir("staying");
+        let source = root.join("incoming");
+        fs::create_dir_all(&source).unwrap();
+        for name in ["a.flac", "b.flac", "cover.jpg"] {
+            fs::write(source.join(name), name).unwrap();
+        }
+
+        let md = track();
+        let template = Template::parse("{title}").unwrap();
+        let library = [source.join("a.flac"), source.join("b.flac")];
+        let plan = plan(&[(library[0].clone(), &md)], &library, &root, &template);
+        assert_eq!(plan.tracks.len(), 1);
+        assert!(plan.companions.is_empty());
+    }
+
+    #[test]
+    fn renames_lyrics_and_cue_sheets_with_their_tracks() {
+        let root = temp_dir("sidecars");
+        let source = root.join("incoming");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("rip.flac"), "audio").unwrap();
+        fs::write(source.join("rip.lrc"), "[00:01.00]Hello").unwrap();
+        fs::write(
+            source.join("rip.cue"),
+            concat!(
+                "TITLE \"Live\"\r\nFILE rip.flac WAVE\r\n",
+                "  TRACK 01 AUDIO\r\n    FILE \"other.flac\" WAVE\r\n",
+            ),
+        )
+        .unwrap();
+
+        let md = track();
+        let template = Template::parse("{album}/{title}").unwrap();
+        let library = [source.join("rip.flac")];
+        let plan = plan(&[(library[0].clone(), &md)], &library, &root, &template);
+        let dest = root.join("First_ Live");
+        let companions: Vec<&Path> = plan.companions.iter().map(|m| m.to.as_path()).collect();
+        assert_eq!(companions, [dest.join("Intro.lrc"), dest.join("rip.cue")]);
+
+        let (done, failed) = execute(&plan, &root);
+        assert!(failed.is_empty(), "{failed:?}");
+        assert_eq!(done.len(), 3);
+        assert_eq!(
+            fs::read_to_string(dest.join("Intro.lrc")).unwrap(),
+            "[00:01.00]Hello"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("rip.cue")).unwrap(),
+            concat!(
+                "TITLE \"Live\"\r\nFILE \"Intro.flac\" WAVE\r\n",
+                "  TRACK 01 AUDIO\r\n    FILE \"other.flac\" WAVE\r\n",
+            ),
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Parametric equalizer profiles: any number of peaking, shelving, low-pass
+//! and high-pass filters with a preamp, as AutoEQ publishes them for
+//! headphones in `ParametricEQ.txt` files and Equalizer APO reads them from its
+//! configuration files.
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Q of filters that don't say, that of a Butterworth filter.
+pub const DEFAULT_Q: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+/// Range of filter frequencies in Hz.
+pub const MIN_FREQUENCY: u32 = 10;
+pub const MAX_FREQUENCY: u32 = 24000;
+
+/// Range of filter gains and the preamp in tenths of a decibel.
+pub const MIN_GAIN: i32 = -300;
+pub const MAX_GAIN: i32 = 300;
+
+/// Range of Q in hundredths.
+const MIN_Q: u32 = 1;
+const MAX_Q: u32 = 10000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterKind {
+    #[default]
+    Peak,
+    LowShelf,
+    HighShelf,
+    LowPass,
+    HighPass,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 5] = [
+        FilterKind::Peak,
+        FilterKind::LowShelf,
+        FilterKind::HighShelf,
+        FilterKind::LowPass,
+        FilterKind::HighPass,
+    ];
+
+    /// Whether filters of the kind boost or cut by a gain, rather than only
+    /// letting frequencies through.
+    pub fn has_gain(self) -> bool {
+        !matches!(self, FilterKind::LowPass | FilterKind::HighPass)
+    }
+
+    /// The kind of an Equalizer APO filter type, such as `PK` or `LSC`.
+    fn from_apo(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "PK" | "PEQ" | "MODAL" => FilterKind::Peak,
+            "LS" | "LSC" => FilterKind::LowShelf,
+            "HS" | "HSC" => FilterKind::HighShelf,
+            "LP" | "LPQ" => FilterKind::LowPass,
+            "HP" | "HPQ" => FilterKind::HighPass,
+            _ => return None,
+        })
+    }
+}
+
+/// One filter of a profile. Values are kept in whole units small enough to
+/// be exact in the configuration: hertz, tenths of a decibel and hundredths
+/// of Q.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Filter {
+    pub enabled: bool,
+    pub kind: FilterKind,
+    /// Centre frequency, or corner frequency of shelves and passes
+    pub frequency: u32,
+    /// Ignored by low-pass and high-pass filters
+    pub gain: i32,
+    pub q: u32,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(FilterKind::Peak, 1000.0, 0.0, DEFAULT_Q)
+    }
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, frequency: f64, gain: f64, q: f64) -> Self {
+        let mut filter = Self {
+            enabled: true,
+            kind,
+            frequency: 0,
+            gain: 0,
+            q: 0,
+        };
+        filter.set_frequency(frequency);
+        filter.set_gain(gain);
+        filter.set_q(q);
+        filter
+    }
+
+    pub fn set_frequency(&mut self, hz: f64) {
+        self.frequency = (hz.round() as u32).clamp(MIN_FREQUENCY, MAX_FREQUENCY);
+    }
+
+    pub fn set_gain(&mut self, db: f64) {
+        self.gain = tenths(db);
+    }
+
+    pub fn set_q(&mut self, q: f64) {
+        self.q = ((q * 100.0).round() as u32).clamp(MIN_Q, MAX_Q);
+    }
+
+    pub fn gain_db(&self) -> f64 {
+        f64::from(self.gain) / 10.0
+    }
+
+    pub fn q_factor(&self) -> f64 {
+        f64::from(self.q.max(MIN_Q)) / 100.0
+    }
+
+    /// Width of the band in Hz, for filters that take it rather than Q.
+    pub fn bandwidth_hz(&self) -> f64 {
+        f64::from(self.frequency) / self.q_factor()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParametricProfile {
+    pub name: String,
+    /// In tenths of a decibel
+    pub preamp: i32,
+    pub filters: Vec<Filter>,
+}
+
+impl ParametricProfile {
+    pub fn preamp_db(&self) -> f64 {
+        f64::from(self.preamp) / 10.0
+    }
+
+    pub fn set_preamp(&mut self, db: f64) {
+        self.preamp = tenths(db);
+    }
+}
+
+/// A profile read from a file, with the lines that couldn't be used.
+#[derive(Clone, Debug)]
+pub struct Import {
+    pub profile: ParametricProfile,
+    pub skipped: Vec<String>,
+}
+
+fn tenths(db: f64) -> i32 {
+    ((db * 10.0).round() as i32).clamp(MIN_GAIN, MAX_GAIN)
+}
+
+/// Name for a profile imported from `path`: the file's name without the
+/// suffix AutoEQ gives its files.
+pub fn profile_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = stem.trim_end_matches("ParametricEQ").trim_end();
+    if name.is_empty() {
+        stem
+    } else {
+        name.to_string()
+    }
+}
+
+/// Read an AutoEQ `ParametricEQ.txt` or Equalizer APO configuration file:
+///
+/// ```text
+/// Preamp: -6.2 dB
+/// Filter 1: ON LSC Fc 105 Hz Gain 5.5 dB Q 0.70
+/// Filter 2: ON PK Fc 2000 Hz Gain -3 dB BW Oct 1.0
+/// ```
+///
+/// Preamp lines add up, as in Equalizer APO. Filters that are `OFF` are kept
+/// but disabled. Other commands, such as `Include` or `GraphicEQ`, and filter
+/// types without a counterpart here are skipped.
+pub fn parse_apo(name: &str, text: &str) -> Result<Import> {
+    let mut profile = ParametricProfile {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    let mut preamp = 0.0;
+    let mut found = false;
+    let mut skipped = Vec::new();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((command, rest)) = line.split_once(':') else {
+            skipped.push(line.to_string());
+            continue;
+        };
+        let command = command.trim().to_ascii_lowercase();
+        let parsed = if command == "preamp" {
+            number_before(rest, "db").map(|db| preamp += db)
+        } else if command == "filter" || command.starts_with("filter ") {
+            parse_filter(rest).map(|filter| profile.filters.push(filter))
+        } else {
+            None
+        };
+        match parsed {
+            Some(()) => found = true,
+            None => skipped.push(line.to_string()),
+        }
+    }
+    if !found {
+        bail!("no equalizer filters found");
+    }
+    profile.set_preamp(preamp);
+    Ok(Import { profile, skipped })
+}
+
+/// The number in `text`, such as `-6.2 dB`, optionally followed by `unit`.
+fn number_before(text: &str, unit: &str) -> Option<f64> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+    let number = lower.strip_suffix(unit).unwrap_or(&lower);
+    number.trim().parse().ok()
+}
+
+/// A filter from what follows `Filter n:`, such as
+/// `ON PK Fc 105 Hz Gain 5.5 dB Q 0.70`.
+fn parse_filter(spec: &str) -> Option<Filter> {
+    let tokens: Vec<&str> = spec.split_whitespace().collect();
+    let enabled = match tokens.first()?.to_ascii_uppercase().as_str() {
+        "ON" => true,
+        "OFF" => false,
+        _ => return None,
+    };
+    let kind = FilterKind::from_apo(tokens.get(1)?)?;
+    // Values follow their keys: `Fc 105 Hz`, `Gain 5.5 dB`, `Q 0.7`, `BW Oct 1`
+    let value = |key: &str| {
+        let at = tokens.iter().position(|t| t.eq_ignore_ascii_case(key))?;
+        let next = tokens.get(at + 1)?;
+        let number = if next.eq_ignore_ascii_case("oct") {
+            tokens.get(at + 2)?
+        } else {
+            next
+        };
+        number.parse::<f64>().ok()
+    };
+    let frequency = value("Fc")?;
+    let gain = if kind.has_gain() { value("Gain")? } else { 0.0 };
+    let q = match (value("Q"), value("BW")) {
+        (Some(q), _) => q,
+        (None, Some(octaves)) => {
+            let ratio = 2f64.powf(octaves);
+            ratio.sqrt() / (ratio - 1.0)
+        }
+        (None, None) => DEFAULT_Q,
+    };
+    let mut filter = Filter::new(kind, frequency, gain, q);
+    filter.enabled = enabled;
+    Some(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_autoeq_parametric_eq() {
+        let text = "Preamp: -6.4 dB\n\
+                    Filter 1: ON LSC Fc 105 Hz Gain 6.5 dB Q 0.70\n\
+                    Filter 2: ON PK Fc 2263 Hz Gain -3.2 dB Q 1.84\n\
+                    Filter 3: ON HSC Fc 10000 Hz Gain -1.1 dB Q 0.70\n";
+        let import = parse_apo("HD 600", text).unwrap();
+        let profile = import.profile;
+        assert!(import.skipped.is_empty());
+        assert_eq!(profile.name, "HD 600");
+        assert_eq!(profile.preamp, -64);
+        assert_eq!(profile.filters.len(), 3);
+        assert_eq!(
+            profile.filters[0],
+            Filter::new(FilterKind::LowShelf, 105.0, 6.5, 0.7)
+        );
+        assert_eq!(profile.filters[1].gain_db(), -3.2);
+        assert_eq!(profile.filters[1].q, 184);
+        assert_eq!(profile.filters[2].kind, FilterKind::HighShelf);
+    }
+
+    #[test]
+    fn reads_equalizer_apo_config() {
+        let text = "# Speakers\n\
+                    Device: Speakers\n\
+                    Preamp: -3 dB\n\
+                    Preamp: -1.5 dB\n\
+                    Filter: ON PK Fc 50 Hz Gain 4 dB BW Oct 1.0\n\
+                    Filter: OFF HP Fc 30 Hz\n\
+                    Filter: ON LPQ Fc 18000 Hz Q 0.5\n\
+                    Filter: ON BP Fc 1000 Hz\n\
+                    GraphicEQ: 20 0; 40 1\n";
+        let import = parse_apo("Speakers", text).unwrap();
+        let profile = import.profile;
+        assert_eq!(profile.preamp, -45);
+        assert_eq!(profile.filters.len(), 3);
+        // One octave is a Q of about 1.41
+        assert_eq!(profile.filters[0].q, 141);
+        assert!(!profile.filters[1].enabled);
+        assert_eq!(profile.filters[1].kind, FilterKind::HighPass);
+        assert_eq!(profile.filters[1].q_factor(), 0.71);
+        assert_eq!(profile.filters[2].kind, FilterKind::LowPass);
+        assert_eq!(profile.filters[2].gain, 0);
+        assert_eq!(import.skipped.len(), 3);
+    }
+
+    #[test]
+    fn rejects_files_without_filters() {
+        assert!(parse_apo("empty", "# nothing\nGraphicEQ: 20 0").is_err());
+    }
+
+    #[test]
+    fn names_profiles_after_files() {
+        let name = |p: &str| profile_name(Path::new(p));
+        assert_eq!(
+            name("/eq/Sennheiser HD 600 ParametricEQ.txt"),
+            "Sennheiser HD 600"
+        );
+        assert_eq!(name("config.txt"), "config");
+        assert_eq!(name("ParametricEQ.txt"), "ParametricEQ");
+    }
+}
@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Plain M3U and extended M3U/M3U8 playlists.
+
+use std::path::Path;
+use std::time::Duration;
+
+use super::{relative_location, PlaylistEntry};
+
+/// Parse the text of an M3U playlist.
+///
+/// `#EXTINF:<seconds>,<artist> - <title>` lines annotate the next location; any
+/// other line starting with `#` is treated as a comment.
+pub fn parse(text: &str) -> Vec<PlaylistEntry> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut entries = Vec::new();
+    let mut pending: Option<PlaylistEntry> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            pending = Some(parse_extinf(info));
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut entry = pending.take().unwrap_or_default();
+        entry.location = line.to_string();
+        entries.push(entry);
+    }
+
+    entries
+}
+
+/// Parse the part of an `#EXTINF` line after the colon.
+fn parse_extinf(info: &str) -> PlaylistEntry {
+    let mut entry = PlaylistEntry::default();
+
+    // Attributes like `group-title="a,b"` may precede the separating comma, so
+    // skip commas inside quotes and take only the first token as the duration.
+    let mut in_quotes = false;
+    let split = info.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ',' if !in_quotes => Some(i),
+        _ => None,
+    });
+    let (head, display) = match split {
+        Some(i) => (&info[..i], info[i + 1..].trim()),
+        None => (info, ""),
+    };
+
+    entry.duration = head
+        .split_whitespace()
+        .next()
+        .and_then(|d| d.parse::<f64>().ok())
+        .filter(|d| *d >= 0.0 && d.is_finite())
+        .map(Duration::from_secs_f64);
+
+    if !display.is_empty() {
+        match display.split_once(" - ") {
+            Some((artist, title)) => {
+                entry.artist = Some(artist.trim().to_string());
+                entry.title = Some(title.trim().to_string());
+            }
+            None => entry.title = Some(display.to_string()),
+        }
+    }
+
+    entry
+}
+
+/// Serialize entries as an extended M3U playlist stored in `base_dir`.
+///
+/// Entry locations that are local paths below `base_dir` are written relative
+/// to it; everything else is written as-is.
+pub fn write(entries: &[PlaylistEntry], base_dir: &Path) -> String {
+    let mut out = String::from("#EXTM3U\n");
+
+    for entry in entries {
+        if entry.title.is_some() || entry.artist.is_some() || entry.duration.is_some() {
+            let secs = entry.duration.map(|d| d.as_secs() as i64).unwrap_or(-1);
+            let display = match (&entry.artist, &entry.title) {
+                (Some(artist), Some(title)) => format!("{artist} - {title}"),
+                (None, Some(title)) => title.clone(),
+                (Some(artist), None) => artist.clone(),
+                (None, None) => String::new(),
+            };
+            out.push_str(&format!("#EXTINF:{secs},{display}\n"));
+        }
+
+        let location = Path::new(&entry.location);
+        if location.is_absolute() {
+            out.push_str(&relative_location(location, base_dir));
+        } else {
+            out.push_str(&entry.location);
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playlist::{decode_text, resolve, resolve_location};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parses_extended_entries() {
+        let text = "#EXTM3U\n\
+                    #EXTINF:123,Daft Punk - One More Time\n\
+                    music/one.flac\n\
+                    #EXTINF:-1,Untitled Stream\n\
+                    http://radio.example/stream\n";
+        let entries = parse(text);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].location, "music/one.flac");
+        assert_eq!(entries[0].artist.as_deref(), Some("Daft Punk"));
+        assert_eq!(entries[0].title.as_deref(), Some("One More Time"));
+        assert_eq!(entries[0].duration, Some(Duration::from_secs(123)));
+        assert_eq!(entries[1].duration, None);
+        assert_eq!(entries[1].artist, None);
+        assert_eq!(entries[1].title.as_deref(), Some("Untitled Stream"));
+    }
+
+    #[test]
+    fn skips_comments_blank_lines_and_bom() {
+        let bytes = b"\xEF\xBB\xBF#EXTM3U\r\n# a comment\r\n\r\n#EXTALB:Album\r\na.mp3\r\n  b.mp3  \r\n";
+        let entries = parse(&decode_text(bytes));
+
+        let locations: Vec<_> = entries.iter().map(|e| e.location.as_str()).collect();
+        assert_eq!(locations, ["a.mp3", "b.mp3"]);
+        assert!(entries.iter().all(|e| e.title.is_none()));
+    }
+
+    #[test]
+    fn extinf_applies_only_to_next_location() {
+        let entries = parse("#EXTINF:10,First\none.mp3\ntwo.mp3\n");
+        assert_eq!(entries[0].title.as_deref(), Some("First"));
+        assert_eq!(entries[1], PlaylistEntry::new("two.mp3"));
+    }
+
+    #[test]
+    fn extinf_with_attributes_and_fractional_duration() {
+        let entries = parse("#EXTINF:61.5 tvg-id=\"x\" group-title=\"a,b\",Title\nx.ogg\n");
+        assert_eq!(entries[0].duration, Some(Duration::from_millis(61_500)));
+        assert_eq!(entries[0].title.as_deref(), Some("Title"));
+    }
+
+    #[test]
+    fn decodes_latin1_fallback() {
+        let entries = parse(&decode_text(b"Bj\xF6rk/J\xF3ga.mp3\n"));
+        assert_eq!(entries[0].location, "Björk/Jóga.mp3");
+    }
+
+    #[test]
+    fn keeps_utf8_locations() {
+        let entries = parse("#EXTINF:200,坂本龍一 - Merry Christmas Mr. Lawrence\n坂本龍一/01.flac\n");
+        assert_eq!(entries[0].location, "坂本龍一/01.flac");
+        assert_eq!(entries[0].artist.as_deref(), Some("坂本龍一"));
+    }
+
+    #[test]
+    fn resolves_relative_absolute_and_file_uris() {
+        let base = Path::new("/music/playlists");
+
+        assert_eq!(
+            resolve_location("../Album/01.flac", base),
+            Some(PathBuf::from("/music/Album/01.flac"))
+        );
+        assert_eq!(
+            resolve_location("./02.flac", base),
+            Some(PathBuf::from("/music/playlists/02.flac"))
+        );
+        assert_eq!(
+            resolve_location("/srv/a.mp3", base),
+            Some(PathBuf::from("/srv/a.mp3"))
+        );
+        assert_eq!(
+            resolve_location("file:///srv/My%20Music/a%23b.mp3", base),
+            Some(PathBuf::from("/srv/My Music/a#b.mp3"))
+        );
+    }
+
+    #[test]
+    fn resolves_windows_style_paths() {
+        let base = Path::new("/music");
+
+        assert_eq!(
+            resolve_location("Artist\\Album\\01 Song.mp3", base),
+            Some(PathBuf::from("/music/Artist/Album/01 Song.mp3"))
+        );
+        assert_eq!(resolve_location("C:\\Music\\01.mp3", base), None);
+        assert_eq!(resolve_location("d:/Music/01.mp3", base), None);
+        assert_eq!(resolve_location("\\\\nas\\share\\01.mp3", base), None);
+    }
+
+    #[test]
+    fn remote_uris_are_unresolved() {
+        let base = Path::new("/music");
+        assert_eq!(resolve_location("http://radio.example/stream", base), None);
+        assert_eq!(resolve_location("https://x.example/a.mp3", base), None);
+        assert_eq!(resolve_location("", base), None);
+    }
+
+    #[test]
+    fn reports_missing_files_as_unresolved() {
+        let dir = std::env::temp_dir().join(format!("m3u-resolve-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/present.mp3"), b"").unwrap();
+
+        let entries = parse("sub/present.mp3\nsub/missing.mp3\nhttp://radio.example/\n");
+        let resolved = resolve(entries, &dir, &HashMap::new());
+
+        assert_eq!(
+            resolved.tracks,
+            [dir.join("sub/present.mp3"), PathBuf::from("http://radio.example/")]
+        );
+        let unresolved: Vec<_> = resolved.unresolved.iter().map(|e| e.location.as_str()).collect();
+        assert_eq!(unresolved, ["sub/missing.mp3"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_relative_paths_below_playlist_dir() {
+        let base = Path::new("/music/lists");
+        let entries = vec![
+            PlaylistEntry {
+                location: "/music/lists/a/01.flac".into(),
+                title: Some("One".into()),
+                artist: Some("Artist".into()),
+                duration: Some(Duration::from_secs(90)),
+                ..Default::default()
+            },
+            PlaylistEntry::new("/elsewhere/02.mp3"),
+            PlaylistEntry {
+                title: Some("Only Title".into()),
+                ..PlaylistEntry::new("http://radio.example/")
+            },
+        ];
+
+        assert_eq!(
+            write(&entries, base),
+            "#EXTM3U\n\
+             #EXTINF:90,Artist - One\n\
+             a/01.flac\n\
+             /elsewhere/02.mp3\n\
+             #EXTINF:-1,Only Title\n\
+             http://radio.example/\n"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_write_and_parse() {
+        let base = Path::new("/music");
+        let entries = vec![PlaylistEntry {
+            location: "/music/x/Ünïcödé - song.opus".into(),
+            title: Some("Ünïcödé".into()),
+            artist: Some("Ärtist".into()),
+            duration: Some(Duration::from_secs(1)),
+            ..Default::default()
+        }];
+
+        let parsed = parse(&write(&entries, base));
+        assert_eq!(parsed[0].location, "x/Ünïcödé - song.opus");
+        assert_eq!(parsed[0].title, entries[0].title);
+        assert_eq!(parsed[0].artist, entries[0].artist);
+        assert_eq!(parsed[0].duration, entries[0].duration);
+        assert_eq!(
+            resolve_location(&parsed[0].location, base).as_deref(),
+            Some(Path::new("/music/x/Ünïcödé - song.opus"))
+        );
+    }
+}
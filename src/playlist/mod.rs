@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Playlist file formats and resolution of their entries to local tracks.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+use crate::audio::backend::TrackMetadata;
+
+pub mod m3u;
+pub mod pls;
+pub mod smart;
+pub mod store;
+pub mod xspf;
+
+/// How far apart two durations may be for tracks to count as the same recording.
+const DURATION_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// A single entry as written in a playlist file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    /// Path or URI exactly as it appears in the file.
+    pub location: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub duration: Option<Duration>,
+    /// Cover art location (XSPF `image`).
+    pub image: Option<String>,
+}
+
+impl PlaylistEntry {
+    pub fn new(location: impl Into<String>) -> Self {
+        Self {
+            location: location.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The outcome of loading a playlist from disk.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedPlaylist {
+    /// Entries that point to existing local files or remote streams, in playlist
+    /// order. Streams are kept as their URI, which the backend plays directly.
+    pub tracks: Vec<PathBuf>,
+    /// Entries that could not be mapped to an existing file.
+    pub unresolved: Vec<PlaylistEntry>,
+}
+
+/// Playlist formats understood by [`load`] and [`save`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u,
+    M3u8,
+    Pls,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    /// Detect the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "m3u" => Some(Self::M3u),
+            "m3u8" => Some(Self::M3u8),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            _ => None,
+        }
+    }
+}
+
+/// Read a playlist file and resolve its entries against the file system, falling
+/// back to a title/artist lookup in `library` for entries without a usable location.
+pub fn load(path: &Path, library: &HashMap<PathBuf, TrackMetadata>) -> Result<ResolvedPlaylist> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| anyhow!("unsupported playlist format: {:?}", path))?;
+    let bytes = fs::read(path).map_err(|e| anyhow!("failed to read {:?}: {e}", path))?;
+
+    let entries = match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => m3u::parse(&decode_text(&bytes)),
+        PlaylistFormat::Pls => pls::parse(&decode_text(&bytes)),
+        PlaylistFormat::Xspf => xspf::parse(&decode_text(&bytes))?,
+    };
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    Ok(resolve(entries, base_dir, library))
+}
+
+/// Write `entries` to `path` in the format implied by its extension.
+pub fn save(path: &Path, entries: &[PlaylistEntry]) -> Result<()> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| anyhow!("unsupported playlist format: {:?}", path))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let contents = match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => m3u::write(entries, base_dir),
+        PlaylistFormat::Pls => pls::write(entries, base_dir),
+        PlaylistFormat::Xspf => xspf::write(entries),
+    };
+
+    fs::write(path, contents).map_err(|e| anyhow!("failed to write {:?}: {e}", path))
+}
+
+/// Split entries into playable tracks and unresolved leftovers.
+///
+/// Local entries must exist on disk; remote URIs are passed through as streams.
+/// Entries that cannot be located are matched against `library` by title and artist.
+pub fn resolve(
+    entries: Vec<PlaylistEntry>,
+    base_dir: &Path,
+    library: &HashMap<PathBuf, TrackMetadata>,
+) -> ResolvedPlaylist {
+    let mut resolved = ResolvedPlaylist::default();
+    for entry in entries {
+        if is_remote_uri(&entry.location) {
+            resolved.tracks.push(PathBuf::from(entry.location.trim()));
+            continue;
+        }
+
+        let local = resolve_location(&entry.location, base_dir).filter(|p| p.is_file());
+        match local.or_else(|| find_in_library(&entry, library)) {
+            Some(path) => resolved.tracks.push(path),
+            None => resolved.unresolved.push(entry),
+        }
+    }
+    resolved
+}
+
+/// Find a library track whose title and artist match the entry's, ignoring case.
+/// When both sides know their duration, it must agree within a couple of seconds.
+pub fn find_in_library(
+    entry: &PlaylistEntry,
+    library: &HashMap<PathBuf, TrackMetadata>,
+) -> Option<PathBuf> {
+    let title = normalize_tag(entry.title.as_deref()?);
+    let artist = entry.artist.as_deref().map(normalize_tag);
+
+    let mut matches: Vec<&PathBuf> = library
+        .iter()
+        .filter(|(_, md)| {
+            md.title.as_deref().map(normalize_tag).as_ref() == Some(&title)
+                && (artist.is_none() || md.artist.as_deref().map(normalize_tag) == artist)
+                && entry
+                    .duration
+                    .zip(md.duration)
+                    .is_none_or(|(a, b)| a.abs_diff(b) <= DURATION_TOLERANCE)
+        })
+        .map(|(path, _)| path)
+        .collect();
+
+    // Prefer the same album when the playlist names one, then a stable order
+    matches.sort_by_key(|path| {
+        let album_matches = entry.album.as_deref().map(normalize_tag)
+            == library[*path].album.as_deref().map(normalize_tag);
+        (!album_matches, (*path).clone())
+    });
+    matches.first().map(|p| (*p).clone())
+}
+
+fn normalize_tag(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Whether the location is a URI with a non-`file` scheme, e.g. an internet radio stream.
+pub fn is_remote_uri(location: &str) -> bool {
+    let location = location.trim();
+    has_uri_scheme(location) && !location.get(..7).is_some_and(|s| s.eq_ignore_ascii_case("file://"))
+}
+
+/// Map a playlist location to a local path without touching the file system.
+///
+/// Handles `file://` URIs, absolute paths and paths relative to `base_dir`.
+/// Backslash separators from playlists written on Windows are normalized, but
+/// drive-letter and UNC paths cannot be mapped and yield `None`, as do remote URIs.
+pub fn resolve_location(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    let location = location.trim();
+    if location.is_empty() {
+        return None;
+    }
+
+    if has_uri_scheme(location) {
+        let url = Url::parse(location).ok()?;
+        return if url.scheme() == "file" {
+            url.to_file_path().ok().map(|p| normalize(&p))
+        } else {
+            None
+        };
+    }
+
+    if is_windows_absolute(location) {
+        return None;
+    }
+
+    let path = PathBuf::from(location.replace('\\', "/"));
+    if path.is_absolute() {
+        Some(normalize(&path))
+    } else {
+        Some(normalize(&base_dir.join(path)))
+    }
+}
+
+/// Express `track` the way it should appear in a playlist stored in `base_dir`:
+/// relative when the track lives below the playlist's directory, absolute otherwise.
+pub fn relative_location(track: &Path, base_dir: &Path) -> String {
+    let location = match track.strip_prefix(base_dir) {
+        Ok(rel) if !base_dir.as_os_str().is_empty() => rel,
+        _ => track,
+    };
+    location.to_string_lossy().into_owned()
+}
+
+/// Decode playlist bytes as UTF-8, dropping a BOM, with a Latin-1 fallback for
+/// legacy `.m3u` files.
+pub fn decode_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn has_uri_scheme(location: &str) -> bool {
+    match location.split_once("://") {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+fn is_windows_absolute(location: &str) -> bool {
+    let bytes = location.as_bytes();
+    let drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    drive || location.starts_with("\\\\")
+}
+
+/// Collapse `.` and `..` components without resolving symlinks.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() && !out.has_root() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
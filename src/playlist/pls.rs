@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! PLS playlists, as used by internet radio directories and older players.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{relative_location, PlaylistEntry};
+
+/// Parse the text of a PLS playlist.
+///
+/// Keys are matched case-insensitively and entries are ordered by their index,
+/// so `File2` listed before `File1` still ends up second.
+pub fn parse(text: &str) -> Vec<PlaylistEntry> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut entries: BTreeMap<u32, PlaylistEntry> = BTreeMap::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('[') || line.starts_with(';') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+
+        let (field, index) = match key.find(|c: char| c.is_ascii_digit()) {
+            Some(i) => (&key[..i], &key[i..]),
+            None => continue,
+        };
+        let Ok(index) = index.parse::<u32>() else {
+            continue;
+        };
+
+        let entry = entries.entry(index).or_default();
+        match field {
+            "file" => entry.location = value.to_string(),
+            "title" if !value.is_empty() => entry.title = Some(value.to_string()),
+            "length" => {
+                entry.duration = value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|secs| *secs >= 0)
+                    .map(|secs| Duration::from_secs(secs as u64));
+            }
+            _ => {}
+        }
+    }
+
+    entries
+        .into_values()
+        .filter(|e| !e.location.is_empty())
+        .collect()
+}
+
+/// Serialize entries as a version 2 PLS playlist stored in `base_dir`.
+pub fn write(entries: &[PlaylistEntry], base_dir: &Path) -> String {
+    let mut out = String::from("[playlist]\n");
+
+    for (i, entry) in entries.iter().enumerate() {
+        let n = i + 1;
+        let location = Path::new(&entry.location);
+        let location = if location.is_absolute() {
+            relative_location(location, base_dir)
+        } else {
+            entry.location.clone()
+        };
+        out.push_str(&format!("File{n}={location}\n"));
+
+        let title = match (&entry.artist, &entry.title) {
+            (Some(artist), Some(title)) => Some(format!("{artist} - {title}")),
+            (None, Some(title)) => Some(title.clone()),
+            _ => None,
+        };
+        if let Some(title) = title {
+            out.push_str(&format!("Title{n}={title}\n"));
+        }
+
+        let secs = entry.duration.map(|d| d.as_secs() as i64).unwrap_or(-1);
+        out.push_str(&format!("Length{n}={secs}\n"));
+    }
+
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries_in_index_order() {
+        let text = "[playlist]\n\
+                    NumberOfEntries=2\n\
+                    file2=http://radio.example:8000/stream\n\
+                    Title2=Example Radio\n\
+                    Length2=-1\n\
+                    File1=Music\\Album\\01.mp3\n\
+                    Title1=Artist - Song\n\
+                    Length1=215\n\
+                    Version=2\n";
+        let entries = parse(text);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].location, "Music\\Album\\01.mp3");
+        assert_eq!(entries[0].title.as_deref(), Some("Artist - Song"));
+        assert_eq!(entries[0].duration, Some(Duration::from_secs(215)));
+        assert_eq!(entries[1].location, "http://radio.example:8000/stream");
+        assert_eq!(entries[1].duration, None);
+    }
+
+    #[test]
+    fn ignores_entries_without_file() {
+        let entries = parse("[playlist]\nTitle1=Orphan\nFile2=a.ogg\n");
+        assert_eq!(entries, [PlaylistEntry::new("a.ogg")]);
+    }
+
+    #[test]
+    fn writes_and_parses_back() {
+        let entries = vec![
+            PlaylistEntry {
+                title: Some("Song".into()),
+                duration: Some(Duration::from_secs(10)),
+                ..PlaylistEntry::new("/music/a.flac")
+            },
+            PlaylistEntry::new("http://radio.example/"),
+        ];
+        let text = write(&entries, Path::new("/music"));
+
+        assert_eq!(
+            text,
+            "[playlist]\nFile1=a.flac\nTitle1=Song\nLength1=10\n\
+             File2=http://radio.example/\nLength2=-1\nNumberOfEntries=2\nVersion=2\n"
+        );
+        let parsed = parse(&text);
+        assert_eq!(parsed[0].location, "a.flac");
+        assert_eq!(parsed[1].location, "http://radio.example/");
+    }
+}
@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Smart playlists: tracks picked from the library by rules, re-evaluated whenever
+//! the library metadata changes.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::backend::TrackMetadata;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A track property a rule can test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Field {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Year,
+    /// Length in seconds, or `m:ss`
+    Duration,
+    PlayCount,
+    SkipCount,
+    Rating,
+    Favorite,
+    DateAdded,
+    FirstPlayed,
+    LastPlayed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Text,
+    Number,
+    /// Compared in days relative to now
+    Date,
+    /// Either set or not
+    Flag,
+}
+
+impl Field {
+    pub const ALL: [Field; 13] = [
+        Field::Title,
+        Field::Artist,
+        Field::Album,
+        Field::Genre,
+        Field::Year,
+        Field::Duration,
+        Field::PlayCount,
+        Field::SkipCount,
+        Field::Rating,
+        Field::Favorite,
+        Field::DateAdded,
+        Field::FirstPlayed,
+        Field::LastPlayed,
+    ];
+
+    fn kind(self) -> FieldKind {
+        match self {
+            Field::Title | Field::Artist | Field::Album | Field::Genre => FieldKind::Text,
+            Field::Year
+            | Field::Duration
+            | Field::PlayCount
+            | Field::SkipCount
+            | Field::Rating => FieldKind::Number,
+            Field::DateAdded | Field::FirstPlayed | Field::LastPlayed => FieldKind::Date,
+            Field::Favorite => FieldKind::Flag,
+        }
+    }
+
+    /// The operators that make sense for this field, in the order they are offered.
+    pub fn operators(self) -> &'static [Operator] {
+        match self.kind() {
+            FieldKind::Text => &[
+                Operator::Is,
+                Operator::IsNot,
+                Operator::Contains,
+                Operator::DoesNotContain,
+                Operator::IsEmpty,
+            ],
+            FieldKind::Number => &[
+                Operator::Is,
+                Operator::IsNot,
+                Operator::LessThan,
+                Operator::AtMost,
+                Operator::GreaterThan,
+                Operator::AtLeast,
+                Operator::IsEmpty,
+            ],
+            FieldKind::Date => &[Operator::InLast, Operator::NotInLast, Operator::IsEmpty],
+            FieldKind::Flag => &[Operator::IsSet, Operator::IsNotSet],
+        }
+    }
+
+    fn text(self, md: &TrackMetadata) -> Option<&str> {
+        match self {
+            Field::Title => md.title.as_deref(),
+            Field::Artist => md.artist.as_deref(),
+            Field::Album => md.album.as_deref(),
+            Field::Genre => md.genre.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn number(self, md: &TrackMetadata) -> Option<f64> {
+        match self {
+            Field::Year => md.year.map(f64::from),
+            Field::Duration => md.duration.map(|d| d.as_secs_f64()),
+            Field::PlayCount => Some(f64::from(md.play_count)),
+            Field::SkipCount => Some(f64::from(md.skip_count)),
+            Field::Rating => md.rating.map(f64::from),
+            _ => None,
+        }
+    }
+
+    fn date(self, md: &TrackMetadata) -> Option<u64> {
+        match self {
+            Field::DateAdded => md.date_added,
+            Field::FirstPlayed => md.first_played,
+            Field::LastPlayed => md.last_played,
+            _ => None,
+        }
+    }
+
+    fn flag(self, md: &TrackMetadata) -> bool {
+        match self {
+            Field::Favorite => md.favorite,
+            _ => false,
+        }
+    }
+
+    fn parse_number(self, value: &str) -> Option<f64> {
+        let value = value.trim();
+        if self == Field::Duration {
+            if let Some((minutes, seconds)) = value.split_once(':') {
+                let minutes: u32 = minutes.parse().ok()?;
+                let seconds: f64 = seconds.parse().ok()?;
+                return Some(f64::from(minutes) * 60.0 + seconds);
+            }
+        }
+        value.parse().ok()
+    }
+}
+
+/// How a rule compares a field with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operator {
+    Is,
+    IsNot,
+    Contains,
+    DoesNotContain,
+    LessThan,
+    AtMost,
+    GreaterThan,
+    AtLeast,
+    /// Within the last N days
+    InLast,
+    /// Not within the last N days, including never
+    NotInLast,
+    /// The track has no value for the field
+    IsEmpty,
+    IsSet,
+    IsNotSet,
+}
+
+impl Operator {
+    /// Whether the rule compares against a value the user enters.
+    pub fn needs_value(self) -> bool {
+        !matches!(
+            self,
+            Operator::IsEmpty | Operator::IsSet | Operator::IsNotSet
+        )
+    }
+}
+
+/// A single condition such as "year is less than 1970".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub field: Field,
+    pub operator: Operator,
+    /// The value as typed; interpreted according to the field.
+    pub value: String,
+}
+
+impl Rule {
+    pub fn new(field: Field, operator: Operator, value: impl Into<String>) -> Self {
+        Self {
+            field,
+            operator,
+            value: value.into(),
+        }
+    }
+
+    /// Whether the track satisfies the rule. Values that don't parse for the
+    /// field never match.
+    pub fn matches(&self, md: &TrackMetadata, now: u64) -> bool {
+        match self.field.kind() {
+            FieldKind::Text => {
+                let field = self.field.text(md).map(str::to_lowercase);
+                let value = self.value.trim().to_lowercase();
+                match self.operator {
+                    Operator::Is => field.is_some_and(|f| f.trim() == value),
+                    Operator::IsNot => field.is_none_or(|f| f.trim() != value),
+                    Operator::Contains => field.is_some_and(|f| f.contains(&value)),
+                    Operator::DoesNotContain => field.is_none_or(|f| !f.contains(&value)),
+                    Operator::IsEmpty => field.is_none(),
+                    _ => false,
+                }
+            }
+            FieldKind::Number => {
+                let field = self.field.number(md);
+                if self.operator == Operator::IsEmpty {
+                    return field.is_none();
+                }
+                let Some(value) = self.field.parse_number(&self.value) else {
+                    return false;
+                };
+                match (self.operator, field) {
+                    (Operator::IsNot, None) => true,
+                    (_, None) => false,
+                    (Operator::Is, Some(f)) => f == value,
+                    (Operator::IsNot, Some(f)) => f != value,
+                    (Operator::LessThan, Some(f)) => f < value,
+                    (Operator::AtMost, Some(f)) => f <= value,
+                    (Operator::GreaterThan, Some(f)) => f > value,
+                    (Operator::AtLeast, Some(f)) => f >= value,
+                    _ => false,
+                }
+            }
+            FieldKind::Date => {
+                let field = self.field.date(md);
+                if self.operator == Operator::IsEmpty {
+                    return field.is_none();
+                }
+                let Ok(days) = self.value.trim().parse::<u64>() else {
+                    return false;
+                };
+                let cutoff = now.saturating_sub(days.saturating_mul(SECONDS_PER_DAY));
+                match self.operator {
+                    Operator::InLast => field.is_some_and(|t| t >= cutoff),
+                    Operator::NotInLast => field.is_none_or(|t| t < cutoff),
+                    _ => false,
+                }
+            }
+            FieldKind::Flag => match self.operator {
+                Operator::IsSet => self.field.flag(md),
+                Operator::IsNotSet => !self.field.flag(md),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Whether a track has to satisfy every rule or just one of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Match {
+    #[default]
+    All,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitUnit {
+    Tracks,
+    Minutes,
+    Hours,
+}
+
+/// Caps the playlist at a number of tracks or a total playing time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limit {
+    pub unit: LimitUnit,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    #[default]
+    Random,
+    MostPlayed,
+    LeastPlayed,
+    RecentlyAdded,
+    RecentlyPlayed,
+    HighestRated,
+    Artist,
+    Title,
+    Year,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 9] = [
+        SortOrder::Random,
+        SortOrder::MostPlayed,
+        SortOrder::LeastPlayed,
+        SortOrder::RecentlyAdded,
+        SortOrder::RecentlyPlayed,
+        SortOrder::HighestRated,
+        SortOrder::Artist,
+        SortOrder::Title,
+        SortOrder::Year,
+    ];
+}
+
+/// A playlist whose tracks are whatever in the library matches its rules.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmartPlaylist {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub match_mode: Match,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub limit: Option<Limit>,
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// Seed for the random order, kept so the shuffle doesn't change every time
+    /// the library does.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl SmartPlaylist {
+    pub fn new(id: u32, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            match_mode: Match::All,
+            rules: Vec::new(),
+            limit: None,
+            sort: SortOrder::Random,
+            seed: random_seed(),
+        }
+    }
+
+    /// Pick a new random order.
+    pub fn reshuffle(&mut self) {
+        self.seed = random_seed();
+    }
+
+    /// Whether the track satisfies the playlist's rules. No rules match everything.
+    pub fn matches(&self, md: &TrackMetadata, now: u64) -> bool {
+        match self.match_mode {
+            Match::All => self.rules.iter().all(|r| r.matches(md, now)),
+            Match::Any => self.rules.is_empty() || self.rules.iter().any(|r| r.matches(md, now)),
+        }
+    }
+
+    /// The library tracks in the playlist, sorted and limited. `now` is in seconds
+    /// since the Unix epoch.
+    pub fn evaluate(&self, library: &HashMap<PathBuf, TrackMetadata>, now: u64) -> Vec<PathBuf> {
+        let mut tracks: Vec<(&PathBuf, &TrackMetadata)> = library
+            .iter()
+            .filter(|(_, md)| self.matches(md, now))
+            .collect();
+
+        // Sort by path first so ties come out the same on every evaluation
+        tracks.sort_by(|a, b| a.0.cmp(b.0));
+        let text = |s: &Option<String>| s.as_deref().map(str::to_lowercase);
+        match self.sort {
+            SortOrder::Random => tracks.sort_by_cached_key(|(path, _)| self.shuffle_key(path)),
+            SortOrder::MostPlayed => tracks.sort_by_key(|(_, md)| Reverse(md.play_count)),
+            SortOrder::LeastPlayed => tracks.sort_by_key(|(_, md)| md.play_count),
+            SortOrder::RecentlyAdded => tracks.sort_by_key(|(_, md)| Reverse(md.date_added)),
+            SortOrder::RecentlyPlayed => tracks.sort_by_key(|(_, md)| Reverse(md.last_played)),
+            SortOrder::HighestRated => tracks.sort_by_key(|(_, md)| Reverse(md.rating)),
+            SortOrder::Artist => {
+                tracks.sort_by_cached_key(|(_, md)| (text(&md.artist), text(&md.album)))
+            }
+            SortOrder::Title => tracks.sort_by_cached_key(|(_, md)| text(&md.title)),
+            SortOrder::Year => tracks.sort_by_key(|(_, md)| md.year),
+        }
+
+        let Some(limit) = self.limit else {
+            return tracks.into_iter().map(|(p, _)| p.clone()).collect();
+        };
+        let max_time = match limit.unit {
+            LimitUnit::Tracks => {
+                return tracks
+                    .into_iter()
+                    .take(limit.value as usize)
+                    .map(|(p, _)| p.clone())
+                    .collect();
+            }
+            LimitUnit::Minutes => Duration::from_secs(u64::from(limit.value) * 60),
+            LimitUnit::Hours => Duration::from_secs(u64::from(limit.value) * 60 * 60),
+        };
+
+        let mut total = Duration::ZERO;
+        tracks
+            .into_iter()
+            .take_while(|(_, md)| {
+                total += md.duration.unwrap_or_default();
+                total <= max_time
+            })
+            .map(|(p, _)| p.clone())
+            .collect()
+    }
+
+    fn shuffle_key(&self, path: &Path) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        path.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+fn random_seed() -> u64 {
+    std::collections::hash_map::RandomState::new().hash_one(std::time::SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn library() -> HashMap<PathBuf, TrackMetadata> {
+        let track = |genre: &str, year: u32, plays: u32, added_days_ago: u64, rating: Option<u8>| {
+            TrackMetadata {
+                genre: Some(genre.into()),
+                year: Some(year),
+                play_count: plays,
+                date_added: Some(NOW - added_days_ago * SECONDS_PER_DAY),
+                rating,
+                duration: Some(Duration::from_secs(60 * 60)),
+                ..Default::default()
+            }
+        };
+        HashMap::from([
+            (PathBuf::from("/m/a.flac"), track("Jazz", 1959, 12, 400, Some(5))),
+            (PathBuf::from("/m/b.flac"), track("jazz", 1975, 3, 10, None)),
+            (PathBuf::from("/m/c.flac"), track("Rock", 1969, 0, 2, Some(4))),
+            (PathBuf::from("/m/d.flac"), track("Jazz", 1965, 0, 90, Some(2))),
+        ])
+    }
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| PathBuf::from(format!("/m/{n}.flac"))).collect()
+    }
+
+    #[test]
+    fn combines_rules_with_all_and_any() {
+        let mut playlist = SmartPlaylist::new(1, "Old jazz");
+        playlist.sort = SortOrder::Year;
+        playlist.rules = vec![
+            Rule::new(Field::Genre, Operator::Is, "Jazz"),
+            Rule::new(Field::Year, Operator::LessThan, "1970"),
+        ];
+        assert_eq!(playlist.evaluate(&library(), NOW), paths(&["a", "d"]));
+
+        playlist.match_mode = Match::Any;
+        assert_eq!(playlist.evaluate(&library(), NOW), paths(&["a", "d", "c", "b"]));
+    }
+
+    #[test]
+    fn matches_stats_and_dates() {
+        let library = library();
+        let eval = |rule: Rule| {
+            let mut playlist = SmartPlaylist::new(1, "Test");
+            playlist.sort = SortOrder::Title;
+            playlist.rules = vec![rule];
+            let mut tracks = playlist.evaluate(&library, NOW);
+            tracks.sort();
+            tracks
+        };
+
+        assert_eq!(eval(Rule::new(Field::DateAdded, Operator::InLast, "30")), paths(&["b", "c"]));
+        assert_eq!(eval(Rule::new(Field::PlayCount, Operator::GreaterThan, "10")), paths(&["a"]));
+        assert_eq!(eval(Rule::new(Field::PlayCount, Operator::Is, "0")), paths(&["c", "d"]));
+        assert_eq!(eval(Rule::new(Field::Rating, Operator::AtLeast, "4")), paths(&["a", "c"]));
+        assert_eq!(eval(Rule::new(Field::Rating, Operator::IsEmpty, "")), paths(&["b"]));
+        assert_eq!(eval(Rule::new(Field::LastPlayed, Operator::NotInLast, "7")).len(), 4);
+        assert!(eval(Rule::new(Field::Year, Operator::LessThan, "soon")).is_empty());
+    }
+
+    #[test]
+    fn matches_favorites() {
+        let mut library = library();
+        library.get_mut(Path::new("/m/c.flac")).unwrap().favorite = true;
+        let mut playlist = SmartPlaylist::new(1, "Favorites");
+        playlist.sort = SortOrder::Title;
+
+        playlist.rules = vec![Rule::new(Field::Favorite, Operator::IsSet, "")];
+        assert_eq!(playlist.evaluate(&library, NOW), paths(&["c"]));
+
+        playlist.rules = vec![Rule::new(Field::Favorite, Operator::IsNotSet, "")];
+        assert_eq!(playlist.evaluate(&library, NOW).len(), 3);
+        assert!(!Operator::IsSet.needs_value());
+    }
+
+    #[test]
+    fn sorts_and_limits() {
+        let mut playlist = SmartPlaylist::new(1, "Top");
+        playlist.sort = SortOrder::MostPlayed;
+        playlist.limit = Some(Limit {
+            unit: LimitUnit::Tracks,
+            value: 2,
+        });
+        assert_eq!(playlist.evaluate(&library(), NOW), paths(&["a", "b"]));
+
+        playlist.sort = SortOrder::RecentlyAdded;
+        playlist.limit = Some(Limit {
+            unit: LimitUnit::Hours,
+            value: 3,
+        });
+        assert_eq!(playlist.evaluate(&library(), NOW), paths(&["c", "b", "d"]));
+    }
+
+    #[test]
+    fn random_order_is_stable_until_reshuffled() {
+        let library = library();
+        let mut playlist = SmartPlaylist::new(1, "Shuffle");
+        let first = playlist.evaluate(&library, NOW);
+        assert_eq!(first.len(), 4);
+        assert_eq!(playlist.evaluate(&library, NOW), first);
+
+        let seed = playlist.seed;
+        playlist.reshuffle();
+        assert_ne!(playlist.seed, seed);
+    }
+
+    #[test]
+    fn parses_durations_as_minutes_and_seconds() {
+        let md = TrackMetadata {
+            duration: Some(Duration::from_secs(215)),
+            ..Default::default()
+        };
+        assert!(Rule::new(Field::Duration, Operator::GreaterThan, "3:30").matches(&md, NOW));
+        assert!(!Rule::new(Field::Duration, Operator::GreaterThan, "240").matches(&md, NOW));
+    }
+}
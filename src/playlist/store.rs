@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! User-created named and smart playlists, persisted as JSON in the app's data directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::smart::SmartPlaylist;
+use super::{find_in_library, PlaylistEntry};
+use crate::audio::backend::TrackMetadata;
+
+/// A playlist entry: the track's path plus the tags needed to find it again
+/// after the file has been moved or renamed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackRef {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub duration: Option<Duration>,
+}
+
+impl TrackRef {
+    /// Reference a track, fingerprinting it with its library metadata if known.
+    pub fn new(path: PathBuf, metadata: Option<&TrackMetadata>) -> Self {
+        Self {
+            title: metadata.and_then(|m| m.title.clone()),
+            artist: metadata.and_then(|m| m.artist.clone()),
+            album: metadata.and_then(|m| m.album.clone()),
+            duration: metadata.and_then(|m| m.duration),
+            path,
+        }
+    }
+
+    /// Locate the track in the library: by path first, then by its tag fingerprint.
+    pub fn locate(&self, library: &HashMap<PathBuf, TrackMetadata>) -> Option<PathBuf> {
+        if library.contains_key(&self.path) || self.path.is_file() {
+            return Some(self.path.clone());
+        }
+        find_in_library(&self.to_entry(), library)
+    }
+
+    fn to_entry(&self) -> PlaylistEntry {
+        PlaylistEntry {
+            location: self.path.to_string_lossy().into_owned(),
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
+            duration: self.duration,
+            image: None,
+        }
+    }
+}
+
+/// A named, user-ordered list of tracks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamedPlaylist {
+    pub id: u32,
+    pub name: String,
+    pub tracks: Vec<TrackRef>,
+}
+
+impl NamedPlaylist {
+    /// Resolve the entries to library paths, skipping tracks that can't be found.
+    pub fn resolve(&self, library: &HashMap<PathBuf, TrackMetadata>) -> Vec<PathBuf> {
+        self.tracks.iter().filter_map(|t| t.locate(library)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+}
+
+/// All named and smart playlists, in the order they are shown. Both kinds share
+/// one id space.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistStore {
+    playlists: Vec<NamedPlaylist>,
+    #[serde(default)]
+    smart: Vec<SmartPlaylist>,
+}
+
+impl PlaylistStore {
+    /// Load the store from `path`; a missing file yields an empty store.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| anyhow!("failed to parse {:?}: {e}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow!("failed to read {:?}: {e}", path)),
+        }
+    }
+
+    /// Write the store to `path` through a temporary file so a crash can't truncate it.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn playlists(&self) -> &[NamedPlaylist] {
+        &self.playlists
+    }
+
+    pub fn get(&self, id: u32) -> Option<&NamedPlaylist> {
+        self.playlists.iter().find(|p| p.id == id)
+    }
+
+    fn get_mut(&mut self, id: u32) -> Option<&mut NamedPlaylist> {
+        self.playlists.iter_mut().find(|p| p.id == id)
+    }
+
+    /// The name of the named or smart playlist with this id.
+    pub fn name(&self, id: u32) -> Option<&str> {
+        match self.get(id) {
+            Some(playlist) => Some(&playlist.name),
+            None => self.smart(id).map(|p| p.name.as_str()),
+        }
+    }
+
+    pub fn smart_playlists(&self) -> &[SmartPlaylist] {
+        &self.smart
+    }
+
+    pub fn smart(&self, id: u32) -> Option<&SmartPlaylist> {
+        self.smart.iter().find(|p| p.id == id)
+    }
+
+    pub fn smart_mut(&mut self, id: u32) -> Option<&mut SmartPlaylist> {
+        self.smart.iter_mut().find(|p| p.id == id)
+    }
+
+    fn next_id(&self) -> u32 {
+        let named = self.playlists.iter().map(|p| p.id);
+        let smart = self.smart.iter().map(|p| p.id);
+        named.chain(smart).map(|id| id + 1).max().unwrap_or(1)
+    }
+
+    /// Create an empty playlist and return its id.
+    pub fn create(&mut self, name: impl Into<String>) -> u32 {
+        let id = self.next_id();
+        self.playlists.push(NamedPlaylist {
+            id,
+            name: name.into(),
+            tracks: Vec::new(),
+        });
+        id
+    }
+
+    /// Create a smart playlist with no rules and return its id.
+    pub fn create_smart(&mut self, name: impl Into<String>) -> u32 {
+        let id = self.next_id();
+        self.smart.push(SmartPlaylist::new(id, name));
+        id
+    }
+
+    /// Replace the smart playlist with the same id.
+    pub fn update_smart(&mut self, playlist: SmartPlaylist) -> bool {
+        match self.smart_mut(playlist.id) {
+            Some(existing) => {
+                *existing = playlist;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn rename(&mut self, id: u32, name: impl Into<String>) -> bool {
+        if let Some(playlist) = self.get_mut(id) {
+            playlist.name = name.into();
+            true
+        } else if let Some(playlist) = self.smart_mut(id) {
+            playlist.name = name.into();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Copy a playlist under `name`, placing it right after the original.
+    pub fn duplicate(&mut self, id: u32, name: impl Into<String>) -> Option<u32> {
+        let new_id = self.next_id();
+        if let Some(pos) = self.playlists.iter().position(|p| p.id == id) {
+            let copy = NamedPlaylist {
+                id: new_id,
+                name: name.into(),
+                tracks: self.playlists[pos].tracks.clone(),
+            };
+            self.playlists.insert(pos + 1, copy);
+        } else {
+            let pos = self.smart.iter().position(|p| p.id == id)?;
+            let copy = SmartPlaylist {
+                id: new_id,
+                name: name.into(),
+                ..self.smart[pos].clone()
+            };
+            self.smart.insert(pos + 1, copy);
+        }
+        Some(new_id)
+    }
+
+    pub fn delete(&mut self, id: u32) -> bool {
+        let before = self.playlists.len() + self.smart.len();
+        self.playlists.retain(|p| p.id != id);
+        self.smart.retain(|p| p.id != id);
+        self.playlists.len() + self.smart.len() != before
+    }
+
+    pub fn add_tracks(&mut self, id: u32, tracks: impl IntoIterator<Item = TrackRef>) -> bool {
+        match self.get_mut(id) {
+            Some(playlist) => {
+                playlist.tracks.extend(tracks);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_track(&mut self, id: u32, index: usize) -> bool {
+        match self.get_mut(id) {
+            Some(playlist) if index < playlist.tracks.len() => {
+                playlist.tracks.remove(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move the entry at `from` so that it ends up at index `to`.
+    pub fn move_track(&mut self, id: u32, from: usize, to: usize) -> bool {
+        match self.get_mut(id) {
+            Some(playlist) if from < playlist.tracks.len() && to < playlist.tracks.len() => {
+                let track = playlist.tracks.remove(from);
+                playlist.tracks.insert(to, track);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Point entries at the new paths of files that were moved. Returns
+    /// whether anything changed and the store should be saved.
+    pub fn move_paths(&mut self, moved: &HashMap<PathBuf, PathBuf>) -> bool {
+        let mut changed = false;
+        for track in self.playlists.iter_mut().flat_map(|p| p.tracks.iter_mut()) {
+            if let Some(to) = moved.get(&track.path) {
+                track.path = to.clone();
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Point entries whose file moved at their new location in the library.
+    /// Returns whether anything changed and the store should be saved.
+    pub fn relink(&mut self, library: &HashMap<PathBuf, TrackMetadata>) -> bool {
+        let mut changed = false;
+        for track in self.playlists.iter_mut().flat_map(|p| p.tracks.iter_mut()) {
+            if library.contains_key(&track.path) || track.path.is_file() {
+                continue;
+            }
+            if let Some(path) = find_in_library(&track.to_entry(), library) {
+                track.path = path;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(path: &str, title: &str) -> TrackRef {
+        let metadata = TrackMetadata {
+            title: Some(title.into()),
+            artist: Some("Artist".into()),
+            duration: Some(Duration::from_secs(200)),
+            ..Default::default()
+        };
+        TrackRef::new(PathBuf::from(path), Some(&metadata))
+    }
+
+    fn paths(store: &PlaylistStore, id: u32) -> Vec<&Path> {
+        store.get(id).unwrap().tracks.iter().map(|t| t.path.as_path()).collect()
+    }
+
+    #[test]
+    fn saves_and_loads_playlists() {
+        let dir = std::env::temp_dir().join(format!("playlist-store-{}", std::process::id()));
+        let path = dir.join("playlists.json");
+        assert!(PlaylistStore::load(&path).unwrap().playlists().is_empty());
+
+        let mut store = PlaylistStore::default();
+        let mix = store.create("Mix");
+        store.add_tracks(mix, [track("/music/a.flac", "A"), track("/music/b.flac", "B")]);
+        let smart = store.create_smart("Recent");
+        store.save(&path).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let loaded = PlaylistStore::load(&path).unwrap();
+        assert_eq!(loaded.playlists(), store.playlists());
+        assert_eq!(loaded.smart_playlists(), store.smart_playlists());
+        assert_eq!(loaded.name(smart), Some("Recent"));
+        assert_eq!(loaded.get(mix).unwrap().tracks[0].title.as_deref(), Some("A"));
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(PlaylistStore::load(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn named_and_smart_playlists_share_ids() {
+        let mut store = PlaylistStore::default();
+        let named = store.create("Named");
+        let smart = store.create_smart("Smart");
+        assert_ne!(named, smart);
+
+        let copy = store.duplicate(smart, "Smart copy").unwrap();
+        assert_eq!(store.smart(copy).unwrap().name, "Smart copy");
+        assert!(store.rename(named, "Renamed"));
+        assert_eq!(store.name(named), Some("Renamed"));
+
+        assert!(store.delete(smart));
+        assert!(!store.delete(smart));
+        assert_eq!(store.name(smart), None);
+    }
+
+    #[test]
+    fn reorders_and_relinks_entries() {
+        let mut store = PlaylistStore::default();
+        let id = store.create("Mix");
+        let tracks = ["/old/a.flac", "/old/b.flac", "/old/c.flac"];
+        store.add_tracks(id, tracks.iter().map(|p| track(p, p)));
+
+        assert!(store.move_track(id, 0, 2));
+        assert!(!store.move_track(id, 0, 3));
+        assert!(store.remove_track(id, 0));
+        assert_eq!(paths(&store, id), [Path::new("/old/c.flac"), Path::new("/old/a.flac")]);
+
+        let moved = HashMap::from([("/old/c.flac".into(), "/new/c.flac".into())]);
+        assert!(store.move_paths(&moved));
+
+        // Found again by its tags after being moved outside the app
+        let library = HashMap::from([(
+            PathBuf::from("/new/a.flac"),
+            TrackMetadata {
+                title: Some("/old/a.flac".into()),
+                artist: Some("Artist".into()),
+                duration: Some(Duration::from_secs(201)),
+                ..Default::default()
+            },
+        )]);
+        assert!(store.relink(&library));
+        assert_eq!(paths(&store, id), [Path::new("/new/c.flac"), Path::new("/new/a.flac")]);
+        assert_eq!(store.get(id).unwrap().resolve(&library), [PathBuf::from("/new/a.flac")]);
+    }
+}
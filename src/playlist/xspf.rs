@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! XSPF ("spiff") XML shareable playlists.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+use super::{is_remote_uri, PlaylistEntry};
+
+const XSPF_NS: &str = "http://xspf.org/ns/0/";
+
+/// Parse the text of an XSPF playlist.
+///
+/// Tracks with several `location` elements keep the first local one, if any.
+pub fn parse(text: &str) -> Result<Vec<PlaylistEntry>> {
+    let doc = roxmltree::Document::parse(text).map_err(|e| anyhow!("invalid XSPF: {e}"))?;
+
+    let root = doc.root_element();
+    if !root.has_tag_name("playlist") {
+        return Err(anyhow!("invalid XSPF: root element is not <playlist>"));
+    }
+
+    let tracks = root
+        .children()
+        .find(|n| n.has_tag_name("trackList"))
+        .into_iter()
+        .flat_map(|list| list.children().filter(|n| n.has_tag_name("track")));
+
+    let entries = tracks
+        .map(|track| {
+            let text = |name: &str| {
+                track
+                    .children()
+                    .find(|n| n.has_tag_name(name))
+                    .and_then(|n| n.text())
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+            };
+
+            let locations: Vec<String> = track
+                .children()
+                .filter(|n| n.has_tag_name("location"))
+                .filter_map(|n| n.text())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            let location = locations
+                .iter()
+                .find(|l| !is_remote_uri(l))
+                .or_else(|| locations.first())
+                .cloned()
+                .unwrap_or_default();
+
+            PlaylistEntry {
+                location,
+                title: text("title"),
+                artist: text("creator"),
+                album: text("album"),
+                duration: text("duration")
+                    .and_then(|d| d.parse::<u64>().ok())
+                    .map(Duration::from_millis),
+                image: text("image"),
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Serialize entries as an XSPF playlist. Local paths are written as `file://` URIs,
+/// which XSPF requires for `location`.
+pub fn write(entries: &[PlaylistEntry]) -> String {
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"{XSPF_NS}\">\n  <trackList>\n"
+    );
+
+    for entry in entries {
+        out.push_str("    <track>\n");
+
+        let location = if is_remote_uri(&entry.location) || entry.location.starts_with("file://") {
+            Some(entry.location.clone())
+        } else {
+            Url::from_file_path(&entry.location).ok().map(String::from)
+        };
+        let fields = [
+            ("location", location),
+            ("title", entry.title.clone()),
+            ("creator", entry.artist.clone()),
+            ("album", entry.album.clone()),
+            ("duration", entry.duration.map(|d| d.as_millis().to_string())),
+            ("image", entry.image.clone()),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                out.push_str(&format!("      <{name}>{}</{name}>\n", escape(&value)));
+            }
+        }
+
+        out.push_str("    </track>\n");
+    }
+
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::backend::TrackMetadata;
+    use crate::playlist::resolve;
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>Mix</title>
+  <trackList>
+    <track>
+      <location>http://mirror.example/a.ogg</location>
+      <location>file:///music/A%20%26%20B/a.ogg</location>
+      <title>Song &amp; Dance</title>
+      <creator>A &amp; B</creator>
+      <album>First</album>
+      <duration>185500</duration>
+      <image>http://img.example/a.jpg</image>
+    </track>
+    <track>
+      <title>Lost Track</title>
+      <creator>Somebody</creator>
+    </track>
+  </trackList>
+</playlist>"#;
+
+    #[test]
+    fn parses_track_fields() {
+        let entries = parse(SAMPLE).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].location, "file:///music/A%20%26%20B/a.ogg");
+        assert_eq!(entries[0].title.as_deref(), Some("Song & Dance"));
+        assert_eq!(entries[0].artist.as_deref(), Some("A & B"));
+        assert_eq!(entries[0].album.as_deref(), Some("First"));
+        assert_eq!(entries[0].duration, Some(Duration::from_millis(185_500)));
+        assert_eq!(entries[0].image.as_deref(), Some("http://img.example/a.jpg"));
+        assert_eq!(entries[1].location, "");
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse("<rss></rss>").is_err());
+        assert!(parse("not xml").is_err());
+    }
+
+    #[test]
+    fn falls_back_to_library_match_without_location() {
+        let mut library = HashMap::new();
+        library.insert(
+            PathBuf::from("/music/somebody/lost.flac"),
+            TrackMetadata {
+                title: Some("lost  track".into()),
+                artist: Some("SOMEBODY".into()),
+                ..Default::default()
+            },
+        );
+        library.insert(
+            PathBuf::from("/music/other/lost.flac"),
+            TrackMetadata {
+                title: Some("Lost Track".into()),
+                artist: Some("Other".into()),
+                ..Default::default()
+            },
+        );
+
+        let resolved = resolve(parse(SAMPLE).unwrap(), Path::new("/"), &library);
+        assert_eq!(resolved.tracks, [PathBuf::from("/music/somebody/lost.flac")]);
+        assert_eq!(resolved.unresolved.len(), 1);
+    }
+
+    #[test]
+    fn writes_escaped_file_uris() {
+        let entries = vec![PlaylistEntry {
+            title: Some("<Intro>".into()),
+            duration: Some(Duration::from_secs(2)),
+            ..PlaylistEntry::new("/music/R&B/01 intro.flac")
+        }];
+        let text = write(&entries);
+
+        assert!(text.contains("<location>file:///music/R&amp;B/01%20intro.flac</location>"));
+        assert!(text.contains("<title>&lt;Intro&gt;</title>"));
+        assert!(text.contains("<duration>2000</duration>"));
+
+        let parsed = parse(&text).unwrap();
+        assert_eq!(parsed[0].location, "file:///music/R&B/01%20intro.flac");
+        assert_eq!(parsed[0].title.as_deref(), Some("<Intro>"));
+    }
+}
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Practicing a passage: A–B loops that play a section of a track over and
+//! over, saved per track as named bookmarks, with an optional pause before
+//! each repetition and a speed that rises as the passage is repeated.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Shortest section that can be looped.
+pub const MIN_LOOP: Duration = Duration::from_millis(250);
+
+/// Longest pause before a repetition, in seconds.
+pub const MAX_GAP: u32 = 10;
+
+/// Most repetitions played before the speed steps up.
+pub const MAX_STEP_EVERY: u32 = 10;
+
+/// A loop saved with a track. Positions are in milliseconds from the start
+/// of the track.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoopBookmark {
+    pub name: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl LoopBookmark {
+    pub fn new(name: &str, (start, end): (Duration, Duration)) -> Self {
+        Self {
+            name: name.to_string(),
+            start: start.as_millis() as u64,
+            end: end.as_millis() as u64,
+        }
+    }
+
+    pub fn range(&self) -> (Duration, Duration) {
+        (
+            Duration::from_millis(self.start),
+            Duration::from_millis(self.end),
+        )
+    }
+}
+
+/// The section between two points set in either order, unless it is too
+/// short to loop.
+pub fn loop_range(a: Duration, b: Duration) -> Option<(Duration, Duration)> {
+    let (start, end) = if a <= b { (a, b) } else { (b, a) };
+    (end - start >= MIN_LOOP).then_some((start, end))
+}
+
+/// Save `bookmark` among a track's loops, replacing one of the same name,
+/// and keep them in the order they come in the track.
+pub fn save_bookmark(loops: &mut Vec<LoopBookmark>, bookmark: LoopBookmark) {
+    loops.retain(|l| l.name != bookmark.name);
+    loops.push(bookmark);
+    loops.sort_by_key(|l| (l.start, l.end));
+}
+
+/// Where a loop starts and ends along a seek bar for a track of `duration`,
+/// as fractions of its length.
+pub fn span_fractions(
+    (start, end): (Duration, Duration),
+    duration: Duration,
+) -> Option<(f32, f32)> {
+    if duration.is_zero() || start >= duration {
+        return None;
+    }
+    let fraction = |d: Duration| (d.as_secs_f64() / duration.as_secs_f64()).min(1.0) as f32;
+    Some((fraction(start), fraction(end)))
+}
+
+/// How a loop is practiced. Speeds are in percent of normal speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeSettings {
+    /// Seconds of silence before each repetition, to count in
+    pub gap: u32,
+    /// Speed of the first time through
+    pub start_rate: u32,
+    /// Added to the speed every `step_every` repetitions; 0 keeps it steady
+    pub rate_step: u32,
+    pub step_every: u32,
+    /// Speed the steps stop at
+    pub target_rate: u32,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> Self {
+        Self {
+            gap: 0,
+            start_rate: 100,
+            rate_step: 0,
+            step_every: 1,
+            target_rate: 100,
+        }
+    }
+}
+
+impl PracticeSettings {
+    /// Whether the speed rises as the loop repeats.
+    pub fn speeds_up(&self) -> bool {
+        self.rate_step > 0 && self.target_rate > self.start_rate
+    }
+
+    /// Speed of the loop's `repetition`th time through, the first being 0.
+    pub fn rate(&self, repetition: u32) -> u32 {
+        if !self.speeds_up() {
+            return self.start_rate;
+        }
+        let steps = repetition / self.step_every.max(1);
+        self.start_rate
+            .saturating_add(steps.saturating_mul(self.rate_step))
+            .min(self.target_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn orders_loop_points() {
+        assert_eq!(loop_range(ms(5000), ms(2000)), Some((ms(2000), ms(5000))));
+        assert_eq!(loop_range(ms(2000), ms(2100)), None);
+    }
+
+    #[test]
+    fn replaces_bookmarks_by_name() {
+        let mut loops = Vec::new();
+        save_bookmark(
+            &mut loops,
+            LoopBookmark::new("solo", (ms(60_000), ms(90_000))),
+        );
+        save_bookmark(&mut loops, LoopBookmark::new("intro", (ms(0), ms(15_000))));
+        save_bookmark(
+            &mut loops,
+            LoopBookmark::new("solo", (ms(62_000), ms(88_000))),
+        );
+        let names: Vec<_> = loops.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, ["intro", "solo"]);
+        assert_eq!(loops[1].range(), (ms(62_000), ms(88_000)));
+    }
+
+    #[test]
+    fn places_loops_along_the_seek_bar() {
+        let duration = ms(200_000);
+        assert_eq!(
+            span_fractions((ms(50_000), ms(100_000)), duration),
+            Some((0.25, 0.5))
+        );
+        assert_eq!(
+            span_fractions((ms(150_000), ms(300_000)), duration),
+            Some((0.75, 1.0))
+        );
+        assert_eq!(span_fractions((ms(0), ms(1000)), Duration::ZERO), None);
+    }
+
+    #[test]
+    fn speeds_up_towards_the_target() {
+        let settings = PracticeSettings {
+            start_rate: 70,
+            rate_step: 5,
+            step_every: 2,
+            target_rate: 85,
+            ..Default::default()
+        };
+        let rates: Vec<_> = (0..9).map(|n| settings.rate(n)).collect();
+        assert_eq!(rates, [70, 70, 75, 75, 80, 80, 85, 85, 85]);
+
+        let steady = PracticeSettings {
+            start_rate: 80,
+            ..Default::default()
+        };
+        assert!(!steady.speeds_up());
+        assert_eq!(steady.rate(10), 80);
+    }
+}
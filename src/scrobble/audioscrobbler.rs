@@ -0,0 +1,399 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Client for the AudioScrobbler 2.0 API spoken by Last.fm, Libre.fm and
+//! self-hosted servers such as Maloja.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use super::{Scrobble, Scrobbler, SubmitError, MIN_LENGTH};
+
+/// Most listens `track.scrobble` accepts in one call.
+pub const MAX_BATCH: usize = 50;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How to get a session key for submitting listens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Credentials {
+    /// A session key obtained earlier
+    SessionKey(String),
+    /// Log in with `auth.getMobileSession` on first use
+    Password { username: String, password: String },
+}
+
+/// An AudioScrobbler 2.0 server with the API account and user to submit as.
+#[derive(Debug)]
+pub struct AudioScrobbler {
+    api_root: String,
+    api_key: String,
+    secret: String,
+    credentials: Credentials,
+    /// Session key once known
+    session: Mutex<Option<String>>,
+    agent: ureq::Agent,
+}
+
+impl AudioScrobbler {
+    pub const LASTFM_API_ROOT: &'static str = "https://ws.audioscrobbler.com/2.0/";
+
+    /// A client for the server at `api_root`, signing calls with the API
+    /// account's `api_key` and `secret`.
+    pub fn new(api_root: &str, api_key: &str, secret: &str, credentials: Credentials) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("music-player/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let session = match &credentials {
+            Credentials::SessionKey(key) => Some(key.trim().to_string()),
+            Credentials::Password { .. } => None,
+        };
+        Self {
+            api_root: api_root.trim().to_string(),
+            api_key: api_key.trim().to_string(),
+            secret: secret.trim().to_string(),
+            credentials,
+            session: Mutex::new(session),
+            agent,
+        }
+    }
+
+    /// The session key, logging in first if there is none yet.
+    pub fn session_key(&self) -> Result<String, SubmitError> {
+        if let Some(key) = self.session.lock().unwrap().clone() {
+            return Ok(key);
+        }
+        let Credentials::Password { username, password } = &self.credentials else {
+            return Err(SubmitError::Unauthorized("no session key".into()));
+        };
+
+        let response = self.call(
+            "auth.getMobileSession",
+            vec![
+                ("username".into(), username.clone()),
+                ("password".into(), password.clone()),
+            ],
+        )?;
+        let key = response["session"]["key"]
+            .as_str()
+            .ok_or_else(|| SubmitError::Unauthorized("no session key in response".into()))?
+            .to_string();
+        *self.session.lock().unwrap() = Some(key.clone());
+        Ok(key)
+    }
+
+    /// Call `method` with the session key. A session that expired is replaced
+    /// by logging in again, when there is a password to do so.
+    fn call_with_session(
+        &self,
+        method: &str,
+        params: Vec<(String, String)>,
+    ) -> Result<Value, SubmitError> {
+        let with_key = |key: String| {
+            let mut params = params.clone();
+            params.push(("sk".into(), key));
+            params
+        };
+
+        let result = self.call(method, with_key(self.session_key()?));
+        let expired = matches!(&result, Err(SubmitError::Unauthorized(_)))
+            && matches!(self.credentials, Credentials::Password { .. });
+        if !expired {
+            return result;
+        }
+        *self.session.lock().unwrap() = None;
+        self.call(method, with_key(self.session_key()?))
+    }
+
+    /// Make a signed call to `method` and return the response.
+    fn call(&self, method: &str, mut params: Vec<(String, String)>) -> Result<Value, SubmitError> {
+        params.push(("method".into(), method.to_string()));
+        params.push(("api_key".into(), self.api_key.clone()));
+        let signature = sign(&params, &self.secret);
+        params.push(("api_sig".into(), signature));
+        params.push(("format".into(), "json".into()));
+
+        let form: Vec<(&str, &str)> = params
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let (status, body) = match self.agent.post(&self.api_root).send_form(&form) {
+            Ok(response) => (response.status(), response.into_string()),
+            Err(ureq::Error::Status(status, response)) => (status, response.into_string()),
+            Err(ureq::Error::Transport(err)) => {
+                return Err(SubmitError::Unavailable(err.to_string()))
+            }
+        };
+
+        let value = body
+            .ok()
+            .and_then(|body| serde_json::from_str::<Value>(&body).ok());
+        if let Some(code) = value.as_ref().and_then(|v| v["error"].as_i64()) {
+            let message = value.as_ref().and_then(|v| v["message"].as_str());
+            return Err(api_error(code, message.unwrap_or_default().to_string()));
+        }
+        match (status, value) {
+            (200..=299, Some(value)) => Ok(value),
+            (429 | 500.., _) => Err(SubmitError::Unavailable(format!("HTTP {status}"))),
+            _ => Err(SubmitError::Rejected(format!(
+                "unexpected response, HTTP {status}"
+            ))),
+        }
+    }
+}
+
+impl Scrobbler for AudioScrobbler {
+    fn id(&self) -> &'static str {
+        "audioscrobbler"
+    }
+
+    fn max_batch(&self) -> usize {
+        MAX_BATCH
+    }
+
+    fn playing_now(&self, scrobble: &Scrobble) -> Result<(), SubmitError> {
+        let mut params = vec![
+            ("artist".into(), scrobble.artist.clone()),
+            ("track".into(), scrobble.title.clone()),
+        ];
+        if let Some(album) = &scrobble.album {
+            params.push(("album".into(), album.clone()));
+        }
+        if let Some(duration) = scrobble.duration {
+            params.push(("duration".into(), duration.to_string()));
+        }
+        self.call_with_session("track.updateNowPlaying", params)
+            .map(|_| ())
+    }
+
+    fn submit(&self, listens: &[Scrobble]) -> Result<(), SubmitError> {
+        // Tracks of unknown length are left for the server to judge
+        let listens: Vec<&Scrobble> = listens
+            .iter()
+            .filter(|s| s.duration.is_none_or(|d| d > MIN_LENGTH.as_secs()))
+            .collect();
+        if listens.is_empty() {
+            return Ok(());
+        }
+        let mut params = Vec::new();
+        for (i, scrobble) in listens.iter().enumerate() {
+            params.push((format!("artist[{i}]"), scrobble.artist.clone()));
+            params.push((format!("track[{i}]"), scrobble.title.clone()));
+            params.push((format!("timestamp[{i}]"), scrobble.listened_at.to_string()));
+            if let Some(album) = &scrobble.album {
+                params.push((format!("album[{i}]"), album.clone()));
+            }
+            if let Some(duration) = scrobble.duration {
+                params.push((format!("duration[{i}]"), duration.to_string()));
+            }
+        }
+        self.call_with_session("track.scrobble", params).map(|_| ())
+    }
+}
+
+/// The `api_sig` of a call: the MD5 of its parameters as `<name><value>`
+/// ordered by name, followed by the API secret.
+fn sign(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut text = String::new();
+    for (name, value) in sorted {
+        text.push_str(name);
+        text.push_str(value);
+    }
+    text.push_str(secret);
+    format!("{:x}", md5::compute(text))
+}
+
+/// Map an API error code to what should happen to the listens.
+fn api_error(code: i64, message: String) -> SubmitError {
+    let message = format!("error {code}: {message}");
+    match code {
+        // Authentication failed, bad signature, bad key or session, token
+        // problems, suspended API key
+        4 | 9 | 10 | 13 | 14 | 15 | 26 => SubmitError::Unauthorized(message),
+        // Service offline, temporarily unavailable, rate limited
+        11 | 16 | 29 => SubmitError::Unavailable(message),
+        _ => SubmitError::Rejected(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobble::{test_server, THRESHOLD};
+    use std::collections::HashMap;
+
+    fn scrobble(listened_at: u64) -> Scrobble {
+        Scrobble {
+            artist: "Artist".into(),
+            title: format!("Track {listened_at}"),
+            album: None,
+            duration: Some(180),
+            listened_at,
+        }
+    }
+
+    fn form(body: &str) -> HashMap<String, String> {
+        url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect()
+    }
+
+    const SESSION: &str = r#"{"session":{"name":"user","key":"KEY","subscriber":0}}"#;
+    const SCROBBLED: &str = r#"{"scrobbles":{"@attr":{"accepted":2,"ignored":0}}}"#;
+
+    #[test]
+    fn signs_parameters_in_name_order() {
+        let params = vec![
+            ("token".to_string(), "yyy".to_string()),
+            ("api_key".to_string(), "xxx".to_string()),
+            ("method".to_string(), "auth.getSession".to_string()),
+        ];
+        let expected = format!(
+            "{:x}",
+            md5::compute("api_keyxxxmethodauth.getSessiontokenyyysecret")
+        );
+        assert_eq!(sign(&params, "secret"), expected);
+    }
+
+    #[test]
+    fn logs_in_then_scrobbles_a_batch() {
+        let (root, requests) = test_server::serve(vec![("200 OK", SESSION), ("200 OK", SCROBBLED)]);
+        let client = AudioScrobbler::new(
+            &root,
+            "key",
+            "secret",
+            Credentials::Password {
+                username: "user".into(),
+                password: "pass".into(),
+            },
+        );
+
+        client.submit(&[scrobble(100), scrobble(200)]).unwrap();
+
+        let login = form(&requests.recv().unwrap().body);
+        assert_eq!(login["method"], "auth.getMobileSession");
+        assert_eq!(login["username"], "user");
+
+        let call = form(&requests.recv().unwrap().body);
+        assert_eq!(call["method"], "track.scrobble");
+        assert_eq!(call["sk"], "KEY");
+        assert_eq!(call["artist[1]"], "Artist");
+        assert_eq!(call["timestamp[1]"], "200");
+        assert_eq!(call["format"], "json");
+        let mut signed: Vec<(String, String)> = call
+            .iter()
+            .filter(|(k, _)| *k != "api_sig" && *k != "format")
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        signed.sort();
+        assert_eq!(call["api_sig"], sign(&signed, "secret"));
+        assert_eq!(client.session_key(), Ok("KEY".into()));
+    }
+
+    #[test]
+    fn leaves_out_short_tracks() {
+        let (root, requests) = test_server::serve(vec![("200 OK", SCROBBLED)]);
+        let credentials = Credentials::SessionKey("KEY".into());
+        let client = AudioScrobbler::new(&root, "key", "secret", credentials);
+        let short = Scrobble {
+            duration: Some(30),
+            ..scrobble(100)
+        };
+        let unknown = Scrobble {
+            duration: None,
+            ..scrobble(300)
+        };
+
+        client.submit(std::slice::from_ref(&short)).unwrap();
+        client.submit(&[short, scrobble(200), unknown]).unwrap();
+
+        // Only the second call reached the server
+        let call = form(&requests.recv().unwrap().body);
+        assert_eq!(call["timestamp[0]"], "200");
+        assert_eq!(call["timestamp[1]"], "300");
+        assert!(!call.contains_key("timestamp[2]"));
+    }
+
+    #[test]
+    fn never_reaches_the_threshold_for_short_tracks() {
+        let jingle = Some(Duration::from_secs(20));
+        assert!(!THRESHOLD.is_reached(Duration::from_secs(20), jingle));
+        assert!(!THRESHOLD.is_reached(MIN_LENGTH, Some(MIN_LENGTH)));
+
+        let song = Some(MIN_LENGTH + Duration::from_secs(1));
+        assert!(THRESHOLD.is_reached(Duration::from_secs(16), song));
+    }
+
+    #[test]
+    fn logs_in_again_when_the_session_expired() {
+        let (root, requests) = test_server::serve(vec![
+            (
+                "403 Forbidden",
+                r#"{"error":9,"message":"Invalid session key"}"#,
+            ),
+            ("200 OK", SESSION),
+            ("200 OK", "{}"),
+        ]);
+        let client = AudioScrobbler::new(
+            &root,
+            "key",
+            "secret",
+            Credentials::Password {
+                username: "user".into(),
+                password: "pass".into(),
+            },
+        );
+        *client.session.lock().unwrap() = Some("OLD".into());
+
+        client.playing_now(&scrobble(1)).unwrap();
+
+        assert_eq!(form(&requests.recv().unwrap().body)["sk"], "OLD");
+        assert_eq!(
+            form(&requests.recv().unwrap().body)["method"],
+            "auth.getMobileSession"
+        );
+        let call = form(&requests.recv().unwrap().body);
+        assert_eq!(call["method"], "track.updateNowPlaying");
+        assert_eq!(call["sk"], "KEY");
+    }
+
+    #[test]
+    fn classifies_api_errors() {
+        let (root, _requests) = test_server::serve(vec![
+            (
+                "200 OK",
+                r#"{"error":16,"message":"Temporarily unavailable"}"#,
+            ),
+            ("200 OK", r#"{"error":6,"message":"Invalid parameters"}"#),
+        ]);
+        let client = AudioScrobbler::new(
+            &root,
+            "key",
+            "secret",
+            Credentials::SessionKey("KEY".into()),
+        );
+        assert!(matches!(
+            client.submit(&[scrobble(1)]),
+            Err(SubmitError::Unavailable(_))
+        ));
+        assert!(matches!(
+            client.submit(&[scrobble(1)]),
+            Err(SubmitError::Rejected(_))
+        ));
+
+        let offline = AudioScrobbler::new(
+            &test_server::unreachable(),
+            "key",
+            "secret",
+            Credentials::SessionKey("KEY".into()),
+        );
+        assert!(matches!(
+            offline.submit(&[scrobble(1)]),
+            Err(SubmitError::Unavailable(_))
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Client for the ListenBrainz listen submission API.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use super::{Scrobble, Scrobbler, SubmitError};
+
+/// Most listens sent in one import request; the API allows up to 1000.
+pub const MAX_BATCH: usize = 100;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A ListenBrainz server and the user token to submit listens with.
+#[derive(Debug, Clone)]
+pub struct ListenBrainz {
+    api_root: String,
+    token: String,
+    agent: ureq::Agent,
+}
+
+impl ListenBrainz {
+    pub const DEFAULT_API_ROOT: &'static str = "https://api.listenbrainz.org";
+
+    /// A client for the server at `api_root`, such as `DEFAULT_API_ROOT` or a
+    /// self-hosted instance.
+    pub fn new(api_root: &str, token: &str) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .user_agent(concat!("music-player/", env!("CARGO_PKG_VERSION")))
+            .build();
+        Self {
+            api_root: api_root.trim_end_matches('/').to_string(),
+            token: token.trim().to_string(),
+            agent,
+        }
+    }
+
+    fn submit_listens(&self, listen_type: &str, payload: Vec<Value>) -> Result<(), SubmitError> {
+        let body = json!({ "listen_type": listen_type, "payload": payload });
+        let response = self
+            .agent
+            .post(&format!("{}/1/submit-listens", self.api_root))
+            .set("Authorization", &format!("Token {}", self.token))
+            .send_json(body);
+
+        match response {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, response)) => {
+                let message = error_message(response);
+                Err(match status {
+                    401 | 403 => SubmitError::Unauthorized(message),
+                    429 | 500.. => SubmitError::Unavailable(message),
+                    _ => SubmitError::Rejected(message),
+                })
+            }
+            Err(ureq::Error::Transport(err)) => Err(SubmitError::Unavailable(err.to_string())),
+        }
+    }
+}
+
+impl Scrobbler for ListenBrainz {
+    fn id(&self) -> &'static str {
+        "listenbrainz"
+    }
+
+    fn max_batch(&self) -> usize {
+        MAX_BATCH
+    }
+
+    fn playing_now(&self, scrobble: &Scrobble) -> Result<(), SubmitError> {
+        self.submit_listens("playing_now", vec![track_payload(scrobble, false)])
+    }
+
+    fn submit(&self, listens: &[Scrobble]) -> Result<(), SubmitError> {
+        let listen_type = if listens.len() == 1 {
+            "single"
+        } else {
+            "import"
+        };
+        let payload = listens.iter().map(|s| track_payload(s, true)).collect();
+        self.submit_listens(listen_type, payload)
+    }
+}
+
+fn track_payload(scrobble: &Scrobble, with_time: bool) -> Value {
+    let mut additional_info = json!({
+        "media_player": "COSMIC Music Player",
+        "submission_client": "music-player",
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(duration) = scrobble.duration {
+        additional_info["duration_ms"] = json!(duration * 1000);
+    }
+
+    let mut track_metadata = json!({
+        "artist_name": scrobble.artist,
+        "track_name": scrobble.title,
+        "additional_info": additional_info,
+    });
+    if let Some(album) = &scrobble.album {
+        track_metadata["release_name"] = json!(album);
+    }
+
+    let mut listen = json!({ "track_metadata": track_metadata });
+    if with_time {
+        listen["listened_at"] = json!(scrobble.listened_at);
+    }
+    listen
+}
+
+/// The `error` field of an API error response, or the status line.
+fn error_message(response: ureq::Response) -> String {
+    let status = format!("{} {}", response.status(), response.status_text());
+    response
+        .into_string()
+        .ok()
+        .and_then(|body| serde_json::from_str::<Value>(&body).ok())
+        .and_then(|v| v["error"].as_str().map(str::to_string))
+        .unwrap_or(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobble::test_server;
+
+    fn scrobble(listened_at: u64) -> Scrobble {
+        Scrobble {
+            artist: "Artist".into(),
+            title: "Title".into(),
+            album: Some("Album".into()),
+            duration: Some(180),
+            listened_at,
+        }
+    }
+
+    fn ok() -> Vec<(&'static str, &'static str)> {
+        vec![("200 OK", r#"{"status":"ok"}"#)]
+    }
+
+    #[test]
+    fn submits_listens_with_token() {
+        let (root, requests) = test_server::serve(ok());
+        let client = ListenBrainz::new(&format!("{root}/"), "secret");
+
+        client.submit(&[scrobble(10), scrobble(20)]).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert!(request.head.starts_with("POST /1/submit-listens "));
+        assert!(request.head.contains("Authorization: Token secret"));
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["listen_type"], "import");
+        assert_eq!(body["payload"][1]["listened_at"], 20);
+        let track = &body["payload"][0]["track_metadata"];
+        assert_eq!(track["artist_name"], "Artist");
+        assert_eq!(track["release_name"], "Album");
+        assert_eq!(track["additional_info"]["duration_ms"], 180_000);
+    }
+
+    #[test]
+    fn playing_now_has_no_timestamp() {
+        let (root, requests) = test_server::serve(ok());
+        ListenBrainz::new(&root, "secret")
+            .playing_now(&scrobble(10))
+            .unwrap();
+
+        let body: Value = serde_json::from_str(&requests.recv().unwrap().body).unwrap();
+        assert_eq!(body["listen_type"], "playing_now");
+        assert!(body["payload"][0].get("listened_at").is_none());
+    }
+
+    #[test]
+    fn classifies_failures() {
+        let (root, _requests) = test_server::serve(vec![(
+            "401 Unauthorized",
+            r#"{"code":401,"error":"Invalid authorization token."}"#,
+        )]);
+        let result = ListenBrainz::new(&root, "wrong").submit(&[scrobble(1)]);
+        assert_eq!(
+            result,
+            Err(SubmitError::Unauthorized(
+                "Invalid authorization token.".into()
+            ))
+        );
+
+        let (root, _requests) = test_server::serve(vec![(
+            "400 Bad Request",
+            r#"{"code":400,"error":"Value for key listened_at is too low."}"#,
+        )]);
+        let result = ListenBrainz::new(&root, "secret").submit(&[scrobble(1), scrobble(2)]);
+        assert!(matches!(result, Err(SubmitError::Rejected(_))));
+
+        let (root, _requests) = test_server::serve(vec![("503 Service Unavailable", "")]);
+        let result = ListenBrainz::new(&root, "secret").submit(&[scrobble(1)]);
+        assert!(matches!(result, Err(SubmitError::Unavailable(_))));
+
+        let result =
+            ListenBrainz::new(&test_server::unreachable(), "secret").submit(&[scrobble(1)]);
+        assert!(matches!(result, Err(SubmitError::Unavailable(_))));
+    }
+}
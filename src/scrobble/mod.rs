@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Submitting listens to scrobbling services from a background thread, queueing
+//! them on disk while a service can't be reached.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::backend::TrackMetadata;
+use crate::audio::listen::PlayThreshold;
+
+pub mod audioscrobbler;
+pub mod listenbrainz;
+pub mod queue;
+#[cfg(test)]
+pub(crate) mod test_server;
+
+pub use audioscrobbler::AudioScrobbler;
+pub use listenbrainz::ListenBrainz;
+pub use queue::ScrobbleQueue;
+
+/// Tracks this long or shorter are never submitted.
+pub const MIN_LENGTH: Duration = Duration::from_secs(30);
+
+/// When a listen is submitted: after half the track or four minutes, whichever
+/// comes first, and only for tracks longer than `MIN_LENGTH`, as ListenBrainz
+/// and Last.fm ask of clients.
+pub const THRESHOLD: PlayThreshold = PlayThreshold {
+    fraction: 0.5,
+    max: Duration::from_secs(4 * 60),
+    min_length: MIN_LENGTH,
+};
+
+/// How long to wait before retrying queued listens after a failed submission.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A listen of a track, as sent to a scrobbling service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub artist: String,
+    pub title: String,
+    #[serde(default)]
+    pub album: Option<String>,
+    /// Track length in seconds
+    #[serde(default)]
+    pub duration: Option<u64>,
+    /// When the listen started, in seconds since the Unix epoch
+    pub listened_at: u64,
+}
+
+impl Scrobble {
+    /// A scrobble of the track described by `md`, or `None` when it lacks the
+    /// artist and title every service requires.
+    pub fn new(md: &TrackMetadata, listened_at: u64) -> Option<Self> {
+        Some(Self {
+            artist: md.artist.clone().filter(|a| !a.trim().is_empty())?,
+            title: md.title.clone().filter(|t| !t.trim().is_empty())?,
+            album: md.album.clone(),
+            duration: md.duration.map(|d| d.as_secs()),
+            listened_at,
+        })
+    }
+}
+
+/// Why a submission failed, which decides what happens to the queued listens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubmitError {
+    /// The service could not be reached or is busy; try again later
+    Unavailable(String),
+    /// The credentials were refused; nothing is sent until they change
+    Unauthorized(String),
+    /// The service refused the listens themselves; retrying won't help
+    Rejected(String),
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmitError::Unavailable(msg) => write!(f, "service unavailable: {msg}"),
+            SubmitError::Unauthorized(msg) => write!(f, "not authorized: {msg}"),
+            SubmitError::Rejected(msg) => write!(f, "listens rejected: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+/// A scrobbling service listens can be submitted to.
+pub trait Scrobbler: fmt::Debug + Send {
+    /// Short stable name, used for the service's queue file and in logs
+    fn id(&self) -> &'static str;
+
+    /// Most listens `submit` accepts at once
+    fn max_batch(&self) -> usize;
+
+    /// Tell the service what is playing right now.
+    fn playing_now(&self, scrobble: &Scrobble) -> Result<(), SubmitError>;
+
+    /// Submit finished listens, at most `max_batch` at a time.
+    fn submit(&self, listens: &[Scrobble]) -> Result<(), SubmitError>;
+}
+
+#[derive(Debug)]
+pub enum ScrobbleCommand {
+    /// A track started playing
+    NowPlaying(Scrobble),
+    /// A track was listened to long enough to be submitted
+    Listen(Scrobble),
+    /// Submit to these services from now on
+    SetScrobblers(Vec<Box<dyn Scrobbler>>),
+}
+
+/// A configured service with the listens it hasn't accepted yet.
+struct Service {
+    scrobbler: Box<dyn Scrobbler>,
+    queue: ScrobbleQueue,
+    /// Set after the credentials are refused, until new ones are configured
+    refused: bool,
+}
+
+impl Service {
+    /// Submit queued listens, logging why it stopped if not all of them went out.
+    fn flush(&mut self) {
+        if self.refused || self.queue.is_empty() {
+            return;
+        }
+        let scrobbler = &self.scrobbler;
+        match self
+            .queue
+            .flush(scrobbler.max_batch(), |batch| scrobbler.submit(batch))
+        {
+            Ok(_) => {}
+            Err(err @ SubmitError::Unauthorized(_)) => {
+                eprintln!(
+                    "{} refused the credentials, keeping listens queued: {err}",
+                    scrobbler.id()
+                );
+                self.refused = true;
+            }
+            Err(err) => eprintln!("failed to submit listens to {}: {err}", scrobbler.id()),
+        }
+    }
+}
+
+/// Start the scrobbling thread. Each service keeps the listens it can't take
+/// yet in its own queue file in `queue_dir`, and they are retried in batches
+/// until it accepts them.
+pub fn start(queue_dir: PathBuf, scrobblers: Vec<Box<dyn Scrobbler>>) -> Sender<ScrobbleCommand> {
+    let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || run(queue_dir, scrobblers, cmd_rx));
+    cmd_tx
+}
+
+fn load_services(queue_dir: &Path, scrobblers: Vec<Box<dyn Scrobbler>>) -> Vec<Service> {
+    scrobblers
+        .into_iter()
+        .filter_map(|scrobbler| {
+            let file = queue_dir.join(format!("{}.json", scrobbler.id()));
+            match ScrobbleQueue::load(file) {
+                Ok(queue) => Some(Service {
+                    scrobbler,
+                    queue,
+                    refused: false,
+                }),
+                Err(err) => {
+                    eprintln!("not scrobbling to {}: {err}", scrobbler.id());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn run(queue_dir: PathBuf, scrobblers: Vec<Box<dyn Scrobbler>>, cmd_rx: Receiver<ScrobbleCommand>) {
+    let mut services = load_services(&queue_dir, scrobblers);
+
+    // Listens left over from the last session
+    services.iter_mut().for_each(Service::flush);
+
+    loop {
+        match cmd_rx.recv_timeout(RETRY_INTERVAL) {
+            Ok(ScrobbleCommand::NowPlaying(scrobble)) => {
+                for service in services.iter().filter(|s| !s.refused) {
+                    // Only informative, so it is not queued when it fails
+                    if let Err(err) = service.scrobbler.playing_now(&scrobble) {
+                        eprintln!(
+                            "failed to send playing now to {}: {err}",
+                            service.scrobbler.id()
+                        );
+                    }
+                }
+            }
+            Ok(ScrobbleCommand::Listen(scrobble)) => {
+                for service in &mut services {
+                    service.queue.push(scrobble.clone());
+                    if let Err(err) = service.queue.save() {
+                        eprintln!("failed to save scrobble queue: {err}");
+                    }
+                    service.flush();
+                }
+            }
+            Ok(ScrobbleCommand::SetScrobblers(scrobblers)) => {
+                services = load_services(&queue_dir, scrobblers);
+                services.iter_mut().for_each(Service::flush);
+            }
+            Err(RecvTimeoutError::Timeout) => services.iter_mut().for_each(Service::flush),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn never_submits_short_tracks() {
+        let secs = Duration::from_secs;
+        // A jingle heard from start to end is still not a listen
+        assert!(!THRESHOLD.is_reached(secs(20), Some(secs(20))));
+        assert!(!THRESHOLD.is_reached(secs(30), Some(secs(30))));
+        assert!(THRESHOLD.is_reached(secs(16), Some(secs(31))));
+        // Without a length, only the four minute cap applies
+        assert!(THRESHOLD.is_reached(secs(240), None));
+    }
+}
@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Listens waiting to be submitted, kept on disk so they survive restarts.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use super::{Scrobble, SubmitError};
+
+/// Listens not yet accepted by the service, oldest first.
+#[derive(Debug, Clone)]
+pub struct ScrobbleQueue {
+    file: PathBuf,
+    pending: Vec<Scrobble>,
+}
+
+impl ScrobbleQueue {
+    /// Load the queue kept in `file`; a missing or unreadable file yields an
+    /// empty queue, so a damaged file doesn't stop scrobbling.
+    pub fn load(file: impl Into<PathBuf>) -> Result<Self> {
+        let file = file.into();
+        let pending = match fs::read(&file) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                eprintln!("discarding unreadable scrobble queue {:?}: {e}", file);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(anyhow!("failed to read {:?}: {e}", file)),
+        };
+        Ok(Self { file, pending })
+    }
+
+    pub fn pending(&self) -> &[Scrobble] {
+        &self.pending
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn push(&mut self, scrobble: Scrobble) {
+        self.pending.push(scrobble);
+    }
+
+    /// Write the queue to its file, replacing it atomically.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.file.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.pending)?)?;
+        fs::rename(&tmp, &self.file)?;
+        Ok(())
+    }
+
+    /// Hand the queued listens to `submit` in batches of at most `batch_size`,
+    /// oldest first, until the queue is empty or a batch fails. A rejected
+    /// batch is sent again one listen at a time, and only the listens the
+    /// service rejects on their own are dropped; any other failure leaves the
+    /// remaining listens queued. Returns how many listens were submitted.
+    pub fn flush(
+        &mut self,
+        batch_size: usize,
+        mut submit: impl FnMut(&[Scrobble]) -> Result<(), SubmitError>,
+    ) -> Result<usize, SubmitError> {
+        let mut submitted = 0;
+        let mut result = Ok(());
+        'queue: while !self.pending.is_empty() {
+            let count = batch_size.max(1).min(self.pending.len());
+            match submit(&self.pending[..count]) {
+                Ok(()) => submitted += count,
+                // One bad listen fails the whole batch
+                Err(SubmitError::Rejected(_)) if count > 1 => {
+                    for _ in 0..count {
+                        match submit(&self.pending[..1]) {
+                            Ok(()) => submitted += 1,
+                            Err(SubmitError::Rejected(msg)) => {
+                                eprintln!("dropping rejected listen: {msg}");
+                            }
+                            Err(err) => {
+                                result = Err(err);
+                                break 'queue;
+                            }
+                        }
+                        self.pending.remove(0);
+                    }
+                    continue;
+                }
+                Err(SubmitError::Rejected(msg)) => {
+                    eprintln!("dropping rejected listen: {msg}");
+                }
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+            self.pending.drain(..count);
+        }
+
+        if let Err(err) = self.save() {
+            eprintln!("failed to save scrobble queue: {err}");
+        }
+        result.map(|()| submitted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrobble(listened_at: u64) -> Scrobble {
+        Scrobble {
+            artist: "Artist".into(),
+            title: format!("Track {listened_at}"),
+            album: None,
+            duration: Some(200),
+            listened_at,
+        }
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("scrobble-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("queue.json");
+        let _ = fs::remove_file(&file);
+        file
+    }
+
+    #[test]
+    fn keeps_listens_queued_while_offline() {
+        let file = temp_file("offline");
+        let mut queue = ScrobbleQueue::load(&file).unwrap();
+        for at in 1..=5 {
+            queue.push(scrobble(at));
+        }
+        queue.save().unwrap();
+
+        // The first batch goes out before the connection drops
+        let mut batches = Vec::new();
+        let result = queue.flush(2, |batch| {
+            batches.push(batch.len());
+            if batches.len() == 1 {
+                Ok(())
+            } else {
+                Err(SubmitError::Unavailable("offline".into()))
+            }
+        });
+        assert_eq!(result, Err(SubmitError::Unavailable("offline".into())));
+        assert_eq!(batches, [2, 2]);
+
+        let mut reloaded = ScrobbleQueue::load(&file).unwrap();
+        let times: Vec<u64> = reloaded.pending().iter().map(|s| s.listened_at).collect();
+        assert_eq!(times, [3, 4, 5]);
+
+        // Back online, the rest goes out in order
+        let mut sent = Vec::new();
+        let result = reloaded.flush(2, |batch| {
+            sent.extend(batch.iter().map(|s| s.listened_at));
+            Ok(())
+        });
+        assert_eq!(result, Ok(3));
+        assert_eq!(sent, [3, 4, 5]);
+        assert!(ScrobbleQueue::load(&file).unwrap().is_empty());
+    }
+
+    #[test]
+    fn drops_rejected_batches() {
+        let file = temp_file("rejected");
+        let mut queue = ScrobbleQueue::load(&file).unwrap();
+        queue.push(scrobble(1));
+        queue.push(scrobble(2));
+
+        let result = queue.flush(1, |batch| {
+            if batch[0].listened_at == 1 {
+                Err(SubmitError::Rejected("invalid".into()))
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(result, Ok(1));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn drops_only_the_rejected_listens_of_a_batch() {
+        let file = temp_file("rejected-batch");
+        let mut queue = ScrobbleQueue::load(&file).unwrap();
+        for at in 1..=5 {
+            queue.push(scrobble(at));
+        }
+
+        let mut sent = Vec::new();
+        let result = queue.flush(3, |batch| {
+            if batch.iter().any(|s| s.listened_at == 2) {
+                return Err(SubmitError::Rejected("invalid".into()));
+            }
+            if batch.iter().any(|s| s.listened_at == 3) && batch.len() == 1 {
+                return Err(SubmitError::Unavailable("offline".into()));
+            }
+            sent.extend(batch.iter().map(|s| s.listened_at));
+            Ok(())
+        });
+        // 1 goes out alone, 2 is dropped, and 3 waits for the service to return
+        assert_eq!(result, Err(SubmitError::Unavailable("offline".into())));
+        assert_eq!(sent, [1]);
+        let times: Vec<u64> = queue.pending().iter().map(|s| s.listened_at).collect();
+        assert_eq!(times, [3, 4, 5]);
+
+        let result = queue.flush(3, |batch| {
+            sent.extend(batch.iter().map(|s| s.listened_at));
+            Ok(())
+        });
+        assert_eq!(result, Ok(3));
+        assert_eq!(sent, [1, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A minimal HTTP server standing in for scrobbling services in tests.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+/// A request as the server received it.
+pub struct Request {
+    /// Request line and headers
+    pub head: String,
+    pub body: String,
+}
+
+/// Serve one request per `(status, body)` response, in order, passing each
+/// request back through the returned channel. Returns the server's root URL.
+pub fn serve(responses: Vec<(&'static str, &'static str)>) -> (String, mpsc::Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let root = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                head.push_str(&line);
+            }
+            let mut request = vec![0; length];
+            reader.read_exact(&mut request).unwrap();
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            let body = String::from_utf8(request).unwrap();
+            tx.send(Request { head, body }).unwrap();
+        }
+    });
+    (root, rx)
+}
+
+/// A root URL nothing listens on.
+pub fn unreachable() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Listening and library statistics computed from the history and library metadata.
+//!
+//! The functions here read files and walk the whole history, so callers should
+//! run them off the UI thread.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Datelike, Timelike};
+
+use crate::audio::backend::TrackMetadata;
+use crate::audio::cue::source_file;
+use crate::history::HistoryEntry;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// How many entries the top lists keep.
+pub const TOP_COUNT: usize = 10;
+
+/// The period listening statistics cover, ending now.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatsRange {
+    Week,
+    #[default]
+    Month,
+    Year,
+    All,
+}
+
+impl StatsRange {
+    pub const ALL: [StatsRange; 4] = [
+        StatsRange::Week,
+        StatsRange::Month,
+        StatsRange::Year,
+        StatsRange::All,
+    ];
+
+    /// The earliest start time included, in seconds since the Unix epoch.
+    pub fn since(self, now: u64) -> Option<u64> {
+        let days = match self {
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+            StatsRange::Year => 365,
+            StatsRange::All => return None,
+        };
+        Some(now.saturating_sub(days * SECONDS_PER_DAY))
+    }
+}
+
+/// An artist, album, track or genre with how much it was listened to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ranked {
+    pub name: String,
+    /// Listens that counted as plays
+    pub plays: u32,
+    pub listened: Duration,
+}
+
+/// What was listened to within a range.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListeningStats {
+    pub range: StatsRange,
+    /// Number of listens, including skipped ones
+    pub listens: usize,
+    pub total_listened: Duration,
+    pub top_artists: Vec<Ranked>,
+    pub top_albums: Vec<Ranked>,
+    pub top_tracks: Vec<Ranked>,
+    /// Listening time by genre, most listened first
+    pub genres: Vec<Ranked>,
+    /// Listening time by local hour of the day the listen started
+    pub by_hour: [Duration; 24],
+    /// Listening time by local day of the week, Monday first
+    pub by_weekday: [Duration; 7],
+}
+
+/// Compute listening statistics for the history entries within `range`.
+/// `now` is in seconds since the Unix epoch.
+pub fn listening_stats(
+    history: &[HistoryEntry],
+    library: &HashMap<PathBuf, TrackMetadata>,
+    range: StatsRange,
+    now: u64,
+) -> ListeningStats {
+    let since = range.since(now).unwrap_or(0);
+    let mut stats = ListeningStats {
+        range,
+        ..Default::default()
+    };
+
+    let mut artists: HashMap<String, Ranked> = HashMap::new();
+    let mut albums: HashMap<String, Ranked> = HashMap::new();
+    let mut tracks: HashMap<String, Ranked> = HashMap::new();
+    let mut genres: HashMap<String, Ranked> = HashMap::new();
+
+    for entry in history.iter().filter(|e| e.started_at >= since) {
+        let listened = entry.listened();
+        stats.listens += 1;
+        stats.total_listened += listened;
+
+        let started = entry.started();
+        stats.by_hour[started.hour() as usize] += listened;
+        stats.by_weekday[started.weekday().num_days_from_monday() as usize] += listened;
+
+        // The tags recorded with the listen win; the library fills in the rest
+        let md = library.get(&entry.path);
+        let artist = entry
+            .artist
+            .clone()
+            .or_else(|| md.and_then(|m| m.artist.clone()));
+        let album = entry
+            .album
+            .clone()
+            .or_else(|| md.and_then(|m| m.album.clone()));
+        let title = entry
+            .title
+            .clone()
+            .or_else(|| md.and_then(|m| m.title.clone()));
+        let genre = md.and_then(|m| m.genre.clone());
+
+        let add = |map: &mut HashMap<String, Ranked>, name: String| {
+            let ranked = map.entry(name.clone()).or_insert_with(|| Ranked {
+                name,
+                ..Default::default()
+            });
+            ranked.plays += u32::from(entry.counted);
+            ranked.listened += listened;
+        };
+        if let Some(artist) = &artist {
+            add(&mut artists, artist.clone());
+        }
+        if let Some(album) = album {
+            // Albums by different artists often share a name
+            let name = match &artist {
+                Some(artist) => format!("{album} — {artist}"),
+                None => album,
+            };
+            add(&mut albums, name);
+        }
+        let track = match (title, &artist) {
+            (Some(title), Some(artist)) => format!("{title} — {artist}"),
+            (Some(title), None) => title,
+            (None, _) => file_name(&entry.path),
+        };
+        add(&mut tracks, track);
+        if let Some(genre) = genre {
+            add(&mut genres, genre);
+        }
+    }
+
+    stats.top_artists = top(artists, Some(TOP_COUNT));
+    stats.top_albums = top(albums, Some(TOP_COUNT));
+    stats.top_tracks = top(tracks, Some(TOP_COUNT));
+    stats.genres = top(genres, None);
+    stats
+}
+
+/// Rank by plays, then listening time, then name, keeping at most `limit` entries.
+fn top(map: HashMap<String, Ranked>, limit: Option<usize>) -> Vec<Ranked> {
+    let mut ranked: Vec<Ranked> = map.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.plays
+            .cmp(&a.plays)
+            .then(b.listened.cmp(&a.listened))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    if let Some(limit) = limit {
+        ranked.truncate(limit);
+    }
+    ranked
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+/// Bitrate ranges library tracks are grouped into, as `(lower bound in kbps, label)`.
+pub const BITRATE_BUCKETS: [(u32, &str); 6] = [
+    (0, "< 128 kbps"),
+    (128, "128–191 kbps"),
+    (192, "192–255 kbps"),
+    (256, "256–319 kbps"),
+    (320, "320–699 kbps"),
+    (700, "≥ 700 kbps"),
+];
+
+/// What the library is made of.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LibraryStats {
+    pub tracks: usize,
+    pub total_duration: Duration,
+    /// Total size of the audio files in bytes
+    pub total_size: u64,
+    /// Track count by file extension, most common first
+    pub formats: Vec<(String, usize)>,
+    /// Track count per `BITRATE_BUCKETS` entry; tracks without a known bitrate are left out
+    pub bitrates: [usize; BITRATE_BUCKETS.len()],
+}
+
+/// Compute library composition. Reads the size of every file, so it touches the disk.
+pub fn library_stats(library: &HashMap<PathBuf, TrackMetadata>) -> LibraryStats {
+    let mut stats = LibraryStats {
+        tracks: library.len(),
+        ..Default::default()
+    };
+    let mut formats: HashMap<String, usize> = HashMap::new();
+    let mut counted_files: HashSet<PathBuf> = HashSet::new();
+
+    for (path, md) in library {
+        stats.total_duration += md.duration.unwrap_or_default();
+
+        // CUE tracks carved from one file count its size once
+        let file = source_file(path);
+        if counted_files.insert(file.clone()) {
+            if let Ok(meta) = fs::metadata(&file) {
+                stats.total_size += meta.len();
+            }
+        }
+
+        let format = file
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        *formats.entry(format).or_default() += 1;
+
+        if let Some(bitrate) = md.bitrate {
+            let bucket = BITRATE_BUCKETS
+                .iter()
+                .rposition(|(lower, _)| bitrate >= *lower)
+                .unwrap_or(0);
+            stats.bitrates[bucket] += 1;
+        }
+    }
+
+    let mut formats: Vec<(String, usize)> = formats.into_iter().collect();
+    formats.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    stats.formats = formats;
+    stats
+}
+
+/// Listening statistics for a range together with the library composition.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub listening: ListeningStats,
+    pub library: LibraryStats,
+}
+
+/// Compute all statistics. `now` is in seconds since the Unix epoch.
+pub fn compute(
+    history: &[HistoryEntry],
+    library: &HashMap<PathBuf, TrackMetadata>,
+    range: StatsRange,
+    now: u64,
+) -> Statistics {
+    Statistics {
+        listening: listening_stats(history, library, range, now),
+        library: library_stats(library),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::listen::ListenEnd;
+    use crate::history::PlaySource;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn listen(
+        title: &str,
+        artist: &str,
+        days_ago: u64,
+        minutes: u64,
+        counted: bool,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            path: PathBuf::from(format!("/m/{title}.flac")),
+            started_at: NOW - days_ago * SECONDS_PER_DAY,
+            listened_ms: minutes * 60_000,
+            end: if counted {
+                ListenEnd::Completed
+            } else {
+                ListenEnd::Skipped
+            },
+            counted,
+            source: PlaySource::Library,
+            title: Some(title.into()),
+            artist: Some(artist.into()),
+            album: Some("Album".into()),
+        }
+    }
+
+    #[test]
+    fn ranks_listens_within_range() {
+        let history = vec![
+            listen("Old", "Ann", 100, 4, true),
+            listen("A", "Ann", 3, 4, true),
+            listen("B", "Bob", 2, 3, true),
+            listen("B", "Bob", 1, 3, true),
+            listen("C", "Cat", 1, 1, false),
+        ];
+        let library = HashMap::from([(
+            PathBuf::from("/m/B.flac"),
+            TrackMetadata {
+                genre: Some("Jazz".into()),
+                ..Default::default()
+            },
+        )]);
+
+        let week = listening_stats(&history, &library, StatsRange::Week, NOW);
+        assert_eq!(week.listens, 4);
+        assert_eq!(week.total_listened, Duration::from_secs(11 * 60));
+        let artists: Vec<(&str, u32)> = week
+            .top_artists
+            .iter()
+            .map(|r| (r.name.as_str(), r.plays))
+            .collect();
+        assert_eq!(artists, [("Bob", 2), ("Ann", 1), ("Cat", 0)]);
+        assert_eq!(week.top_tracks[0].name, "B — Bob");
+        assert_eq!(week.top_albums.len(), 3);
+        assert_eq!(week.genres[0].name, "Jazz");
+        assert_eq!(week.by_hour.iter().sum::<Duration>(), week.total_listened);
+        assert_eq!(
+            week.by_weekday.iter().sum::<Duration>(),
+            week.total_listened
+        );
+
+        let all = listening_stats(&history, &library, StatsRange::All, NOW);
+        assert_eq!(all.listens, 5);
+        assert_eq!(all.top_artists[0].plays, 2);
+    }
+
+    #[test]
+    fn groups_library_by_format_and_bitrate() {
+        let track = |bitrate: Option<u32>| TrackMetadata {
+            bitrate,
+            duration: Some(Duration::from_secs(100)),
+            ..Default::default()
+        };
+        let library = HashMap::from([
+            (PathBuf::from("/missing/a.mp3"), track(Some(320))),
+            (PathBuf::from("/missing/b.MP3"), track(Some(128))),
+            (PathBuf::from("/missing/c.flac"), track(Some(900))),
+            (PathBuf::from("/missing/d.opus"), track(None)),
+        ]);
+
+        let stats = library_stats(&library);
+        assert_eq!(stats.tracks, 4);
+        assert_eq!(stats.total_duration, Duration::from_secs(400));
+        assert_eq!(stats.total_size, 0);
+        assert_eq!(stats.formats[0], ("mp3".to_string(), 2));
+        assert_eq!(stats.bitrates, [0, 1, 0, 0, 1, 1]);
+    }
+}